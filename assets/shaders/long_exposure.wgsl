@@ -0,0 +1,27 @@
+// Draws the long exposure texture with its colors scaled by `keep` and its
+// brightness as its opacity, so black is see-through and streaks add onto
+// whatever's behind them.
+
+#import bevy_sprite::mesh2d_types
+#import bevy_sprite::mesh2d_view_bindings
+
+@group(1) @binding(0)
+var<uniform> keep: f32;
+@group(1) @binding(1)
+var texture: texture_2d<f32>;
+@group(1) @binding(2)
+var texture_sampler: sampler;
+
+struct FragmentInput {
+    #import bevy_sprite::mesh2d_vertex_output
+};
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    let color = textureSample(texture, texture_sampler, in.uv).rgb * keep;
+    let brightness = max(color.r, max(color.g, color.b));
+    if brightness <= 0.0 {
+        return vec4<f32>(0.0);
+    }
+    return vec4<f32>(color / brightness, brightness);
+}
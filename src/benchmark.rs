@@ -0,0 +1,231 @@
+// Benchmark mode runs the sim at a few escalating populations and times how
+// long each stage takes so it's easy to tell when something got slower.
+// Stages are timed by stamping the clock between the system sets, so anything
+// unrelated running in parallel (like the UI) can make the numbers a bit noisy.
+
+use bevy::{app::AppExit, prelude::*, utils::Instant};
+
+use crate::{
+    files::{save_file, timestamp},
+    DeterministicMode, FactorInfo, InitialPopulations, RestartEvent, SimState, SystemStages,
+};
+
+const BENCHMARK_POPULATIONS: [usize; 4] = [1_000, 5_000, 20_000, 50_000];
+/// Ticks measured at each population
+const TICKS_PER_ROUND: u32 = 120;
+/// Ticks skipped after spawning so the restart itself doesn't count
+const WARMUP_TICKS: u32 = 10;
+
+pub const STAGE_NAMES: [&str; 4] = ["Flocking", "Apply", "Move", "Cache"];
+
+#[derive(Debug, Clone)]
+pub struct BenchmarkRound {
+    pub population: usize,
+    /// Average time of a whole frame
+    pub frame_millis: f32,
+    /// Average time spent in each of `STAGE_NAMES`
+    pub stage_millis: [f32; 4],
+}
+
+/// What's going on with the benchmark, if anything.
+#[derive(Debug, Default, Resource)]
+pub struct BenchmarkMode {
+    /// Quit once it's done. For running from the command line with `--bench`.
+    pub exit_when_done: bool,
+    pub results: Vec<BenchmarkRound>,
+    /// Where the last results were saved (or why they weren't)
+    pub status: Option<String>,
+    run: Option<BenchmarkRun>,
+}
+
+impl BenchmarkMode {
+    pub fn is_running(&self) -> bool {
+        self.run.is_some()
+    }
+
+    /// The population being measured right now
+    pub fn current_population(&self) -> Option<usize> {
+        self.run
+            .as_ref()
+            .map(|run| BENCHMARK_POPULATIONS[run.round])
+    }
+}
+
+/// Everything that gets put back once the benchmark is done.
+#[derive(Debug)]
+struct BenchmarkRun {
+    round: usize,
+    ticks: u32,
+    stamps: [Option<Instant>; 5],
+    frame_total: f32,
+    stage_totals: [f32; 4],
+    previous_deterministic: bool,
+    previous_populations: Vec<usize>,
+}
+
+pub struct StartBenchmarkEvent;
+
+fn even_split(population: usize, type_count: usize) -> Vec<usize> {
+    let type_count = type_count.max(1);
+    (0..type_count)
+        .map(|index| population / type_count + (index < population % type_count) as usize)
+        .collect()
+}
+
+fn start_benchmark_system(
+    mut start_events: EventReader<StartBenchmarkEvent>,
+    mut benchmark: ResMut<BenchmarkMode>,
+    mut deterministic_mode: ResMut<DeterministicMode>,
+    mut initial_populations: ResMut<InitialPopulations>,
+    factor_info: Res<FactorInfo>,
+    mut restart_events: EventWriter<RestartEvent>,
+    mut next_state: ResMut<NextState<SimState>>,
+) {
+    if start_events.iter().count() == 0 || benchmark.is_running() {
+        return;
+    }
+
+    benchmark.results.clear();
+    benchmark.status = None;
+    benchmark.run = Some(BenchmarkRun {
+        round: 0,
+        ticks: 0,
+        stamps: [None; 5],
+        frame_total: 0.0,
+        stage_totals: [0.0; 4],
+        previous_deterministic: deterministic_mode.0,
+        previous_populations: initial_populations.0.clone(),
+    });
+    // Every frame has to run exactly one tick of every stage to be comparable
+    deterministic_mode.0 = true;
+    initial_populations.0 = even_split(BENCHMARK_POPULATIONS[0], factor_info.factors.len());
+    restart_events.send(RestartEvent);
+    next_state.set(SimState::Running);
+}
+
+fn stamp_system<const STAMP: usize>(mut benchmark: ResMut<BenchmarkMode>) {
+    if let Some(run) = benchmark.run.as_mut() {
+        run.stamps[STAMP] = Some(Instant::now());
+    }
+}
+
+fn format_results(results: &[BenchmarkRound]) -> String {
+    let mut lines = vec![format!(
+        "Population | Frame (ms) | {} (ms)",
+        STAGE_NAMES.join(" (ms) | ")
+    )];
+    for round in results {
+        lines.push(format!(
+            "{} | {:.2} | {}",
+            round.population,
+            round.frame_millis,
+            round
+                .stage_millis
+                .iter()
+                .map(|millis| format!("{:.2}", millis))
+                .collect::<Vec<_>>()
+                .join(" | ")
+        ));
+    }
+    lines.join("\n")
+}
+
+fn benchmark_tick_system(
+    time: Res<Time>,
+    mut benchmark: ResMut<BenchmarkMode>,
+    mut deterministic_mode: ResMut<DeterministicMode>,
+    mut initial_populations: ResMut<InitialPopulations>,
+    factor_info: Res<FactorInfo>,
+    mut restart_events: EventWriter<RestartEvent>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    let Some(run) = benchmark.run.as_mut() else {
+        return;
+    };
+
+    let stamps = std::mem::take(&mut run.stamps);
+    run.ticks += 1;
+    if run.ticks <= WARMUP_TICKS {
+        return;
+    }
+    for (stage, total) in run.stage_totals.iter_mut().enumerate() {
+        if let (Some(start), Some(end)) = (stamps[stage], stamps[stage + 1]) {
+            *total += end.duration_since(start).as_secs_f32() * 1000.0;
+        }
+    }
+    run.frame_total += time.raw_delta_seconds() * 1000.0;
+    if run.ticks < WARMUP_TICKS + TICKS_PER_ROUND {
+        return;
+    }
+
+    let measured = TICKS_PER_ROUND as f32;
+    let round = BenchmarkRound {
+        population: BENCHMARK_POPULATIONS[run.round],
+        frame_millis: run.frame_total / measured,
+        stage_millis: run.stage_totals.map(|total| total / measured),
+    };
+    run.round += 1;
+    run.ticks = 0;
+    run.frame_total = 0.0;
+    run.stage_totals = [0.0; 4];
+
+    if let Some(&population) = BENCHMARK_POPULATIONS.get(run.round) {
+        initial_populations.0 = even_split(population, factor_info.factors.len());
+        restart_events.send(RestartEvent);
+        benchmark.results.push(round);
+        return;
+    }
+
+    // All done so put everything back the way it was
+    let Some(run) = benchmark.run.take() else {
+        return;
+    };
+    deterministic_mode.0 = run.previous_deterministic;
+    initial_populations.0 = run.previous_populations;
+    restart_events.send(RestartEvent);
+    benchmark.results.push(round);
+
+    let results = format_results(&benchmark.results);
+    info!("Benchmark results:\n{}", results);
+    let file_name = format!("rusty-boids-benchmark-{}.txt", timestamp());
+    benchmark.status = Some(
+        match save_file(&file_name, results.as_bytes(), "text/plain") {
+            Ok(path) => format!("Saved benchmark to {}", path),
+            Err(error) => format!("Couldn't save benchmark: {}", error),
+        },
+    );
+
+    if benchmark.exit_when_done {
+        exit_events.send(AppExit);
+    }
+}
+
+#[derive(Default)]
+pub struct BenchmarkPlugin;
+
+impl Plugin for BenchmarkPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BenchmarkMode::default())
+            .add_event::<StartBenchmarkEvent>()
+            .add_system(start_benchmark_system.in_base_set(CoreSet::PreUpdate))
+            .add_systems(
+                (
+                    stamp_system::<0>
+                        .after(SystemStages::Spawn)
+                        .before(SystemStages::Calculate),
+                    stamp_system::<1>
+                        .after(SystemStages::Calculate)
+                        .before(SystemStages::Apply),
+                    stamp_system::<2>
+                        .after(SystemStages::Apply)
+                        .before(SystemStages::Act),
+                    stamp_system::<3>
+                        .after(SystemStages::Act)
+                        .before(SystemStages::Cache),
+                    stamp_system::<4>.after(SystemStages::Cache),
+                )
+                    .chain(),
+            )
+            .add_system(benchmark_tick_system.in_base_set(CoreSet::Last));
+    }
+}
@@ -0,0 +1,470 @@
+// Flocking in a box instead of on a screen. It's its own little sim with the
+// same factors and features as the 2D one but none of its extras (UI, tools,
+// overlays). Only built with the `boids-3d` feature and started with `--3d`.
+//
+// Drag with the left mouse button to orbit the camera and scroll to zoom.
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseWheel},
+    prelude::*,
+    utils::HashMap,
+};
+use rand::{prelude::*, rngs::StdRng};
+
+use crate::{scenarios::Scenario, CreatureType, FactorInfo, Factors, Features};
+
+/// The box creatures wrap around in, centered on the origin.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct Volume {
+    pub size: Vec3,
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Volume {
+            size: Vec3::new(800.0, 600.0, 600.0),
+        }
+    }
+}
+
+impl Volume {
+    fn min(&self) -> Vec3 {
+        -self.size / 2.0
+    }
+
+    fn random_point(&self, rng: &mut impl Rng) -> Vec3 {
+        self.min() + self.size * Vec3::new(rng.gen(), rng.gen(), rng.gen())
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct Boid3d {
+    pub direction: Vec3,
+}
+
+/// Same idea as the 2D hash grid with one more axis. Rebuilt every frame.
+#[derive(Debug, Default, Resource)]
+struct HashGrid3d {
+    chunk_size: f32,
+    cells: HashMap<IVec3, Vec<(Entity, Vec3)>>,
+}
+
+impl HashGrid3d {
+    fn cell_of(&self, position: Vec3) -> IVec3 {
+        (position / self.chunk_size).floor().as_ivec3()
+    }
+
+    fn rebuild(&mut self, chunk_size: f32, creatures: impl Iterator<Item = (Entity, Vec3)>) {
+        self.chunk_size = chunk_size;
+        self.cells.clear();
+        for (entity, position) in creatures {
+            let cell = self.cell_of(position);
+            self.cells.entry(cell).or_default().push((entity, position));
+        }
+    }
+
+    fn nearby(&self, position: Vec3, radius: f32) -> impl Iterator<Item = &(Entity, Vec3)> + '_ {
+        let min = self.cell_of(position - Vec3::splat(radius));
+        let max = self.cell_of(position + Vec3::splat(radius));
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| (x, y)))
+            .flat_map(move |(x, y)| (min.z..=max.z).map(move |z| IVec3::new(x, y, z)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct OrbitCamera {
+    pub focus: Vec3,
+    pub radius: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        OrbitCamera {
+            focus: Vec3::ZERO,
+            radius: 1400.0,
+            yaw: 0.6,
+            pitch: 0.4,
+        }
+    }
+}
+
+impl OrbitCamera {
+    fn transform(&self) -> Transform {
+        let rotation = Quat::from_rotation_y(self.yaw) * Quat::from_rotation_x(-self.pitch);
+        let position = self.focus + rotation * Vec3::new(0.0, 0.0, self.radius);
+        Transform::from_translation(position).looking_at(self.focus, Vec3::Y)
+    }
+}
+
+fn spawn_boid(
+    commands: &mut Commands,
+    rng: &mut impl Rng,
+    volume: &Volume,
+    creature_type: CreatureType,
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+) {
+    let direction = Vec3::new(
+        rng.gen_range(-1.0..1.0),
+        rng.gen_range(-1.0..1.0),
+        rng.gen_range(-1.0..1.0),
+    )
+    .normalize_or_zero();
+    let position = volume.random_point(rng);
+    commands
+        .spawn(PbrBundle {
+            mesh,
+            material,
+            transform: Transform::from_translation(position)
+                .looking_at(position + direction, Vec3::Y),
+            ..default()
+        })
+        .insert(Boid3d { direction })
+        .insert(creature_type);
+}
+
+#[derive(Debug, Clone, Resource)]
+struct InitialSetup3d {
+    populations: Vec<usize>,
+    seed: u64,
+}
+
+fn setup_3d_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    volume: Res<Volume>,
+    factor_info: Res<FactorInfo>,
+    setup: Res<InitialSetup3d>,
+) {
+    let orbit_camera = OrbitCamera::default();
+    commands
+        .spawn(Camera3dBundle {
+            transform: orbit_camera.transform(),
+            ..default()
+        })
+        .insert(orbit_camera);
+    commands.spawn(DirectionalLightBundle {
+        transform: Transform::from_xyz(1.0, 2.0, 1.5).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+    commands.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: 0.4,
+    });
+
+    // Edges of the box so it's obvious where things wrap
+    let edge_material = materials.add(StandardMaterial {
+        base_color: Color::DARK_GRAY,
+        unlit: true,
+        ..default()
+    });
+    let half = volume.size / 2.0;
+    for axis in 0..3 {
+        let mut edge_size = Vec3::splat(2.0);
+        edge_size[axis] = volume.size[axis];
+        let edge_mesh = meshes.add(Mesh::from(shape::Box::new(
+            edge_size.x,
+            edge_size.y,
+            edge_size.z,
+        )));
+        for corner in 0..4 {
+            let mut offset = Vec3::ZERO;
+            let (first, second) = ((axis + 1) % 3, (axis + 2) % 3);
+            offset[first] = if corner & 1 == 0 {
+                -half[first]
+            } else {
+                half[first]
+            };
+            offset[second] = if corner & 2 == 0 {
+                -half[second]
+            } else {
+                half[second]
+            };
+            commands.spawn(PbrBundle {
+                mesh: edge_mesh.clone(),
+                material: edge_material.clone(),
+                transform: Transform::from_translation(offset),
+                ..default()
+            });
+        }
+    }
+
+    let mut rng = StdRng::seed_from_u64(setup.seed);
+    let mut creature_types = factor_info.factors.keys().copied().collect::<Vec<_>>();
+    creature_types.sort_by_key(|creature_type| creature_type.0);
+    for creature_type in creature_types {
+        let factors = &factor_info.factors[&creature_type];
+        // Longer than it is wide so it's easy to tell which way it's going
+        let mesh = meshes.add(Mesh::from(shape::Box::new(
+            factors.size * 0.5,
+            factors.size * 0.5,
+            factors.size * 1.5,
+        )));
+        let material = materials.add(StandardMaterial {
+            base_color: factors.color,
+            ..default()
+        });
+        let population = setup.populations.get(creature_type.0).copied().unwrap_or(0);
+        for _ in 0..population {
+            spawn_boid(
+                &mut commands,
+                &mut rng,
+                &volume,
+                creature_type,
+                mesh.clone(),
+                material.clone(),
+            );
+        }
+    }
+}
+
+fn hash_grid_3d_system(
+    mut hash_grid: ResMut<HashGrid3d>,
+    factor_info: Res<FactorInfo>,
+    creature_query: Query<(Entity, &Transform), With<Boid3d>>,
+) {
+    // Cells about as big as the farthest anyone reacts to anything
+    let chunk_size = factor_info
+        .factors
+        .values()
+        .map(Factors::reach)
+        .fold(20.0, f32::max);
+    hash_grid.rebuild(
+        chunk_size,
+        creature_query
+            .iter()
+            .map(|(entity, transform)| (entity, transform.translation)),
+    );
+}
+
+/// Every force on a creature added up. Same rules as the 2D flocking, just with a z.
+fn steer(
+    entity: Entity,
+    position: Vec3,
+    creature_type: CreatureType,
+    factor_info: &FactorInfo,
+    features: &Features,
+    hash_grid: &HashGrid3d,
+    creature_query: &Query<(Entity, &Transform, &Boid3d, &CreatureType)>,
+) -> Vec3 {
+    let factors = &factor_info.factors[&creature_type];
+    let mut position_sum = Vec3::ZERO;
+    let mut cohesion_count = 0;
+    let mut direction_sum = Vec3::ZERO;
+    let mut alignment_count = 0;
+    let mut close_position_sum = Vec3::ZERO;
+    let mut separation_count = 0;
+    let mut closest_prey: Option<(f32, Vec3)> = None;
+    let mut wanted = Vec3::ZERO;
+
+    let reach = factors.reach();
+    for &(other, other_position) in hash_grid.nearby(position, reach) {
+        if other == entity {
+            continue;
+        }
+        let distance = position.distance(other_position);
+        if distance > reach {
+            continue;
+        }
+        let Ok((_, _, other_boid, other_type)) = creature_query.get(other) else {
+            continue;
+        };
+
+        if *other_type == creature_type {
+            if !features.flocking {
+                continue;
+            }
+            if distance <= factors.cohesion_radius {
+                cohesion_count += 1;
+                position_sum += other_position;
+            }
+            if distance <= factors.alignment_radius {
+                alignment_count += 1;
+                direction_sum += other_boid.direction;
+            }
+            if distance <= factors.separation_radius {
+                separation_count += 1;
+                close_position_sum += other_position;
+            }
+            if distance <= factors.size * 2.0 {
+                wanted +=
+                    (position - other_position).normalize_or_zero() * factors.collision_avoidance;
+            }
+            continue;
+        }
+
+        if distance > factors.vision {
+            continue;
+        }
+        if features.chasing
+            && factors.predator_of.contains(other_type)
+            && closest_prey.map_or(true, |(closest, _)| distance < closest)
+        {
+            closest_prey = Some((distance, other_position));
+        }
+        let is_hunted = factor_info
+            .factors
+            .get(other_type)
+            .map_or(false, |other_factors| {
+                other_factors.predator_of.contains(&creature_type)
+            });
+        if features.running && is_hunted {
+            wanted += (position - other_position).normalize_or_zero() * factors.scare;
+        }
+    }
+
+    if cohesion_count > 0 {
+        let average_position = position_sum / cohesion_count as f32;
+        wanted += (average_position - position).normalize_or_zero() * factors.cohesion;
+    }
+    if alignment_count > 0 {
+        let average_direction = direction_sum / alignment_count as f32;
+        wanted += average_direction.normalize_or_zero() * factors.alignment;
+    }
+    if separation_count > 0 {
+        let average_close_position = close_position_sum / separation_count as f32;
+        wanted += (position - average_close_position).normalize_or_zero() * factors.separation;
+    }
+    if let Some((_, prey_position)) = closest_prey {
+        wanted += (prey_position - position).normalize_or_zero() * factors.chase;
+    }
+    wanted
+}
+
+fn flocking_3d_system(
+    time: Res<Time>,
+    factor_info: Res<FactorInfo>,
+    features: Res<Features>,
+    hash_grid: Res<HashGrid3d>,
+    mut queries: ParamSet<(
+        Query<(Entity, &Transform, &Boid3d, &CreatureType)>,
+        Query<&mut Boid3d>,
+    )>,
+) {
+    let delta_seconds = time.delta_seconds();
+    let creature_query = queries.p0();
+    let new_directions = creature_query
+        .iter()
+        .map(|(entity, transform, boid, &creature_type)| {
+            let steering = steer(
+                entity,
+                transform.translation,
+                creature_type,
+                &factor_info,
+                &features,
+                &hash_grid,
+                &creature_query,
+            );
+            // Close to lerping towards each force one at a time like in 2D
+            let strength = (steering.length() * delta_seconds).min(1.0);
+            let direction = boid
+                .direction
+                .lerp(steering.normalize_or_zero(), strength)
+                .normalize_or_zero();
+            (entity, direction)
+        })
+        .collect::<Vec<_>>();
+
+    let mut boid_query = queries.p1();
+    for (entity, direction) in new_directions {
+        if let Ok(mut boid) = boid_query.get_mut(entity) {
+            if direction != Vec3::ZERO {
+                boid.direction = direction;
+            }
+        }
+    }
+}
+
+fn move_3d_system(
+    time: Res<Time>,
+    volume: Res<Volume>,
+    factor_info: Res<FactorInfo>,
+    mut creature_query: Query<(&mut Transform, &Boid3d, &CreatureType)>,
+) {
+    let delta_seconds = time.delta_seconds();
+    let (min, size) = (volume.min(), volume.size);
+    for (mut transform, boid, creature_type) in creature_query.iter_mut() {
+        let speed = factor_info.factors[creature_type].speed;
+        let moved = transform.translation + boid.direction * speed * delta_seconds;
+        // Out one side, in the other
+        let offset = moved - min;
+        let wrapped = min
+            + Vec3::new(
+                offset.x.rem_euclid(size.x),
+                offset.y.rem_euclid(size.y),
+                offset.z.rem_euclid(size.z),
+            );
+        *transform =
+            Transform::from_translation(wrapped).looking_at(wrapped + boid.direction, Vec3::Y);
+    }
+}
+
+fn orbit_camera_system(
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut motion_events: EventReader<MouseMotion>,
+    mut wheel_events: EventReader<MouseWheel>,
+    mut camera_query: Query<(&mut OrbitCamera, &mut Transform)>,
+) {
+    let motion = motion_events.iter().map(|event| event.delta).sum::<Vec2>();
+    let scroll = wheel_events.iter().map(|event| event.y).sum::<f32>();
+    for (mut orbit_camera, mut transform) in camera_query.iter_mut() {
+        if mouse_buttons.pressed(MouseButton::Left) {
+            orbit_camera.yaw -= motion.x * 0.005;
+            // Stop just short of straight up or down so the view doesn't flip
+            orbit_camera.pitch = (orbit_camera.pitch + motion.y * 0.005).clamp(-1.5, 1.5);
+        }
+        orbit_camera.radius = (orbit_camera.radius * (1.0 - scroll * 0.1)).clamp(50.0, 10_000.0);
+        *transform = orbit_camera.transform();
+    }
+}
+
+/// A 3D version of the sim. Use instead of `BoidsPlugin`, not alongside it.
+pub struct Boids3dPlugin {
+    scenario: Scenario,
+    seed: Option<u64>,
+}
+
+impl Default for Boids3dPlugin {
+    fn default() -> Self {
+        Boids3dPlugin {
+            scenario: Scenario::food_chain(),
+            seed: None,
+        }
+    }
+}
+
+impl Boids3dPlugin {
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_scenario(mut self, scenario: Scenario) -> Self {
+        self.scenario = scenario;
+        self
+    }
+}
+
+impl Plugin for Boids3dPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FactorInfo {
+            factors: self.scenario.factors.clone(),
+        })
+        .insert_resource(self.scenario.features.clone())
+        .insert_resource(Volume::default())
+        .insert_resource(HashGrid3d::default())
+        .insert_resource(InitialSetup3d {
+            populations: self.scenario.populations.clone(),
+            seed: self.seed.unwrap_or_else(rand::random),
+        })
+        .add_startup_system(setup_3d_system)
+        .add_systems((hash_grid_3d_system, flocking_3d_system, move_3d_system).chain())
+        .add_system(orbit_camera_system);
+    }
+}
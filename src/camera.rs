@@ -0,0 +1,247 @@
+// Getting around a world that's bigger than the window. Arrow keys or WASD pan,
+// the scroll wheel zooms in and out around the cursor, and Home zooms out to
+// fit the whole world. Creatures that are off screen get hidden so a huge
+// world only costs as much to draw as what's in view, and they can be drawn
+// the same size on screen at any zoom so flocks far away don't disappear.
+
+use bevy::{
+    ecs::system::SystemParam,
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    math::Vec3Swizzles,
+    prelude::*,
+    window::PrimaryWindow,
+};
+use bevy_egui::EguiContexts;
+
+use crate::{
+    selection::CameraFollow, ui::has_ui, CreatureType, Cursor, FactorInfo, MainCamera,
+    SystemStages, WorldBounds,
+};
+
+/// How far in and out the camera can zoom, as world units per screen pixel
+pub const MIN_ZOOM: f32 = 0.1;
+pub const MAX_ZOOM: f32 = 50.0;
+
+/// Scrolling in pixels (trackpads) moves this much for every line a wheel
+/// would have moved
+const PIXELS_PER_LINE: f32 = 50.0;
+
+const PAN_KEYS: [(KeyCode, Vec2); 8] = [
+    (KeyCode::Up, Vec2::Y),
+    (KeyCode::W, Vec2::Y),
+    (KeyCode::Down, Vec2::NEG_Y),
+    (KeyCode::S, Vec2::NEG_Y),
+    (KeyCode::Left, Vec2::NEG_X),
+    (KeyCode::A, Vec2::NEG_X),
+    (KeyCode::Right, Vec2::X),
+    (KeyCode::D, Vec2::X),
+];
+
+#[derive(Debug, Clone, Resource)]
+pub struct CameraControls {
+    /// Screen pixels per second, so it feels the same at any zoom
+    pub pan_speed: f32,
+    /// How much one notch of the scroll wheel zooms
+    pub zoom_step: f32,
+    /// Hide creatures that are off screen
+    pub cull_off_screen: bool,
+    /// Draw every creature `constant_size_pixels` across on screen no matter
+    /// how big it is or how far out the camera is
+    pub constant_size: bool,
+    pub constant_size_pixels: f32,
+}
+
+impl Default for CameraControls {
+    fn default() -> Self {
+        CameraControls {
+            pan_speed: 800.0,
+            zoom_step: 1.15,
+            cull_off_screen: true,
+            constant_size: false,
+            constant_size_pixels: 6.0,
+        }
+    }
+}
+
+/// Keys, scrolling, and the cursor, along with egui to know when it wants them
+#[derive(SystemParam)]
+struct CameraInput<'w, 's> {
+    keys: Res<'w, Input<KeyCode>>,
+    cursor: Res<'w, Cursor>,
+    wheel_events: EventReader<'w, 's, MouseWheel>,
+    egui_context: EguiContexts<'w, 's>,
+}
+
+fn camera_controls_system(
+    time: Res<Time>,
+    input: CameraInput,
+    controls: Res<CameraControls>,
+    world_bounds: Res<WorldBounds>,
+    mut camera_follow: ResMut<CameraFollow>,
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    let CameraInput {
+        keys,
+        cursor,
+        mut wheel_events,
+        mut egui_context,
+    } = input;
+    let ctx = egui_context.ctx_mut();
+    let (typing, over_ui) = (ctx.wants_keyboard_input(), ctx.is_pointer_over_area());
+    // Alt+Scroll goes through creature types instead
+    let alt = keys.any_pressed([KeyCode::LAlt, KeyCode::RAlt]);
+    let scroll = wheel_events
+        .iter()
+        .map(|wheel| match wheel.unit {
+            MouseScrollUnit::Line => wheel.y,
+            MouseScrollUnit::Pixel => wheel.y / PIXELS_PER_LINE,
+        })
+        .sum::<f32>();
+    let scroll = if alt || over_ui { 0.0 } else { scroll };
+    let pan = if typing {
+        Vec2::ZERO
+    } else {
+        PAN_KEYS
+            .iter()
+            .filter(|(key, _)| keys.pressed(*key))
+            .map(|(_, direction)| *direction)
+            .sum::<Vec2>()
+    };
+    let fit = !typing && keys.just_pressed(KeyCode::Home);
+    if scroll == 0.0 && pan == Vec2::ZERO && !fit {
+        return;
+    }
+
+    let (Ok(window), Ok((mut transform, mut projection))) =
+        (primary_query.get_single(), camera_query.get_single_mut())
+    else {
+        return;
+    };
+    let mut position = transform.translation.xy();
+    if fit {
+        let window_size = Vec2::new(window.width(), window.height());
+        projection.scale = (world_bounds.size() / window_size)
+            .max_element()
+            .clamp(MIN_ZOOM, MAX_ZOOM);
+        position = Vec2::ZERO;
+    }
+    if pan != Vec2::ZERO {
+        // Steering the camera means it's not following anyone anymore
+        camera_follow.enabled = false;
+        // Keeps working while paused or in slow motion
+        position +=
+            pan.normalize() * controls.pan_speed * projection.scale * time.raw_delta_seconds();
+    }
+    if scroll != 0.0 {
+        // Whatever's under the cursor stays under it
+        let scale = (projection.scale * controls.zoom_step.powf(-scroll)).clamp(MIN_ZOOM, MAX_ZOOM);
+        position = cursor.position + (position - cursor.position) * scale / projection.scale;
+        projection.scale = scale;
+    }
+    // Wandering off into the void isn't useful
+    position = position.clamp(world_bounds.min(), world_bounds.max());
+    transform.translation = position.extend(transform.translation.z);
+}
+
+/// The window and main camera, which is all it takes to know what's on screen
+#[derive(SystemParam)]
+pub(crate) struct CameraView<'w, 's> {
+    primary_query: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    camera_query: Query<
+        'w,
+        's,
+        (&'static GlobalTransform, &'static OrthographicProjection),
+        With<MainCamera>,
+    >,
+}
+
+impl CameraView<'_, '_> {
+    /// The part of the world in the window, if there's a window to be in
+    pub(crate) fn visible_area(&self) -> Option<Rect> {
+        let window = self.primary_query.get_single().ok()?;
+        let (camera_transform, projection) = self.camera_query.get_single().ok()?;
+        Some(Rect::from_center_half_size(
+            camera_transform.translation().xy(),
+            Vec2::new(window.width(), window.height()) * projection.scale / 2.0,
+        ))
+    }
+}
+
+fn cull_off_screen_system(
+    controls: Res<CameraControls>,
+    factor_info: Res<FactorInfo>,
+    camera_view: CameraView,
+    mut creature_query: Query<(&Transform, &mut Visibility), With<CreatureType>>,
+) {
+    let view = match camera_view.visible_area() {
+        Some(view) if controls.cull_off_screen => {
+            // Padded by the biggest creature so nothing pops at the edges
+            let largest_size = factor_info
+                .factors
+                .values()
+                .map(|factors| factors.size)
+                .fold(0.0, f32::max);
+            Some(Rect::from_center_half_size(
+                view.center(),
+                view.half_size() + largest_size,
+            ))
+        }
+        _ => None,
+    };
+    if view.is_none() && !controls.is_changed() {
+        return;
+    }
+    for (transform, mut visibility) in creature_query.iter_mut() {
+        let shown = view.is_none_or(|view| view.contains(transform.translation.xy()));
+        let wanted = if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        // Only touching the ones that changed keeps change detection quiet
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+/// Scales creatures up or down against the zoom. Only the look changes, they
+/// still bump into things at their real size.
+pub(crate) fn constant_size_system(
+    controls: Res<CameraControls>,
+    camera_query: Query<&OrthographicProjection, With<MainCamera>>,
+    mut creature_query: Query<(&Sprite, &mut Transform), With<CreatureType>>,
+) {
+    if !controls.constant_size && !controls.is_changed() {
+        return;
+    }
+    let Ok(projection) = camera_query.get_single() else {
+        return;
+    };
+    for (sprite, mut transform) in creature_query.iter_mut() {
+        let scale = if controls.constant_size {
+            let size = sprite.custom_size.map_or(1.0, |size| size.max_element());
+            controls.constant_size_pixels * projection.scale / size.max(f32::EPSILON)
+        } else {
+            1.0
+        };
+        // Motion smear stretches y on top of this afterwards
+        if transform.scale.x != scale {
+            transform.scale.x = scale;
+            transform.scale.y = scale;
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraControls>()
+            .add_system(camera_controls_system.run_if(has_ui))
+            .add_system(cull_off_screen_system.after(SystemStages::Act))
+            .add_system(constant_size_system.after(SystemStages::Act));
+    }
+}
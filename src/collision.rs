@@ -0,0 +1,168 @@
+// Separation only steers, so cranking cohesion way up can still pile a whole
+// flock onto one spot. This shoves overlapping creatures straight apart after
+// they've moved, with bigger ones getting pushed around less.
+
+use bevy::{math::Vec3Swizzles, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    growth::BodySize, move_system, spatial::SpatialQuery, variation::Variation,
+    wrap_borders_system, CreatureType, FactorInfo, FrozenTypes, SimState, SystemStages,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CollisionSettings {
+    pub enabled: bool,
+    /// How much of the overlap gets fixed each pass
+    pub stiffness: f32,
+    /// More passes settle crowds better but cost more
+    pub iterations: usize,
+}
+
+impl Default for CollisionSettings {
+    fn default() -> Self {
+        CollisionSettings {
+            enabled: false,
+            stiffness: 0.5,
+            iterations: 2,
+        }
+    }
+}
+
+struct Body {
+    entity: Entity,
+    position: Vec2,
+    radius: f32,
+    /// Frozen creatures don't get pushed, they only push
+    frozen: bool,
+}
+
+/// Reused between frames so collisions only allocate while these grow.
+#[derive(Default)]
+struct CollisionScratch {
+    bodies: Vec<Body>,
+    corrections: Vec<Vec2>,
+    indices: HashMap<Entity, usize>,
+}
+
+type CollidingCreature<'a> = (
+    Entity,
+    &'a mut Transform,
+    &'a CreatureType,
+    Option<&'a Variation>,
+    Option<&'a BodySize>,
+);
+
+fn collision_system(
+    settings: Res<CollisionSettings>,
+    factor_info: Res<FactorInfo>,
+    frozen_types: Res<FrozenTypes>,
+    spatial_query: SpatialQuery,
+    mut creature_query: Query<CollidingCreature>,
+    mut scratch: Local<CollisionScratch>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    let CollisionScratch {
+        bodies,
+        corrections,
+        indices,
+    } = &mut *scratch;
+
+    bodies.clear();
+    for (entity, transform, creature_type, variation, body_size) in creature_query.iter() {
+        let Some(factors) = factor_info.factors.get(creature_type) else {
+            continue;
+        };
+        let size = body_size.map_or_else(
+            || variation.copied().unwrap_or_default().size(factors),
+            |body_size| body_size.0,
+        );
+        bodies.push(Body {
+            entity,
+            position: transform.translation.xy(),
+            radius: size / 2.0,
+            frozen: frozen_types.contains(creature_type),
+        });
+    }
+    // Same order every run so deterministic mode stays deterministic
+    bodies.sort_unstable_by_key(|body| body.entity);
+    indices.clear();
+    indices.extend(
+        bodies
+            .iter()
+            .enumerate()
+            .map(|(index, body)| (body.entity, index)),
+    );
+    let largest = bodies.iter().map(|body| body.radius).fold(0.0, f32::max);
+
+    for _ in 0..settings.iterations {
+        corrections.clear();
+        corrections.resize(bodies.len(), Vec2::ZERO);
+        for (index, body) in bodies.iter().enumerate() {
+            for other in spatial_query.get_nearby_entities(body.position, body.radius + largest) {
+                // Each pair only once
+                let Some(&other_index) = indices
+                    .get(&other)
+                    .filter(|&&other_index| other_index > index)
+                else {
+                    continue;
+                };
+                let other_body = &bodies[other_index];
+                let offset = body.position - other_body.position;
+                let distance = offset.length();
+                let overlap = body.radius + other_body.radius - distance;
+                if overlap <= 0.0 {
+                    continue;
+                }
+                // Right on top of each other doesn't have a direction so pick one
+                let normal = if distance > f32::EPSILON {
+                    offset / distance
+                } else {
+                    Vec2::X
+                };
+
+                // The lighter one moves more, going by area
+                let (mass, other_mass) = (body.radius.powi(2), other_body.radius.powi(2));
+                let share = match (body.frozen, other_body.frozen) {
+                    (true, true) => continue,
+                    (true, false) => 0.0,
+                    (false, true) => 1.0,
+                    (false, false) => other_mass / (mass + other_mass).max(f32::EPSILON),
+                };
+                let push = normal * overlap * settings.stiffness;
+                corrections[index] += push * share;
+                corrections[other_index] -= push * (1.0 - share);
+            }
+        }
+        for (body, correction) in bodies.iter_mut().zip(corrections.iter()) {
+            body.position += *correction;
+        }
+    }
+
+    for body in bodies.iter() {
+        let Ok((_, mut transform, ..)) = creature_query.get_mut(body.entity) else {
+            continue;
+        };
+        if transform.translation.xy() != body.position {
+            transform.translation = body.position.extend(transform.translation.z);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            collision_system
+                .after(move_system)
+                .before(wrap_borders_system)
+                .in_set(SystemStages::Act)
+                .in_set(OnUpdate(SimState::Running)),
+        );
+    }
+}
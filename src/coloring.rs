@@ -0,0 +1,232 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    update_factors_system, ApplyForceEvent, CreatureType, Direction, Energy, FactorInfo, Factors,
+    ForceKind, HashGrid, SystemStages,
+};
+
+/// What a creature's color tells you.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Resource)]
+pub enum CreatureColorMode {
+    /// Just the color from its factors
+    #[default]
+    Type,
+    /// Red when starving up to the type color when full
+    Energy,
+    /// Dim when alone in its grid cell up to the type color in the most
+    /// crowded one, so flock cores stand out from stragglers
+    Density,
+}
+
+impl CreatureColorMode {
+    pub const ALL: [CreatureColorMode; 3] = [
+        CreatureColorMode::Type,
+        CreatureColorMode::Energy,
+        CreatureColorMode::Density,
+    ];
+}
+
+impl std::fmt::Display for CreatureColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CreatureColorMode::Type => write!(f, "Type Color"),
+            CreatureColorMode::Energy => write!(f, "Energy"),
+            CreatureColorMode::Density => write!(f, "Local Density"),
+        }
+    }
+}
+
+const EMPTY_ENERGY_COLOR: Color = Color::RED;
+/// How bright a creature all alone in its cell is compared to its type color
+const SPARSE_BRIGHTNESS: f32 = 0.25;
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let [r, g, b, a] = from.as_rgba_f32();
+    let [to_r, to_g, to_b, to_a] = to.as_rgba_f32();
+    Color::rgba(
+        r + (to_r - r) * t,
+        g + (to_g - g) * t,
+        b + (to_b - b) * t,
+        a + (to_a - a) * t,
+    )
+}
+
+/// Overrides the normal colors to dig into what the sim is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Resource)]
+pub enum DebugColorMode {
+    #[default]
+    None,
+    /// Hue from the direction it's facing
+    Heading,
+    /// Blue for the slowest types up to red for the fastest
+    Speed,
+    /// Each hash grid chunk gets its own color
+    GridCell,
+    /// Fleeing, chasing, flocking, or alone based on the forces it got this tick
+    State,
+}
+
+impl DebugColorMode {
+    pub const ALL: [DebugColorMode; 5] = [
+        DebugColorMode::None,
+        DebugColorMode::Heading,
+        DebugColorMode::Speed,
+        DebugColorMode::GridCell,
+        DebugColorMode::State,
+    ];
+}
+
+impl std::fmt::Display for DebugColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DebugColorMode::GridCell => write!(f, "Grid Cell"),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+const FLEEING_COLOR: Color = Color::YELLOW;
+const CHASING_COLOR: Color = Color::RED;
+const FLOCKING_COLOR: Color = Color::CYAN;
+const ALONE_COLOR: Color = Color::GRAY;
+
+fn debug_color(
+    mode: DebugColorMode,
+    entity: Entity,
+    direction: Vec2,
+    factors: &Factors,
+    fastest_speed: f32,
+    hash_grid: &HashGrid,
+    forces: &HashMap<Entity, HashSet<ForceKind>>,
+) -> Option<Color> {
+    match mode {
+        DebugColorMode::None => None,
+        DebugColorMode::Heading => {
+            let angle = direction
+                .y
+                .atan2(direction.x)
+                .to_degrees()
+                .rem_euclid(360.0);
+            Some(Color::hsl(angle, 0.9, 0.6))
+        }
+        DebugColorMode::Speed => {
+            let speed = factors.speed * direction.length();
+            let t = (speed / fastest_speed).clamp(0.0, 1.0);
+            Some(lerp_color(Color::BLUE, Color::RED, t))
+        }
+        DebugColorMode::GridCell => {
+            let (row, column) = hash_grid.cell_of(entity)?;
+            // Neighboring cells should look different so the steps are coprime-ish
+            let index = (row * 7 + column * 13).rem_euclid(12);
+            Some(Color::hsl(index as f32 * 30.0, 0.8, 0.55))
+        }
+        DebugColorMode::State => {
+            let kinds = forces.get(&entity);
+            let has = |kind| kinds.is_some_and(|kinds| kinds.contains(&kind));
+            Some(if has(ForceKind::Scare) {
+                FLEEING_COLOR
+            } else if has(ForceKind::Chase) {
+                CHASING_COLOR
+            } else if kinds.is_some() {
+                FLOCKING_COLOR
+            } else {
+                ALONE_COLOR
+            })
+        }
+    }
+}
+
+fn creature_color_system(
+    color_mode: Res<CreatureColorMode>,
+    debug_color_mode: Res<DebugColorMode>,
+    factor_info: Res<FactorInfo>,
+    hash_grid: Res<HashGrid>,
+    mut force_reader: EventReader<ApplyForceEvent>,
+    mut creature_query: Query<(Entity, &CreatureType, &Energy, &Direction, &mut Sprite)>,
+) {
+    let mut forces: HashMap<Entity, HashSet<ForceKind>> = HashMap::default();
+    for ApplyForceEvent(entity, _, _, kind) in force_reader.iter() {
+        forces.entry(*entity).or_default().insert(*kind);
+    }
+
+    if *debug_color_mode != DebugColorMode::None {
+        // Flocking runs on a fixed timestep so some frames have no forces at
+        // all. Keep the last colors instead of flashing everything to alone.
+        if *debug_color_mode == DebugColorMode::State && forces.is_empty() {
+            return;
+        }
+        let fastest_speed = factor_info
+            .factors
+            .values()
+            .map(|factors| factors.speed)
+            .fold(f32::EPSILON, f32::max);
+        for (entity, creature_type, _, direction, mut sprite) in creature_query.iter_mut() {
+            let factors = factor_info.factors.get(creature_type).unwrap();
+            sprite.color = debug_color(
+                *debug_color_mode,
+                entity,
+                direction.0,
+                factors,
+                fastest_speed,
+                &hash_grid,
+                &forces,
+            )
+            .unwrap_or(factors.color);
+        }
+        return;
+    }
+
+    match *color_mode {
+        CreatureColorMode::Energy => {
+            for (_, creature_type, energy, _, mut sprite) in creature_query.iter_mut() {
+                let factors = factor_info.factors.get(creature_type).unwrap();
+                let fullness = (energy.0 / factors.max_energy).clamp(0.0, 1.0);
+                sprite.color = lerp_color(EMPTY_ENERGY_COLOR, factors.color, fullness);
+            }
+        }
+        CreatureColorMode::Density => {
+            let busiest = hash_grid.cells().map(|(_, count)| count).max().unwrap_or(1);
+            // Log scale so a cell of 5 and a cell of 500 don't both look crowded
+            // next to one of 5000
+            let scale = (busiest as f32).ln().max(f32::EPSILON);
+            for (entity, creature_type, _, _, mut sprite) in creature_query.iter_mut() {
+                let color = factor_info.factors.get(creature_type).unwrap().color;
+                let count = hash_grid.cell_count(entity).max(1);
+                let crowding = ((count as f32).ln() / scale).clamp(0.0, 1.0);
+                let [r, g, b, a] = color.as_rgba_f32();
+                let sparse = Color::rgba(
+                    r * SPARSE_BRIGHTNESS,
+                    g * SPARSE_BRIGHTNESS,
+                    b * SPARSE_BRIGHTNESS,
+                    a,
+                );
+                sprite.color = lerp_color(sparse, color, crowding);
+            }
+        }
+        // Put the normal colors back once when switching away
+        CreatureColorMode::Type if color_mode.is_changed() || debug_color_mode.is_changed() => {
+            for (_, creature_type, _, _, mut sprite) in creature_query.iter_mut() {
+                sprite.color = factor_info.factors.get(creature_type).unwrap().color;
+            }
+        }
+        CreatureColorMode::Type => {}
+    }
+}
+
+#[derive(Default)]
+pub struct ColoringPlugin;
+
+impl Plugin for ColoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CreatureColorMode::default())
+            .insert_resource(DebugColorMode::default())
+            .add_system(
+                creature_color_system
+                    .in_set(SystemStages::Cache)
+                    .after(update_factors_system),
+            );
+    }
+}
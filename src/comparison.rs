@@ -0,0 +1,314 @@
+// Split screen A/B testing. The right half of the window shows a second copy
+// of the sim running in its own `App` with its own factors and features, so
+// tweaking one side can be compared against the other as they play out. Both
+// start from the same seed and populations so the only difference is the settings.
+//
+// The second sim never renders anything itself. Its creatures get mirrored
+// into this world as plain sprites that only the right camera can see.
+
+use bevy::{
+    asset::AssetPlugin,
+    core_pipeline::clear_color::ClearColorConfig,
+    diagnostic::DiagnosticsPlugin,
+    input::InputPlugin,
+    prelude::*,
+    render::{camera::Viewport, view::RenderLayers},
+    utils::HashMap,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    scenarios::Scenario, BoidsPlugin, CreatureType, DeterministicMode, FactorInfo, Features,
+    InitialPopulations, MainCamera, RestartEvent, SimRng, SimState, TimeScale, WorldBounds,
+};
+
+/// Only the right camera draws this layer
+const COMPARISON_LAYER: u8 = 1;
+
+/// Settings for the right side. Edit these and set `changed` to push them over.
+#[derive(Debug, Default, Resource)]
+pub struct Comparison {
+    pub enabled: bool,
+    pub factor_info: FactorInfo,
+    pub features: Features,
+    pub changed: bool,
+    /// Restart the right side on its next update
+    pub restart: bool,
+}
+
+impl Comparison {
+    /// Right side starts (or starts over) with the same settings as the left
+    pub fn copy_from(&mut self, factor_info: &FactorInfo, features: &Features) {
+        self.factor_info = factor_info.clone();
+        self.features = features.clone();
+        self.changed = true;
+    }
+}
+
+/// Kept out of the `Comparison` resource since an `App` can't be sent between threads.
+struct ComparisonSim {
+    app: App,
+    /// Entity in the second sim -> its sprite over here
+    mirrors: HashMap<Entity, Entity>,
+}
+
+#[derive(Component)]
+struct ComparisonCamera;
+
+#[derive(Component)]
+struct Mirror;
+
+fn create_sim(world: &World) -> App {
+    let comparison = world.resource::<Comparison>();
+    let world_bounds = world.resource::<WorldBounds>();
+    let scenario = Scenario {
+        name: "Comparison",
+        description: "",
+        features: comparison.features.clone(),
+        factors: comparison.factor_info.factors.clone(),
+        populations: world.resource::<InitialPopulations>().0.clone(),
+    };
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(AssetPlugin::default())
+        .add_plugin(InputPlugin)
+        .add_plugin(DiagnosticsPlugin)
+        .add_asset::<Image>()
+        // Obstacles are meshes, even if nothing here ever draws them
+        .add_asset::<Mesh>()
+        .add_asset::<ColorMaterial>()
+        .insert_resource(*world.resource::<DeterministicMode>())
+        .add_plugin(
+            BoidsPlugin::from_scenario(scenario)
+                .with_seed(Some(world.resource::<SimRng>().seed()))
+                .with_world_bounds(world_bounds.width, world_bounds.height)
+                .with_ui(false)
+                .with_persistence(false),
+        );
+    // Stand ins for the real window and camera. They get synced with the real
+    // ones every frame so level of detail skips the same creatures on both sides.
+    app.world.spawn((Window::default(), PrimaryWindow));
+    app.world
+        .spawn(Camera2dBundle::default())
+        .insert(MainCamera);
+    app
+}
+
+fn half_viewport(window: &Window, right: bool) -> Viewport {
+    let half_width = window.physical_width() / 2;
+    Viewport {
+        physical_position: UVec2::new(if right { half_width } else { 0 }, 0),
+        physical_size: UVec2::new(half_width.max(1), window.physical_height().max(1)),
+        ..default()
+    }
+}
+
+fn set_viewport(camera: &mut Camera, viewport: Option<Viewport>) {
+    let same = match (&camera.viewport, &viewport) {
+        (Some(current), Some(wanted)) => {
+            current.physical_position == wanted.physical_position
+                && current.physical_size == wanted.physical_size
+        }
+        (None, None) => true,
+        _ => false,
+    };
+    if !same {
+        camera.viewport = viewport;
+    }
+}
+
+fn stop_comparison(world: &mut World) {
+    world.remove_non_send_resource::<ComparisonSim>();
+    let leftovers = world
+        .query_filtered::<Entity, Or<(With<Mirror>, With<ComparisonCamera>)>>()
+        .iter(world)
+        .collect::<Vec<_>>();
+    for entity in leftovers {
+        world.despawn(entity);
+    }
+    let mut cameras = world.query_filtered::<&mut Camera, With<MainCamera>>();
+    for mut camera in cameras.iter_mut(world) {
+        set_viewport(&mut camera, None);
+    }
+}
+
+/// Steps the second sim once per frame and copies what it did over here.
+fn comparison_system(world: &mut World) {
+    let enabled = world.resource::<Comparison>().enabled;
+    let running = world.get_non_send_resource::<ComparisonSim>().is_some();
+    if !enabled {
+        if running {
+            stop_comparison(world);
+        }
+        return;
+    }
+    if !running {
+        // Always starts off the same as the left side
+        let factor_info = world.resource::<FactorInfo>().clone();
+        let features = world.resource::<Features>().clone();
+        world
+            .resource_mut::<Comparison>()
+            .copy_from(&factor_info, &features);
+        let app = create_sim(world);
+        world.insert_non_send_resource(ComparisonSim {
+            app,
+            mirrors: HashMap::default(),
+        });
+        world.resource_mut::<Comparison>().changed = false;
+        world
+            .spawn(Camera2dBundle {
+                camera: Camera {
+                    order: 1,
+                    ..default()
+                },
+                camera_2d: Camera2d {
+                    clear_color: ClearColorConfig::Custom(Color::BLACK),
+                },
+                ..default()
+            })
+            .insert(RenderLayers::layer(COMPARISON_LAYER))
+            .insert(ComparisonCamera);
+    }
+
+    let Ok(window) = world
+        .query_filtered::<&Window, With<PrimaryWindow>>()
+        .get_single(world)
+        .cloned()
+    else {
+        return;
+    };
+    let Ok((camera_transform, projection)) = world
+        .query_filtered::<(&Transform, &OrthographicProjection), With<MainCamera>>()
+        .get_single(world)
+        .map(|(transform, projection)| (*transform, projection.scale))
+    else {
+        return;
+    };
+
+    // Both halves look at the same spot
+    let mut main_cameras = world.query_filtered::<&mut Camera, With<MainCamera>>();
+    for mut camera in main_cameras.iter_mut(world) {
+        set_viewport(&mut camera, Some(half_viewport(&window, false)));
+    }
+    let mut comparison_cameras = world.query_filtered::<
+        (&mut Camera, &mut Transform, &mut OrthographicProjection),
+        With<ComparisonCamera>,
+    >();
+    for (mut camera, mut transform, mut comparison_projection) in comparison_cameras.iter_mut(world)
+    {
+        set_viewport(&mut camera, Some(half_viewport(&window, true)));
+        *transform = camera_transform;
+        comparison_projection.scale = projection;
+    }
+
+    let sim_state = world.resource::<State<SimState>>().0.clone();
+    let time_scale = *world.resource::<TimeScale>();
+    let deterministic_mode = *world.resource::<DeterministicMode>();
+    let world_bounds = *world.resource::<WorldBounds>();
+    let comparison = world.resource::<Comparison>();
+    let settings = comparison
+        .changed
+        .then(|| (comparison.factor_info.clone(), comparison.features.clone()));
+    let restart = comparison.restart;
+    if settings.is_some() || restart {
+        let mut comparison = world.resource_mut::<Comparison>();
+        comparison.changed = false;
+        comparison.restart = false;
+    }
+
+    let mut sim = world.remove_non_send_resource::<ComparisonSim>().unwrap();
+    {
+        let sim_world = &mut sim.app.world;
+        if let Some((factor_info, features)) = settings {
+            sim_world.insert_resource(factor_info);
+            sim_world.insert_resource(features);
+        }
+        if restart {
+            sim_world
+                .resource_mut::<Events<RestartEvent>>()
+                .send(RestartEvent);
+        }
+        if sim_world.resource::<State<SimState>>().0 != sim_state {
+            sim_world
+                .resource_mut::<NextState<SimState>>()
+                .set(sim_state);
+        }
+        if *sim_world.resource::<TimeScale>() != time_scale {
+            sim_world.insert_resource(time_scale);
+        }
+        if *sim_world.resource::<DeterministicMode>() != deterministic_mode {
+            sim_world.insert_resource(deterministic_mode);
+        }
+        if *sim_world.resource::<WorldBounds>() != world_bounds {
+            sim_world.insert_resource(world_bounds);
+        }
+        let mut sim_windows = sim_world.query_filtered::<&mut Window, With<PrimaryWindow>>();
+        for mut sim_window in sim_windows.iter_mut(sim_world) {
+            sim_window.resolution = window.resolution.clone();
+        }
+        // Nothing over there propagates transforms so the global one gets set too
+        let mut sim_cameras = sim_world.query_filtered::<(
+            &mut Transform,
+            &mut GlobalTransform,
+            &mut OrthographicProjection,
+        ), With<MainCamera>>();
+        for (mut transform, mut global_transform, mut sim_projection) in
+            sim_cameras.iter_mut(sim_world)
+        {
+            *transform = camera_transform;
+            *global_transform = GlobalTransform::from(camera_transform);
+            sim_projection.scale = projection;
+        }
+    }
+
+    sim.app.update();
+
+    // Mirror every creature over as a sprite only the right camera sees
+    let creatures = sim
+        .app
+        .world
+        .query_filtered::<(Entity, &Transform, &Sprite), With<CreatureType>>()
+        .iter(&sim.app.world)
+        .map(|(entity, transform, sprite)| (entity, *transform, sprite.clone()))
+        .collect::<Vec<_>>();
+    let mut alive = HashMap::default();
+    for (source, transform, sprite) in creatures {
+        let mirror = match sim.mirrors.get(&source) {
+            Some(&mirror) if world.get_entity(mirror).is_some() => {
+                let mut mirror_entity = world.entity_mut(mirror);
+                *mirror_entity.get_mut::<Transform>().unwrap() = transform;
+                *mirror_entity.get_mut::<Sprite>().unwrap() = sprite;
+                mirror
+            }
+            _ => world
+                .spawn(SpriteBundle {
+                    sprite,
+                    transform,
+                    ..default()
+                })
+                .insert(RenderLayers::layer(COMPARISON_LAYER))
+                .insert(Mirror)
+                .id(),
+        };
+        alive.insert(source, mirror);
+    }
+    for (source, mirror) in sim.mirrors.drain() {
+        if !alive.contains_key(&source) {
+            world.despawn(mirror);
+        }
+    }
+    sim.mirrors = alive;
+
+    world.insert_non_send_resource(sim);
+}
+
+#[derive(Default)]
+pub struct ComparisonPlugin;
+
+impl Plugin for ComparisonPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Comparison::default())
+            .add_system(comparison_system.in_base_set(CoreSet::PostUpdate));
+    }
+}
@@ -0,0 +1,183 @@
+// A running record of how each creature type is doing, for pulling into a
+// spreadsheet afterwards. Every `interval` seconds of sim time it writes down
+// the population and average energy of each type, along with how many showed
+// up and died since the last sample. Times are sim time so a paused or sped up
+// sim lines up with what actually happened in it.
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    files::{save_file, timestamp},
+    lifecycle::{BoidKilled, BoidSpawned, DeathCause},
+    CreatureType, Energy, FactorInfo, SimState, SimTime,
+};
+
+/// Past this many samples every other one gets folded into its neighbour
+const MAX_SAMPLES: usize = 10_000;
+
+#[derive(Debug, Clone, Default)]
+pub struct TypeSample {
+    pub population: usize,
+    pub average_energy: f32,
+    pub births: usize,
+    pub deaths: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub time: f32,
+    pub types: Vec<TypeSample>,
+}
+
+/// Population and energy over time, sampled every `interval` seconds of sim
+/// time. Long runs get thinned out rather than growing forever, which doubles
+/// the interval each time.
+#[derive(Debug, Resource)]
+pub struct DataLog {
+    pub interval: f32,
+    pub samples: Vec<Sample>,
+    pub status: Option<String>,
+    elapsed: f32,
+    since_last_sample: f32,
+    births: HashMap<CreatureType, usize>,
+    deaths: HashMap<CreatureType, usize>,
+}
+
+impl Default for DataLog {
+    fn default() -> Self {
+        DataLog {
+            interval: 1.0,
+            samples: vec![],
+            status: None,
+            elapsed: 0.0,
+            since_last_sample: 0.0,
+            births: HashMap::default(),
+            deaths: HashMap::default(),
+        }
+    }
+}
+
+impl DataLog {
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.births.clear();
+        self.deaths.clear();
+        self.elapsed = 0.0;
+        self.since_last_sample = 0.0;
+    }
+
+    /// Merges each pair of samples into the later one so births and deaths
+    /// still add up to the same totals
+    fn decimate(&mut self) {
+        let mut merged = Vec::with_capacity(self.samples.len() / 2 + 1);
+        let mut samples = std::mem::take(&mut self.samples).into_iter();
+        while let Some(first) = samples.next() {
+            let Some(mut second) = samples.next() else {
+                merged.push(first);
+                break;
+            };
+            for (type_sample, earlier) in second.types.iter_mut().zip(first.types) {
+                type_sample.births += earlier.births;
+                type_sample.deaths += earlier.deaths;
+            }
+            merged.push(second);
+        }
+        self.samples = merged;
+        self.interval *= 2.0;
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = "time,type,population,average_energy,births,deaths\n".to_string();
+        for sample in self.samples.iter() {
+            for (index, type_sample) in sample.types.iter().enumerate() {
+                csv.push_str(&format!(
+                    "{:.2},{},{},{:.3},{},{}\n",
+                    sample.time,
+                    index,
+                    type_sample.population,
+                    type_sample.average_energy,
+                    type_sample.births,
+                    type_sample.deaths,
+                ));
+            }
+        }
+        csv
+    }
+
+    pub fn export_csv(&mut self) {
+        let file_name = format!("rusty-boids-data-{}.csv", timestamp());
+        self.status = Some(
+            match save_file(&file_name, self.to_csv().as_bytes(), "text/csv") {
+                Ok(path) => format!("Exported {} samples to {}", self.samples.len(), path),
+                Err(error) => format!("Couldn't export CSV: {}", error),
+            },
+        );
+    }
+}
+
+/// Same events the stats go off of, so erasing creatures or clearing them out
+/// for a scenario doesn't count as them dying
+fn track_lifecycle_system(
+    mut data_log: ResMut<DataLog>,
+    mut spawned_reader: EventReader<BoidSpawned>,
+    mut killed_reader: EventReader<BoidKilled>,
+) {
+    for spawned in spawned_reader.iter() {
+        *data_log.births.entry(spawned.creature_type).or_default() += 1;
+    }
+    for killed in killed_reader.iter() {
+        if killed.cause != DeathCause::Despawned {
+            *data_log.deaths.entry(killed.creature_type).or_default() += 1;
+        }
+    }
+}
+
+fn sample_system(
+    sim_time: SimTime,
+    mut data_log: ResMut<DataLog>,
+    factor_info: Res<FactorInfo>,
+    creature_query: Query<(&CreatureType, &Energy)>,
+) {
+    let delta_seconds = sim_time.delta_seconds();
+    data_log.elapsed += delta_seconds;
+    data_log.since_last_sample += delta_seconds;
+    if data_log.since_last_sample < data_log.interval {
+        return;
+    }
+    data_log.since_last_sample = 0.0;
+
+    let mut types = vec![TypeSample::default(); factor_info.factors.len()];
+    for (creature_type, energy) in creature_query.iter() {
+        if let Some(type_sample) = types.get_mut(creature_type.0) {
+            type_sample.population += 1;
+            type_sample.average_energy += energy.0;
+        }
+    }
+
+    let births = std::mem::take(&mut data_log.births);
+    let deaths = std::mem::take(&mut data_log.deaths);
+    for (index, type_sample) in types.iter_mut().enumerate() {
+        if type_sample.population > 0 {
+            type_sample.average_energy /= type_sample.population as f32;
+        }
+        type_sample.births = births.get(&CreatureType(index)).copied().unwrap_or(0);
+        type_sample.deaths = deaths.get(&CreatureType(index)).copied().unwrap_or(0);
+    }
+
+    let time = data_log.elapsed;
+    data_log.samples.push(Sample { time, types });
+    if data_log.samples.len() > MAX_SAMPLES {
+        data_log.decimate();
+    }
+}
+
+#[derive(Default)]
+pub struct DataLogPlugin;
+
+impl Plugin for DataLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DataLog::default())
+            .add_system(track_lifecycle_system)
+            .add_system(sample_system.in_set(OnUpdate(SimState::Running)));
+    }
+}
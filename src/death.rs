@@ -0,0 +1,172 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{CreatureType, SimState, SimTime};
+
+const BURST_PARTICLES: usize = 6;
+const BURST_SPEED: f32 = 40.0;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Reflect, FromReflect, Serialize, Deserialize,
+)]
+pub enum DeathAnimation {
+    None,
+    #[default]
+    Shrink,
+    Fade,
+    Burst,
+}
+
+impl DeathAnimation {
+    pub const ALL: [DeathAnimation; 4] = [
+        DeathAnimation::None,
+        DeathAnimation::Shrink,
+        DeathAnimation::Fade,
+        DeathAnimation::Burst,
+    ];
+}
+
+impl std::fmt::Display for DeathAnimation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Turn these off when nobody is watching (headless runs, benchmarks) and
+/// creatures just pop out of existence like before.
+#[derive(Debug, Clone, Resource)]
+pub struct DeathAnimationSettings {
+    pub enabled: bool,
+    pub duration: f32,
+}
+
+impl Default for DeathAnimationSettings {
+    fn default() -> Self {
+        DeathAnimationSettings {
+            enabled: true,
+            duration: 0.4,
+        }
+    }
+}
+
+/// A creature on its way out. It's no longer a `CreatureType` so nothing else
+/// in the sim can see it while the animation plays.
+#[derive(Debug, Clone, Component)]
+pub struct Dying {
+    pub animation: DeathAnimation,
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+#[derive(Debug, Clone, Component)]
+struct Drift(Vec2);
+
+/// Use this instead of despawning a creature directly so it gets to play its animation.
+pub fn kill_creature(
+    commands: &mut Commands,
+    entity: Entity,
+    animation: DeathAnimation,
+    settings: &DeathAnimationSettings,
+) {
+    if !settings.enabled || animation == DeathAnimation::None {
+        commands.entity(entity).despawn();
+        return;
+    }
+
+    let duration = settings.duration;
+    // The creature could already be gone by the time this runs (killed twice
+    // in one frame, despawned by the user) so it has to check first.
+    commands.add(move |world: &mut World| {
+        let Some(mut creature) = world.get_entity_mut(entity) else {
+            return;
+        };
+        if creature.contains::<Dying>() {
+            return;
+        }
+
+        creature.remove::<CreatureType>();
+        creature.insert(Dying {
+            animation,
+            elapsed: 0.0,
+            duration,
+        });
+
+        if animation != DeathAnimation::Burst {
+            return;
+        }
+
+        let (Some(transform), Some(sprite)) = (
+            creature.get::<Transform>().copied(),
+            creature.get::<Sprite>().cloned(),
+        ) else {
+            return;
+        };
+        creature.despawn();
+
+        let size = sprite.custom_size.unwrap_or(Vec2::ONE) / 2.0;
+        for index in 0..BURST_PARTICLES {
+            let angle = index as f32 / BURST_PARTICLES as f32 * std::f32::consts::TAU;
+            world
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: sprite.color,
+                        custom_size: Some(size),
+                        ..Sprite::default()
+                    },
+                    transform: Transform::from_translation(transform.translation),
+                    ..SpriteBundle::default()
+                })
+                .insert(Drift(Vec2::from_angle(angle) * BURST_SPEED))
+                .insert(Dying {
+                    animation: DeathAnimation::Fade,
+                    elapsed: 0.0,
+                    duration,
+                });
+        }
+    });
+}
+
+fn dying_system(
+    mut commands: Commands,
+    sim_time: SimTime,
+    mut dying_query: Query<(
+        Entity,
+        &mut Dying,
+        &mut Sprite,
+        &mut Transform,
+        Option<&Drift>,
+    )>,
+) {
+    let delta_seconds = sim_time.delta_seconds();
+    for (entity, mut dying, mut sprite, mut transform, drift) in dying_query.iter_mut() {
+        dying.elapsed += delta_seconds;
+        let progress = (dying.elapsed / dying.duration).min(1.0);
+        if progress >= 1.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        if let Some(Drift(velocity)) = drift {
+            let position = transform.translation.xy() + *velocity * delta_seconds;
+            transform.translation = position.extend(transform.translation.z);
+        }
+
+        match dying.animation {
+            DeathAnimation::Shrink => transform.scale = Vec3::splat(1.0 - progress),
+            DeathAnimation::Fade => {
+                sprite.color.set_a(1.0 - progress);
+            }
+            DeathAnimation::None | DeathAnimation::Burst => {}
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DeathPlugin;
+
+impl Plugin for DeathPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DeathAnimationSettings::default())
+            .add_system(dying_system.in_set(OnUpdate(SimState::Running)));
+    }
+}
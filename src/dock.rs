@@ -0,0 +1,206 @@
+// Statistics, Scenarios, Settings, and Edit Factors share one dock on the
+// right side of the screen instead of each being pinned to its own corner.
+// Their tabs can be dragged onto each other to stack them or to the edge of
+// another to split it, the dock's edge drags to resize it, and the arrow at
+// the top folds it away to a strip. The layout is saved with the settings.
+
+use bevy::prelude::*;
+use bevy_egui::egui::{self, containers::panel::PanelState};
+use egui_dock::{DockArea, Node, NodeIndex, Style, TabViewer, Tree};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const MIN_DOCK_WIDTH: f32 = 250.0;
+/// Just enough for the button to bring it back
+const COLLAPSED_WIDTH: f32 = 28.0;
+
+/// Stands in for where the tree's parts were last drawn when saving
+const NOWHERE: egui::Rect = egui::Rect::from_min_max(egui::Pos2::ZERO, egui::Pos2::ZERO);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Panel {
+    Statistics,
+    Scenarios,
+    Settings,
+    EditFactors,
+}
+
+impl Panel {
+    pub const ALL: [Panel; 4] = [
+        Panel::Statistics,
+        Panel::Scenarios,
+        Panel::Settings,
+        Panel::EditFactors,
+    ];
+}
+
+impl std::fmt::Display for Panel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Panel::EditFactors => write!(f, "Edit Factors"),
+            panel => write!(f, "{:?}", panel),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DockLayout {
+    #[serde(serialize_with = "serialize_tree")]
+    pub tree: Tree<Panel>,
+    pub width: f32,
+    pub collapsed: bool,
+}
+
+impl Default for DockLayout {
+    fn default() -> Self {
+        let mut tree = Tree::new(vec![Panel::Statistics, Panel::Scenarios]);
+        tree.split_below(
+            NodeIndex::root(),
+            0.4,
+            vec![Panel::Settings, Panel::EditFactors],
+        );
+        DockLayout {
+            tree,
+            width: 400.0,
+            collapsed: false,
+        }
+    }
+}
+
+impl DockLayout {
+    /// Draws the dock, with `viewer` filling in whichever tabs are showing.
+    /// Only call it before any windows that anchor themselves to the edges
+    /// so they line up against the dock instead of under it.
+    pub fn show(&mut self, ctx: &egui::Context, viewer: &mut impl TabViewer<Tab = Panel>) {
+        // Saved before a panel existed, or every tab of it dragged away somehow
+        for panel in Panel::ALL {
+            if self.tree.find_tab(&panel).is_none() {
+                self.tree.push_to_first_leaf(panel);
+            }
+        }
+
+        if self.collapsed {
+            egui::SidePanel::right("dock_collapsed")
+                .resizable(false)
+                .exact_width(COLLAPSED_WIDTH)
+                .show(ctx, |ui| {
+                    if ui.button("⏴").on_hover_text("Show panels").clicked() {
+                        self.collapsed = false;
+                    }
+                });
+            return;
+        }
+        let id = egui::Id::new("dock");
+        let mut side_panel = egui::SidePanel::right(id)
+            .default_width(self.width)
+            .width_range(MIN_DOCK_WIDTH..=f32::INFINITY);
+        // egui remembers the width the edge was last dragged to, so a width
+        // set from outside (like by resetting the settings) has to be forced
+        let drawn_width = PanelState::load(ctx, id).map(|state| state.rect.width());
+        if drawn_width.is_some_and(|width| (width - self.width).abs() > 0.5) {
+            side_panel = side_panel.exact_width(self.width);
+        }
+        let response = side_panel.show(ctx, |ui| {
+            if ui.small_button("⏵").on_hover_text("Hide panels").clicked() {
+                self.collapsed = true;
+            }
+            DockArea::new(&mut self.tree)
+                .id(egui::Id::new("dock_area"))
+                .style(Style::from_egui(ui.style()))
+                .show_close_buttons(false)
+                .tab_context_menus(false)
+                .show_inside(ui, viewer);
+        });
+        self.width = response.response.rect.width();
+    }
+
+    /// Whether saving this over `other` would change anything. The tree can't
+    /// be compared directly so it's compared by what would get saved.
+    pub fn differs_from(&self, other: &DockLayout) -> bool {
+        self.collapsed != other.collapsed
+            || (self.width - other.width).abs() > 0.5
+            || serde_json::to_value(without_rects(&self.tree)).ok()
+                != serde_json::to_value(without_rects(&other.tree)).ok()
+    }
+}
+
+/// The tree minus where everything was drawn. The dock works all of that out
+/// again every frame, and it's infinite (which JSON can't hold) for anything
+/// that hasn't been drawn yet.
+fn without_rects(tree: &Tree<Panel>) -> Tree<Panel> {
+    let mut tree = tree.clone();
+    for node in tree.iter_mut() {
+        match node {
+            Node::Leaf { rect, viewport, .. } => {
+                *rect = NOWHERE;
+                *viewport = NOWHERE;
+            }
+            Node::Vertical { rect, .. } | Node::Horizontal { rect, .. } => *rect = NOWHERE,
+            Node::Empty => {}
+        }
+    }
+    tree
+}
+
+fn serialize_tree<S: Serializer>(tree: &Tree<Panel>, serializer: S) -> Result<S::Ok, S::Error> {
+    without_rects(tree).serialize(serializer)
+}
+
+/// For reading a saved layout. One that doesn't load, like from a different
+/// version of the dock, goes back to the default instead of taking the rest
+/// of the saved settings down with it.
+pub fn deserialize_or_default<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DockLayout, D::Error> {
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(DockLayout::deserialize(value).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Saved {
+        #[serde(deserialize_with = "deserialize_or_default")]
+        dock: DockLayout,
+    }
+
+    #[test]
+    fn default_layout_has_every_panel() {
+        let layout = DockLayout::default();
+        for panel in Panel::ALL {
+            assert!(
+                layout.tree.find_tab(&panel).is_some(),
+                "{} is missing",
+                panel
+            );
+        }
+    }
+
+    #[test]
+    fn saved_layout_comes_back_the_same() {
+        let mut tree = Tree::new(vec![Panel::Settings]);
+        tree.split_right(
+            NodeIndex::root(),
+            0.3,
+            vec![Panel::EditFactors, Panel::Statistics, Panel::Scenarios],
+        );
+        let layout = DockLayout {
+            tree,
+            width: 321.0,
+            collapsed: true,
+        };
+
+        let json = format!(r#"{{"dock": {}}}"#, serde_json::to_string(&layout).unwrap());
+        let saved: Saved = serde_json::from_str(&json).unwrap();
+        assert!(!saved.dock.differs_from(&layout));
+    }
+
+    #[test]
+    fn broken_layout_goes_back_to_default() {
+        let saved: Saved =
+            serde_json::from_str(r#"{"dock": {"tree": 5, "width": 123.0}}"#).unwrap();
+        assert!(!saved.dock.differs_from(&DockLayout::default()));
+    }
+}
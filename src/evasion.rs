@@ -0,0 +1,56 @@
+// Where each creature is in its zig-zag when it's running from something close
+// (see `Factors::flee_direction`). Everyone starts at a random point so a
+// school doesn't swerve back and forth all in sync.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{CreatureType, FactorInfo, SimRng, SimState, SimTime};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Component, Reflect)]
+#[reflect(Component)]
+pub struct Evasion {
+    /// Radians, goes around once per zig and zag
+    pub phase: f32,
+}
+
+fn new_evasion_system(
+    mut commands: Commands,
+    mut rng: ResMut<SimRng>,
+    creature_query: Query<Entity, (Added<CreatureType>, Without<Evasion>)>,
+) {
+    for entity in creature_query.iter() {
+        let phase = rng.gen_range(0.0..TAU);
+        commands.entity(entity).insert(Evasion { phase });
+    }
+}
+
+fn evasion_system(
+    sim_time: SimTime,
+    factor_info: Res<FactorInfo>,
+    mut creature_query: Query<(&CreatureType, &mut Evasion)>,
+) {
+    let delta_seconds = sim_time.delta_seconds();
+    for (creature_type, mut evasion) in creature_query.iter_mut() {
+        let Some(factors) = factor_info.factors.get(creature_type) else {
+            continue;
+        };
+        if factors.zigzag_amplitude <= 0.0 {
+            continue;
+        }
+        evasion.phase = (evasion.phase + TAU * factors.zigzag_frequency * delta_seconds) % TAU;
+    }
+}
+
+#[derive(Default)]
+pub struct EvasionPlugin;
+
+impl Plugin for EvasionPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Evasion>()
+            .add_system(new_evasion_system.in_base_set(CoreSet::PostUpdate))
+            .add_system(evasion_system.in_set(OnUpdate(SimState::Running)));
+    }
+}
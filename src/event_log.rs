@@ -0,0 +1,260 @@
+// A feed of the interesting things that happen to creatures, for the Event Log
+// window. Spawns and erasing come in big batches so each frame's worth of them
+// turns into one line instead of hundreds.
+
+use std::collections::VecDeque;
+
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+use serde::Serialize;
+
+use crate::{
+    flocks::FlocksMerged,
+    lifecycle::{BoidKilled, BoidSpawned, DeathCause},
+    tags::Tagged,
+    CreatureType, FactorInfo,
+};
+
+/// Older lines than this many get dropped
+const MAX_ENTRIES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum LogKind {
+    Eaten,
+    Starved,
+    Spawned,
+    Erased,
+    Extinct,
+    FlocksMerged,
+    /// Anything happening to or done by a named creature
+    Notable,
+}
+
+impl LogKind {
+    pub const ALL: [LogKind; 7] = [
+        LogKind::Eaten,
+        LogKind::Starved,
+        LogKind::Spawned,
+        LogKind::Erased,
+        LogKind::Extinct,
+        LogKind::FlocksMerged,
+        LogKind::Notable,
+    ];
+}
+
+impl std::fmt::Display for LogKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LogKind::FlocksMerged => write!(f, "Flocks Merged"),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    /// Seconds since the app started
+    pub time: f32,
+    pub kind: LogKind,
+    /// Every type the line is about, for filtering
+    pub creature_types: Vec<CreatureType>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct EventLog {
+    pub entries: VecDeque<LogEntry>,
+    /// Kinds that show up in the window. Everything gets logged either way.
+    pub shown_kinds: Vec<LogKind>,
+    /// Only lines about this type, or everything if there isn't one
+    pub type_filter: Option<CreatureType>,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        EventLog {
+            entries: VecDeque::new(),
+            // Spawns drown out everything else when they're on
+            shown_kinds: vec![
+                LogKind::Eaten,
+                LogKind::Starved,
+                LogKind::Extinct,
+                LogKind::FlocksMerged,
+                LogKind::Notable,
+            ],
+            type_filter: None,
+        }
+    }
+}
+
+impl EventLog {
+    pub fn is_shown(&self, entry: &LogEntry) -> bool {
+        self.shown_kinds.contains(&entry.kind)
+            && self
+                .type_filter
+                .is_none_or(|filter| entry.creature_types.contains(&filter))
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn push(&mut self, time: f32, kind: LogKind, creature_types: Vec<CreatureType>, text: String) {
+        self.entries.push_back(LogEntry {
+            time,
+            kind,
+            creature_types,
+            text,
+        });
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+}
+
+/// Everything that gets written down
+#[derive(SystemParam)]
+struct LogEvents<'w, 's> {
+    spawned_reader: EventReader<'w, 's, BoidSpawned>,
+    killed_reader: EventReader<'w, 's, BoidKilled>,
+    merged_reader: EventReader<'w, 's, FlocksMerged>,
+}
+
+/// What things looked like last frame
+#[derive(Default)]
+struct LastFrame {
+    populations: HashMap<CreatureType, usize>,
+    tag_names: HashMap<Entity, String>,
+}
+
+fn event_log_system(
+    time: Res<Time>,
+    factor_info: Res<FactorInfo>,
+    mut event_log: ResMut<EventLog>,
+    events: LogEvents,
+    creature_query: Query<&CreatureType>,
+    tagged_query: Query<(Entity, &Tagged)>,
+    mut last_frame: Local<LastFrame>,
+) {
+    let LogEvents {
+        mut spawned_reader,
+        mut killed_reader,
+        mut merged_reader,
+    } = events;
+    let LastFrame {
+        populations,
+        tag_names,
+    } = &mut *last_frame;
+    let now = time.elapsed_seconds();
+    let name_of = |creature_type: CreatureType| factor_info.name_of(creature_type);
+    // Dead creatures are usually gone by the time this runs so their names are
+    // remembered from last frame
+    tag_names.extend(
+        tagged_query
+            .iter()
+            .map(|(entity, tagged)| (entity, tagged.0.clone())),
+    );
+    let tag_of = |entity: Entity| tag_names.get(&entity);
+
+    let mut spawned = HashMap::<CreatureType, usize>::default();
+    for event in spawned_reader.iter() {
+        *spawned.entry(event.creature_type).or_default() += 1;
+    }
+    for (creature_type, count) in spawned {
+        let text = format!("Spawned {} {}", count, name_of(creature_type));
+        event_log.push(now, LogKind::Spawned, vec![creature_type], text);
+    }
+
+    let mut erased = HashMap::<CreatureType, usize>::default();
+    for killed in killed_reader.iter() {
+        let killed_type = killed.creature_type;
+        match killed.cause {
+            DeathCause::Eaten { by } => {
+                // The killer is still around when this is read
+                let mut creature_types = vec![killed_type];
+                let killer = match creature_query.get(by) {
+                    Ok(&killer_type) => {
+                        creature_types.push(killer_type);
+                        name_of(killer_type)
+                    }
+                    Err(_) => "Something".to_string(),
+                };
+                let text = format!("{} ate {}", killer, name_of(killed_type));
+                event_log.push(now, LogKind::Eaten, creature_types.clone(), text);
+                if let Some(name) = tag_of(killed.entity) {
+                    let text = format!("{} was eaten by {}", name, tag_of(by).unwrap_or(&killer));
+                    event_log.push(now, LogKind::Notable, creature_types.clone(), text);
+                }
+                if let Some(name) = tag_of(by) {
+                    let text = format!("{} ate {}", name, name_of(killed_type));
+                    event_log.push(now, LogKind::Notable, creature_types, text);
+                }
+            }
+            DeathCause::Starved => {
+                event_log.push(
+                    now,
+                    LogKind::Starved,
+                    vec![killed_type],
+                    format!("{} starved", name_of(killed_type)),
+                );
+                if let Some(name) = tag_of(killed.entity) {
+                    let text = format!("{} starved", name);
+                    event_log.push(now, LogKind::Notable, vec![killed_type], text);
+                }
+            }
+            DeathCause::Despawned => {
+                *erased.entry(killed_type).or_default() += 1;
+                if let Some(name) = tag_of(killed.entity) {
+                    let text = format!("{} was erased", name);
+                    event_log.push(now, LogKind::Notable, vec![killed_type], text);
+                }
+            }
+        }
+    }
+    for (creature_type, count) in erased {
+        let text = format!("Erased {} {}", count, name_of(creature_type));
+        event_log.push(now, LogKind::Erased, vec![creature_type], text);
+    }
+
+    for merged in merged_reader.iter() {
+        let text = format!(
+            "{} flocks of {} joined into {}",
+            merged.merged,
+            name_of(merged.creature_type),
+            merged.size
+        );
+        event_log.push(now, LogKind::FlocksMerged, vec![merged.creature_type], text);
+    }
+
+    // Going from some to none is going extinct
+    let mut new_populations = HashMap::<CreatureType, usize>::default();
+    for &creature_type in creature_query.iter() {
+        *new_populations.entry(creature_type).or_default() += 1;
+    }
+    for (&creature_type, &count) in populations.iter() {
+        if count > 0
+            && !new_populations.contains_key(&creature_type)
+            && factor_info.factors.contains_key(&creature_type)
+        {
+            let text = format!("{} went extinct", name_of(creature_type));
+            event_log.push(now, LogKind::Extinct, vec![creature_type], text);
+        }
+    }
+    *populations = new_populations;
+
+    tag_names.clear();
+    tag_names.extend(
+        tagged_query
+            .iter()
+            .map(|(entity, tagged)| (entity, tagged.0.clone())),
+    );
+}
+
+#[derive(Default)]
+pub struct EventLogPlugin;
+
+impl Plugin for EventLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EventLog::default())
+            .add_system(event_log_system.in_base_set(CoreSet::PostUpdate));
+    }
+}
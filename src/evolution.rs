@@ -0,0 +1,138 @@
+// Keeps a history of each type's average trait multipliers (see `Variation`)
+// and how spread out they are, for the Evolution window. Nothing gets passed
+// down yet but predators still pick off the slow ones first, so a prey type's
+// average speed creeps up over time as the survivors are the fast ones.
+
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{variation::Variation, CreatureType, RestartEvent, SimState, SimTime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TraitKind {
+    Speed,
+    Vision,
+    Size,
+}
+
+impl TraitKind {
+    pub const ALL: [TraitKind; 3] = [TraitKind::Speed, TraitKind::Vision, TraitKind::Size];
+
+    fn of(&self, variation: &Variation) -> f32 {
+        match self {
+            TraitKind::Speed => variation.speed,
+            TraitKind::Vision => variation.vision,
+            TraitKind::Size => variation.size,
+        }
+    }
+}
+
+impl std::fmt::Display for TraitKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TraitSample {
+    /// Sim seconds since the history started
+    pub time: f32,
+    pub means: [f32; TraitKind::ALL.len()],
+    pub variances: [f32; TraitKind::ALL.len()],
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct EvolutionHistory {
+    pub types: HashMap<CreatureType, VecDeque<TraitSample>>,
+    /// Sim seconds between samples
+    pub interval: f32,
+    /// Oldest samples get dropped past this many per type
+    pub capacity: usize,
+    elapsed: f32,
+    since_sample: f32,
+}
+
+impl Default for EvolutionHistory {
+    fn default() -> Self {
+        EvolutionHistory {
+            types: HashMap::default(),
+            interval: 1.0,
+            capacity: 600,
+            elapsed: 0.0,
+            since_sample: 0.0,
+        }
+    }
+}
+
+impl EvolutionHistory {
+    pub fn clear(&mut self) {
+        self.types.clear();
+        self.elapsed = 0.0;
+        self.since_sample = 0.0;
+    }
+
+    pub fn latest(&self, creature_type: &CreatureType) -> Option<&TraitSample> {
+        self.types.get(creature_type)?.back()
+    }
+}
+
+fn record_evolution_system(
+    sim_time: SimTime,
+    mut history: ResMut<EvolutionHistory>,
+    mut restart_events: EventReader<RestartEvent>,
+    creature_query: Query<(&CreatureType, &Variation)>,
+) {
+    if restart_events.iter().count() > 0 {
+        history.clear();
+    }
+    let delta_seconds = sim_time.delta_seconds();
+    history.elapsed += delta_seconds;
+    history.since_sample += delta_seconds;
+    if history.since_sample < history.interval {
+        return;
+    }
+    history.since_sample = 0.0;
+
+    // Sums and sums of squares to get the variance in one pass
+    let mut sums = HashMap::<CreatureType, ([f32; 3], [f32; 3], usize)>::default();
+    for (creature_type, variation) in creature_query.iter() {
+        let (sum, squares, count) = sums.entry(*creature_type).or_default();
+        for (index, kind) in TraitKind::ALL.iter().enumerate() {
+            let value = kind.of(variation);
+            sum[index] += value;
+            squares[index] += value * value;
+        }
+        *count += 1;
+    }
+
+    let (time, capacity) = (history.elapsed, history.capacity);
+    for (creature_type, (sum, squares, count)) in sums {
+        let mut sample = TraitSample {
+            time,
+            count,
+            ..default()
+        };
+        for index in 0..TraitKind::ALL.len() {
+            let mean = sum[index] / count as f32;
+            sample.means[index] = mean;
+            sample.variances[index] = (squares[index] / count as f32 - mean * mean).max(0.0);
+        }
+        let samples = history.types.entry(creature_type).or_default();
+        samples.push_back(sample);
+        while samples.len() > capacity {
+            samples.pop_front();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct EvolutionPlugin;
+
+impl Plugin for EvolutionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EvolutionHistory>()
+            .add_system(record_evolution_system.in_set(OnUpdate(SimState::Running)));
+    }
+}
@@ -0,0 +1,341 @@
+// Long exposure trails, like light painting. Creatures also get drawn by a
+// second camera into a texture that's never cleared, just faded a little every
+// frame, so wherever they've been glows for a while. Unlike the sprite trails
+// this costs the same with a hundred creatures as with a hundred thousand.
+//
+// The fading is done by drawing last frame's texture into this frame's one
+// (there are two that take turns) through a shader that dims it. That quad goes
+// where the camera was last frame so the streaks stay put when panning and
+// zooming. The texture's then shown behind the creatures with its brightness
+// as its opacity so it adds onto whatever's under it.
+
+use bevy::{
+    core_pipeline::{clear_color::ClearColorConfig, tonemapping::Tonemapping},
+    ecs::system::SystemParam,
+    math::Vec3Swizzles,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            AsBindGroup, Extent3d, ShaderRef, TextureDescriptor, TextureDimension, TextureFormat,
+            TextureUsages,
+        },
+        view::RenderLayers,
+    },
+    sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle},
+    window::PrimaryWindow,
+};
+
+use crate::{CreatureType, MainCamera};
+
+/// Comparison mode already has layer 1
+const EXPOSURE_LAYER: u8 = 2;
+/// Behind every creature but still in front of the camera's near plane
+const EXPOSURE_DEPTH: f32 = -0.05;
+
+#[derive(Debug, Clone, Resource)]
+pub struct LongExposure {
+    pub enabled: bool,
+    /// Seconds for a streak to fade to half as bright
+    pub half_life: f32,
+}
+
+impl Default for LongExposure {
+    fn default() -> Self {
+        LongExposure {
+            enabled: false,
+            half_life: 1.0,
+        }
+    }
+}
+
+/// Draws `texture` with its colors multiplied by `keep` and its brightness as
+/// its opacity. Used both for fading one frame into the next (`keep` under 1)
+/// and for showing the result (`keep` at 1).
+#[derive(Debug, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "6f1a0c8e-3b7d-4c52-9a1e-2d8f5b4c7e90"]
+pub struct ExposureMaterial {
+    #[uniform(0)]
+    keep: f32,
+    #[texture(1)]
+    #[sampler(2)]
+    texture: Handle<Image>,
+}
+
+impl Material2d for ExposureMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/long_exposure.wgsl".into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+struct ExposureCamera;
+
+/// Everything that's around while the exposure is on
+#[derive(Debug, Resource)]
+struct ExposureParts {
+    images: [Handle<Image>; 2],
+    /// Which image is being drawn into this frame
+    current: usize,
+    camera: Entity,
+    fade: Entity,
+    fade_material: Handle<ExposureMaterial>,
+    display: Entity,
+    display_material: Handle<ExposureMaterial>,
+    /// Where the camera was looking last frame, as center and size
+    last_view: Option<(Vec2, Vec2)>,
+}
+
+fn exposure_image(width: u32, height: u32) -> Image {
+    let size = Extent3d {
+        width: width.max(1),
+        height: height.max(1),
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("long_exposure"),
+            size,
+            dimension: TextureDimension::D2,
+            // Plenty of precision so dim streaks keep fading instead of
+            // getting stuck a step above black
+            format: TextureFormat::Rgba16Float,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    image
+}
+
+#[derive(SystemParam)]
+struct ExposureAssets<'w> {
+    images: ResMut<'w, Assets<Image>>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<ExposureMaterial>>,
+}
+
+/// Sets everything up when the exposure gets turned on and takes it all down
+/// again when it's turned off. Creatures get drawn by the exposure camera as
+/// well as the main one by being on both layers.
+fn toggle_exposure_system(
+    mut commands: Commands,
+    exposure: Res<LongExposure>,
+    parts: Option<Res<ExposureParts>>,
+    assets: ExposureAssets,
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    unlayered_query: Query<Entity, (With<CreatureType>, Without<RenderLayers>)>,
+    layered_query: Query<Entity, (With<CreatureType>, With<RenderLayers>)>,
+) {
+    let ExposureAssets {
+        mut images,
+        mut meshes,
+        mut materials,
+    } = assets;
+    if !exposure.enabled {
+        if let Some(parts) = parts {
+            for entity in [parts.camera, parts.fade, parts.display] {
+                commands.entity(entity).despawn();
+            }
+            for image in parts.images.iter() {
+                images.remove(image);
+            }
+            commands.remove_resource::<ExposureParts>();
+            for entity in layered_query.iter() {
+                commands.entity(entity).remove::<RenderLayers>();
+            }
+        }
+        return;
+    }
+
+    for entity in unlayered_query.iter() {
+        commands
+            .entity(entity)
+            .insert(RenderLayers::from_layers(&[0, EXPOSURE_LAYER]));
+    }
+    if parts.is_some() {
+        return;
+    }
+    let Ok(window) = primary_query.get_single() else {
+        return;
+    };
+
+    let (width, height) = (window.physical_width(), window.physical_height());
+    let images = [
+        images.add(exposure_image(width, height)),
+        images.add(exposure_image(width, height)),
+    ];
+    let quad = meshes.add(shape::Quad::new(Vec2::ONE).into());
+    let fade_material = materials.add(ExposureMaterial {
+        keep: 1.0,
+        texture: images[1].clone(),
+    });
+    let display_material = materials.add(ExposureMaterial {
+        keep: 1.0,
+        texture: images[0].clone(),
+    });
+
+    let camera = commands
+        .spawn(Camera2dBundle {
+            camera: Camera {
+                // Before the main camera so what it draws is ready to be shown
+                order: -1,
+                target: RenderTarget::Image(images[0].clone()),
+                hdr: true,
+                ..default()
+            },
+            camera_2d: Camera2d {
+                clear_color: ClearColorConfig::Custom(Color::NONE),
+            },
+            tonemapping: Tonemapping::None,
+            ..default()
+        })
+        .insert(RenderLayers::layer(EXPOSURE_LAYER))
+        .insert(ExposureCamera)
+        .id();
+    let fade = commands
+        .spawn(MaterialMesh2dBundle {
+            mesh: quad.clone().into(),
+            material: fade_material.clone(),
+            transform: Transform::from_xyz(0.0, 0.0, EXPOSURE_DEPTH),
+            ..default()
+        })
+        .insert(RenderLayers::layer(EXPOSURE_LAYER))
+        .id();
+    let display = commands
+        .spawn(MaterialMesh2dBundle {
+            mesh: quad.into(),
+            material: display_material.clone(),
+            transform: Transform::from_xyz(0.0, 0.0, EXPOSURE_DEPTH),
+            ..default()
+        })
+        .id();
+
+    commands.insert_resource(ExposureParts {
+        images,
+        current: 0,
+        camera,
+        fade,
+        fade_material,
+        display,
+        display_material,
+        last_view: None,
+    });
+}
+
+type ExposureCameraParts<'a> = (
+    &'a mut Camera,
+    &'a mut Transform,
+    &'a mut OrthographicProjection,
+);
+
+/// The main camera, and the exposure camera and quads that follow it around
+#[derive(SystemParam)]
+struct ExposureViews<'w, 's> {
+    main_camera_query:
+        Query<'w, 's, (&'static Transform, &'static OrthographicProjection), With<MainCamera>>,
+    exposure_camera_query:
+        Query<'w, 's, ExposureCameraParts<'static>, (With<ExposureCamera>, Without<MainCamera>)>,
+    quad_query: Query<'w, 's, &'static mut Transform, (Without<Camera>, Without<MainCamera>)>,
+}
+
+/// Swaps which image gets drawn into, and lines the exposure camera and both
+/// quads up with the main camera
+fn expose_system(
+    time: Res<Time>,
+    exposure: Res<LongExposure>,
+    parts: Option<ResMut<ExposureParts>>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<ExposureMaterial>>,
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    views: ExposureViews,
+) {
+    let ExposureViews {
+        main_camera_query,
+        mut exposure_camera_query,
+        mut quad_query,
+    } = views;
+    let Some(mut parts) = parts else {
+        return;
+    };
+    let (Ok(window), Ok((main_transform, main_projection))) =
+        (primary_query.get_single(), main_camera_query.get_single())
+    else {
+        return;
+    };
+    let Ok((mut camera, mut camera_transform, mut projection)) =
+        exposure_camera_query.get_mut(parts.camera)
+    else {
+        return;
+    };
+
+    // Resizing wipes both images, which is fine since the view changed anyway
+    let size = Extent3d {
+        width: window.physical_width().max(1),
+        height: window.physical_height().max(1),
+        ..default()
+    };
+    for handle in parts.images.iter() {
+        if let Some(image) = images.get(handle) {
+            if image.texture_descriptor.size != size {
+                images.get_mut(handle).unwrap().resize(size);
+            }
+        }
+    }
+
+    parts.current = 1 - parts.current;
+    let (current, last) = (
+        parts.images[parts.current].clone(),
+        parts.images[1 - parts.current].clone(),
+    );
+    camera.target = RenderTarget::Image(current.clone());
+    camera_transform.translation = main_transform.translation;
+    // The image is in physical pixels, which the projection takes as logical
+    projection.scale = main_projection.scale / window.scale_factor() as f32;
+
+    let view = (
+        main_transform.translation.xy(),
+        Vec2::new(window.width(), window.height()) * main_projection.scale,
+    );
+    let (last_center, last_size) = parts.last_view.unwrap_or(view);
+    parts.last_view = Some(view);
+
+    let keep = 0.5f32.powf(time.delta_seconds() / exposure.half_life.max(0.01));
+    if let Some(material) = materials.get_mut(&parts.fade_material) {
+        material.keep = keep;
+        material.texture = last;
+    }
+    if let Some(material) = materials.get_mut(&parts.display_material) {
+        material.texture = current;
+    }
+    if let Ok(mut transform) = quad_query.get_mut(parts.fade) {
+        transform.translation = last_center.extend(EXPOSURE_DEPTH);
+        transform.scale = last_size.extend(1.0);
+    }
+    if let Ok(mut transform) = quad_query.get_mut(parts.display) {
+        transform.translation = view.0.extend(EXPOSURE_DEPTH);
+        transform.scale = view.1.extend(1.0);
+    }
+}
+
+#[derive(Default)]
+pub struct ExposurePlugin;
+
+impl Plugin for ExposurePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LongExposure>()
+            .add_plugin(Material2dPlugin::<ExposureMaterial>::default())
+            .add_system(toggle_exposure_system)
+            .add_system(
+                expose_system
+                    .in_base_set(CoreSet::PostUpdate)
+                    .before(bevy::transform::TransformSystem::TransformPropagate),
+            );
+    }
+}
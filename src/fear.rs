@@ -0,0 +1,121 @@
+// Prey keep running for a bit after losing sight of a predator instead of
+// going right back to flocking like nothing happened. Whatever got scared this
+// tick remembers where the closest predator was and keeps running away from
+// there, less and less, for its type's fear memory.
+
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    spatial::SpatialQuery, ApplyForceEvent, CreatureType, FactorInfo, ForceKind, SimState, SimTime,
+    SystemStages,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Component, Reflect)]
+#[reflect(Component)]
+pub struct Fear {
+    /// Where the predator was last seen
+    pub from: Vec2,
+    /// Seconds until it calms down
+    pub remaining: f32,
+    pub duration: f32,
+}
+
+impl Default for Fear {
+    fn default() -> Self {
+        Fear {
+            from: Vec2::ZERO,
+            remaining: 0.0,
+            duration: 1.0,
+        }
+    }
+}
+
+impl Fear {
+    /// 1 right after seeing the predator down to 0 once it's calmed down
+    pub fn level(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 0.0;
+        }
+        (self.remaining / self.duration).clamp(0.0, 1.0)
+    }
+}
+
+fn calm_down_system(
+    mut commands: Commands,
+    sim_time: SimTime,
+    mut fear_query: Query<(Entity, &mut Fear)>,
+) {
+    let delta_seconds = sim_time.delta_seconds();
+    for (entity, mut fear) in fear_query.iter_mut() {
+        fear.remaining -= delta_seconds;
+        if fear.remaining <= 0.0 {
+            commands.entity(entity).remove::<Fear>();
+        }
+    }
+}
+
+/// Runs after [`calm_down_system`] so getting scared again wins over calming down
+fn fear_system(
+    mut commands: Commands,
+    factor_info: Res<FactorInfo>,
+    spatial_query: SpatialQuery,
+    mut force_reader: EventReader<ApplyForceEvent>,
+    creature_query: Query<(&Transform, &CreatureType)>,
+    mut scared: Local<Vec<Entity>>,
+) {
+    scared.clear();
+    scared.extend(
+        force_reader
+            .iter()
+            .filter(|ApplyForceEvent(_, _, _, kind)| *kind == ForceKind::Scare)
+            .map(|ApplyForceEvent(entity, ..)| *entity),
+    );
+    scared.sort_unstable();
+    scared.dedup();
+
+    // Only the few that are running right now look around again
+    for &entity in scared.iter() {
+        let Ok((transform, creature_type)) = creature_query.get(entity) else {
+            continue;
+        };
+        let Some(factors) = factor_info.factors.get(creature_type) else {
+            continue;
+        };
+        if factors.fear_memory <= 0.0 {
+            continue;
+        }
+        let position = transform.translation.xy();
+        let closest_predator = spatial_query
+            .get_nearby_entities(position, factors.vision)
+            .into_iter()
+            .filter_map(|other| creature_query.get(other).ok())
+            .filter(|(_, other_type)| {
+                factor_info
+                    .factors
+                    .get(other_type)
+                    .is_some_and(|other| other.predator_of.contains(creature_type))
+            })
+            .map(|(other_transform, _)| other_transform.translation.xy())
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)));
+        if let Some(from) = closest_predator {
+            commands.entity(entity).insert(Fear {
+                from,
+                remaining: factors.fear_memory,
+                duration: factors.fear_memory,
+            });
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct FearPlugin;
+
+impl Plugin for FearPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Fear>().add_systems(
+            (calm_down_system, fear_system.after(calm_down_system))
+                .in_set(SystemStages::Apply)
+                .in_set(OnUpdate(SimState::Running)),
+        );
+    }
+}
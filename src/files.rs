@@ -0,0 +1,139 @@
+// Saving things out of the sim. Natively we just write to the working directory
+// but the browser has no file system so we hand the user a download instead.
+
+pub fn timestamp() -> u64 {
+    // `SystemTime` panics on the web so we ask JS for the time instead
+    #[cfg(target_arch = "wasm32")]
+    return (js_sys::Date::now() / 1000.0) as u64;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Saves `contents` under `file_name` and returns where it ended up.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_file(file_name: &str, contents: &[u8], _mime_type: &str) -> Result<String, String> {
+    std::fs::write(file_name, contents).map_err(|error| error.to_string())?;
+    let path = std::fs::canonicalize(file_name).map_err(|error| error.to_string())?;
+    Ok(path.display().to_string())
+}
+
+/// Milliseconds a download's blob URL sticks around after the download starts
+#[cfg(target_arch = "wasm32")]
+const REVOKE_DELAY_MS: i32 = 10_000;
+
+/// Saves `contents` under `file_name` and returns where it ended up.
+#[cfg(target_arch = "wasm32")]
+pub fn save_file(file_name: &str, contents: &[u8], mime_type: &str) -> Result<String, String> {
+    use wasm_bindgen::JsCast;
+
+    let to_string = |error: wasm_bindgen::JsValue| format!("{:?}", error);
+
+    let bytes = js_sys::Uint8Array::from(contents);
+    let parts = js_sys::Array::of1(&bytes);
+    let mut properties = web_sys::BlobPropertyBag::new();
+    properties.type_(mime_type);
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &properties)
+        .map_err(to_string)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(to_string)?;
+
+    let window = web_sys::window().ok_or("No window to download from")?;
+    let document = window.document().ok_or("No document to download from")?;
+    let anchor = document
+        .create_element("a")
+        .map_err(to_string)?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|_| "Couldn't create a download link".to_string())?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+
+    // Some browsers haven't started reading the blob by the time `click`
+    // returns, and revoking it then cancels the download
+    let revoke = wasm_bindgen::closure::Closure::once_into_js(move || {
+        let _ = web_sys::Url::revoke_object_url(&url);
+    });
+    window
+        .set_timeout_with_callback_and_timeout_and_arguments_0(
+            revoke.unchecked_ref(),
+            REVOKE_DELAY_MS,
+        )
+        .map_err(to_string)?;
+    Ok(format!("Downloads/{}", file_name))
+}
+
+/// Reads back something written by [`save_file`]. There's nothing to read from on the web.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_file(file_name: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(file_name).map_err(|error| error.to_string())
+}
+
+/// Reads back something written by [`save_file`]. There's nothing to read from on the web.
+#[cfg(target_arch = "wasm32")]
+pub fn load_file(_file_name: &str) -> Result<Vec<u8>, String> {
+    Err("Loading files isn't supported on the web".to_string())
+}
+
+/// Browser `localStorage`. There's no such thing natively so it's always empty there.
+#[cfg(target_arch = "wasm32")]
+pub fn local_storage_get(key: &str) -> Option<String> {
+    web_sys::window()?
+        .local_storage()
+        .ok()??
+        .get_item(key)
+        .ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn local_storage_set(key: &str, value: &str) -> Result<(), String> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or("No localStorage available")?
+        .set_item(key, value)
+        .map_err(|error| format!("{:?}", error))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn local_storage_remove(key: &str) {
+    if let Some(storage) =
+        web_sys::window().and_then(|window| window.local_storage().ok().flatten())
+    {
+        let _ = storage.remove_item(key);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn local_storage_get(_key: &str) -> Option<String> {
+    None
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn local_storage_set(_key: &str, _value: &str) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn local_storage_remove(_key: &str) {}
+
+/// `?a=1&b=2` from the page URL as `[("a", "1"), ("b", "2")]`. Empty natively.
+pub fn url_query_parameters() -> Vec<(String, String)> {
+    #[cfg(target_arch = "wasm32")]
+    let search = web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .unwrap_or_default();
+    #[cfg(not(target_arch = "wasm32"))]
+    let search = String::new();
+
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (key.to_string(), value.to_string()),
+            None => (pair.to_string(), String::new()),
+        })
+        .collect()
+}
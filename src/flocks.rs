@@ -0,0 +1,176 @@
+// Works out which creatures are flying together every so often so big flocks
+// joining up can be noticed. Creatures of a type are in the same flock when
+// there's a chain of them each close enough to pull the next one in, roughly.
+// It bins them into cells as wide as their cohesion radius and joins touching
+// cells instead of checking every pair, which is plenty for sounds and stats.
+
+use bevy::{math::Vec3Swizzles, prelude::*, utils::HashMap};
+
+use crate::{CreatureType, FactorInfo, SimState};
+
+/// Seconds between working out the flocks again
+const FLOCK_INTERVAL: f32 = 0.5;
+
+/// Sent when two or more flocks of at least `FlockSettings::merge_size` become one.
+#[derive(Debug, Clone)]
+pub struct FlocksMerged {
+    pub creature_type: CreatureType,
+    /// Middle of the flock they made
+    pub position: Vec2,
+    /// How many are in the flock they made
+    pub size: usize,
+    /// How many big flocks went into it
+    pub merged: usize,
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct FlockSettings {
+    /// Flocks smaller than this don't count when they join another one
+    pub merge_size: usize,
+}
+
+impl Default for FlockSettings {
+    fn default() -> Self {
+        FlockSettings { merge_size: 12 }
+    }
+}
+
+/// The flocks from the last time they were worked out.
+#[derive(Debug, Default, Resource)]
+pub struct Flocks {
+    /// Which flock each creature is in
+    pub flock_of: HashMap<Entity, usize>,
+    /// How many creatures are in each flock
+    pub sizes: Vec<usize>,
+}
+
+impl Flocks {
+    pub fn largest(&self) -> usize {
+        self.sizes.iter().copied().max().unwrap_or(0)
+    }
+}
+
+fn find(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(parents, a), find(parents, b));
+    if a != b {
+        parents[b] = a;
+    }
+}
+
+fn flocks_system(
+    time: Res<Time>,
+    factor_info: Res<FactorInfo>,
+    settings: Res<FlockSettings>,
+    mut flocks: ResMut<Flocks>,
+    mut merged_writer: EventWriter<FlocksMerged>,
+    creature_query: Query<(Entity, &CreatureType, &Transform)>,
+    mut since_update: Local<f32>,
+) {
+    *since_update += time.delta_seconds();
+    if *since_update < FLOCK_INTERVAL {
+        return;
+    }
+    *since_update = 0.0;
+
+    // Every occupied cell gets an index and a list of who's in it
+    let mut cell_indices = HashMap::<(CreatureType, IVec2), usize>::default();
+    let mut cells = Vec::<(CreatureType, Vec<(Entity, Vec2)>)>::new();
+    for (entity, &creature_type, transform) in creature_query.iter() {
+        let Some(factors) = factor_info.factors.get(&creature_type) else {
+            continue;
+        };
+        let position = transform.translation.xy();
+        let cell = (position / factors.cohesion_radius.max(1.0))
+            .floor()
+            .as_ivec2();
+        let index = *cell_indices
+            .entry((creature_type, cell))
+            .or_insert_with(|| {
+                cells.push((creature_type, Vec::new()));
+                cells.len() - 1
+            });
+        cells[index].1.push((entity, position));
+    }
+
+    let mut parents = (0..cells.len()).collect::<Vec<_>>();
+    for (&(creature_type, cell), &index) in cell_indices.iter() {
+        for offset in [
+            IVec2::new(1, 0),
+            IVec2::new(0, 1),
+            IVec2::new(1, 1),
+            IVec2::new(1, -1),
+        ] {
+            if let Some(&other) = cell_indices.get(&(creature_type, cell + offset)) {
+                union(&mut parents, index, other);
+            }
+        }
+    }
+
+    // Roots become flock ids
+    let mut flock_ids = HashMap::<usize, usize>::default();
+    let mut new_flocks = Flocks::default();
+    let mut members = Vec::<(CreatureType, Vec<(Entity, Vec2)>)>::new();
+    for (index, (creature_type, cell_members)) in cells.iter().enumerate() {
+        let root = find(&mut parents, index);
+        let flock = *flock_ids.entry(root).or_insert_with(|| {
+            new_flocks.sizes.push(0);
+            members.push((*creature_type, Vec::new()));
+            new_flocks.sizes.len() - 1
+        });
+        new_flocks.sizes[flock] += cell_members.len();
+        members[flock].1.extend(cell_members.iter().copied());
+        for &(entity, _) in cell_members.iter() {
+            new_flocks.flock_of.insert(entity, flock);
+        }
+    }
+
+    // A flock made out of more than one big old flock is a merge
+    for (creature_type, flock_members) in members {
+        if flock_members.len() < settings.merge_size {
+            continue;
+        }
+        let mut old_flocks = flock_members
+            .iter()
+            .filter_map(|(entity, _)| flocks.flock_of.get(entity).copied())
+            .filter(|&old| flocks.sizes[old] >= settings.merge_size)
+            .collect::<Vec<_>>();
+        old_flocks.sort_unstable();
+        old_flocks.dedup();
+        if old_flocks.len() < 2 {
+            continue;
+        }
+        let center = flock_members
+            .iter()
+            .map(|(_, position)| *position)
+            .sum::<Vec2>()
+            / flock_members.len() as f32;
+        merged_writer.send(FlocksMerged {
+            creature_type,
+            position: center,
+            size: flock_members.len(),
+            merged: old_flocks.len(),
+        });
+    }
+
+    *flocks = new_flocks;
+}
+
+#[derive(Default)]
+pub struct FlockPlugin;
+
+impl Plugin for FlockPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FlockSettings::default())
+            .insert_resource(Flocks::default())
+            .add_event::<FlocksMerged>()
+            .add_system(flocks_system.in_set(OnUpdate(SimState::Running)));
+    }
+}
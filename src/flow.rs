@@ -0,0 +1,214 @@
+// A current running through the world that carries creatures along with it,
+// for choreographing big movements the flocking wouldn't make on its own. It
+// can come from one of a few built in patterns or be painted as an image, where
+// a pixel's hue is the direction it flows (red goes right, then around
+// counterclockwise through green and blue) and its brightness is how strong.
+
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    files::load_file, ApplyForceEvent, CreatureType, ForceKind, FrozenTypes, SimState,
+    SystemStages, WorldBounds,
+};
+
+/// Flows weaker than this (out of 1) aren't worth sending
+const MIN_FLOW: f32 = 0.02;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FlowPattern {
+    #[default]
+    Off,
+    /// Around and around the middle of the world
+    Vortex,
+    /// Spiraling into the middle
+    Whirlpool,
+    /// Lanes going opposite ways
+    Shear,
+    /// Meandering rivers going right
+    Waves,
+    /// Whatever image got loaded
+    Image,
+}
+
+impl FlowPattern {
+    pub const ALL: [FlowPattern; 6] = [
+        FlowPattern::Off,
+        FlowPattern::Vortex,
+        FlowPattern::Whirlpool,
+        FlowPattern::Shear,
+        FlowPattern::Waves,
+        FlowPattern::Image,
+    ];
+}
+
+impl std::fmt::Display for FlowPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct FlowSettings {
+    pub pattern: FlowPattern,
+    /// How hard the current turns creatures where it's at its strongest
+    pub strength: f32,
+    /// World units across the lanes and waves
+    pub scale: f32,
+    /// Relative to where the sim was started from
+    pub image_path: String,
+    /// What happened last time an image was loaded
+    pub status: Option<String>,
+}
+
+impl Default for FlowSettings {
+    fn default() -> Self {
+        FlowSettings {
+            pattern: FlowPattern::Off,
+            strength: 3.0,
+            scale: 200.0,
+            image_path: "flow.png".to_string(),
+            status: None,
+        }
+    }
+}
+
+/// Loads the image at `FlowSettings::image_path` and switches over to it
+pub struct LoadFlowImageEvent;
+
+/// A loaded flow image, already turned into directions and strengths
+#[derive(Debug, Clone, Default, Resource)]
+pub struct FlowImage {
+    width: usize,
+    height: usize,
+    flows: Vec<Vec2>,
+}
+
+impl FlowImage {
+    fn from_image(pixels: &image::RgbImage) -> Self {
+        let flows = pixels
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b] = pixel.0.map(|channel| channel as f32 / 255.0);
+                let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+                let spread = max - min;
+                // Grays don't point anywhere
+                if spread < f32::EPSILON {
+                    return Vec2::ZERO;
+                }
+                let hue = if max == r {
+                    ((g - b) / spread).rem_euclid(6.0)
+                } else if max == g {
+                    (b - r) / spread + 2.0
+                } else {
+                    (r - g) / spread + 4.0
+                };
+                Vec2::from_angle(hue / 6.0 * std::f32::consts::TAU) * max
+            })
+            .collect();
+        FlowImage {
+            width: pixels.width() as usize,
+            height: pixels.height() as usize,
+            flows,
+        }
+    }
+
+    /// Stretched over the whole world
+    fn at(&self, world_bounds: &WorldBounds, position: Vec2) -> Vec2 {
+        if self.flows.is_empty() {
+            return Vec2::ZERO;
+        }
+        let uv = (position - world_bounds.min()) / world_bounds.size();
+        let x = ((uv.x * self.width as f32) as usize).min(self.width - 1);
+        // Images go top down but the world goes bottom up
+        let y = (((1.0 - uv.y) * self.height as f32) as usize).min(self.height - 1);
+        self.flows[y * self.width + x]
+    }
+}
+
+impl FlowSettings {
+    /// Which way the current goes here and how strong it is, up to 1
+    pub fn at(&self, image: &FlowImage, world_bounds: &WorldBounds, position: Vec2) -> Vec2 {
+        let scale = self.scale.max(1.0);
+        let half_size = world_bounds.size() / 2.0;
+        match self.pattern {
+            FlowPattern::Off => Vec2::ZERO,
+            FlowPattern::Vortex => position.perp().normalize_or_zero(),
+            FlowPattern::Whirlpool => {
+                (position.perp() - position * 0.5).normalize_or_zero()
+                    * (position.length() / half_size.min_element()).min(1.0)
+            }
+            FlowPattern::Shear => Vec2::X * (position.y / scale * std::f32::consts::PI).sin(),
+            FlowPattern::Waves => {
+                let slope = (position.x / scale * std::f32::consts::PI).cos();
+                Vec2::new(1.0, slope).normalize()
+            }
+            FlowPattern::Image => image.at(world_bounds, position),
+        }
+    }
+}
+
+fn load_flow_image_system(
+    mut settings: ResMut<FlowSettings>,
+    mut flow_image: ResMut<FlowImage>,
+    mut load_events: EventReader<LoadFlowImageEvent>,
+) {
+    if load_events.iter().count() == 0 {
+        return;
+    }
+    match load_file(&settings.image_path)
+        .and_then(|bytes| image::load_from_memory(&bytes).map_err(|error| error.to_string()))
+    {
+        Ok(loaded) => {
+            *flow_image = FlowImage::from_image(&loaded.to_rgb8());
+            settings.pattern = FlowPattern::Image;
+            settings.status = Some(format!("Loaded {}x{}", flow_image.width, flow_image.height));
+        }
+        Err(error) => settings.status = Some(format!("Couldn't load it: {}", error)),
+    }
+}
+
+fn flow_system(
+    settings: Res<FlowSettings>,
+    image: Res<FlowImage>,
+    world_bounds: Res<WorldBounds>,
+    frozen_types: Res<FrozenTypes>,
+    creature_query: Query<(Entity, &Transform, &CreatureType)>,
+    mut force_writer: EventWriter<ApplyForceEvent>,
+) {
+    if settings.pattern == FlowPattern::Off || settings.strength <= 0.0 {
+        return;
+    }
+    for (entity, transform, creature_type) in creature_query.iter() {
+        if frozen_types.contains(creature_type) {
+            continue;
+        }
+        let flow = settings.at(&image, &world_bounds, transform.translation.xy());
+        let strength = flow.length();
+        if strength < MIN_FLOW {
+            continue;
+        }
+        force_writer.send(ApplyForceEvent(
+            entity,
+            flow / strength,
+            settings.strength * strength,
+            ForceKind::Flow,
+        ));
+    }
+}
+
+#[derive(Default)]
+pub struct FlowPlugin;
+
+impl Plugin for FlowPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FlowSettings>()
+            .init_resource::<FlowImage>()
+            .add_event::<LoadFlowImageEvent>()
+            .add_system(load_flow_image_system)
+            .add_system(
+                flow_system
+                    .in_set(SystemStages::Calculate)
+                    .in_set(OnUpdate(SimState::Running)),
+            );
+    }
+}
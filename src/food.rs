@@ -0,0 +1,289 @@
+// Patches of plants for grazers to eat. Each one has some biomass that gets
+// eaten down by grazers inside it and grows back logistically, so a patch that's
+// been picked nearly bare takes a long while to recover while a half eaten one
+// bounces back fast. They're seeded in clusters so there are rich areas worth
+// flocking to and empty stretches in between.
+
+use bevy::{
+    ecs::system::SystemParam,
+    math::Vec3Swizzles,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    spatial::SpatialQuery, CreatureType, Energy, EnergyChangeEvent, FactorInfo, RestartEvent,
+    SimRng, SimState, SimTime, SystemStages, WorldBounds,
+};
+
+/// Pixels across the patch texture
+const TEXTURE_SIZE: u32 = 64;
+/// Picked clean patches still regrow from this much of their capacity
+const REGROWTH_FLOOR: f32 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoodSettings {
+    pub enabled: bool,
+    pub clusters: usize,
+    pub patches_per_cluster: usize,
+    /// How far patches can be from the middle of their cluster
+    pub cluster_spread: f32,
+    pub patch_radius: f32,
+    /// Most biomass a patch can hold
+    pub capacity: f32,
+    /// Logistic growth rate per second
+    pub regrowth_rate: f32,
+    /// Biomass each grazer can eat per second
+    pub graze_rate: f32,
+    /// Energy a grazer gets from each unit of biomass
+    pub energy_per_biomass: f32,
+    pub color: Color,
+}
+
+impl Default for FoodSettings {
+    fn default() -> Self {
+        FoodSettings {
+            enabled: false,
+            clusters: 4,
+            patches_per_cluster: 5,
+            cluster_spread: 120.0,
+            patch_radius: 30.0,
+            capacity: 100.0,
+            regrowth_rate: 0.1,
+            graze_rate: 4.0,
+            energy_per_biomass: 1.0,
+            color: Color::rgb(0.3, 0.8, 0.3),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Component, Reflect)]
+#[reflect(Component)]
+pub struct FoodPatch {
+    pub biomass: f32,
+    pub capacity: f32,
+    pub radius: f32,
+}
+
+impl Default for FoodPatch {
+    fn default() -> Self {
+        FoodPatch {
+            biomass: 0.0,
+            capacity: 1.0,
+            radius: 1.0,
+        }
+    }
+}
+
+impl FoodPatch {
+    /// 0 for bare up to 1 for full
+    pub fn richness(&self) -> f32 {
+        if self.capacity <= 0.0 {
+            return 0.0;
+        }
+        (self.biomass / self.capacity).clamp(0.0, 1.0)
+    }
+}
+
+/// Throws out every patch and seeds new ones.
+pub struct ReseedFoodEvent;
+
+/// A soft edged disc so patches don't look like big squares.
+#[derive(Debug, Clone, Default, Resource)]
+struct PatchTexture(Handle<Image>);
+
+fn patch_image() -> Image {
+    let center = (TEXTURE_SIZE as f32 - 1.0) / 2.0;
+    let mut data = Vec::with_capacity((TEXTURE_SIZE * TEXTURE_SIZE * 4) as usize);
+    for y in 0..TEXTURE_SIZE {
+        for x in 0..TEXTURE_SIZE {
+            let distance = Vec2::new(x as f32 - center, y as f32 - center).length() / center;
+            let alpha = (1.0 - distance).clamp(0.0, 1.0).sqrt();
+            data.extend_from_slice(&[255, 255, 255, (alpha * 255.0) as u8]);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: TEXTURE_SIZE,
+            height: TEXTURE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn setup_food_system(
+    mut images: ResMut<Assets<Image>>,
+    mut patch_texture: ResMut<PatchTexture>,
+    mut reseed_events: EventWriter<ReseedFoodEvent>,
+) {
+    patch_texture.0 = images.add(patch_image());
+    reseed_events.send(ReseedFoodEvent);
+}
+
+fn patch_color(settings: &FoodSettings, patch: &FoodPatch) -> Color {
+    settings.color.with_a(0.1 + 0.5 * patch.richness())
+}
+
+/// Everything that wipes out the patches and lays down new ones
+#[derive(SystemParam)]
+struct ReseedTriggers<'w, 's> {
+    reseed_events: EventReader<'w, 's, ReseedFoodEvent>,
+    restart_events: EventReader<'w, 's, RestartEvent>,
+    was_enabled: Local<'s, bool>,
+}
+
+impl ReseedTriggers<'_, '_> {
+    fn triggered(&mut self, enabled: bool) -> bool {
+        let toggled = enabled != *self.was_enabled;
+        *self.was_enabled = enabled;
+        let reseed = self.reseed_events.iter().count() > 0;
+        let restarted = self.restart_events.iter().count() > 0;
+        toggled || reseed || restarted
+    }
+}
+
+/// Restarting reseeds too, right after the creatures so runs with the same
+/// seed get the same patches.
+fn seed_food_system(
+    mut commands: Commands,
+    mut rng: ResMut<SimRng>,
+    settings: Res<FoodSettings>,
+    world_bounds: Res<WorldBounds>,
+    patch_texture: Res<PatchTexture>,
+    mut triggers: ReseedTriggers,
+    patch_query: Query<Entity, With<FoodPatch>>,
+) {
+    if !triggers.triggered(settings.enabled) {
+        return;
+    }
+
+    for entity in patch_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    if !settings.enabled {
+        return;
+    }
+
+    let (min, max) = (world_bounds.min(), world_bounds.max());
+    for _ in 0..settings.clusters {
+        let cluster_center = Vec2::new(rng.gen_range(min.x..max.x), rng.gen_range(min.y..max.y));
+        for _ in 0..settings.patches_per_cluster {
+            let offset = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU))
+                * rng.gen_range(0.0..=settings.cluster_spread);
+            let position = world_bounds.wrap(cluster_center + offset);
+            let patch = FoodPatch {
+                // Starting out anywhere from half to all the way full
+                biomass: settings.capacity * rng.gen_range(0.5..=1.0),
+                capacity: settings.capacity,
+                radius: settings.patch_radius,
+            };
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: patch_color(&settings, &patch),
+                        custom_size: Some(Vec2::splat(patch.radius * 2.0)),
+                        ..default()
+                    },
+                    texture: patch_texture.0.clone(),
+                    // Above the boundary lines and under the creatures
+                    transform: Transform::from_translation(position.extend(-0.5)),
+                    ..default()
+                },
+                patch,
+            ));
+        }
+    }
+}
+
+fn graze_system(
+    sim_time: SimTime,
+    settings: Res<FoodSettings>,
+    factor_info: Res<FactorInfo>,
+    spatial_query: SpatialQuery,
+    mut patch_query: Query<(&Transform, &mut FoodPatch)>,
+    creature_query: Query<(&Transform, &CreatureType, &Energy)>,
+    mut energy_change_writer: EventWriter<EnergyChangeEvent>,
+) {
+    let delta_seconds = sim_time.delta_seconds();
+    let bite = settings.graze_rate * delta_seconds;
+    for (patch_transform, mut patch) in patch_query.iter_mut() {
+        let center = patch_transform.translation.xy();
+        for entity in spatial_query.get_nearby_entities(center, patch.radius) {
+            if patch.biomass <= 0.0 {
+                break;
+            }
+            let Ok((transform, creature_type, energy)) = creature_query.get(entity) else {
+                continue;
+            };
+            let Some(factors) = factor_info.factors.get(creature_type) else {
+                continue;
+            };
+            // Full grazers leave it for the others
+            if !factors.grazer
+                || energy.0 >= factors.max_energy
+                || transform.translation.xy().distance(center) > patch.radius
+            {
+                continue;
+            }
+            let eaten = bite.min(patch.biomass);
+            patch.biomass -= eaten;
+            energy_change_writer.send(EnergyChangeEvent(
+                entity,
+                eaten * settings.energy_per_biomass,
+            ));
+        }
+    }
+}
+
+fn regrowth_system(
+    sim_time: SimTime,
+    settings: Res<FoodSettings>,
+    mut patch_query: Query<&mut FoodPatch>,
+) {
+    let delta_seconds = sim_time.delta_seconds();
+    for mut patch in patch_query.iter_mut() {
+        if patch.biomass >= patch.capacity {
+            continue;
+        }
+        let biomass = patch.biomass.max(patch.capacity * REGROWTH_FLOOR);
+        let growth = settings.regrowth_rate * biomass * (1.0 - biomass / patch.capacity);
+        patch.biomass = (biomass + growth * delta_seconds).min(patch.capacity);
+    }
+}
+
+fn patch_appearance_system(
+    settings: Res<FoodSettings>,
+    mut patch_query: Query<(Ref<FoodPatch>, &mut Sprite)>,
+) {
+    for (patch, mut sprite) in patch_query.iter_mut() {
+        if patch.is_changed() || settings.is_changed() {
+            sprite.color = patch_color(&settings, &patch);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct FoodPlugin;
+
+impl Plugin for FoodPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<FoodPatch>()
+            .init_resource::<PatchTexture>()
+            .add_event::<ReseedFoodEvent>()
+            .add_startup_system(setup_food_system)
+            .add_system(seed_food_system.in_set(SystemStages::Cache))
+            .add_systems(
+                (graze_system, regrowth_system)
+                    .chain()
+                    .in_set(SystemStages::Act)
+                    .in_set(OnUpdate(SimState::Running)),
+            )
+            .add_system(patch_appearance_system);
+    }
+}
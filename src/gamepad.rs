@@ -0,0 +1,285 @@
+// Controller support for when there's no mouse around, like a kiosk or the
+// couch. The left stick pans, the right stick zooms, and the buttons can be
+// rebound in Settings > Gamepad.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    camera::{MAX_ZOOM, MIN_ZOOM},
+    selection::CameraFollow,
+    CreatureType, FactorInfo, MainCamera, SimState, SimStep, Spawner, SystemStages,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAction {
+    /// Hold to keep spawning the selected type in the middle of the screen
+    Spawn,
+    Pause,
+    Step,
+    NextType,
+    PreviousType,
+    CenterCamera,
+}
+
+impl GamepadAction {
+    pub const ALL: [GamepadAction; 6] = [
+        GamepadAction::Spawn,
+        GamepadAction::Pause,
+        GamepadAction::Step,
+        GamepadAction::NextType,
+        GamepadAction::PreviousType,
+        GamepadAction::CenterCamera,
+    ];
+}
+
+impl std::fmt::Display for GamepadAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GamepadAction::NextType => write!(f, "Next Type"),
+            GamepadAction::PreviousType => write!(f, "Previous Type"),
+            GamepadAction::CenterCamera => write!(f, "Center Camera"),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// Buttons that can be bound to an action
+pub const BINDABLE_BUTTONS: [GamepadButtonType; 14] = [
+    GamepadButtonType::South,
+    GamepadButtonType::East,
+    GamepadButtonType::North,
+    GamepadButtonType::West,
+    GamepadButtonType::LeftTrigger,
+    GamepadButtonType::RightTrigger,
+    GamepadButtonType::LeftTrigger2,
+    GamepadButtonType::RightTrigger2,
+    GamepadButtonType::Select,
+    GamepadButtonType::Start,
+    GamepadButtonType::DPadUp,
+    GamepadButtonType::DPadDown,
+    GamepadButtonType::DPadLeft,
+    GamepadButtonType::DPadRight,
+];
+
+/// What the button is usually called on an Xbox style controller
+pub fn button_name(button: GamepadButtonType) -> &'static str {
+    match button {
+        GamepadButtonType::South => "A",
+        GamepadButtonType::East => "B",
+        GamepadButtonType::North => "Y",
+        GamepadButtonType::West => "X",
+        GamepadButtonType::LeftTrigger => "LB",
+        GamepadButtonType::RightTrigger => "RB",
+        GamepadButtonType::LeftTrigger2 => "LT",
+        GamepadButtonType::RightTrigger2 => "RT",
+        GamepadButtonType::Select => "Back",
+        GamepadButtonType::Start => "Start",
+        GamepadButtonType::DPadUp => "D-Pad Up",
+        GamepadButtonType::DPadDown => "D-Pad Down",
+        GamepadButtonType::DPadLeft => "D-Pad Left",
+        GamepadButtonType::DPadRight => "D-Pad Right",
+        _ => "Other",
+    }
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct GamepadBindings {
+    pub enabled: bool,
+    pub buttons: Vec<(GamepadAction, GamepadButtonType)>,
+    /// World units per second at normal zoom with the stick pushed all the way
+    pub pan_speed: f32,
+    /// How much the zoom doubles (or halves) per second
+    pub zoom_speed: f32,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        GamepadBindings {
+            enabled: true,
+            buttons: vec![
+                (GamepadAction::Spawn, GamepadButtonType::South),
+                (GamepadAction::Pause, GamepadButtonType::Start),
+                (GamepadAction::Step, GamepadButtonType::East),
+                (GamepadAction::NextType, GamepadButtonType::RightTrigger),
+                (GamepadAction::PreviousType, GamepadButtonType::LeftTrigger),
+                (GamepadAction::CenterCamera, GamepadButtonType::North),
+            ],
+            pan_speed: 600.0,
+            zoom_speed: 1.5,
+        }
+    }
+}
+
+impl GamepadBindings {
+    fn buttons_for(&self, action: GamepadAction) -> impl Iterator<Item = GamepadButtonType> + '_ {
+        self.buttons
+            .iter()
+            .filter(move |(bound_action, _)| *bound_action == action)
+            .map(|(_, button)| *button)
+    }
+}
+
+/// The bindings along with the state of every connected gamepad's buttons
+#[derive(SystemParam)]
+struct GamepadInput<'w> {
+    bindings: Res<'w, GamepadBindings>,
+    gamepads: Res<'w, Gamepads>,
+    buttons: Res<'w, Input<GamepadButton>>,
+}
+
+impl GamepadInput<'_> {
+    fn enabled(&self) -> bool {
+        self.bindings.enabled
+    }
+
+    /// Whether any connected gamepad has a button for `action` held down
+    fn pressed(&self, action: GamepadAction) -> bool {
+        self.gamepads.iter().any(|gamepad| {
+            self.bindings
+                .buttons_for(action)
+                .any(|button| self.buttons.pressed(GamepadButton::new(gamepad, button)))
+        })
+    }
+
+    fn just_pressed(&self, action: GamepadAction) -> bool {
+        self.gamepads.iter().any(|gamepad| {
+            self.bindings.buttons_for(action).any(|button| {
+                self.buttons
+                    .just_pressed(GamepadButton::new(gamepad, button))
+            })
+        })
+    }
+}
+
+fn gamepad_camera_system(
+    time: Res<Time>,
+    input: GamepadInput,
+    axes: Res<Axis<GamepadAxis>>,
+    mut camera_follow: ResMut<CameraFollow>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    if !input.enabled() {
+        return;
+    }
+    let axis = |gamepad, axis_type| {
+        axes.get(GamepadAxis::new(gamepad, axis_type))
+            .unwrap_or(0.0)
+    };
+    let (mut pan, mut zoom) = (Vec2::ZERO, 0.0);
+    for gamepad in input.gamepads.iter() {
+        pan += Vec2::new(
+            axis(gamepad, GamepadAxisType::LeftStickX),
+            axis(gamepad, GamepadAxisType::LeftStickY),
+        );
+        zoom += axis(gamepad, GamepadAxisType::RightStickY);
+    }
+    let center = input.just_pressed(GamepadAction::CenterCamera);
+    if pan == Vec2::ZERO && zoom == 0.0 && !center {
+        return;
+    }
+
+    let Ok((mut transform, mut projection)) = camera_query.get_single_mut() else {
+        return;
+    };
+    // Keeps working while paused or in slow motion
+    let delta_seconds = time.raw_delta_seconds();
+    if center {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+    }
+    if pan != Vec2::ZERO {
+        // Steering the camera means it's not following anyone anymore
+        camera_follow.enabled = false;
+        let offset = pan * input.bindings.pan_speed * projection.scale * delta_seconds;
+        transform.translation += offset.extend(0.0);
+    }
+    if zoom != 0.0 {
+        // Stick up zooms in
+        projection.scale = (projection.scale
+            * input.bindings.zoom_speed.powf(-zoom * delta_seconds))
+        .clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
+
+fn gamepad_actions_system(
+    input: GamepadInput,
+    factor_info: Res<FactorInfo>,
+    sim_state: Res<State<SimState>>,
+    mut next_sim_state: ResMut<NextState<SimState>>,
+    mut sim_step: ResMut<SimStep>,
+    mut selected_creature_type: ResMut<CreatureType>,
+) {
+    if !input.enabled() {
+        return;
+    }
+    let just_pressed = |action| input.just_pressed(action);
+
+    if just_pressed(GamepadAction::Pause) {
+        next_sim_state.set(match sim_state.0 {
+            SimState::Running => SimState::Paused,
+            _ => SimState::Running,
+        });
+    }
+    if just_pressed(GamepadAction::Step) && sim_state.0 == SimState::Paused {
+        sim_step.request();
+    }
+
+    let type_count = factor_info.factors.len().max(1);
+    if just_pressed(GamepadAction::NextType) {
+        selected_creature_type.0 = (selected_creature_type.0 + 1) % type_count;
+    }
+    if just_pressed(GamepadAction::PreviousType) {
+        selected_creature_type.0 = (selected_creature_type.0 + type_count - 1) % type_count;
+    }
+}
+
+/// Same as Shift+Click but always in the middle of the screen.
+fn gamepad_spawn_system(
+    time: Res<Time>,
+    input: GamepadInput,
+    mut spawner: Spawner,
+    camera_query: Query<&Transform, With<MainCamera>>,
+    mut carry: Local<Option<f32>>,
+) {
+    if !input.enabled() || !input.pressed(GamepadAction::Spawn) {
+        *carry = None;
+        return;
+    }
+    let Ok(camera_transform) = camera_query.get_single() else {
+        return;
+    };
+
+    // The first press drops the usual bunch and holding keeps painting more
+    let spawn_properties = &spawner.spawn_properties;
+    let count = match carry.as_mut() {
+        None => {
+            *carry = Some(0.0);
+            spawn_properties.amount
+        }
+        Some(carry) => {
+            *carry += spawn_properties.paint_rate * time.delta_seconds();
+            let count = carry.floor();
+            *carry -= count;
+            count as usize
+        }
+    };
+    let center = camera_transform.translation.truncate();
+    for _ in 0..count {
+        spawner.spawn_around(center);
+    }
+}
+
+#[derive(Default)]
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GamepadBindings::default())
+            .add_systems((gamepad_camera_system, gamepad_actions_system))
+            .add_system(
+                gamepad_spawn_system
+                    .in_set(SystemStages::Spawn)
+                    .in_set(OnUpdate(SimState::Running)),
+            );
+    }
+}
@@ -0,0 +1,136 @@
+// A spot for a whole type to head towards, for herding flocks through mazes
+// and obstacle courses. G puts the selected type's goal under the cursor (or
+// moves it there) and Shift+G takes it away. How hard each type heads for its
+// goal is its `goal_seek` factor. When the goal's in another room they head
+// for the portal that gets them there instead.
+
+use bevy::{math::Vec3Swizzles, prelude::*, utils::HashMap, window::PrimaryWindow};
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    overlays::WorldToScreen,
+    rgba_u8,
+    rooms::Rooms,
+    ui::{has_ui, overlays_shown},
+    ApplyForceEvent, CreatureType, Cursor, FactorInfo, ForceKind, FrozenTypes, MainCamera,
+    SimState, SystemStages,
+};
+
+/// Creatures this close to their goal stop pushing towards it so they don't
+/// jitter on top of it
+const ARRIVE_RADIUS: f32 = 5.0;
+/// Screen pixels across the marker
+const MARKER_RADIUS: f32 = 8.0;
+
+#[derive(Debug, Clone, Default, Resource)]
+pub struct Goals(pub HashMap<CreatureType, Vec2>);
+
+fn place_goal_system(
+    mut egui_context: EguiContexts,
+    keys: Res<Input<KeyCode>>,
+    cursor: Res<Cursor>,
+    selected_creature_type: Res<CreatureType>,
+    mut goals: ResMut<Goals>,
+) {
+    if !keys.just_pressed(KeyCode::G) || egui_context.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keys.pressed(KeyCode::LShift) {
+        goals.0.remove(&*selected_creature_type);
+    } else {
+        goals.0.insert(*selected_creature_type, cursor.position);
+    }
+}
+
+fn goal_seek_system(
+    goals: Res<Goals>,
+    rooms: Res<Rooms>,
+    factor_info: Res<FactorInfo>,
+    frozen_types: Res<FrozenTypes>,
+    creature_query: Query<(Entity, &Transform, &CreatureType)>,
+    mut force_writer: EventWriter<ApplyForceEvent>,
+) {
+    if goals.0.is_empty() {
+        return;
+    }
+    for (entity, transform, creature_type) in creature_query.iter() {
+        let (Some(goal), Some(factors)) = (
+            goals.0.get(creature_type),
+            factor_info.factors.get(creature_type),
+        ) else {
+            continue;
+        };
+        if factors.goal_seek <= 0.0 || frozen_types.contains(creature_type) {
+            continue;
+        }
+        let position = transform.translation.xy();
+        if position.distance_squared(*goal) <= ARRIVE_RADIUS * ARRIVE_RADIUS {
+            continue;
+        }
+        let direction = (rooms.waypoint(position, *goal) - position).normalize_or_zero();
+        if direction == Vec2::ZERO {
+            continue;
+        }
+        force_writer.send(ApplyForceEvent(
+            entity,
+            direction,
+            factors.goal_seek,
+            ForceKind::Goal,
+        ));
+    }
+}
+
+/// A crosshair in the type's color on each goal
+fn goal_marker_system(
+    mut egui_context: EguiContexts,
+    goals: Res<Goals>,
+    factor_info: Res<FactorInfo>,
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    if goals.0.is_empty() {
+        return;
+    }
+    let Ok(window) = primary_query.get_single() else {
+        return;
+    };
+    let (camera, camera_transform) = camera_query.single();
+    let to_screen = WorldToScreen::new(camera, camera_transform, window);
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+
+    for (creature_type, goal) in goals.0.iter() {
+        let (Some(center), Some(factors)) = (
+            to_screen.point(*goal),
+            factor_info.factors.get(creature_type),
+        ) else {
+            continue;
+        };
+        let [r, g, b, _] = rgba_u8(factors.color);
+        let stroke = egui::Stroke::new(2.0, egui::Color32::from_rgb(r, g, b));
+        painter.circle_stroke(center, MARKER_RADIUS, stroke);
+        let (across, down) = (
+            egui::vec2(MARKER_RADIUS * 1.5, 0.0),
+            egui::vec2(0.0, MARKER_RADIUS * 1.5),
+        );
+        painter.line_segment([center - across, center + across], stroke);
+        painter.line_segment([center - down, center + down], stroke);
+    }
+}
+
+#[derive(Default)]
+pub struct GoalPlugin;
+
+impl Plugin for GoalPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Goals>()
+            .add_system(place_goal_system.run_if(has_ui))
+            .add_system(goal_marker_system.run_if(overlays_shown))
+            .add_system(
+                goal_seek_system
+                    .in_set(SystemStages::Calculate)
+                    .in_set(OnUpdate(SimState::Running)),
+            );
+    }
+}
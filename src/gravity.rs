@@ -0,0 +1,145 @@
+// Gravity wells pull creatures in with an inverse-square (by default) attraction.
+// Since creatures always move at their own speed they can't fall straight in and
+// end up swinging around the well instead, which makes for some neat orbits.
+// Middle click to drop one and middle click on it again to take it away.
+
+use bevy::{input::mouse::MouseButtonInput, math::Vec3Swizzles, prelude::*, utils::HashSet};
+
+use crate::{
+    ApplyForceEvent, CreatureType, Cursor, ForceKind, FrozenTypes, SimState, SystemStages,
+};
+
+/// How close a middle click has to be to a well to remove it
+const PICK_RADIUS: f32 = 10.0;
+/// Closer than this counts as this far so nothing gets an infinite pull
+const MIN_DISTANCE: f32 = 5.0;
+/// Pulls can't turn anything faster than this
+const MAX_PULL: f32 = 50.0;
+/// Pulls weaker than this aren't worth sending
+const MIN_PULL: f32 = 0.05;
+const WELL_SIZE: f32 = 10.0;
+const WELL_COLOR: Color = Color::VIOLET;
+
+#[derive(Debug, Clone, PartialEq, Component, Reflect)]
+#[reflect(Component)]
+pub struct GravityWell {
+    pub mass: f32,
+    /// Power of the distance the pull drops off with. 2 is real gravity.
+    pub falloff: f32,
+    /// Only these types get pulled, or every type if it's empty
+    pub affected_types: HashSet<CreatureType>,
+}
+
+impl Default for GravityWell {
+    fn default() -> Self {
+        GravityWell {
+            mass: 2000.0,
+            falloff: 2.0,
+            affected_types: HashSet::default(),
+        }
+    }
+}
+
+impl GravityWell {
+    pub fn affects(&self, creature_type: &CreatureType) -> bool {
+        self.affected_types.is_empty() || self.affected_types.contains(creature_type)
+    }
+
+    /// How hard it pulls on something this far away
+    pub fn pull(&self, distance: f32) -> f32 {
+        (self.mass / distance.max(MIN_DISTANCE).powf(self.falloff.max(0.1))).min(MAX_PULL)
+    }
+
+    /// Past this the pull is too weak to matter
+    pub fn range(&self) -> f32 {
+        (self.mass.max(0.0) / MIN_PULL).powf(1.0 / self.falloff.max(0.1))
+    }
+}
+
+/// What new wells start with. Each well can be changed on its own after.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct NewGravityWell(pub GravityWell);
+
+fn place_well_system(
+    mut commands: Commands,
+    cursor: Res<Cursor>,
+    new_well: Res<NewGravityWell>,
+    mut mouse_button_events: EventReader<MouseButtonInput>,
+    well_query: Query<(Entity, &Transform), With<GravityWell>>,
+) {
+    for event in mouse_button_events.iter() {
+        if event.button != MouseButton::Middle || event.state.is_pressed() {
+            continue;
+        }
+        let clicked = well_query.iter().find(|(_, transform)| {
+            transform.translation.xy().distance(cursor.position) <= PICK_RADIUS
+        });
+        if let Some((entity, _)) = clicked {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: WELL_COLOR,
+                    custom_size: Some(Vec2::splat(WELL_SIZE)),
+                    ..default()
+                },
+                // Turned into a diamond so it doesn't look like a creature
+                transform: Transform::from_translation(cursor.position.extend(0.5))
+                    .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+                ..default()
+            },
+            new_well.0.clone(),
+        ));
+    }
+}
+
+fn gravity_system(
+    frozen_types: Res<FrozenTypes>,
+    well_query: Query<(&Transform, &GravityWell)>,
+    creature_query: Query<(Entity, &Transform, &CreatureType)>,
+    mut force_writer: EventWriter<ApplyForceEvent>,
+) {
+    if well_query.is_empty() {
+        return;
+    }
+    for (entity, transform, creature_type) in creature_query.iter() {
+        if frozen_types.contains(creature_type) {
+            continue;
+        }
+        let position = transform.translation.xy();
+        for (well_transform, well) in well_query.iter() {
+            if !well.affects(creature_type) {
+                continue;
+            }
+            let offset = well_transform.translation.xy() - position;
+            let distance = offset.length();
+            if distance > well.range() || distance < f32::EPSILON {
+                continue;
+            }
+            force_writer.send(ApplyForceEvent(
+                entity,
+                offset / distance,
+                well.pull(distance),
+                ForceKind::Gravity,
+            ));
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct GravityPlugin;
+
+impl Plugin for GravityPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<GravityWell>()
+            .init_resource::<NewGravityWell>()
+            .add_system(place_well_system)
+            .add_system(
+                gravity_system
+                    .in_set(SystemStages::Calculate)
+                    .in_set(OnUpdate(SimState::Running)),
+            );
+    }
+}
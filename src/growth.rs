@@ -0,0 +1,133 @@
+// Creatures keep getting bigger the longer they live and shrink a bit when
+// they're running low on energy. Everything that cares how big a creature is
+// (eating, collisions, the sprite) goes off of its BodySize, which also rolls in
+// its variation.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{variation::Variation, CreatureType, Energy, FactorInfo, SimState, SimTime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrowthSettings {
+    pub enabled: bool,
+    /// How much bigger the oldest creatures get than young adults
+    pub max_age_scale: f32,
+    /// Seconds it takes to do most of that growing
+    pub growth_time: f32,
+    /// How much energy changes size. At 0.2 a starving creature is 10% smaller
+    /// than a half full one and a full one is 10% bigger.
+    pub energy_influence: f32,
+    /// Predators can only eat prey at most this many times their size. 0 lets
+    /// anything eat anything.
+    pub predation_size_ratio: f32,
+}
+
+impl Default for GrowthSettings {
+    fn default() -> Self {
+        GrowthSettings {
+            enabled: false,
+            max_age_scale: 1.5,
+            growth_time: 60.0,
+            energy_influence: 0.2,
+            predation_size_ratio: 0.0,
+        }
+    }
+}
+
+impl GrowthSettings {
+    /// Whether something this big is allowed to eat something that big
+    pub fn can_eat(&self, killer_size: f32, killed_size: f32) -> bool {
+        self.predation_size_ratio <= 0.0 || killed_size <= killer_size * self.predation_size_ratio
+    }
+}
+
+/// Sim seconds since the creature showed up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Component, Reflect)]
+#[reflect(Component)]
+pub struct Age(pub f32);
+
+/// How big the creature actually is right now, across.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Component, Reflect)]
+#[reflect(Component)]
+pub struct BodySize(pub f32);
+
+fn new_age_system(
+    mut commands: Commands,
+    creature_query: Query<Entity, (Added<CreatureType>, Without<Age>)>,
+) {
+    for entity in creature_query.iter() {
+        commands.entity(entity).insert(Age::default());
+    }
+}
+
+fn aging_system(sim_time: SimTime, mut age_query: Query<&mut Age>) {
+    let delta_seconds = sim_time.delta_seconds();
+    for mut age in age_query.iter_mut() {
+        age.0 += delta_seconds;
+    }
+}
+
+type GrowingCreature<'a> = (
+    Entity,
+    &'a CreatureType,
+    &'a mut Sprite,
+    Option<&'a Energy>,
+    Option<&'a Age>,
+    Option<&'a Variation>,
+    Option<&'a mut BodySize>,
+);
+
+/// Runs after everything else that sizes sprites so this has the last word.
+fn body_size_system(
+    mut commands: Commands,
+    settings: Res<GrowthSettings>,
+    factor_info: Res<FactorInfo>,
+    mut creature_query: Query<GrowingCreature>,
+) {
+    for (entity, creature_type, mut sprite, energy, age, variation, body_size) in
+        creature_query.iter_mut()
+    {
+        let Some(factors) = factor_info.factors.get(creature_type) else {
+            continue;
+        };
+        let mut size = variation.copied().unwrap_or_default().size(factors);
+        if settings.enabled {
+            let age = age.map_or(0.0, |age| age.0);
+            let grown = 1.0 - (-age / settings.growth_time.max(1.0)).exp();
+            size *= 1.0 + (settings.max_age_scale - 1.0) * grown;
+            if let Some(energy) = energy {
+                let fullness = (energy.0 / factors.max_energy.max(1.0)).clamp(0.0, 1.0);
+                size *= 1.0 + settings.energy_influence * (fullness - 0.5);
+            }
+        }
+
+        match body_size {
+            Some(mut body_size) => {
+                if body_size.0 != size {
+                    body_size.0 = size;
+                }
+            }
+            None => {
+                commands.entity(entity).insert(BodySize(size));
+            }
+        }
+        if sprite.custom_size != Some(Vec2::splat(size)) {
+            sprite.custom_size = Some(Vec2::splat(size));
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct GrowthPlugin;
+
+impl Plugin for GrowthPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Age>()
+            .register_type::<BodySize>()
+            .add_system(new_age_system.in_base_set(CoreSet::PostUpdate))
+            .add_system(aging_system.in_set(OnUpdate(SimState::Running)))
+            .add_system(body_size_system.in_base_set(CoreSet::PostUpdate));
+    }
+}
@@ -0,0 +1,92 @@
+use bevy::{math::Vec3Swizzles, prelude::*, utils::HashSet};
+
+use crate::{
+    spatial::SpatialQuery, ApplyForceEvent, CreatureType, Cursor, ForceKind, SimState, SystemStages,
+};
+
+#[derive(Debug, Resource)]
+pub struct GustProperties {
+    pub radius: f32,
+    pub strength: f32,
+}
+
+impl Default for GustProperties {
+    fn default() -> Self {
+        GustProperties {
+            radius: 30.0,
+            strength: 1.0,
+        }
+    }
+}
+
+pub(crate) fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    if length_squared == 0.0 {
+        return point.distance(start);
+    }
+    let t = ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+    point.distance(start + segment * t)
+}
+
+/// Right click and drag to swipe through the creatures like a hand through a school of fish.
+fn gust_system(
+    timer: Res<Time>,
+    cursor: Res<Cursor>,
+    spatial_query: SpatialQuery,
+    gust_properties: Res<GustProperties>,
+    mut last_position: Local<Option<Vec2>>,
+    creature_query: Query<&Transform, With<CreatureType>>,
+    mut force_writer: EventWriter<ApplyForceEvent>,
+) {
+    if !cursor.button_states[2] {
+        *last_position = None;
+        return;
+    }
+
+    let end = cursor.position;
+    let Some(start) = last_position.replace(end) else {
+        return;
+    };
+
+    let swipe = end - start;
+    let delta_seconds = timer.delta_seconds();
+    if swipe.length_squared() == 0.0 || delta_seconds == 0.0 {
+        return;
+    }
+    let drag_speed = swipe.length() / delta_seconds;
+    let direction = swipe.normalize();
+    // Keep the lerp from overshooting on really fast flicks
+    let factor = (gust_properties.strength * drag_speed * 0.01).min(1.0 / delta_seconds);
+
+    // Walk along the swept segment asking the hash grid for anything nearby
+    let radius = gust_properties.radius;
+    let steps = (swipe.length() / radius).ceil().max(1.0) as usize;
+    let mut candidates = HashSet::default();
+    for step in 0..=steps {
+        let point = start.lerp(end, step as f32 / steps as f32);
+        candidates.extend(spatial_query.get_nearby_entities(point, radius));
+    }
+
+    for entity in candidates {
+        let Ok(transform) = creature_query.get(entity) else {
+            continue;
+        };
+        if distance_to_segment(transform.translation.xy(), start, end) <= radius {
+            force_writer.send(ApplyForceEvent(entity, direction, factor, ForceKind::Gust));
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct GustPlugin;
+
+impl Plugin for GustPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GustProperties::default()).add_system(
+            gust_system
+                .in_set(SystemStages::Calculate)
+                .in_set(OnUpdate(SimState::Running)),
+        );
+    }
+}
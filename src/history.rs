@@ -0,0 +1,260 @@
+// Undo and redo for everything done in the Edit Factors window. Each edit keeps
+// the factors from before and after it so going back is just putting the old
+// ones back. A whole slider drag counts as one edit.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_egui::EguiContexts;
+
+use crate::{remove_creature_type, CreatureType, FactorInfo, FrozenTypes};
+
+/// Edits past this many ago are forgotten
+const MAX_HISTORY: usize = 100;
+
+#[derive(Debug, Clone)]
+pub enum FactorEdit {
+    /// Sliders, checkboxes, pickers, and adding types
+    Changed {
+        before: FactorInfo,
+        after: FactorInfo,
+    },
+    /// Undoing turns the converted creatures back into the original type
+    DuplicatedType {
+        original: CreatureType,
+        duplicate: CreatureType,
+        converted: Vec<Entity>,
+        before: FactorInfo,
+        after: FactorInfo,
+    },
+    /// Undoing brings the type back but not the creatures that were that type
+    RemovedType {
+        creature_type: CreatureType,
+        before: FactorInfo,
+        frozen_before: FrozenTypes,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryAction {
+    Undo,
+    Redo,
+}
+
+#[derive(Debug, Default, Resource)]
+pub struct FactorHistory {
+    undo: Vec<FactorEdit>,
+    redo: Vec<FactorEdit>,
+    /// Factors from before the drag (or whatever) that's still going on
+    edit_start: Option<FactorInfo>,
+    /// From the buttons, done by `history_system` since it has everything needed
+    requested: Option<HistoryAction>,
+}
+
+impl FactorHistory {
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn request(&mut self, action: HistoryAction) {
+        self.requested = Some(action);
+    }
+
+    /// For when the factors get swapped out from outside the editor (loading a
+    /// scenario, resetting, warm starting) and undoing would bring back ones
+    /// from before that.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.edit_start = None;
+    }
+
+    pub(crate) fn record(&mut self, edit: FactorEdit) {
+        self.edit_start = None;
+        self.redo.clear();
+        self.undo.push(edit);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Called every frame with the factors from before and after the editor
+    /// drew. Changes pile up until the mouse is let go (or the text box is
+    /// left) and then become one edit.
+    pub(crate) fn track(&mut self, before: &FactorInfo, after: &FactorInfo, editing: bool) {
+        if before != after && self.edit_start.is_none() {
+            self.edit_start = Some(before.clone());
+        }
+        if editing {
+            return;
+        }
+        if let Some(start) = self.edit_start.take() {
+            if start != *after {
+                self.record(FactorEdit::Changed {
+                    before: start,
+                    after: after.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Puts a set of factors in, getting rid of creatures and frozen types left
+/// without any (like ones of a type that got un-added).
+pub(crate) fn restore_factors(
+    commands: &mut Commands,
+    all_factors: &mut FactorInfo,
+    frozen_types: &mut FrozenTypes,
+    creature_query: &Query<(Entity, &mut CreatureType)>,
+    factors: FactorInfo,
+) {
+    for (entity, creature_type) in creature_query.iter() {
+        if !factors.factors.contains_key(creature_type) {
+            commands.entity(entity).despawn();
+        }
+    }
+    frozen_types
+        .0
+        .retain(|frozen| factors.factors.contains_key(frozen));
+    *all_factors = factors;
+}
+
+/// Ctrl+Z and Ctrl+Y (or Ctrl+Shift+Z)
+#[derive(SystemParam)]
+struct HistoryShortcuts<'w, 's> {
+    keys: Res<'w, Input<KeyCode>>,
+    egui_context: EguiContexts<'w, 's>,
+}
+
+impl HistoryShortcuts<'_, '_> {
+    fn pressed(&mut self) -> Option<HistoryAction> {
+        let keys = &self.keys;
+        let ctrl = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+        let shift = keys.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+        // Text boxes have their own undo
+        let typing = self.egui_context.ctx_mut().wants_keyboard_input();
+        match (ctrl && !typing, shift) {
+            (true, false) if keys.just_pressed(KeyCode::Z) => Some(HistoryAction::Undo),
+            (true, true) if keys.just_pressed(KeyCode::Z) => Some(HistoryAction::Redo),
+            (true, _) if keys.just_pressed(KeyCode::Y) => Some(HistoryAction::Redo),
+            _ => None,
+        }
+    }
+}
+
+fn history_system(
+    mut commands: Commands,
+    mut shortcuts: HistoryShortcuts,
+    mut history: ResMut<FactorHistory>,
+    mut all_factors: ResMut<FactorInfo>,
+    mut frozen_types: ResMut<FrozenTypes>,
+    mut selected_creature_type: ResMut<CreatureType>,
+    mut creature_query: Query<(Entity, &mut CreatureType)>,
+) {
+    let pressed = shortcuts.pressed();
+    let Some(action) = history.requested.take().or(pressed) else {
+        return;
+    };
+
+    let edit = match action {
+        HistoryAction::Undo => history.undo.pop(),
+        HistoryAction::Redo => history.redo.pop(),
+    };
+    let Some(edit) = edit else {
+        return;
+    };
+    match (&edit, action) {
+        (FactorEdit::Changed { before, .. }, HistoryAction::Undo) => restore_factors(
+            &mut commands,
+            &mut all_factors,
+            &mut frozen_types,
+            &creature_query,
+            before.clone(),
+        ),
+        (FactorEdit::Changed { after, .. }, HistoryAction::Redo) => restore_factors(
+            &mut commands,
+            &mut all_factors,
+            &mut frozen_types,
+            &creature_query,
+            after.clone(),
+        ),
+        (
+            FactorEdit::RemovedType {
+                creature_type: removed,
+                before,
+                frozen_before,
+            },
+            HistoryAction::Undo,
+        ) => {
+            // Everyone that got moved down a type goes back up
+            for (_, mut creature_type) in creature_query.iter_mut() {
+                if creature_type.0 >= removed.0 {
+                    creature_type.0 += 1;
+                }
+            }
+            *all_factors = before.clone();
+            *frozen_types = frozen_before.clone();
+            *selected_creature_type = *removed;
+        }
+        (
+            FactorEdit::DuplicatedType {
+                original,
+                duplicate,
+                converted,
+                before,
+                after,
+            },
+            _,
+        ) => {
+            let (from, to, factors) = match action {
+                HistoryAction::Undo => (duplicate, original, before),
+                HistoryAction::Redo => (original, duplicate, after),
+            };
+            // Converted before restoring so none of them count as orphans
+            for &entity in converted.iter() {
+                if let Ok((_, mut creature_type)) = creature_query.get_mut(entity) {
+                    if *creature_type == *from {
+                        *creature_type = *to;
+                    }
+                }
+            }
+            restore_factors(
+                &mut commands,
+                &mut all_factors,
+                &mut frozen_types,
+                &creature_query,
+                factors.clone(),
+            );
+            *selected_creature_type = *to;
+        }
+        (FactorEdit::RemovedType { creature_type, .. }, HistoryAction::Redo) => {
+            remove_creature_type(
+                &mut commands,
+                &mut all_factors,
+                &mut frozen_types,
+                &mut creature_query,
+                *creature_type,
+            );
+        }
+    }
+    match action {
+        HistoryAction::Undo => history.redo.push(edit),
+        HistoryAction::Redo => history.undo.push(edit),
+    }
+    history.edit_start = None;
+    selected_creature_type.0 = selected_creature_type
+        .0
+        .min(all_factors.factors.len().saturating_sub(1));
+}
+
+#[derive(Default)]
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FactorHistory::default())
+            .add_system(history_system);
+    }
+}
@@ -0,0 +1,103 @@
+// Strict mode checks things that should always be true across systems and
+// panics the moment they aren't. Logic bugs in here usually don't crash, they
+// just show up as "huh, the boids are acting weird" which is way harder to debug.
+
+use bevy::prelude::*;
+
+use crate::{ApplyForceEvent, CreatureType, Energy, FactorInfo, HashGrid, SimState, SystemStages};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct StrictMode(pub bool);
+
+fn is_strict(strict_mode: Res<StrictMode>) -> bool {
+    strict_mode.0
+}
+
+fn check_energy_system(
+    factor_info: Res<FactorInfo>,
+    creature_query: Query<(Entity, &CreatureType, &Energy)>,
+) {
+    for (entity, creature_type, energy) in creature_query.iter() {
+        let Some(factors) = factor_info.factors.get(creature_type) else {
+            panic!(
+                "Invariant violated: {:?} is {} which has no factors (only {} types exist)",
+                entity,
+                creature_type,
+                factor_info.factors.len()
+            );
+        };
+        if energy.0 > factors.max_energy || energy.0 < 0.0 {
+            panic!(
+                "Invariant violated: {:?} ({}) has {} energy which is outside of 0..={}",
+                entity, creature_type, energy.0, factors.max_energy
+            );
+        }
+    }
+}
+
+fn check_spatial_index_system(
+    hash_grid: Res<HashGrid>,
+    creature_query: Query<(Entity, &Transform), With<CreatureType>>,
+    // Anything spawned this frame only gets indexed next frame
+    new_creature_query: Query<Entity, Added<CreatureType>>,
+) {
+    for (entity, transform) in creature_query.iter() {
+        if new_creature_query.get(entity).is_ok() {
+            continue;
+        }
+        if !hash_grid.contains(entity) {
+            panic!(
+                "Invariant violated: {:?} is alive but missing from the spatial index",
+                entity
+            );
+        }
+        // Catches keys wrapping around or colliding far away from the origin
+        let position = transform.translation.truncate();
+        let expected = hash_grid.chunk_of(position);
+        if hash_grid.cell_of(entity) != Some(expected) {
+            panic!(
+                "Invariant violated: {:?} at {:?} is filed under {:?} instead of {:?}",
+                entity,
+                position,
+                hash_grid.cell_of(entity),
+                expected
+            );
+        }
+    }
+}
+
+fn check_force_targets_system(
+    mut force_reader: EventReader<ApplyForceEvent>,
+    creature_query: Query<(), With<CreatureType>>,
+) {
+    for ApplyForceEvent(entity, force, factor, kind) in force_reader.iter() {
+        if creature_query.get(*entity).is_err() {
+            panic!(
+                "Invariant violated: {} force {:?} * {} was sent to {:?} which isn't a living creature",
+                kind, force, factor, entity
+            );
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct InvariantsPlugin {
+    pub strict: bool,
+}
+
+impl Plugin for InvariantsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(StrictMode(self.strict))
+            .add_system(
+                check_force_targets_system
+                    .in_set(SystemStages::Apply)
+                    .in_set(OnUpdate(SimState::Running))
+                    .run_if(is_strict),
+            )
+            .add_systems(
+                (check_energy_system, check_spatial_index_system)
+                    .in_base_set(CoreSet::PostUpdate)
+                    .distributive_run_if(is_strict),
+            );
+    }
+}
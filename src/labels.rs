@@ -0,0 +1,189 @@
+// Text floating over creatures showing their name, energy, or type. Text is
+// pricey to lay out so only creatures on screen get one and past a limit only
+// the ones closest to the middle of the screen do. The label entities get
+// reused between frames instead of respawned.
+
+use bevy::{ecs::system::SystemParam, math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    camera::CameraView, selection::Selected, tags::Tagged, ui::UiVisibility, CreatureType, Energy,
+    FactorInfo,
+};
+
+const LABEL_FONT_SIZE: f32 = 12.0;
+const LABEL_OFFSET: f32 = 12.0;
+const LABEL_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.8);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LabelContent {
+    /// Its name if it has one, otherwise its type's
+    #[default]
+    Name,
+    Energy,
+    TypeIndex,
+}
+
+impl LabelContent {
+    pub const ALL: [LabelContent; 3] = [
+        LabelContent::Name,
+        LabelContent::Energy,
+        LabelContent::TypeIndex,
+    ];
+}
+
+impl std::fmt::Display for LabelContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LabelContent::TypeIndex => write!(f, "Type Index"),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct LabelSettings {
+    pub enabled: bool,
+    pub content: LabelContent,
+    pub only_selected: bool,
+    /// Past this many only the ones closest to the middle of the screen get one
+    pub max_labels: usize,
+    /// How many creatures were on screen to label last frame, for the UI
+    pub candidates: usize,
+}
+
+impl Default for LabelSettings {
+    fn default() -> Self {
+        LabelSettings {
+            enabled: false,
+            content: LabelContent::Name,
+            only_selected: false,
+            max_labels: 100,
+            candidates: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+pub struct CreatureLabel;
+
+struct Candidate {
+    position: Vec2,
+    distance: f32,
+    text: String,
+}
+
+type LabeledCreature<'a> = (
+    &'a Transform,
+    &'a CreatureType,
+    &'a Energy,
+    Option<&'a Tagged>,
+    Option<&'a Selected>,
+);
+
+type LabelParts<'a> = (&'a mut Transform, &'a mut Text, &'a mut Visibility);
+
+/// Label entities along with what it takes to make more
+#[derive(SystemParam)]
+struct LabelPool<'w, 's> {
+    commands: Commands<'w, 's>,
+    asset_server: Res<'w, AssetServer>,
+    label_query: Query<'w, 's, LabelParts<'static>, (With<CreatureLabel>, Without<CreatureType>)>,
+}
+
+fn label_system(
+    mut settings: ResMut<LabelSettings>,
+    factor_info: Res<FactorInfo>,
+    ui_visibility: Option<Res<UiVisibility>>,
+    camera_view: CameraView,
+    label_pool: LabelPool,
+    creature_query: Query<LabeledCreature>,
+    mut candidates: Local<Vec<Candidate>>,
+) {
+    candidates.clear();
+    let view = camera_view.visible_area().filter(|_| {
+        settings.enabled && ui_visibility.is_some_and(|visibility| visibility.overlays_shown())
+    });
+    if let Some(view) = view {
+        for (transform, creature_type, energy, tagged, selected) in creature_query.iter() {
+            let position = transform.translation.xy();
+            if (settings.only_selected && selected.is_none()) || !view.contains(position) {
+                continue;
+            }
+            let text = match settings.content {
+                // Named ones already have their name over them
+                LabelContent::Name if tagged.is_some() => continue,
+                LabelContent::Name => factor_info.name_of(*creature_type),
+                LabelContent::Energy => format!("{:.0}", energy.0),
+                LabelContent::TypeIndex => creature_type.0.to_string(),
+            };
+            // Goes over the name instead of on top of it
+            let offset = if tagged.is_some() { 2.0 } else { 1.0 } * LABEL_OFFSET;
+            candidates.push(Candidate {
+                position: position + Vec2::Y * offset,
+                distance: position.distance_squared(view.center()),
+                text,
+            });
+        }
+    }
+    if settings.candidates != candidates.len() {
+        settings.candidates = candidates.len();
+    }
+    if candidates.len() > settings.max_labels {
+        candidates.select_nth_unstable_by(settings.max_labels, |a, b| {
+            a.distance.total_cmp(&b.distance)
+        });
+        candidates.truncate(settings.max_labels);
+    }
+
+    let LabelPool {
+        mut commands,
+        asset_server,
+        mut label_query,
+    } = label_pool;
+    let mut labels = label_query.iter_mut();
+    for candidate in candidates.drain(..) {
+        let Some((mut transform, mut text, mut visibility)) = labels.next() else {
+            // Not enough labels yet, more get made for next frame
+            commands.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        candidate.text,
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: LABEL_FONT_SIZE,
+                            color: LABEL_COLOR,
+                        },
+                    )
+                    .with_alignment(TextAlignment::Center),
+                    transform: Transform::from_translation(candidate.position.extend(10.0)),
+                    ..default()
+                },
+                CreatureLabel,
+            ));
+            continue;
+        };
+        transform.translation = candidate.position.extend(transform.translation.z);
+        if text.sections[0].value != candidate.text {
+            text.sections[0].value = candidate.text;
+        }
+        if *visibility != Visibility::Inherited {
+            *visibility = Visibility::Inherited;
+        }
+    }
+    // Leftovers stick around hidden for when they're needed again
+    for (_, _, mut visibility) in labels {
+        if *visibility != Visibility::Hidden {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LabelPlugin;
+
+impl Plugin for LabelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LabelSettings>()
+            .add_system(label_system.in_base_set(CoreSet::PostUpdate));
+    }
+}
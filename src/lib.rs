@@ -0,0 +1,3082 @@
+//! A flocking/predator-prey simulation you can drop into any Bevy app.
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use rusty_boids::{BoidsPlugin, MainCamera};
+//!
+//! fn setup(mut commands: Commands) {
+//!     commands.spawn(Camera2dBundle::default()).insert(MainCamera);
+//! }
+//!
+//! App::new()
+//!     .add_plugins(DefaultPlugins)
+//!     .add_startup_system(setup)
+//!     .add_plugin(BoidsPlugin::default().with_seed(Some(42)))
+//!     .run();
+//! ```
+//!
+//! The camera with [`MainCamera`] is used to figure out where the cursor is in the world.
+
+use bevy::{
+    asset::LoadState,
+    ecs::{schedule::apply_state_transition, system::SystemParam},
+    input::mouse::MouseButtonInput,
+    math::Vec3Swizzles,
+    prelude::*,
+    tasks::ComputeTaskPool,
+    utils::{HashMap, HashSet, StableHashMap, StableHashSet},
+    window::PrimaryWindow,
+};
+use bevy_egui::EguiContexts;
+use rand::{prelude::*, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+
+pub mod benchmark;
+#[cfg(feature = "boids-3d")]
+pub mod boids3d;
+pub mod camera;
+pub mod collision;
+pub mod coloring;
+pub mod comparison;
+pub mod data_log;
+pub mod death;
+pub mod dock;
+pub mod evasion;
+pub mod event_log;
+pub mod evolution;
+pub mod exposure;
+pub mod fear;
+pub mod files;
+pub mod flocks;
+pub mod flow;
+pub mod food;
+pub mod gamepad;
+pub mod goals;
+pub mod gravity;
+pub mod growth;
+pub mod gust;
+pub mod history;
+pub mod invariants;
+pub mod labels;
+pub mod lifecycle;
+pub mod margins;
+pub mod mask;
+pub mod maze;
+pub mod music;
+pub mod obstacles;
+pub mod overlays;
+pub mod packed;
+pub mod palettes;
+pub mod paths;
+pub mod persistence;
+pub mod personality;
+pub mod report;
+pub mod rooms;
+pub mod satiation;
+pub mod scenarios;
+pub mod screenshot;
+pub mod scripting;
+pub mod selection;
+pub mod smear;
+pub mod snapshot;
+pub mod sound;
+pub mod spatial;
+pub mod stats;
+pub mod steering;
+pub mod tags;
+pub mod temperature;
+pub mod theme;
+pub mod timeline;
+pub mod trails;
+pub mod ui;
+pub mod variation;
+pub mod weather;
+pub mod window_mode;
+
+use crate::{
+    benchmark::BenchmarkPlugin,
+    camera::CameraPlugin,
+    collision::{CollisionPlugin, CollisionSettings},
+    coloring::ColoringPlugin,
+    comparison::ComparisonPlugin,
+    data_log::DataLogPlugin,
+    death::{kill_creature, DeathAnimation, DeathAnimationSettings, DeathPlugin},
+    evasion::{Evasion, EvasionPlugin},
+    event_log::EventLogPlugin,
+    evolution::EvolutionPlugin,
+    exposure::ExposurePlugin,
+    fear::{Fear, FearPlugin},
+    flocks::FlockPlugin,
+    flow::FlowPlugin,
+    food::{FoodPlugin, FoodSettings},
+    gamepad::GamepadPlugin,
+    goals::GoalPlugin,
+    gravity::GravityPlugin,
+    growth::{BodySize, GrowthPlugin, GrowthSettings},
+    gust::{distance_to_segment, GustPlugin},
+    history::{FactorHistory, HistoryPlugin},
+    labels::LabelPlugin,
+    lifecycle::{BoidKilled, DeathCause, LifecyclePlugin},
+    margins::MarginPlugin,
+    mask::MaskPlugin,
+    maze::MazePlugin,
+    music::MusicPlugin,
+    obstacles::{ObstaclePlugin, ObstacleSet, ObstacleSettings},
+    packed::{
+        packed_flocking_forces, PackedFlocking, PackedFlockingPlugin, PackedNeighbors,
+        TypeRelations,
+    },
+    paths::PathPlugin,
+    persistence::PersistencePlugin,
+    personality::{Personality, PersonalityPlugin},
+    report::ReportPlugin,
+    rooms::RoomPlugin,
+    satiation::{Satiated, SatiationPlugin, SatiationSettings},
+    scenarios::{Scenario, ScenarioPlugin},
+    scripting::ScriptingPlugin,
+    selection::SelectionPlugin,
+    smear::SmearPlugin,
+    snapshot::SnapshotPlugin,
+    sound::SoundPlugin,
+    spatial::{SpatialIndex, SpatialPlugin, SpatialQuery},
+    stats::StatsPlugin,
+    steering::{steering_forces, SteeringBehaviors, SteeringRules, SteeringScratch},
+    tags::{Tagged, TagsPlugin},
+    temperature::{TemperaturePlugin, TemperatureSettings},
+    timeline::TimelinePlugin,
+    trails::TrailPlugin,
+    ui::{has_ui, UiPlugin},
+    variation::{Variation, VariationPlugin},
+    weather::{Weather, WeatherPlugin, WeatherSettings},
+    window_mode::WindowModePlugin,
+};
+
+pub const IS_WASM: bool = cfg!(target_arch = "wasm32");
+
+#[derive(Default, Resource)]
+pub struct Cursor {
+    pub position: Vec2,
+    pub button_states: [bool; 3],
+}
+
+/// Put this on the camera looking at the boids.
+#[derive(Component)]
+pub struct MainCamera;
+
+fn cursor_system(
+    mut cursor: ResMut<Cursor>,
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    mut mouse_button_events: EventReader<MouseButtonInput>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let (camera, camera_transform) = camera_query.single();
+    let window = primary_query.get_single().unwrap();
+    if let Some(screen_pos) = window.cursor_position() {
+        // Just the part of the window the camera draws to, which is the left
+        // half during a split screen comparison
+        let window_size = camera
+            .logical_viewport_size()
+            .unwrap_or(Vec2::new(window.width(), window.height()));
+
+        let normalized_device_coordinates = (screen_pos / window_size) * 2.0 - Vec2::ONE;
+        let normalized_device_coordinates_to_world =
+            camera_transform.compute_matrix() * camera.projection_matrix().inverse();
+        let world_pos = normalized_device_coordinates_to_world
+            .project_point3(normalized_device_coordinates.extend(-1.0));
+        let world_pos: Vec2 = world_pos.truncate();
+
+        cursor.position = world_pos;
+    }
+    for event in mouse_button_events.iter() {
+        let button_index = match event.button {
+            MouseButton::Left => 0,
+            MouseButton::Middle => 1,
+            MouseButton::Right => 2,
+            _ => continue,
+        };
+        cursor.button_states[button_index] = event.state.is_pressed();
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Resource, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Features {
+    pub chasing: bool,
+    pub running: bool,
+    pub killing: bool,
+    pub flocking: bool,
+    pub energy_draining: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Features {
+            chasing: true,
+            running: true,
+            flocking: true,
+            killing: false,
+            energy_draining: false,
+        }
+    }
+}
+
+pub const INITIAL_POPULATIONS: [usize; 3] = [
+    if IS_WASM { 500 } else { 1000 },
+    if IS_WASM { 50 } else { 200 },
+    if IS_WASM { 50 } else { 300 },
+];
+
+pub const CHUNK_RESOLUTION: usize = 20;
+
+/// Z between draw layers. Everything stays between 0 (the 2d camera doesn't
+/// see much further back) and the things drawn over creatures (0.5 and up).
+const LAYER_DEPTH: f32 = 0.04;
+pub const MAX_LAYER: i32 = 5;
+
+/// The hash grid doesn't bother giving back memory below this many slots
+const MIN_GRID_CAPACITY: usize = 1024;
+
+/// Time step used instead of the frame time when running deterministically.
+pub const DETERMINISTIC_DELTA: f32 = 1.0 / 60.0;
+
+/// The one source of randomness for the simulation. Same seed, same run
+/// (as long as it's a fresh launch in deterministic mode).
+#[derive(Debug, Resource)]
+pub struct SimRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        SimRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        *self = SimRng::new(seed);
+    }
+}
+
+impl Default for SimRng {
+    fn default() -> Self {
+        SimRng::new(rand::thread_rng().gen())
+    }
+}
+
+impl RngCore for SimRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// Steps the sim by a fixed amount every frame instead of following the clock.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct DeterministicMode(pub bool);
+
+impl DeterministicMode {
+    pub fn delta_seconds(&self, time: &Time) -> f32 {
+        if self.0 {
+            DETERMINISTIC_DELTA
+        } else {
+            time.delta_seconds()
+        }
+    }
+}
+
+/// Slow motion (or fast forward) for the whole sim. The UI keeps its own pace.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct TimeScale(pub f32);
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        TimeScale(1.0)
+    }
+}
+
+impl TimeScale {
+    pub const MIN: f32 = 0.1;
+    pub const MAX: f32 = 10.0;
+
+    pub fn set(&mut self, scale: f32) {
+        self.0 = scale.clamp(Self::MIN, Self::MAX);
+    }
+}
+
+/// How much sim time passes this frame. Use this instead of `Time` for anything
+/// that should follow deterministic mode and the time scale.
+#[derive(SystemParam)]
+pub(crate) struct SimTime<'w> {
+    time: Res<'w, Time>,
+    deterministic_mode: Res<'w, DeterministicMode>,
+    time_scale: Res<'w, TimeScale>,
+    step: Res<'w, SimStep>,
+}
+
+impl SimTime<'_> {
+    pub(crate) fn delta_seconds(&self) -> f32 {
+        let delta_seconds = if self.step.stepping {
+            DETERMINISTIC_DELTA
+        } else {
+            self.deterministic_mode.delta_seconds(&self.time)
+        };
+        delta_seconds * self.time_scale.0
+    }
+}
+
+/// Creature types sitting perfectly still while everything else keeps going.
+/// They can still be seen (and eaten) by others.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct FrozenTypes(pub HashSet<CreatureType>);
+
+impl FrozenTypes {
+    pub fn contains(&self, creature_type: &CreatureType) -> bool {
+        self.0.contains(creature_type)
+    }
+}
+
+/// Press `.` while paused to run exactly one tick and pause again.
+#[derive(Debug, Default, Resource)]
+pub struct SimStep {
+    requested: bool,
+    stepping: bool,
+}
+
+impl SimStep {
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    /// True for the one frame a step is running
+    pub fn is_stepping(&self) -> bool {
+        self.stepping
+    }
+}
+
+/// How many ticks the sim has run since it last restarted. A tick is a frame
+/// where it's not paused, which is also what a step runs.
+#[derive(Debug, Default, Resource)]
+pub struct SimTicks(pub u64);
+
+fn count_ticks_system(mut ticks: ResMut<SimTicks>) {
+    ticks.0 += 1;
+}
+
+/// A step is one deterministic tick so the fixed timestep can't run it zero or two times.
+fn ticks_every_frame(deterministic_mode: Res<DeterministicMode>, step: Res<SimStep>) -> bool {
+    deterministic_mode.0 || step.stepping
+}
+
+fn ticks_on_fixed_time(deterministic_mode: Res<DeterministicMode>, step: Res<SimStep>) -> bool {
+    !deterministic_mode.0 && !step.stepping
+}
+
+/// The size of the ecosystem, centered on the origin. This used to just be the
+/// window but then resizing the window would teleport everything around.
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Serialize, Deserialize)]
+pub struct WorldBounds {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        WorldBounds {
+            width: 1600.0,
+            height: 900.0,
+        }
+    }
+}
+
+impl WorldBounds {
+    pub fn new(width: f32, height: f32) -> Self {
+        WorldBounds { width, height }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width, self.height)
+    }
+
+    pub fn min(&self) -> Vec2 {
+        -self.size() / 2.0
+    }
+
+    pub fn max(&self) -> Vec2 {
+        self.size() / 2.0
+    }
+
+    /// Brings a position that left the world back in from the other side
+    pub fn wrap(&self, position: Vec2) -> Vec2 {
+        let offset = position - self.min();
+        Vec2::new(
+            offset.x.rem_euclid(self.width),
+            offset.y.rem_euclid(self.height),
+        ) + self.min()
+    }
+}
+
+/// How many of each type to spawn whenever the sim (re)starts.
+#[derive(Debug, Clone, Resource)]
+pub struct InitialPopulations(pub Vec<usize>);
+
+impl Default for InitialPopulations {
+    fn default() -> Self {
+        InitialPopulations(INITIAL_POPULATIONS.to_vec())
+    }
+}
+
+/// Wipes every creature, reseeds [`SimRng`] and spawns [`InitialPopulations`] again.
+pub struct RestartEvent;
+
+/// Swapping out every creature type at once, which is what loading a scenario,
+/// warm starting, and resetting the settings all come down to.
+#[derive(SystemParam)]
+pub(crate) struct ReplaceFactors<'w, 's> {
+    commands: Commands<'w, 's>,
+    factor_info: ResMut<'w, FactorInfo>,
+    history: Option<ResMut<'w, FactorHistory>>,
+    selected_creature_type: ResMut<'w, CreatureType>,
+    restart_events: EventWriter<'w, RestartEvent>,
+    creature_query: Query<'w, 's, Entity, With<CreatureType>>,
+}
+
+impl ReplaceFactors<'_, '_> {
+    /// Restarts with `factors` in place of the old ones. Old creatures might
+    /// have types the new factors don't know about so they have to be gone
+    /// before anything in `Update` looks them up.
+    pub(crate) fn replace(&mut self, factors: HashMap<CreatureType, Factors>) {
+        for entity in self.creature_query.iter() {
+            self.commands.entity(entity).despawn();
+        }
+        self.factor_info.factors = factors;
+        // The edits being undone were made to factors that are gone now
+        if let Some(history) = self.history.as_mut() {
+            history.clear();
+        }
+        *self.selected_creature_type = CreatureType::default();
+        self.restart_events.send(RestartEvent);
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, States, Default)]
+pub enum SimState {
+    #[default]
+    Running,
+    Paused,
+}
+
+#[derive(Debug, Clone, PartialEq, Resource, Reflect, Serialize, Deserialize)]
+pub struct Factors {
+    /// Shown instead of `Type N` when it isn't empty
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub color: Color,
+    pub speed: f32,
+    /// How far away predators and prey can be seen
+    pub vision: f32,
+    /// Flockmates this close pull it towards the middle of them
+    #[serde(default = "default_flock_radius")]
+    pub cohesion_radius: f32,
+    /// Flockmates this close get matched heading with
+    #[serde(default = "default_flock_radius")]
+    pub alignment_radius: f32,
+    /// Flockmates this close push it away. Real flocks keep this a lot
+    /// smaller than the other two.
+    #[serde(default = "default_separation_radius")]
+    pub separation_radius: f32,
+    pub size: f32,
+    /// Higher layers get drawn over lower ones. Within a layer smaller types
+    /// go on top so they don't get lost under big ones.
+    #[serde(default)]
+    pub layer: i32,
+    pub cohesion: f32,
+    pub separation: f32,
+    pub alignment: f32,
+    pub collision_avoidance: f32,
+    pub scare: f32,
+    pub chase: f32,
+    /// How many of this type it takes to gang up on a predator that's on its
+    /// own instead of running from it. 0 never does.
+    #[serde(default)]
+    pub mob_threshold: usize,
+    /// Gets energy from eating in food patches
+    #[serde(default)]
+    pub grazer: bool,
+    /// Slows down in the rain
+    #[serde(default)]
+    pub slowed_by_rain: bool,
+    /// Hunts together with the rest of its type it can see, all going after
+    /// the same prey from different sides
+    #[serde(default)]
+    pub pack_hunter: bool,
+    /// Seconds it keeps running after losing sight of a predator
+    #[serde(default = "default_fear_memory")]
+    pub fear_memory: f32,
+    /// How hard it swerves side to side running from a predator closer than
+    /// `zigzag_distance`. 0 runs straight.
+    #[serde(default)]
+    pub zigzag_amplitude: f32,
+    /// Zig-zags per second
+    #[serde(default = "default_zigzag_frequency")]
+    pub zigzag_frequency: f32,
+    #[serde(default = "default_zigzag_distance")]
+    pub zigzag_distance: f32,
+    /// Degrees it's comfortable between when temperature is on
+    #[serde(default = "default_comfort_min")]
+    pub comfort_min: f32,
+    #[serde(default = "default_comfort_max")]
+    pub comfort_max: f32,
+    /// How hard it heads for its type's goal when there is one
+    #[serde(default = "default_goal_seek")]
+    pub goal_seek: f32,
+    /// How hard it sticks to its type's patrol path when there is one
+    #[serde(default = "default_path_follow")]
+    pub path_follow: f32,
+    /// How far ahead along its patrol path it aims, so it rounds corners
+    /// instead of going right up to them
+    #[serde(default = "default_path_lookahead")]
+    pub path_lookahead: f32,
+    pub max_energy: f32,
+    pub predator_of: HashSet<CreatureType>,
+    #[serde(default)]
+    pub death_animation: DeathAnimation,
+    /// Image in `assets/` drawn (tinted by `color`) instead of a plain square
+    #[serde(default)]
+    pub texture: Option<String>,
+}
+
+/// The creature images that ship with the sim.
+pub const CREATURE_TEXTURES: [&str; 3] = [
+    "textures/fish.png",
+    "textures/bird.png",
+    "textures/shark.png",
+];
+
+impl Default for Factors {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            description: String::new(),
+            color: Color::PINK,
+            speed: 70.0,
+            vision: 15.0,
+            cohesion_radius: default_flock_radius(),
+            alignment_radius: default_flock_radius(),
+            separation_radius: default_separation_radius(),
+            size: 6.0,
+            layer: 0,
+            cohesion: 1.0,
+            separation: 1.0,
+            alignment: 3.0,
+            collision_avoidance: 4.0,
+            scare: 5.0,
+            chase: 5.0,
+            mob_threshold: 0,
+            grazer: false,
+            slowed_by_rain: false,
+            pack_hunter: false,
+            fear_memory: default_fear_memory(),
+            zigzag_amplitude: 0.0,
+            zigzag_frequency: default_zigzag_frequency(),
+            zigzag_distance: default_zigzag_distance(),
+            comfort_min: default_comfort_min(),
+            comfort_max: default_comfort_max(),
+            goal_seek: default_goal_seek(),
+            path_follow: default_path_follow(),
+            path_lookahead: default_path_lookahead(),
+            max_energy: 100.0,
+            predator_of: HashSet::default(),
+            death_animation: DeathAnimation::default(),
+            texture: None,
+        }
+    }
+}
+
+// Settings from before the radii were split up used the vision for cohesion and
+// alignment and half of it for separation, so these match the default vision
+fn default_flock_radius() -> f32 {
+    15.0
+}
+
+fn default_separation_radius() -> f32 {
+    7.5
+}
+
+fn default_fear_memory() -> f32 {
+    2.0
+}
+
+fn default_zigzag_frequency() -> f32 {
+    2.0
+}
+
+fn default_zigzag_distance() -> f32 {
+    10.0
+}
+
+fn default_comfort_min() -> f32 {
+    10.0
+}
+
+fn default_comfort_max() -> f32 {
+    30.0
+}
+
+fn default_goal_seek() -> f32 {
+    3.0
+}
+
+fn default_path_follow() -> f32 {
+    3.0
+}
+
+fn default_path_lookahead() -> f32 {
+    30.0
+}
+
+impl Factors {
+    /// Z to draw this type at
+    pub fn depth(&self) -> f32 {
+        let layer = self.layer.clamp(-MAX_LAYER, MAX_LAYER) + MAX_LAYER;
+        0.01 + layer as f32 * LAYER_DEPTH - self.size * 0.001
+    }
+
+    /// Farthest anything this type reacts to can be, which is how far out
+    /// neighbors need to be looked up.
+    pub fn reach(&self) -> f32 {
+        self.vision
+            .max(self.cohesion_radius)
+            .max(self.alignment_radius)
+            .max(self.separation_radius)
+            .max(self.size * 2.0)
+    }
+
+    /// Which way to run from a predator `distance` away when straight away is
+    /// `away`. Close enough and it swerves back and forth across that.
+    pub fn flee_direction(&self, away: Vec2, distance: f32, evasion_phase: f32) -> Vec2 {
+        if self.zigzag_amplitude <= 0.0 || distance > self.zigzag_distance {
+            return away;
+        }
+        let swerve = away.perp() * self.zigzag_amplitude * evasion_phase.sin();
+        (away + swerve).normalize_or_zero()
+    }
+
+    /// Whether `group_size` of this type can turn on the `predators` that
+    /// hunt them (it only works when there's just the one)
+    pub fn mobs(&self, group_size: usize, predators: usize) -> bool {
+        self.mob_threshold > 0 && predators == 1 && group_size >= self.mob_threshold
+    }
+
+    /// Rolls new values for everything about how the type moves and hunts.
+    /// The ranges are tighter than the sliders so whatever comes out still
+    /// flocks around instead of jittering in place or zooming off.
+    pub fn randomize(&mut self, rng: &mut impl Rng) {
+        self.speed = rng.gen_range(30.0..=150.0);
+        self.vision = rng.gen_range(10.0..=60.0);
+        self.cohesion_radius = rng.gen_range(10.0..=60.0);
+        self.alignment_radius = rng.gen_range(10.0..=60.0);
+        self.separation_radius = rng.gen_range(3.0..=20.0);
+        self.size = rng.gen_range(2.0..=9.0);
+        self.cohesion = rng.gen_range(0.0..=10.0);
+        self.separation = rng.gen_range(0.0..=10.0);
+        self.alignment = rng.gen_range(0.0..=10.0);
+        self.collision_avoidance = rng.gen_range(1.0..=15.0);
+        self.scare = rng.gen_range(0.0..=20.0);
+        self.chase = rng.gen_range(0.0..=20.0);
+        self.max_energy = rng.gen_range(40.0..=160.0);
+    }
+}
+
+/// Chance a randomized type hunts each of the other types
+const RANDOM_PREY_CHANCE: f64 = 0.3;
+
+/// Picks a new random set of types for a type to hunt (never itself).
+pub(crate) fn randomize_prey(
+    all_factors: &mut FactorInfo,
+    creature_type: CreatureType,
+    rng: &mut impl Rng,
+) {
+    let prey = (0..all_factors.factors.len())
+        .map(CreatureType)
+        .filter(|&other| other != creature_type && rng.gen_bool(RANDOM_PREY_CHANCE))
+        .collect();
+    all_factors
+        .factors
+        .get_mut(&creature_type)
+        .unwrap()
+        .predator_of = prey;
+}
+
+/// 8 bit sRGB channels of a color, for images and egui
+pub fn rgba_u8(color: Color) -> [u8; 4] {
+    color
+        .as_rgba_f32()
+        .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpawnProperties {
+    pub amount: usize,
+    pub radius: f32,
+    /// Creatures per second while dragging with the button held
+    pub paint_rate: f32,
+    /// How different each new creature's speed, vision, and size can be from
+    /// its type's (0.1 is up to 10% either way)
+    pub variation: f32,
+    /// How far boldness and sociability can stray from the middle
+    pub personality_spread: f32,
+}
+
+impl Default for SpawnProperties {
+    fn default() -> Self {
+        SpawnProperties {
+            amount: 10,
+            radius: 10.0,
+            paint_rate: 60.0,
+            variation: 0.1,
+            personality_spread: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+pub struct DespawnProperties {
+    pub radius: f32,
+}
+
+impl Default for DespawnProperties {
+    fn default() -> Self {
+        DespawnProperties { radius: 100.0 }
+    }
+}
+
+// TODO: Maybe generalize this?
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Copy,
+    Component,
+    Eq,
+    Hash,
+    Resource,
+    Reflect,
+    Serialize,
+    Deserialize,
+)]
+#[reflect(Component, Resource)]
+pub struct CreatureType(pub usize);
+
+impl From<usize> for CreatureType {
+    fn from(val: usize) -> Self {
+        CreatureType(val)
+    }
+}
+
+impl std::fmt::Display for CreatureType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Type {}", self.0)
+    }
+}
+
+#[derive(Component, Clone, Debug, Default, PartialEq, Reflect)]
+#[reflect(Component)]
+struct Direction(Vec2);
+
+// Why no work when adding directly to vec2?
+impl From<Vec2> for Direction {
+    fn from(v: Vec2) -> Self {
+        Direction(v)
+    }
+}
+
+impl From<Direction> for Vec2 {
+    fn from(d: Direction) -> Self {
+        d.0
+    }
+}
+
+impl Direction {
+    fn lerp(&mut self, other: Vec2, t: f32) {
+        self.0 = self.0.lerp(other, t).normalize();
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Component, PartialOrd, Reflect)]
+#[reflect(Component)]
+pub struct Energy(pub f32);
+
+/// Which rule a force came from. Only used for debugging and visualization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ForceKind {
+    Cohesion,
+    Alignment,
+    Separation,
+    CollisionAvoidance,
+    Chase,
+    Scare,
+    /// Prey ganging up on a predator or a predator backing off from them
+    Mob,
+    Gust,
+    /// Heading somewhere with a temperature it likes
+    Comfort,
+    /// Pulled in by a gravity well
+    Gravity,
+    /// Carried along by the flow field
+    Flow,
+    /// Heading for its type's goal
+    Goal,
+    /// Sticking to its type's patrol path
+    Path,
+    /// Turning away from a wall up ahead
+    Obstacle,
+    /// Turning back from the edge of the world
+    Margin,
+    Scripted,
+    /// Anything from a `SteeringBehavior` that isn't built in
+    Custom,
+}
+
+impl ForceKind {
+    pub const ALL: [ForceKind; 17] = [
+        ForceKind::Cohesion,
+        ForceKind::Alignment,
+        ForceKind::Separation,
+        ForceKind::CollisionAvoidance,
+        ForceKind::Chase,
+        ForceKind::Scare,
+        ForceKind::Mob,
+        ForceKind::Gust,
+        ForceKind::Comfort,
+        ForceKind::Gravity,
+        ForceKind::Flow,
+        ForceKind::Goal,
+        ForceKind::Path,
+        ForceKind::Obstacle,
+        ForceKind::Margin,
+        ForceKind::Scripted,
+        ForceKind::Custom,
+    ];
+}
+
+impl ForceKind {
+    /// Which forces get through first when steering is prioritized. Lower goes
+    /// first, so not crashing and not being eaten beat keeping up with the flock.
+    pub fn priority(&self) -> u8 {
+        match self {
+            ForceKind::CollisionAvoidance | ForceKind::Obstacle | ForceKind::Margin => 0,
+            ForceKind::Separation => 1,
+            ForceKind::Scare => 2,
+            ForceKind::Gust | ForceKind::Gravity | ForceKind::Flow => 3,
+            ForceKind::Chase | ForceKind::Mob => 4,
+            ForceKind::Comfort
+            | ForceKind::Goal
+            | ForceKind::Path
+            | ForceKind::Scripted
+            | ForceKind::Custom => 5,
+            ForceKind::Alignment => 6,
+            ForceKind::Cohesion => 7,
+        }
+    }
+}
+
+/// How every force on a creature in a tick turns into one turn. They're
+/// always added up first so the order they were sent in doesn't matter.
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Serialize, Deserialize)]
+pub struct SteeringCombination {
+    /// Hand out `priority_budget` to the most important forces first and drop
+    /// whatever doesn't fit, instead of blending all of them
+    pub prioritized: bool,
+    /// Most total factor a creature can turn with in a tick when prioritized
+    pub priority_budget: f32,
+}
+
+impl Default for SteeringCombination {
+    fn default() -> Self {
+        SteeringCombination {
+            prioritized: false,
+            priority_budget: 15.0,
+        }
+    }
+}
+
+impl SteeringCombination {
+    /// Weighted average of the forces and how hard to turn towards it, or
+    /// `None` if nothing's pushing. `forces` are `(priority, force, factor)`
+    /// sorted by priority.
+    fn combine(&self, forces: &[(u8, Vec2, f32)]) -> Option<(Vec2, f32)> {
+        let mut sum = Vec2::ZERO;
+        let mut total_weight = 0.0;
+        let mut start = 0;
+        while start < forces.len() {
+            // Everything with the same priority is in or out together so the
+            // order inside a priority still doesn't matter
+            let priority = forces[start].0;
+            let end = forces[start..]
+                .iter()
+                .position(|&(other, _, _)| other != priority)
+                .map_or(forces.len(), |length| start + length);
+            let mut tier_sum = Vec2::ZERO;
+            let mut tier_weight = 0.0;
+            for &(_, force, factor) in &forces[start..end] {
+                // Negative factors push the other way, same as the lerp used to
+                tier_sum += force * factor;
+                tier_weight += factor.abs();
+            }
+            start = end;
+            let scale = if self.prioritized {
+                let remaining = self.priority_budget - total_weight;
+                if remaining <= 0.0 {
+                    break;
+                }
+                (remaining / tier_weight).min(1.0)
+            } else {
+                1.0
+            };
+            sum += tier_sum * scale;
+            total_weight += tier_weight * scale;
+        }
+        (total_weight > 0.0).then(|| (sum / total_weight, total_weight))
+    }
+}
+
+impl std::fmt::Display for ForceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+pub(crate) struct ApplyForceEvent(pub Entity, pub Vec2, pub f32, pub ForceKind);
+
+struct EnergyChangeEvent(Entity, f32);
+
+#[derive(Debug, Clone, PartialEq, Resource, Default)]
+pub struct FactorInfo {
+    pub factors: HashMap<CreatureType, Factors>,
+}
+
+impl FactorInfo {
+    /// What the type's called, or `Type N` if it hasn't been named
+    pub fn name_of(&self, creature_type: CreatureType) -> String {
+        match self.factors.get(&creature_type) {
+            Some(factors) if !factors.name.trim().is_empty() => factors.name.clone(),
+            _ => creature_type.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
+pub(crate) enum SystemStages {
+    Spawn,
+    Calculate,
+    Apply,
+    Act,
+    Cache,
+}
+
+/// Side length of a hash grid chunk. Around the most common vision radius
+/// works best. Smaller means more chunks to look through for every query and
+/// bigger means more creatures in each one that end up too far away.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct ChunkResolution(pub f32);
+
+impl ChunkResolution {
+    pub const MIN: f32 = 5.0;
+    pub const MAX: f32 = 200.0;
+}
+
+impl Default for ChunkResolution {
+    fn default() -> Self {
+        ChunkResolution(CHUNK_RESOLUTION as f32)
+    }
+}
+
+fn chunk_of(position: Vec2, chunk_size: f32) -> (i32, i32) {
+    (
+        (position.y / chunk_size).floor() as i32,
+        (position.x / chunk_size).floor() as i32,
+    )
+}
+
+/// Uses the stable hashers so neighbors come back in the same order every
+/// launch. The default ones are seeded per process, which would break
+/// deterministic runs.
+#[derive(Debug, Resource)]
+pub(crate) struct HashGrid {
+    grid: StableHashMap<(i32, i32), StableHashSet<Entity>>,
+    associations: StableHashMap<Entity, (i32, i32)>,
+    chunk_size: f32,
+}
+
+impl Default for HashGrid {
+    fn default() -> Self {
+        HashGrid::new(CHUNK_RESOLUTION as f32)
+    }
+}
+
+impl HashGrid {
+    pub(crate) fn new(chunk_size: f32) -> Self {
+        HashGrid {
+            grid: StableHashMap::default(),
+            associations: StableHashMap::default(),
+            chunk_size: chunk_size.max(ChunkResolution::MIN),
+        }
+    }
+
+    /// The `(row, column)` chunk a position falls in. Flooring (instead of
+    /// truncating) keeps every chunk the same size on both sides of the axis.
+    pub(crate) fn chunk_of(&self, position: Vec2) -> (i32, i32) {
+        chunk_of(position, self.chunk_size)
+    }
+
+    fn update_entity(&mut self, entity: Entity, pos: Vec2) {
+        let (i, j) = self.chunk_of(pos);
+
+        // Note: `associations` could be extra overhead compared to the entity storing it.
+        if let Some((old_i, old_j)) = self.associations.get(&entity) {
+            let old_i = *old_i;
+            let old_j = *old_j;
+            if i == old_i && j == old_j {
+                return;
+            }
+
+            if let Some(set) = self.grid.get_mut(&(old_i, old_j)) {
+                set.remove(&entity);
+                if set.is_empty() {
+                    self.grid.remove(&(old_i, old_j));
+                }
+            }
+        }
+
+        self.grid
+            .entry((i, j))
+            .or_insert(StableHashSet::default())
+            .insert(entity);
+        self.associations.insert(entity, (i, j));
+    }
+
+    fn remove_entity(&mut self, entity: Entity) {
+        let Some(cell) = self.associations.remove(&entity) else {
+            return;
+        };
+        if let Some(set) = self.grid.get_mut(&cell) {
+            set.remove(&entity);
+            if set.is_empty() {
+                self.grid.remove(&cell);
+            }
+        }
+    }
+
+    /// Gives memory back after a big die off. Big worlds can leave a lot of
+    /// cells behind that nothing will be in again for a while.
+    fn shrink(&mut self) {
+        let (cells, entities) = (self.grid.len(), self.associations.len());
+        if self.grid.capacity() > (cells * 4).max(MIN_GRID_CAPACITY) {
+            self.grid.shrink_to(cells * 2);
+        }
+        if self.associations.capacity() > (entities * 4).max(MIN_GRID_CAPACITY) {
+            self.associations.shrink_to(entities * 2);
+        }
+    }
+
+    /// Occupied `(row, column)` chunks and how many entities are in each
+    pub(crate) fn cells(&self) -> impl Iterator<Item = ((i32, i32), usize)> + '_ {
+        self.grid.iter().map(|(&cell, set)| (cell, set.len()))
+    }
+
+    /// World space `(min, max)` covered by a chunk
+    pub(crate) fn cell_bounds(&self, (row, column): (i32, i32)) -> (Vec2, Vec2) {
+        let min = Vec2::new(column as f32, row as f32) * self.chunk_size;
+        (min, min + Vec2::splat(self.chunk_size))
+    }
+
+    /// The `(row, column)` chunk an entity was last filed under
+    pub(crate) fn cell_of(&self, entity: Entity) -> Option<(i32, i32)> {
+        self.associations.get(&entity).copied()
+    }
+
+    /// How many entities are in the same chunk as this one, counting itself
+    pub(crate) fn cell_count(&self, entity: Entity) -> usize {
+        self.associations
+            .get(&entity)
+            .and_then(|cell| self.grid.get(cell))
+            .map_or(0, |set| set.len())
+    }
+
+    pub(crate) fn contains(&self, entity: Entity) -> bool {
+        match self.associations.get(&entity) {
+            Some(cell) => self.grid.get(cell).is_some_and(|set| set.contains(&entity)),
+            None => false,
+        }
+    }
+}
+
+impl SpatialIndex for HashGrid {
+    fn get_nearby_entities(&self, position: Vec2, radius: f32) -> Vec<Entity> {
+        let mut result = vec![];
+
+        // Every chunk touched by the square around the circle
+        let (i_begin, j_begin) = self.chunk_of(position - Vec2::splat(radius));
+        let (i_end, j_end) = self.chunk_of(position + Vec2::splat(radius));
+
+        for i in i_begin..=i_end {
+            for j in j_begin..=j_end {
+                if let Some(set) = self.grid.get(&(i, j)) {
+                    result.extend(set.iter());
+                }
+            }
+        }
+
+        result
+    }
+}
+
+pub fn spawn_creature(
+    x: f32,
+    y: f32,
+    direction_vector: Vec2,
+    creature_type: CreatureType,
+    all_factors: &HashMap<CreatureType, Factors>,
+    commands: &mut Commands,
+) {
+    let factors = all_factors.get(&creature_type).unwrap();
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: factors.color,
+                custom_size: Some(Vec2::splat(factors.size)),
+                ..Sprite::default()
+            },
+            transform: Transform {
+                translation: Vec3::new(x, y, factors.depth()),
+                rotation: Quat::from_rotation_z(-direction_vector.x.atan2(direction_vector.y)),
+                ..Transform::default()
+            },
+            ..SpriteBundle::default()
+        })
+        .insert(Direction(direction_vector))
+        .insert(Energy(factors.max_energy))
+        .insert(creature_type);
+}
+
+pub fn spawn_creature_randomly(
+    rng: &mut impl Rng,
+    commands: &mut Commands,
+    creature_type: CreatureType,
+    all_factors: &HashMap<CreatureType, Factors>,
+    min: Vec2,
+    max: Vec2,
+) {
+    let x = rng.gen_range(min.x..=max.x);
+    let y = rng.gen_range(min.y..=max.y);
+    let direction_vector =
+        Vec2::new(rng.gen::<f32>() * 2.0 - 1.0, rng.gen::<f32>() * 2.0 - 1.0).normalize();
+    spawn_creature(x, y, direction_vector, creature_type, all_factors, commands);
+}
+
+/// Evenly spread over the disk, not bunched up in the middle.
+pub fn spawn_creature_randomly_in_circle(
+    rng: &mut impl Rng,
+    commands: &mut Commands,
+    creature_type: CreatureType,
+    all_factors: &HashMap<CreatureType, Factors>,
+    center: Vec2,
+    radius: f32,
+) {
+    let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+    let distance = radius * rng.gen::<f32>().sqrt();
+    let position = center + Vec2::from_angle(angle) * distance;
+    let direction_vector =
+        Vec2::new(rng.gen::<f32>() * 2.0 - 1.0, rng.gen::<f32>() * 2.0 - 1.0).normalize();
+    spawn_creature(
+        position.x,
+        position.y,
+        direction_vector,
+        creature_type,
+        all_factors,
+        commands,
+    );
+}
+
+pub fn spawn_creature_randomly_in_bounds(
+    rng: &mut impl Rng,
+    commands: &mut Commands,
+    creature_type: CreatureType,
+    all_factors: &HashMap<CreatureType, Factors>,
+    world_bounds: &WorldBounds,
+) {
+    spawn_creature_randomly(
+        rng,
+        commands,
+        creature_type,
+        all_factors,
+        world_bounds.min(),
+        world_bounds.max(),
+    );
+}
+
+/// Takes a creature type out of everything, despawning its creatures and
+/// moving every type after it down one so the types stay `0..n`.
+pub(crate) fn remove_creature_type(
+    commands: &mut Commands,
+    all_factors: &mut FactorInfo,
+    frozen_types: &mut FrozenTypes,
+    creature_query: &mut Query<(Entity, &mut CreatureType)>,
+    removed: CreatureType,
+) {
+    // This is so hacky. I hate this. I'm so sorry.
+    let removed_index = removed.0;
+    for (entity, mut creature_type) in creature_query.iter_mut() {
+        if *creature_type.as_ref() == removed {
+            commands.entity(entity).despawn();
+        } else if creature_type.0 > removed_index {
+            creature_type.0 -= 1;
+        }
+    }
+
+    for (mut creature_type, mut factors) in all_factors.factors.drain().collect::<Vec<_>>() {
+        if creature_type == removed {
+            continue;
+        } else if creature_type.0 > removed_index {
+            creature_type.0 -= 1;
+        }
+        factors.predator_of.remove(&removed);
+        for mut prey in factors.predator_of.drain().collect::<Vec<_>>() {
+            if prey == removed {
+                continue;
+            } else if prey.0 > removed_index {
+                prey.0 -= 1;
+            }
+            factors.predator_of.insert(prey);
+        }
+        all_factors.factors.insert(creature_type, factors);
+    }
+    frozen_types.0 = frozen_types
+        .0
+        .drain()
+        .filter(|frozen| frozen.0 != removed_index)
+        .map(|frozen| CreatureType(frozen.0 - (frozen.0 > removed_index) as usize))
+        .collect();
+}
+
+/// Adds a copy of a creature type as a new type at the end. It hunts and is
+/// hunted by the same types as the original but the two leave each other alone.
+pub(crate) fn duplicate_creature_type(
+    all_factors: &mut FactorInfo,
+    original: CreatureType,
+) -> CreatureType {
+    let duplicate = CreatureType(all_factors.factors.len());
+    let mut factors = all_factors.factors.get(&original).unwrap().clone();
+    // Same everything but the color would make them impossible to tell apart
+    let [hue, saturation, lightness, alpha] = factors.color.as_hsla_f32();
+    factors.color = Color::hsla((hue + 40.0) % 360.0, saturation, lightness, alpha);
+    if !factors.name.is_empty() {
+        factors.name = format!("{} Copy", factors.name);
+    }
+    all_factors.factors.insert(duplicate, factors);
+    for factors in all_factors.factors.values_mut() {
+        if factors.predator_of.contains(&original) {
+            factors.predator_of.insert(duplicate);
+        }
+    }
+    duplicate
+}
+
+/// Factors as JSON for sharing. One type is a single object and every type is
+/// a list in type order so it can be told apart when it's pasted back.
+pub(crate) fn factors_to_json(
+    all_factors: &FactorInfo,
+    creature_type: Option<CreatureType>,
+) -> Result<String, String> {
+    let result = match creature_type {
+        Some(creature_type) => serde_json::to_string_pretty(&all_factors.factors[&creature_type]),
+        None => serde_json::to_string_pretty(
+            &(0..all_factors.factors.len())
+                .map(|index| &all_factors.factors[&CreatureType(index)])
+                .collect::<Vec<_>>(),
+        ),
+    };
+    result.map_err(|error| error.to_string())
+}
+
+/// What came out of pasted JSON from `factors_to_json`
+pub(crate) enum PastedFactors {
+    One(Factors),
+    All(FactorInfo),
+}
+
+pub(crate) fn factors_from_json(json: &str) -> Result<PastedFactors, String> {
+    if let Ok(all) = serde_json::from_str::<Vec<Factors>>(json) {
+        if all.is_empty() {
+            return Err("There has to be at least one type".to_string());
+        }
+        let type_count = all.len();
+        let factors = all
+            .into_iter()
+            .enumerate()
+            .map(|(index, mut factors)| {
+                factors.predator_of.retain(|prey| prey.0 < type_count);
+                (CreatureType(index), factors)
+            })
+            .collect();
+        return Ok(PastedFactors::All(FactorInfo { factors }));
+    }
+    serde_json::from_str::<Factors>(json)
+        .map(PastedFactors::One)
+        .map_err(|error| error.to_string())
+}
+
+fn spawn_initial_populations(
+    rng: &mut impl Rng,
+    commands: &mut Commands,
+    populations: &[usize],
+    all_factors: &HashMap<CreatureType, Factors>,
+    world_bounds: &WorldBounds,
+) {
+    populations
+        .iter()
+        .enumerate()
+        .for_each(|(index, &population_size)| {
+            let creature_type = CreatureType(index);
+            if !all_factors.contains_key(&creature_type) {
+                return;
+            }
+            for _ in 0..population_size {
+                spawn_creature_randomly_in_bounds(
+                    rng,
+                    commands,
+                    creature_type,
+                    all_factors,
+                    world_bounds,
+                );
+            }
+        });
+}
+
+/// Everything it takes to fill the world with [`InitialPopulations`]
+#[derive(SystemParam)]
+struct PopulationSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    rng: ResMut<'w, SimRng>,
+    factor_info: Res<'w, FactorInfo>,
+    initial_populations: Res<'w, InitialPopulations>,
+    world_bounds: Res<'w, WorldBounds>,
+}
+
+impl PopulationSpawner<'_, '_> {
+    fn spawn_initial_populations(&mut self) {
+        spawn_initial_populations(
+            self.rng.as_mut(),
+            &mut self.commands,
+            &self.initial_populations.0,
+            &self.factor_info.factors,
+            &self.world_bounds,
+        );
+    }
+}
+
+fn setup_creatures(mut spawner: PopulationSpawner) {
+    spawner.spawn_initial_populations();
+}
+
+fn restart_system(
+    mut spawner: PopulationSpawner,
+    mut hash_grid: ResMut<HashGrid>,
+    mut restart_events: EventReader<RestartEvent>,
+    creature_query: Query<Entity, With<CreatureType>>,
+    mut frozen_types: ResMut<FrozenTypes>,
+    mut ticks: ResMut<SimTicks>,
+) {
+    if restart_events.iter().count() == 0 {
+        return;
+    }
+    ticks.0 = 0;
+    // Types can mean something else entirely after a scenario load
+    frozen_types.0.clear();
+
+    for entity in creature_query.iter() {
+        spawner.commands.entity(entity).despawn();
+    }
+    *hash_grid = HashGrid::new(hash_grid.chunk_size);
+
+    let seed = spawner.rng.seed();
+    spawner.rng.reseed(seed);
+
+    spawner.spawn_initial_populations();
+}
+
+type MovingCreature<'a> = (
+    &'a mut Transform,
+    &'a Direction,
+    &'a CreatureType,
+    Option<&'a Variation>,
+    Option<&'a Satiated>,
+);
+
+fn move_system(
+    mut query: Query<MovingCreature>,
+    factor_info: Res<FactorInfo>,
+    satiation: Res<SatiationSettings>,
+    weather: Res<Weather>,
+    weather_settings: Res<WeatherSettings>,
+    sim_time: SimTime,
+    frozen_types: Res<FrozenTypes>,
+) {
+    let delta_seconds = sim_time.delta_seconds();
+    for (mut transform, direction, creature_type, variation, satiated) in query.iter_mut() {
+        if frozen_types.contains(creature_type) {
+            continue;
+        }
+        let factors = factor_info.factors.get(creature_type).unwrap();
+        let mut speed = variation.map_or(factors.speed, |variation| variation.speed(factors));
+        if satiated.is_some() {
+            speed *= satiation.speed_scale;
+        }
+        speed *= weather.speed_scale(&weather_settings, factors);
+        transform.translation.x += direction.0.x * speed * delta_seconds;
+        transform.translation.y += direction.0.y * speed * delta_seconds;
+        transform.rotation = Quat::from_rotation_z(-direction.0.x.atan2(direction.0.y));
+    }
+}
+
+fn wrap_borders_system(
+    mut query: Query<&mut Transform, With<CreatureType>>,
+    world_bounds: Res<WorldBounds>,
+) {
+    let (min, max) = (world_bounds.min(), world_bounds.max());
+    for mut transform in query.iter_mut() {
+        let position = transform.translation.xy();
+        if position.cmplt(min).any() || position.cmpge(max).any() {
+            // Shrinking the world can leave things way outside of it so this
+            // wraps however far out they are instead of just across one edge
+            let wrapped = world_bounds.wrap(position);
+            transform.translation = wrapped.extend(transform.translation.z);
+        }
+    }
+}
+
+#[derive(Component)]
+struct BoundaryLine;
+
+const BOUNDARY_THICKNESS: f32 = 2.0;
+
+fn setup_boundary_lines(mut commands: Commands) {
+    for _ in 0..4 {
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.25),
+                    ..Sprite::default()
+                },
+                ..SpriteBundle::default()
+            })
+            .insert(BoundaryLine);
+    }
+}
+
+fn boundary_lines_system(
+    world_bounds: Res<WorldBounds>,
+    mut line_query: Query<(&mut Sprite, &mut Transform), With<BoundaryLine>>,
+) {
+    if !world_bounds.is_changed() {
+        return;
+    }
+    let (width, height) = (world_bounds.width, world_bounds.height);
+    let lines = [
+        (
+            Vec2::new(0.0, height / 2.0),
+            Vec2::new(width, BOUNDARY_THICKNESS),
+        ),
+        (
+            Vec2::new(0.0, -height / 2.0),
+            Vec2::new(width, BOUNDARY_THICKNESS),
+        ),
+        (
+            Vec2::new(width / 2.0, 0.0),
+            Vec2::new(BOUNDARY_THICKNESS, height),
+        ),
+        (
+            Vec2::new(-width / 2.0, 0.0),
+            Vec2::new(BOUNDARY_THICKNESS, height),
+        ),
+    ];
+    for ((mut sprite, mut transform), (position, size)) in line_query.iter_mut().zip(lines) {
+        sprite.custom_size = Some(size);
+        // Behind the creatures
+        transform.translation = position.extend(-1.0);
+    }
+}
+
+/// What one creature needs to know about another to steer around it.
+#[derive(Debug, Clone, Copy)]
+pub struct Neighbor {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub direction: Vec2,
+    pub creature_type: CreatureType,
+    pub variation: Variation,
+    pub personality: Personality,
+    /// Full predators don't go after anything
+    pub satiated: bool,
+    /// Still running from a predator it can't see anymore
+    pub fear: Option<Fear>,
+    /// Where it is in its zig-zag, see [`Factors::flee_direction`]
+    pub evasion_phase: f32,
+}
+
+/// Everything the packed flocking path adds up over the neighbors before
+/// turning it into forces.
+#[derive(Default)]
+struct FlockSums {
+    /// Cohesion
+    position_sum: Vec2,
+    cohesion_count: usize,
+    /// Alignment
+    direction_sum: Vec2,
+    alignment_count: usize,
+    /// Separation
+    close_position_sum: Vec2,
+    separation_count: usize,
+    closest_target: Option<(f32, Vec2)>,
+}
+
+impl FlockSums {
+    fn consider_target(&mut self, distance: f32, position: Vec2) {
+        match self.closest_target {
+            Some((closest_distance, _)) if closest_distance <= distance => {}
+            _ => self.closest_target = Some((distance, position)),
+        }
+    }
+
+    fn push_events(
+        self,
+        creature: &Neighbor,
+        factors: &Factors,
+        features: &Features,
+        events: &mut Vec<ApplyForceEvent>,
+    ) {
+        let (entity, position) = (creature.entity, creature.position);
+        if self.cohesion_count > 0 && features.flocking {
+            let average_position = self.position_sum / self.cohesion_count as f32;
+            let cohesion_force = (average_position - position).normalize();
+            events.push(ApplyForceEvent(
+                entity,
+                cohesion_force,
+                factors.cohesion * creature.personality.cohesion_scale(),
+                ForceKind::Cohesion,
+            ));
+        }
+        if self.alignment_count > 0 && features.flocking {
+            let average_direction = self.direction_sum / self.alignment_count as f32;
+            events.push(ApplyForceEvent(
+                entity,
+                average_direction.normalize(),
+                factors.alignment,
+                ForceKind::Alignment,
+            ));
+        }
+        if self.separation_count > 0 && features.flocking {
+            let average_close_position = self.close_position_sum / self.separation_count as f32;
+            let separation_force = (position - average_close_position).normalize();
+            events.push(ApplyForceEvent(
+                entity,
+                separation_force,
+                factors.separation,
+                ForceKind::Separation,
+            ));
+        }
+
+        // Chase
+        if let Some((_, closest_position)) = self.closest_target {
+            let chase_direction = (closest_position - position).normalize();
+            events.push(ApplyForceEvent(
+                entity,
+                chase_direction,
+                factors.chase,
+                ForceKind::Chase,
+            ));
+        }
+    }
+}
+
+/// Splits the world into a grid of tiles that each get simulated on their own
+/// task. Creatures close enough to a tile's border to be seen from the other
+/// side get copied into the neighboring tiles as read only "ghosts" so the only
+/// thing shared between tasks is that thin halo.
+#[derive(Debug, Clone, Resource)]
+pub struct ArenaTiling {
+    pub enabled: bool,
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl Default for ArenaTiling {
+    fn default() -> Self {
+        ArenaTiling {
+            enabled: false,
+            columns: 4,
+            rows: 4,
+        }
+    }
+}
+
+/// Creatures the camera can't see only rethink where they're going every few
+/// ticks. They keep moving in between so nothing looks off when they come back.
+#[derive(Debug, Clone, Resource)]
+pub struct LevelOfDetail {
+    pub enabled: bool,
+    /// Off-screen creatures flock once every this many ticks
+    pub off_screen_interval: u32,
+    /// What the camera saw last frame. `None` treats everything as on screen.
+    visible_area: Option<Rect>,
+}
+
+impl Default for LevelOfDetail {
+    fn default() -> Self {
+        LevelOfDetail {
+            enabled: true,
+            off_screen_interval: 4,
+            visible_area: None,
+        }
+    }
+}
+
+fn level_of_detail_system(
+    mut level_of_detail: ResMut<LevelOfDetail>,
+    deterministic_mode: Res<DeterministicMode>,
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&GlobalTransform, &OrthographicProjection), With<MainCamera>>,
+) {
+    // Deterministic runs shouldn't depend on where the camera happens to be
+    let visible_area = match (primary_query.get_single(), camera_query.get_single()) {
+        (Ok(window), Ok((camera_transform, projection)))
+            if level_of_detail.enabled && !deterministic_mode.0 =>
+        {
+            let half_size = Vec2::new(window.width(), window.height()) * projection.scale / 2.0;
+            Some(Rect::from_center_half_size(
+                camera_transform.translation().xy(),
+                half_size,
+            ))
+        }
+        _ => None,
+    };
+    if level_of_detail.visible_area != visible_area {
+        level_of_detail.visible_area = visible_area;
+    }
+}
+
+/// Reuses each creature's neighbor list for a few ticks instead of asking the
+/// spatial index every time. Flocks don't change who's in them nearly as fast
+/// as the frame rate. Only used when arena tiling is off.
+#[derive(Debug, Resource)]
+pub struct NeighborCache {
+    pub enabled: bool,
+    /// Neighbors get looked up again at least this often (in ticks). Crossing
+    /// into another grid chunk also forces a lookup.
+    pub refresh_interval: u32,
+    entries: HashMap<Entity, CachedNeighbors>,
+}
+
+impl Default for NeighborCache {
+    fn default() -> Self {
+        NeighborCache {
+            enabled: false,
+            refresh_interval: 5,
+            entries: HashMap::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedNeighbors {
+    cell: (i32, i32),
+    entities: Vec<Entity>,
+}
+
+impl NeighborCache {
+    /// The cached neighbors if they're still good to use this tick
+    fn get(&self, entity: Entity, cell: (i32, i32), tick: u32) -> Option<&[Entity]> {
+        if !self.enabled {
+            return None;
+        }
+        // Staggered like level of detail so every creature doesn't refresh at once
+        let due = entity
+            .index()
+            .wrapping_add(tick)
+            .is_multiple_of(self.refresh_interval.max(1));
+        self.entries
+            .get(&entity)
+            .filter(|cached| cached.cell == cell && !due)
+            .map(|cached| cached.entities.as_slice())
+    }
+}
+
+/// Decides which creatures get to flock this tick.
+struct FlockingFilter<'a> {
+    frozen_types: &'a FrozenTypes,
+    visible_area: Option<Rect>,
+    off_screen_interval: u32,
+    tick: u32,
+    /// Only there when obstacles block sight and there are any
+    obstacles: Option<&'a ObstacleSet>,
+}
+
+impl FlockingFilter<'_> {
+    fn skips(&self, creature: &Neighbor) -> bool {
+        if self.frozen_types.contains(&creature.creature_type) {
+            return true;
+        }
+        match self.visible_area {
+            // Staggered by entity so the off-screen ones don't all update on the same tick
+            Some(area) if !area.contains(creature.position) => !creature
+                .entity
+                .index()
+                .wrapping_add(self.tick)
+                .is_multiple_of(self.off_screen_interval.max(1)),
+            _ => false,
+        }
+    }
+
+    fn can_see(&self, creature: &Neighbor, neighbor: &Neighbor) -> bool {
+        self.obstacles
+            .is_none_or(|obstacles| obstacles.line_of_sight(creature.position, neighbor.position))
+    }
+}
+
+#[derive(Default)]
+struct Tile {
+    owned: Vec<Neighbor>,
+    visible: Vec<Neighbor>,
+}
+
+fn tiled_flocking_events(
+    creatures: Vec<Neighbor>,
+    tiling: &ArenaTiling,
+    world_size: Vec2,
+    rules: SteeringRules,
+    filter: &FlockingFilter,
+    chunk_size: f32,
+) -> Vec<ApplyForceEvent> {
+    let factor_info = rules.factor_info;
+    let columns = tiling.columns.max(1);
+    let rows = tiling.rows.max(1);
+    let tile_size = world_size / Vec2::new(columns as f32, rows as f32);
+    let world_min = -world_size / 2.0;
+    let halo = creatures
+        .iter()
+        .filter_map(|creature| {
+            let factors = factor_info.factors.get(&creature.creature_type)?;
+            Some(creature.variation.reach(factors))
+        })
+        .fold(0.0, f32::max);
+
+    let tile_of = |position: Vec2| {
+        let tile = ((position - world_min) / tile_size).floor();
+        (
+            (tile.x.max(0.0) as usize).min(columns - 1),
+            (tile.y.max(0.0) as usize).min(rows - 1),
+        )
+    };
+
+    // Boundary exchange: every creature goes to the tile it's in and gets
+    // shared with every tile whose border is within the farthest reach.
+    let mut tiles = (0..columns * rows)
+        .map(|_| Tile::default())
+        .collect::<Vec<_>>();
+    for creature in creatures {
+        let (column, row) = tile_of(creature.position);
+        tiles[row * columns + column].owned.push(creature);
+
+        let (min_column, min_row) = tile_of(creature.position - Vec2::splat(halo));
+        let (max_column, max_row) = tile_of(creature.position + Vec2::splat(halo));
+        for visible_row in min_row..=max_row {
+            for visible_column in min_column..=max_column {
+                tiles[visible_row * columns + visible_column]
+                    .visible
+                    .push(creature);
+            }
+        }
+    }
+
+    ComputeTaskPool::get()
+        .scope(|scope| {
+            for tile in tiles.iter() {
+                scope.spawn(async move {
+                    let mut events = vec![];
+                    if tile.owned.is_empty() {
+                        return events;
+                    }
+                    let mut scratch = SteeringScratch::default();
+
+                    // Each tile gets its own little hash grid of everything it can see
+                    let mut local_grid: HashMap<(i32, i32), Vec<usize>> = HashMap::default();
+                    let cell_of = |position| chunk_of(position, chunk_size);
+                    for (index, creature) in tile.visible.iter().enumerate() {
+                        local_grid
+                            .entry(cell_of(creature.position))
+                            .or_default()
+                            .push(index);
+                    }
+
+                    for creature in tile.owned.iter() {
+                        if filter.skips(creature) {
+                            continue;
+                        }
+                        let reach = creature
+                            .variation
+                            .reach(&factor_info.factors[&creature.creature_type]);
+                        let (i_begin, j_begin) = cell_of(creature.position - Vec2::splat(reach));
+                        let (i_end, j_end) = cell_of(creature.position + Vec2::splat(reach));
+                        let neighbors = (i_begin..=i_end)
+                            .flat_map(|i| (j_begin..=j_end).map(move |j| (i, j)))
+                            .filter_map(|cell| local_grid.get(&cell))
+                            .flatten()
+                            .map(|&index| tile.visible[index])
+                            .filter(|neighbor| filter.can_see(creature, neighbor));
+                        steering_forces(creature, neighbors, rules, &mut scratch, &mut events);
+                    }
+                    events
+                });
+            }
+        })
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// What's around the creatures that gets in the way of them seeing each other
+#[derive(SystemParam)]
+struct Surroundings<'w> {
+    weather: Res<'w, Weather>,
+    weather_settings: Res<'w, WeatherSettings>,
+    obstacles: Res<'w, ObstacleSet>,
+    obstacle_settings: Res<'w, ObstacleSettings>,
+}
+
+/// Everything about a creature that goes into its [`Neighbor`]
+type FlockingCreature<'a> = (
+    Entity,
+    &'a Direction,
+    &'a Transform,
+    &'a CreatureType,
+    Option<&'a Variation>,
+    Option<&'a Personality>,
+    Option<&'a Satiated>,
+    Option<&'a Fear>,
+    Option<&'a Evasion>,
+);
+
+/// Who steers and how, along with who doesn't at all
+#[derive(SystemParam)]
+struct FlockingRules<'w> {
+    factor_info: Res<'w, FactorInfo>,
+    features: Res<'w, Features>,
+    steering_behaviors: Res<'w, SteeringBehaviors>,
+    frozen_types: Res<'w, FrozenTypes>,
+}
+
+/// Ways of trading accuracy and memory for speed while flocking
+#[derive(SystemParam)]
+struct FlockingShortcuts<'w, 's> {
+    arena_tiling: Res<'w, ArenaTiling>,
+    chunk_resolution: Res<'w, ChunkResolution>,
+    level_of_detail: Res<'w, LevelOfDetail>,
+    neighbor_cache: ResMut<'w, NeighborCache>,
+    packed_flocking: Res<'w, PackedFlocking>,
+    /// Frames so far, for spreading work over several of them
+    tick: Local<'s, u32>,
+}
+
+fn flocking_system(
+    creatures: Query<FlockingCreature>,
+    mut force_writer: EventWriter<ApplyForceEvent>,
+    spatial_query: SpatialQuery,
+    world_bounds: Res<WorldBounds>,
+    rules: FlockingRules,
+    shortcuts: FlockingShortcuts,
+    surroundings: Surroundings,
+) {
+    let FlockingRules {
+        factor_info,
+        features,
+        steering_behaviors,
+        frozen_types,
+    } = rules;
+    let FlockingShortcuts {
+        arena_tiling,
+        chunk_resolution,
+        level_of_detail,
+        mut neighbor_cache,
+        packed_flocking,
+        mut tick,
+    } = shortcuts;
+    if !features.flocking && !features.chasing && !features.running {
+        return;
+    }
+    // Fog shortens everyone's vision and flocking radii together
+    let fog = surroundings
+        .weather
+        .vision_scale(&surroundings.weather_settings);
+    *tick = tick.wrapping_add(1);
+    let filter = FlockingFilter {
+        frozen_types: &frozen_types,
+        visible_area: level_of_detail.visible_area,
+        off_screen_interval: level_of_detail.off_screen_interval,
+        tick: *tick,
+        obstacles: (surroundings.obstacle_settings.blocks_sight
+            && !surroundings.obstacles.is_empty())
+        .then_some(&*surroundings.obstacles),
+    };
+
+    let to_neighbor = |(
+        entity,
+        direction,
+        transform,
+        creature_type,
+        variation,
+        personality,
+        satiated,
+        fear,
+        evasion,
+    ): FlockingCreature| Neighbor {
+        entity,
+        position: transform.translation.xy(),
+        direction: direction.0,
+        creature_type: *creature_type,
+        variation: {
+            let mut variation = variation.copied().unwrap_or_default();
+            variation.vision *= fog;
+            variation
+        },
+        personality: personality.copied().unwrap_or_default(),
+        satiated: satiated.is_some(),
+        fear: fear.copied(),
+        evasion_phase: evasion.map_or(0.0, |evasion| evasion.phase),
+    };
+
+    if arena_tiling.enabled {
+        let creature_vec = creatures.iter().map(to_neighbor).collect::<Vec<_>>();
+        force_writer.send_batch(tiled_flocking_events(
+            creature_vec,
+            &arena_tiling,
+            world_bounds.size(),
+            SteeringRules {
+                behaviors: &steering_behaviors,
+                factor_info: &factor_info,
+                features: &features,
+            },
+            &filter,
+            chunk_resolution.0,
+        ));
+        if !neighbor_cache.entries.is_empty() {
+            neighbor_cache.entries.clear();
+        }
+        return;
+    }
+
+    let compute_task_pool = ComputeTaskPool::get();
+    let creature_vec = creatures.iter().collect::<Vec<_>>();
+    if creature_vec.is_empty() {
+        return;
+    }
+    let creatures_per_thread = (creature_vec.len() / compute_task_pool.thread_num()) + 1;
+
+    // Every task fills its own Vecs so nothing is shared in the hot loop. They
+    // all get sent together once the scope hands them back.
+    let chunk_size = chunk_resolution.0;
+    let tick = *tick;
+    let cache = &*neighbor_cache;
+    // The packed path only knows the built in behaviors and doesn't do mobbing
+    // or pack hunting
+    let unpackable = factor_info
+        .factors
+        .values()
+        .any(|factors| factors.mob_threshold > 0 || factors.pack_hunter);
+    let relations = (packed_flocking.0 && steering_behaviors.is_built_in() && !unpackable)
+        .then(|| TypeRelations::new(&factor_info));
+    let relations = relations.as_ref();
+    let chunk_results = compute_task_pool.scope(|scope| {
+        let features = &features;
+        let behaviors = &steering_behaviors;
+        let spatial_index = spatial_query.index();
+        let factor_info = &factor_info;
+        let creatures = &creatures;
+        let filter = &filter;
+        for chunk in creature_vec.chunks(creatures_per_thread) {
+            scope.spawn(async move {
+                let mut events = vec![];
+                let mut refreshed_neighbors = vec![];
+                let mut packed = PackedNeighbors::default();
+                let mut scratch = SteeringScratch::default();
+                for &creature in chunk {
+                    let creature = to_neighbor(creature);
+                    if filter.skips(&creature) {
+                        continue;
+                    }
+                    let reach = creature
+                        .variation
+                        .reach(factor_info.factors.get(&creature.creature_type).unwrap());
+                    let cell = chunk_of(creature.position, chunk_size);
+                    let mut refreshed = None;
+                    let neighbor_entities = match cache.get(creature.entity, cell, tick) {
+                        Some(cached) => cached,
+                        None => refreshed
+                            .insert(spatial_index.get_nearby_entities(creature.position, reach))
+                            .as_slice(),
+                    };
+                    let neighbors = neighbor_entities
+                        .iter()
+                        .filter_map(|&entity_b| creatures.get(entity_b).ok())
+                        .map(to_neighbor)
+                        .filter(|neighbor| filter.can_see(&creature, neighbor));
+                    match relations {
+                        Some(relations) => packed_flocking_forces(
+                            &creature,
+                            neighbors,
+                            &mut packed,
+                            relations,
+                            factor_info,
+                            features,
+                            &mut events,
+                        ),
+                        None => steering_forces(
+                            &creature,
+                            neighbors,
+                            SteeringRules {
+                                behaviors,
+                                factor_info,
+                                features,
+                            },
+                            &mut scratch,
+                            &mut events,
+                        ),
+                    }
+
+                    if let Some(entities) = refreshed.filter(|_| cache.enabled) {
+                        refreshed_neighbors
+                            .push((creature.entity, CachedNeighbors { cell, entities }));
+                    }
+                }
+                (events, refreshed_neighbors)
+            });
+        }
+    });
+
+    if neighbor_cache.enabled {
+        neighbor_cache
+            .entries
+            .retain(|&entity, _| creatures.contains(entity));
+    } else if !neighbor_cache.entries.is_empty() {
+        neighbor_cache.entries.clear();
+    }
+    for (events, refreshed_neighbors) in chunk_results {
+        force_writer.send_batch(events);
+        neighbor_cache.entries.extend(refreshed_neighbors);
+    }
+}
+
+fn update_factors_system(
+    mut creature_query: Query<(
+        &CreatureType,
+        &mut Sprite,
+        &mut Transform,
+        &mut Energy,
+        Option<&Variation>,
+    )>,
+    factor_info: Res<FactorInfo>,
+) {
+    if factor_info.is_changed() {
+        for (creature_type, mut sprite, mut transform, mut energy, variation) in
+            creature_query.iter_mut()
+        {
+            let factors = factor_info.factors.get(creature_type).unwrap();
+            sprite.color = factors.color;
+            let size = variation.map_or(factors.size, |variation| variation.size(factors));
+            sprite.custom_size = Some(Vec2::splat(size));
+            transform.translation.z = factors.depth();
+            // Lowering max energy shouldn't leave anyone above it
+            if energy.0 > factors.max_energy {
+                energy.0 = factors.max_energy;
+            }
+        }
+    }
+}
+
+/// Adds up every force on each creature and turns it once. Turning a bit for
+/// each force one after another made the result depend on what order they came in.
+fn apply_forces_system(
+    mut force_reader: EventReader<ApplyForceEvent>,
+    mut creature_query: Query<(&mut Direction, &CreatureType)>,
+    sim_time: SimTime,
+    frozen_types: Res<FrozenTypes>,
+    combination: Res<SteeringCombination>,
+    mut forces: Local<Vec<(Entity, u8, Vec2, f32)>>,
+    mut creature_forces: Local<Vec<(u8, Vec2, f32)>>,
+) {
+    let delta_time = sim_time.delta_seconds();
+    forces.clear();
+    forces.extend(
+        force_reader
+            .iter()
+            .map(|ApplyForceEvent(entity, force, factor, kind)| {
+                (*entity, kind.priority(), *force, *factor)
+            }),
+    );
+    forces.sort_unstable_by_key(|&(entity, priority, _, _)| (entity, priority));
+
+    let mut start = 0;
+    while start < forces.len() {
+        let entity = forces[start].0;
+        creature_forces.clear();
+        creature_forces.extend(
+            forces[start..]
+                .iter()
+                .take_while(|&&(other, _, _, _)| other == entity)
+                .map(|&(_, priority, force, factor)| (priority, force, factor)),
+        );
+        start += creature_forces.len();
+
+        let Ok((mut direction, creature_type)) = creature_query.get_mut(entity) else {
+            continue;
+        };
+        if frozen_types.contains(creature_type) {
+            continue;
+        }
+        let Some((target, weight)) = combination.combine(&creature_forces) else {
+            continue;
+        };
+        // Forces that cancel out don't point anywhere to turn to
+        if target.length_squared() < f32::EPSILON {
+            continue;
+        }
+        // Past 1 the lerp overshoots, which gets easy to hit when sped up
+        direction.lerp(target, (weight * delta_time).min(1.0));
+    }
+}
+
+fn creature_texture_system(
+    asset_server: Res<AssetServer>,
+    factor_info: Res<FactorInfo>,
+    mut failed_textures: Local<HashSet<String>>,
+    mut creature_query: Query<(Ref<CreatureType>, &mut Handle<Image>)>,
+) {
+    let mut refresh = factor_info.is_changed();
+    for factors in factor_info.factors.values() {
+        let Some(path) = &factors.texture else {
+            continue;
+        };
+        if failed_textures.contains(path) {
+            continue;
+        }
+        let texture: Handle<Image> = asset_server.load(path.as_str());
+        if asset_server.get_load_state(&texture) == LoadState::Failed {
+            warn!("Couldn't load {}, drawing plain squares instead", path);
+            failed_textures.insert(path.clone());
+            refresh = true;
+        }
+    }
+
+    for (creature_type, mut texture) in creature_query.iter_mut() {
+        if !refresh && !creature_type.is_added() {
+            continue;
+        }
+        let wanted_texture = match &factor_info.factors.get(&creature_type).unwrap().texture {
+            Some(path) if !failed_textures.contains(path) => asset_server.load(path.as_str()),
+            // The default image is a plain white pixel so it's just the color
+            _ => Handle::default(),
+        };
+        if *texture != wanted_texture {
+            *texture = wanted_texture;
+        }
+    }
+}
+
+/// `.` steps while paused, except when it's a period typed into a text box
+fn step_key_system(
+    keys: Res<Input<KeyCode>>,
+    mut egui_context: EguiContexts,
+    sim_state: Res<State<SimState>>,
+    mut sim_step: ResMut<SimStep>,
+) {
+    if keys.just_pressed(KeyCode::Period)
+        && sim_state.0 == SimState::Paused
+        && !egui_context.ctx_mut().wants_keyboard_input()
+    {
+        sim_step.request();
+    }
+}
+
+fn pause_system(
+    keys: Res<Input<KeyCode>>,
+    sim_state: Res<State<SimState>>,
+    sim_step: Res<SimStep>,
+    mut next_sim_state: ResMut<NextState<SimState>>,
+) {
+    // Running for a frame is how every `OnUpdate(SimState::Running)` system gets its one tick
+    if sim_step.requested && sim_state.0 == SimState::Paused {
+        next_sim_state.set(SimState::Running);
+    }
+    if keys.just_pressed(KeyCode::P) {
+        let new_sim_state = match sim_state.0 {
+            SimState::Running => SimState::Paused,
+            _ => SimState::Running,
+        };
+        println!("{:?} to {:?}", sim_state, new_sim_state);
+        next_sim_state.set(new_sim_state);
+    }
+}
+
+/// The state change from a step request lands in `StateTransitions`, right
+/// before the fixed timestep and `Update`, so that's when the step starts.
+fn begin_step_system(sim_state: Res<State<SimState>>, mut sim_step: ResMut<SimStep>) {
+    if sim_step.requested && sim_state.0 == SimState::Running {
+        sim_step.requested = false;
+        sim_step.stepping = true;
+    }
+}
+
+fn end_step_system(mut sim_step: ResMut<SimStep>, mut next_sim_state: ResMut<NextState<SimState>>) {
+    if sim_step.stepping {
+        sim_step.stepping = false;
+        next_sim_state.set(SimState::Paused);
+    }
+}
+
+/// Only with the UI since brackets typed into a text box shouldn't count
+fn time_scale_system(
+    keys: Res<Input<KeyCode>>,
+    mut egui_context: EguiContexts,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    if egui_context.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keys.just_pressed(KeyCode::LBracket) {
+        let scale = time_scale.0 / 2.0;
+        time_scale.set(scale);
+    }
+    if keys.just_pressed(KeyCode::RBracket) {
+        let scale = time_scale.0 * 2.0;
+        time_scale.set(scale);
+    }
+    if keys.just_pressed(KeyCode::Backslash) {
+        time_scale.set(1.0);
+    }
+}
+
+/// Refiles everything when the chunk size changes since the old cells don't mean anything anymore
+fn chunk_resolution_system(
+    chunk_resolution: Res<ChunkResolution>,
+    mut hash_grid: ResMut<HashGrid>,
+    creature_query: Query<(Entity, &Transform), With<CreatureType>>,
+) {
+    if !chunk_resolution.is_changed() || hash_grid.chunk_size == chunk_resolution.0 {
+        return;
+    }
+    *hash_grid = HashGrid::new(chunk_resolution.0);
+    for (entity, transform) in creature_query.iter() {
+        hash_grid.update_entity(entity, transform.translation.xy());
+    }
+}
+
+type MovedCreature = (Changed<Transform>, With<CreatureType>);
+
+/// Only creatures go in the grid. Trails, particles, and creatures that are
+/// already dying move too but would never be taken back out.
+fn hash_grid_update_system(
+    creature_query: Query<(Entity, &Transform), MovedCreature>,
+    mut removed: RemovedComponents<CreatureType>,
+    mut hash_grid: ResMut<HashGrid>,
+) {
+    let mut any_removed = false;
+    for entity in removed.iter() {
+        hash_grid.remove_entity(entity);
+        any_removed = true;
+    }
+    if any_removed {
+        hash_grid.shrink();
+    }
+    for (entity, transform) in creature_query.iter() {
+        hash_grid.update_entity(entity, transform.translation.xy());
+    }
+}
+
+/// Where the cursor was last frame while a spawn/despawn button is being held.
+#[derive(Default)]
+struct PaintStroke {
+    last_position: Option<Vec2>,
+    // Fractions of a creature left over from previous frames
+    carry: f32,
+}
+
+/// Drops creatures of the selected type around a point, the way painting with
+/// Shift+Click and the gamepad both do
+#[derive(SystemParam)]
+pub(crate) struct Spawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    rng: ResMut<'w, SimRng>,
+    factor_info: Res<'w, FactorInfo>,
+    pub(crate) spawn_properties: Res<'w, SpawnProperties>,
+    selected_creature_type: Res<'w, CreatureType>,
+}
+
+impl Spawner<'_, '_> {
+    pub(crate) fn spawn_around(&mut self, center: Vec2) {
+        spawn_creature_randomly_in_circle(
+            self.rng.as_mut(),
+            &mut self.commands,
+            *self.selected_creature_type,
+            &self.factor_info.factors,
+            center,
+            self.spawn_properties.radius,
+        );
+    }
+}
+
+fn spawn_system(
+    timer: Res<Time>,
+    cursor: Res<Cursor>,
+    keys: Res<Input<KeyCode>>,
+    mut spawner: Spawner,
+    mut stroke: Local<PaintStroke>,
+) {
+    if !cursor.button_states[0] || !keys.pressed(KeyCode::LShift) {
+        *stroke = PaintStroke::default();
+        return;
+    }
+
+    // A plain click still drops the usual bunch
+    let Some(last_position) = stroke.last_position.replace(cursor.position) else {
+        for _ in 0..spawner.spawn_properties.amount {
+            spawner.spawn_around(cursor.position);
+        }
+        return;
+    };
+
+    stroke.carry += spawner.spawn_properties.paint_rate * timer.delta_seconds();
+    let count = stroke.carry.floor();
+    stroke.carry -= count;
+    for index in 0..count as usize {
+        // Spread out along the path so fast drags don't leave gaps
+        let t = (index as f32 + 0.5) / count;
+        spawner.spawn_around(last_position.lerp(cursor.position, t));
+    }
+}
+
+/// Takes creatures out by hand, letting everyone listening know they're gone
+#[derive(SystemParam)]
+struct Despawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    killed_writer: EventWriter<'w, BoidKilled>,
+}
+
+impl Despawner<'_, '_> {
+    fn despawn(&mut self, entity: Entity, creature_type: CreatureType, position: Vec2) {
+        self.commands.entity(entity).despawn();
+        self.killed_writer.send(BoidKilled {
+            entity,
+            creature_type,
+            position,
+            cause: DeathCause::Despawned,
+        });
+    }
+}
+
+fn despawn_system(
+    cursor: Res<Cursor>,
+    keys: Res<Input<KeyCode>>,
+    despawn_properties: Res<DespawnProperties>,
+    selected_creature_type: Res<CreatureType>,
+    mut despawner: Despawner,
+    mut last_position: Local<Option<Vec2>>,
+    // Named creatures are worth keeping around
+    creatures_query: Query<(Entity, &Transform, &CreatureType), Without<Tagged>>,
+) {
+    if !cursor.button_states[0] || !keys.pressed(KeyCode::LControl) {
+        *last_position = None;
+        return;
+    }
+
+    // Erases everything along the path since last frame, not just where the cursor is now
+    let start = last_position
+        .replace(cursor.position)
+        .unwrap_or(cursor.position);
+    for (entity, transform, &creature_type) in creatures_query.iter() {
+        let distance = distance_to_segment(transform.translation.xy(), start, cursor.position);
+        if distance <= despawn_properties.radius && *selected_creature_type == creature_type {
+            despawner.despawn(entity, creature_type, transform.translation.xy());
+        }
+    }
+}
+
+/// Everything that can keep a predator from eating
+#[derive(SystemParam)]
+struct Appetite<'w, 's> {
+    frozen_types: Res<'w, FrozenTypes>,
+    satiation: Res<'w, SatiationSettings>,
+    satiated_query: Query<'w, 's, (), With<Satiated>>,
+    growth: Res<'w, GrowthSettings>,
+    body_size_query: Query<'w, 's, &'static BodySize>,
+}
+
+/// What happens to the predator and its prey after a kill
+#[derive(SystemParam)]
+struct KillAftermath<'w, 's> {
+    commands: Commands<'w, 's>,
+    death_animation_settings: Res<'w, DeathAnimationSettings>,
+    energy_change_event_handler: EventWriter<'w, EnergyChangeEvent>,
+    killed_writer: EventWriter<'w, BoidKilled>,
+}
+
+fn kill_system(
+    features: Res<Features>,
+    spatial_query: SpatialQuery,
+    factor_info: Res<FactorInfo>,
+    creatures: Query<(
+        Entity,
+        &Transform,
+        &CreatureType,
+        &Energy,
+        Option<&Variation>,
+    )>,
+    appetite: Appetite,
+    aftermath: KillAftermath,
+) {
+    let Appetite {
+        frozen_types,
+        satiation,
+        satiated_query,
+        growth,
+        body_size_query,
+    } = appetite;
+    let KillAftermath {
+        mut commands,
+        death_animation_settings,
+        mut energy_change_event_handler,
+        mut killed_writer,
+    } = aftermath;
+    if !features.killing {
+        return;
+    }
+    // Pairs get checked from both sides so the same kill can come up twice
+    let mut killed = HashSet::default();
+    // One meal per predator per tick. With satiation on they're also full
+    // from here on, even though they won't have Satiated until the commands run.
+    let mut fed = HashSet::default();
+    // How many of a prey type and how many of their predators are around a spot
+    let mob_counts = |position: Vec2, radius: f32, prey_type: CreatureType| {
+        let (mut group_size, mut predators) = (0, 0);
+        for entity in spatial_query.get_nearby_entities(position, radius) {
+            let Ok((_, transform, creature_type, ..)) = creatures.get(entity) else {
+                continue;
+            };
+            if transform.translation.xy().distance(position) > radius {
+                continue;
+            }
+            if *creature_type == prey_type {
+                group_size += 1;
+            } else if factor_info
+                .factors
+                .get(creature_type)
+                .is_some_and(|factors| factors.predator_of.contains(&prey_type))
+            {
+                predators += 1;
+            }
+        }
+        (group_size, predators)
+    };
+    // Brand new creatures don't have a BodySize until the end of the frame
+    let size_of = |entity: Entity, factors: &Factors, variation: Option<&Variation>| {
+        body_size_query.get(entity).map_or_else(
+            |_| variation.map_or(factors.size, |variation| variation.size(factors)),
+            |body_size| body_size.0,
+        )
+    };
+    creatures.for_each(|(entity_a, transform_a, type_a, energy_a, variation_a)| {
+        let position_a = transform_a.translation.xy();
+        let factors_a = factor_info.factors.get(type_a).unwrap();
+        let size_a = size_of(entity_a, factors_a, variation_a);
+
+        for entity_b in spatial_query.get_nearby_entities(position_a, size_a) {
+            if entity_b == entity_a {
+                continue;
+            }
+            let (position_b, type_b, energy_b, variation_b) = match creatures.get(entity_b) {
+                Ok(creature) => (
+                    creature.1.translation.xy(),
+                    creature.2,
+                    creature.3,
+                    creature.4,
+                ),
+                Err(_) => continue,
+            };
+            let factors_b = factor_info.factors.get(type_b).unwrap();
+            let size_b = size_of(entity_b, factors_b, variation_b);
+
+            let is_a_predator = factors_a.predator_of.contains(type_b);
+            let is_b_predator = factors_b.predator_of.contains(type_a);
+            if position_a.distance(position_b) <= size_a + size_b {
+                // This ternary is disgusting
+                let (killed_entity, killer_entity) = if is_a_predator && is_b_predator {
+                    if energy_a > energy_b {
+                        (entity_a, entity_b)
+                    } else if energy_b > energy_a {
+                        (entity_b, entity_a)
+                    } else {
+                        continue;
+                    }
+                } else if is_a_predator {
+                    (entity_b, entity_a)
+                } else if is_b_predator {
+                    (entity_a, entity_b)
+                } else {
+                    continue;
+                };
+                // Frozen predators don't get to eat but frozen prey is fair game
+                let killer_type = if killer_entity == entity_a {
+                    type_a
+                } else {
+                    type_b
+                };
+                // Something that just got eaten isn't around to eat anymore
+                if killed.contains(&killer_entity)
+                    || frozen_types.contains(killer_type)
+                    || satiated_query.contains(killer_entity)
+                    || fed.contains(&killer_entity)
+                {
+                    continue;
+                }
+                // Too big to swallow
+                let (killer_size, killed_size) = if killer_entity == entity_a {
+                    (size_a, size_b)
+                } else {
+                    (size_b, size_a)
+                };
+                if !growth.can_eat(killer_size, killed_size) {
+                    continue;
+                }
+                let (killed_factors, killed_type, killed_position) = if killed_entity == entity_a {
+                    (factors_a, type_a, position_a)
+                } else {
+                    (factors_b, type_b, position_b)
+                };
+                // A predator on its own can't pick off a group big enough to mob it
+                if killed_factors.mob_threshold > 0 {
+                    let (killer_factors, killer_position) = if killer_entity == entity_a {
+                        (factors_a, position_a)
+                    } else {
+                        (factors_b, position_b)
+                    };
+                    let (group_size, predators) =
+                        mob_counts(killer_position, killer_factors.vision, *killed_type);
+                    if killed_factors.mobs(group_size, predators) {
+                        continue;
+                    }
+                }
+                if !killed.insert(killed_entity) {
+                    continue;
+                }
+                killed_writer.send(BoidKilled {
+                    entity: killed_entity,
+                    creature_type: *killed_type,
+                    position: killed_position,
+                    cause: DeathCause::Eaten { by: killer_entity },
+                });
+                energy_change_event_handler.send(EnergyChangeEvent(killer_entity, 4.0));
+                fed.insert(killer_entity);
+                if satiation.duration > 0.0 {
+                    commands
+                        .entity(killer_entity)
+                        .insert(Satiated::new(satiation.duration));
+                }
+                kill_creature(
+                    &mut commands,
+                    killed_entity,
+                    killed_factors.death_animation,
+                    &death_animation_settings,
+                );
+            }
+        }
+    });
+}
+
+fn energy_drain_system(
+    sim_time: SimTime,
+    features: Res<Features>,
+    frozen_types: Res<FrozenTypes>,
+    creatures: Query<(Entity, &CreatureType), With<Energy>>,
+    mut energy_change_event_handler: EventWriter<EnergyChangeEvent>,
+) {
+    if !features.energy_draining {
+        return;
+    }
+    let delta_seconds = sim_time.delta_seconds();
+    let burn_rate = 2f32;
+    creatures.for_each(|(entity, creature_type)| {
+        if frozen_types.contains(creature_type) {
+            return;
+        }
+        energy_change_event_handler.send(EnergyChangeEvent(entity, -delta_seconds * burn_rate))
+    });
+}
+
+fn apply_energy_change_system(
+    mut commands: Commands,
+    factor_info: Res<FactorInfo>,
+    death_animation_settings: Res<DeathAnimationSettings>,
+    mut creature_query: Query<(Entity, &mut Energy, &CreatureType, &Transform)>,
+    mut energy_change_even_handler: EventReader<EnergyChangeEvent>,
+    mut killed_writer: EventWriter<BoidKilled>,
+) {
+    let mut starved = HashSet::default();
+    for EnergyChangeEvent(entity, change) in energy_change_even_handler.iter() {
+        if let Ok((entity, mut energy, creature_type, transform)) = creature_query.get_mut(*entity)
+        {
+            let factors = factor_info.factors.get(creature_type).unwrap();
+            energy.0 += change;
+            energy.0 = energy.0.clamp(0.0, factors.max_energy);
+            if energy.0 <= 0.0 && starved.insert(entity) {
+                killed_writer.send(BoidKilled {
+                    entity,
+                    creature_type: *creature_type,
+                    position: transform.translation.xy(),
+                    cause: DeathCause::Starved,
+                });
+                kill_creature(
+                    &mut commands,
+                    entity,
+                    factors.death_animation,
+                    &death_animation_settings,
+                );
+            }
+        }
+    }
+}
+
+/// The whole simulation. Configure the starting state with the `with_*` methods:
+///
+/// ```no_run
+/// # use rusty_boids::{scenarios::Scenario, BoidsPlugin};
+/// let plugin = BoidsPlugin::default()
+///     .with_scenario(Scenario::predator_prey())
+///     .with_population(0, 2000)
+///     .with_ui(false);
+/// ```
+pub struct BoidsPlugin {
+    initial_factors: HashMap<CreatureType, Factors>,
+    initial_features: Features,
+    initial_populations: Vec<usize>,
+    seed: Option<u64>,
+    deterministic: bool,
+    world_bounds: WorldBounds,
+    ui: bool,
+    persistence: bool,
+}
+
+impl Default for BoidsPlugin {
+    fn default() -> Self {
+        Self::from_scenario(Scenario::food_chain())
+    }
+}
+
+impl BoidsPlugin {
+    pub fn from_scenario(scenario: Scenario) -> Self {
+        Self {
+            initial_factors: scenario.factors,
+            initial_features: scenario.features,
+            initial_populations: scenario.populations,
+            seed: None,
+            deterministic: false,
+            world_bounds: WorldBounds::default(),
+            ui: true,
+            persistence: true,
+        }
+    }
+
+    /// Replaces the factors, features and populations all at once.
+    pub fn with_scenario(self, scenario: Scenario) -> Self {
+        Self {
+            seed: self.seed,
+            deterministic: self.deterministic,
+            world_bounds: self.world_bounds,
+            ui: self.ui,
+            persistence: self.persistence,
+            ..Self::from_scenario(scenario)
+        }
+    }
+
+    /// Creature types have to be numbered `0..factors.len()`.
+    pub fn with_factors(mut self, factors: HashMap<CreatureType, Factors>) -> Self {
+        self.initial_factors = factors;
+        self
+    }
+
+    /// Adds a new creature type after the existing ones.
+    pub fn with_type(mut self, factors: Factors, population: usize) -> Self {
+        let creature_type = CreatureType(self.initial_factors.len());
+        self.initial_factors.insert(creature_type, factors);
+        self.with_population(creature_type.0, population)
+    }
+
+    pub fn with_features(mut self, features: Features) -> Self {
+        self.initial_features = features;
+        self
+    }
+
+    pub fn with_populations(mut self, populations: Vec<usize>) -> Self {
+        self.initial_populations = populations;
+        self
+    }
+
+    /// Indices past the last creature type are ignored, so a bad link can't
+    /// make a huge list of populations.
+    pub fn with_population(mut self, creature_index: usize, population: usize) -> Self {
+        if creature_index >= self.initial_factors.len() {
+            return self;
+        }
+        if self.initial_populations.len() <= creature_index {
+            self.initial_populations.resize(creature_index + 1, 0);
+        }
+        self.initial_populations[creature_index] = population;
+        self
+    }
+
+    /// Turn this off if you bring your own UI (or none at all).
+    pub fn with_ui(mut self, ui: bool) -> Self {
+        self.ui = ui;
+        self
+    }
+
+    /// Turn this off to keep the browser from restoring (and saving) settings.
+    pub fn with_persistence(mut self, persistence: bool) -> Self {
+        self.persistence = persistence;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    pub fn with_world_bounds(mut self, width: f32, height: f32) -> Self {
+        self.world_bounds = WorldBounds::new(width, height);
+        self
+    }
+}
+
+impl Plugin for BoidsPlugin {
+    fn build(&self, app: &mut App) {
+        // Insert Resources
+        app.insert_resource(FactorInfo {
+            factors: self.initial_factors.clone(),
+        })
+        .insert_resource(self.initial_features.clone())
+        .insert_resource(HashGrid::default())
+        .insert_resource(ChunkResolution::default())
+        .insert_resource(LevelOfDetail::default())
+        .insert_resource(SteeringCombination::default())
+        .insert_resource(SatiationSettings::default())
+        .insert_resource(FoodSettings::default())
+        .insert_resource(TemperatureSettings::default())
+        .insert_resource(WeatherSettings::default())
+        .insert_resource(CollisionSettings::default())
+        .insert_resource(GrowthSettings::default())
+        .insert_resource(NeighborCache::default())
+        .insert_resource(CreatureType::default())
+        // Registered so they show up in inspectors and anything else going through reflection
+        .register_type::<Factors>()
+        .register_type::<Features>()
+        .register_type::<Energy>()
+        .register_type::<Direction>()
+        .register_type::<CreatureType>()
+        .register_type::<HashSet<CreatureType>>()
+        .register_type::<DeathAnimation>()
+        .insert_resource(DespawnProperties::default())
+        .insert_resource(SpawnProperties::default())
+        .insert_resource(FixedTime::new_from_secs(1.0 / 30.0))
+        .insert_resource(match self.seed {
+            Some(seed) => SimRng::new(seed),
+            None => SimRng::default(),
+        })
+        .insert_resource(DeterministicMode(self.deterministic))
+        .insert_resource(TimeScale::default())
+        .insert_resource(SimStep::default())
+        .insert_resource(SimTicks::default())
+        .insert_resource(FrozenTypes::default())
+        .insert_resource(InitialPopulations(self.initial_populations.clone()))
+        .insert_resource(ArenaTiling::default())
+        .insert_resource(SteeringBehaviors::default())
+        .insert_resource(self.world_bounds)
+        .insert_resource(Cursor::default())
+        .add_event::<ApplyForceEvent>()
+        .add_event::<EnergyChangeEvent>()
+        .add_event::<RestartEvent>()
+        .add_state::<SimState>()
+        .add_plugin(LifecyclePlugin)
+        .add_plugin(FlockPlugin)
+        .add_plugin(StatsPlugin)
+        .add_plugin(VariationPlugin)
+        .add_plugin(PersonalityPlugin)
+        .add_plugin(SatiationPlugin)
+        .add_plugin(FearPlugin)
+        .add_plugin(EvasionPlugin)
+        .add_plugin(FoodPlugin)
+        .add_plugin(TemperaturePlugin)
+        .add_plugin(WeatherPlugin)
+        .add_plugin(FlowPlugin)
+        .add_plugin(GoalPlugin)
+        .add_plugin(PathPlugin)
+        .add_plugin(MarginPlugin)
+        .add_plugin(GravityPlugin)
+        .add_plugin(ObstaclePlugin)
+        .add_plugin(MazePlugin)
+        .add_plugin(MaskPlugin)
+        .add_plugin(RoomPlugin)
+        .add_plugin(CollisionPlugin)
+        .add_plugin(GrowthPlugin)
+        .add_plugin(EvolutionPlugin)
+        .add_plugin(TagsPlugin)
+        .add_plugin(LabelPlugin)
+        .add_plugin(ScenarioPlugin)
+        .add_plugin(ReportPlugin)
+        .add_plugin(SnapshotPlugin)
+        .add_plugin(DataLogPlugin)
+        .add_plugin(DeathPlugin)
+        .add_plugin(GustPlugin)
+        .add_plugin(TimelinePlugin)
+        .add_plugin(SelectionPlugin)
+        .add_plugin(CameraPlugin)
+        .add_plugin(SmearPlugin)
+        .add_plugin(SpatialPlugin)
+        .add_plugin(PackedFlockingPlugin)
+        .add_plugin(BenchmarkPlugin)
+        .add_plugin(ScriptingPlugin)
+        .add_plugin(GamepadPlugin)
+        .add_plugin(TrailPlugin)
+        .add_plugin(ExposurePlugin)
+        .add_plugin(ColoringPlugin)
+        .add_startup_system(setup_creatures)
+        .add_startup_system(setup_boundary_lines)
+        .add_system(boundary_lines_system)
+        .add_system(cursor_system.in_base_set(CoreSet::PreUpdate))
+        .add_system(
+            begin_step_system
+                .after(apply_state_transition::<SimState>)
+                .in_base_set(CoreSet::StateTransitions),
+        )
+        .add_system(end_step_system.in_base_set(CoreSet::PostUpdate))
+        .configure_sets(
+            (
+                SystemStages::Spawn,
+                SystemStages::Calculate,
+                SystemStages::Apply,
+                SystemStages::Act,
+                SystemStages::Cache,
+            )
+                .chain(),
+        )
+        .add_systems((
+            update_factors_system,
+            creature_texture_system,
+            step_key_system.before(pause_system).run_if(has_ui),
+            pause_system,
+            time_scale_system.run_if(has_ui),
+        ))
+        .add_system(restart_system.in_set(SystemStages::Spawn))
+        .add_systems(
+            (despawn_system, spawn_system, kill_system)
+                .chain()
+                .after(restart_system)
+                .in_set(SystemStages::Spawn)
+                .in_set(OnUpdate(SimState::Running)),
+        )
+        .add_system(level_of_detail_system.in_base_set(CoreSet::PreUpdate))
+        .add_systems(
+            (flocking_system, energy_drain_system)
+                .in_set(SystemStages::Calculate)
+                .in_set(OnUpdate(SimState::Running))
+                .distributive_run_if(ticks_on_fixed_time)
+                .in_schedule(CoreSchedule::FixedUpdate),
+        )
+        // Deterministic runs can't depend on how many fixed ticks fit in a frame
+        .add_systems(
+            (flocking_system, energy_drain_system)
+                .in_set(SystemStages::Calculate)
+                .in_set(OnUpdate(SimState::Running))
+                .distributive_run_if(ticks_every_frame),
+        )
+        .add_systems(
+            (apply_forces_system, apply_energy_change_system)
+                .chain()
+                .in_set(SystemStages::Apply)
+                .in_set(OnUpdate(SimState::Running)),
+        )
+        .add_systems(
+            (move_system, wrap_borders_system, count_ticks_system)
+                .chain()
+                .in_set(SystemStages::Act)
+                .in_set(OnUpdate(SimState::Running)),
+        )
+        .add_systems(
+            (chunk_resolution_system, hash_grid_update_system)
+                .chain()
+                .in_set(SystemStages::Cache),
+        );
+
+        if self.persistence {
+            app.add_plugin(PersistencePlugin);
+        }
+        if self.ui {
+            app.add_plugin(UiPlugin)
+                .add_plugin(ComparisonPlugin)
+                .add_plugin(HistoryPlugin)
+                .add_plugin(WindowModePlugin)
+                .add_plugin(SoundPlugin)
+                .add_plugin(MusicPlugin)
+                .add_plugin(EventLogPlugin);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHUNK: f32 = CHUNK_RESOLUTION as f32;
+
+    fn grid_with(positions: &[Vec2]) -> HashGrid {
+        let mut hash_grid = HashGrid::new(CHUNK);
+        for (index, &position) in positions.iter().enumerate() {
+            hash_grid.update_entity(Entity::from_raw(index as u32), position);
+        }
+        hash_grid
+    }
+
+    #[test]
+    fn wrap_brings_positions_back_from_the_other_side() {
+        let world_bounds = WorldBounds::new(100.0, 50.0);
+        assert_eq!(
+            world_bounds.wrap(Vec2::new(10.0, -5.0)),
+            Vec2::new(10.0, -5.0)
+        );
+        assert_eq!(
+            world_bounds.wrap(Vec2::new(55.0, -30.0)),
+            Vec2::new(-45.0, 20.0)
+        );
+        // The max edge is the same place as the min edge
+        assert_eq!(world_bounds.wrap(Vec2::new(50.0, 25.0)), world_bounds.min());
+    }
+
+    #[test]
+    fn wrap_handles_positions_several_worlds_away() {
+        let world_bounds = WorldBounds::new(100.0, 50.0);
+        assert_eq!(
+            world_bounds.wrap(Vec2::new(-340.0, 260.0)),
+            Vec2::new(-40.0, 10.0)
+        );
+    }
+
+    #[test]
+    fn chunk_of_floors_negative_positions() {
+        let hash_grid = HashGrid::new(CHUNK);
+        assert_eq!(hash_grid.chunk_of(Vec2::new(0.0, 0.0)), (0, 0));
+        assert_eq!(hash_grid.chunk_of(Vec2::new(-0.5, -0.5)), (-1, -1));
+        assert_eq!(hash_grid.chunk_of(Vec2::new(-CHUNK, CHUNK)), (1, -1));
+        assert_eq!(
+            hash_grid.chunk_of(Vec2::new(-CHUNK - 0.5, -2.0 * CHUNK - 0.5)),
+            (-3, -2)
+        );
+    }
+
+    #[test]
+    fn chunk_of_goes_past_i8_range() {
+        let hash_grid = HashGrid::new(CHUNK);
+        let far = 300.0 * CHUNK + 1.0;
+        assert_eq!(hash_grid.chunk_of(Vec2::new(far, -far)), (-301, 300));
+        assert_eq!(
+            hash_grid.chunk_of(Vec2::new(-1000.0 * CHUNK, 128.0 * CHUNK)),
+            (128, -1000)
+        );
+    }
+
+    #[test]
+    fn cell_bounds_contain_their_positions_far_from_origin() {
+        let hash_grid = HashGrid::new(CHUNK);
+        for position in [
+            Vec2::new(-0.5, -0.5),
+            Vec2::new(127.5 * CHUNK, -127.5 * CHUNK),
+            Vec2::new(-128.5 * CHUNK, 128.5 * CHUNK),
+            Vec2::new(-5000.3 * CHUNK, -4000.7 * CHUNK),
+            Vec2::new(9000.2 * CHUNK, 3000.9 * CHUNK),
+        ] {
+            let (min, max) = hash_grid.cell_bounds(hash_grid.chunk_of(position));
+            assert!(
+                min.x <= position.x && position.x < max.x,
+                "{position} not in {min}..{max}"
+            );
+            assert!(
+                min.y <= position.y && position.y < max.y,
+                "{position} not in {min}..{max}"
+            );
+        }
+    }
+
+    #[test]
+    fn nearby_entities_cross_negative_chunk_borders() {
+        let center = Vec2::new(-200.0 * CHUNK - 1.0, -200.0 * CHUNK - 1.0);
+        let hash_grid = grid_with(&[center, center + Vec2::new(2.0, 2.0)]);
+        assert_ne!(
+            hash_grid.cell_of(Entity::from_raw(0)),
+            hash_grid.cell_of(Entity::from_raw(1))
+        );
+
+        let mut nearby = hash_grid.get_nearby_entities(center, 3.0);
+        nearby.sort();
+        assert_eq!(nearby, vec![Entity::from_raw(0), Entity::from_raw(1)]);
+    }
+
+    #[test]
+    fn nearby_entities_dont_alias_far_chunks() {
+        // 256 chunks apart would land in the same cell if the keys wrapped
+        // around like i8s
+        let position = Vec2::new(-130.5 * CHUNK, 140.5 * CHUNK);
+        let aliased = position + Vec2::new(256.0 * CHUNK, -256.0 * CHUNK);
+        let hash_grid = grid_with(&[position, aliased]);
+
+        assert_eq!(
+            hash_grid.get_nearby_entities(position, 1.0),
+            vec![Entity::from_raw(0)]
+        );
+        assert_eq!(
+            hash_grid.get_nearby_entities(aliased, 1.0),
+            vec![Entity::from_raw(1)]
+        );
+    }
+}
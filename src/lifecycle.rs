@@ -0,0 +1,72 @@
+// Events for creatures coming and going so other plugins (sounds, particles,
+// stats) can react without digging through the sim's systems.
+
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::CreatureType;
+
+/// Sent the frame a creature shows up, no matter what spawned it.
+#[derive(Debug, Clone)]
+pub struct BoidSpawned {
+    pub entity: Entity,
+    pub creature_type: CreatureType,
+    pub position: Vec2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+    Eaten {
+        by: Entity,
+    },
+    Starved,
+    /// Erased with LCtrl+Click
+    Despawned,
+}
+
+/// Sent when a creature dies, before its death animation plays.
+#[derive(Debug, Clone)]
+pub struct BoidKilled {
+    pub entity: Entity,
+    pub creature_type: CreatureType,
+    pub position: Vec2,
+    pub cause: DeathCause,
+}
+
+/// Reserved for when reproduction lands. Nothing sends this yet, so nothing in
+/// here listens for it either.
+#[derive(Debug, Clone)]
+pub struct BoidReproduced {
+    pub parent: Entity,
+    pub child: Entity,
+    pub creature_type: CreatureType,
+    pub position: Vec2,
+}
+
+/// Spawning happens all over the place (clicks, restarts, scenarios, snapshots)
+/// so it's easier to notice new creatures than to send from every one of those.
+fn spawned_events_system(
+    creature_query: Query<(Entity, &CreatureType, &Transform), Added<CreatureType>>,
+    mut spawned_writer: EventWriter<BoidSpawned>,
+) {
+    spawned_writer.send_batch(
+        creature_query
+            .iter()
+            .map(|(entity, &creature_type, transform)| BoidSpawned {
+                entity,
+                creature_type,
+                position: transform.translation.xy(),
+            }),
+    );
+}
+
+#[derive(Default)]
+pub struct LifecyclePlugin;
+
+impl Plugin for LifecyclePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BoidSpawned>()
+            .add_event::<BoidKilled>()
+            .add_event::<BoidReproduced>()
+            .add_system(spawned_events_system.in_base_set(CoreSet::PostUpdate));
+    }
+}
@@ -1,25 +1,11 @@
 use bevy::{
     diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
-    input::mouse::MouseButtonInput,
     prelude::*,
     window::{PrimaryWindow, WindowResolution},
 };
-mod boids;
-mod ui;
-use boids::*;
+use rusty_boids::*;
 use std::time::Duration;
 
-#[derive(Default, Resource)]
-pub struct Cursor {
-    pub position: Vec2,
-    pub button_states: [bool; 3],
-}
-
-#[derive(Component)]
-struct MainCamera;
-
-pub const IS_WASM: bool = cfg!(target_arch = "wasm32");
-
 // Got to find out why these `cfg` directives with `wasm` don't work for me
 // Weird that the macro works though...
 // #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
@@ -44,44 +30,103 @@ fn setup_window(mut primary_query: Query<&mut Window, With<PrimaryWindow>>) {
     window.title = "Le Boids".to_string();
 }
 
-fn cursor_system(
-    mut cursor: ResMut<Cursor>,
-    primary_query: Query<&Window, With<PrimaryWindow>>,
-    mut mouse_button_events: EventReader<MouseButtonInput>,
-    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-) {
-    let (camera, camera_transform) = camera_query.single();
-    let window = primary_query.get_single().unwrap();
-    if let Some(screen_pos) = window.cursor_position() {
-        let window_size = Vec2::new(window.width(), window.height());
-
-        let normalized_device_coordinates = (screen_pos / window_size) * 2.0 - Vec2::ONE;
-        let normalized_device_coordinates_to_world =
-            camera_transform.compute_matrix() * camera.projection_matrix().inverse();
-        let world_pos = normalized_device_coordinates_to_world
-            .project_point3(normalized_device_coordinates.extend(-1.0));
-        let world_pos: Vec2 = world_pos.truncate();
-
-        cursor.position = world_pos;
+/// Grabs the value after `--<name>` (or from `--<name>=<value>`) on the command line.
+/// Another `--<option>` right after it isn't a value, so a bare flag gives `None`.
+fn cli_argument(name: &str) -> Option<String> {
+    let flag = format!("--{}", name);
+    let mut arguments = std::env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        if argument == flag {
+            return arguments.next().filter(|value| !value.starts_with("--"));
+        }
+        if let Some(value) = argument.strip_prefix(&format!("{}=", flag)) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+fn cli_flag(name: &str) -> bool {
+    let flag = format!("--{}", name);
+    std::env::args().skip(1).any(|argument| argument == flag)
+}
+
+/// Command line arguments natively and URL query parameters (`?seed=42`) on the web.
+struct LaunchOptions {
+    query: Vec<(String, String)>,
+}
+
+impl LaunchOptions {
+    fn new() -> Self {
+        LaunchOptions {
+            query: files::url_query_parameters(),
+        }
+    }
+
+    fn value(&self, name: &str) -> Option<String> {
+        cli_argument(name).or_else(|| {
+            self.query
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        })
     }
-    for event in mouse_button_events.iter() {
-        let button_index = match event.button {
-            MouseButton::Left => 0,
-            MouseButton::Middle => 1,
-            MouseButton::Right => 2,
-            _ => continue,
+
+    /// A bare `--<name>` turns it on and `--<name> 0` (or `=0`) turns it off
+    fn flag(&self, name: &str) -> Option<bool> {
+        match self.value(name) {
+            Some(value) => Some(!matches!(value.as_str(), "0" | "false" | "off")),
+            None if cli_flag(name) => Some(true),
+            None => None,
+        }
+    }
+
+    /// `pop0=2000&pop1=10` (or `--pop0 2000`) style overrides as
+    /// `(creature index, population)`. The command line goes last so it wins.
+    fn populations(&self) -> Vec<(usize, usize)> {
+        let cli = std::env::args().skip(1).filter_map(|argument| {
+            let name = argument.strip_prefix("--")?.split('=').next()?.to_string();
+            let value = cli_argument(&name)?;
+            Some((name, value))
+        });
+        self.query
+            .iter()
+            .cloned()
+            .chain(cli)
+            .filter_map(|(key, value)| {
+                let index = key.strip_prefix("pop")?.parse().ok()?;
+                Some((index, value.parse().ok()?))
+            })
+            .collect()
+    }
+
+    fn apply_features(&self, features: &mut Features) {
+        let apply = |name: &str, feature: &mut bool| {
+            if let Some(enabled) = self.flag(name) {
+                *feature = enabled;
+            }
         };
-        cursor.button_states[button_index] = event.state.is_pressed();
+        apply("chasing", &mut features.chasing);
+        apply("running", &mut features.running);
+        apply("killing", &mut features.killing);
+        apply("flocking", &mut features.flocking);
+        apply("energy_draining", &mut features.energy_draining);
     }
 }
 
 fn main() {
     let mut app = App::new();
 
+    let options = LaunchOptions::new();
+    let seed = options
+        .value("seed")
+        .and_then(|seed| seed.parse::<u64>().ok());
+    let deterministic = options.flag("deterministic").unwrap_or(false);
+    let strict = options.flag("strict").unwrap_or(false);
+    let bench = options.flag("bench").unwrap_or(false);
+
     // Startup Things
     app.add_startup_system(setup_window) // IDK Why the window doesn't resize with the descriptor
-        .add_startup_system(setup_cameras)
-        .insert_resource(Cursor::default())
         .insert_resource(ClearColor(Color::rgb(0.0, 0.0, 0.0)))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -93,13 +138,50 @@ fn main() {
             ..default()
         }));
 
-    app.add_system(cursor_system);
+    // Flocking in a box instead, without any of the 2D extras
+    #[cfg(feature = "boids-3d")]
+    if options.flag("3d").unwrap_or(false) {
+        app.add_plugin(boids3d::Boids3dPlugin::default().with_seed(seed));
+        app.run();
+        return;
+    }
+
+    app.add_startup_system(setup_cameras);
 
     // Adding Boids Simulation which includes the UI plugin
-    app.add_plugin(BoidsPlugin::default());
+    let boids_plugin = options.populations().into_iter().fold(
+        BoidsPlugin::default()
+            .with_seed(seed)
+            .with_deterministic(deterministic)
+            .with_world_bounds(WIDTH, HEIGHT),
+        |plugin, (index, population)| plugin.with_population(index, population),
+    );
+    app.add_plugin(boids_plugin);
+
+    // Done after the plugin so a shared link wins over settings the browser remembered
+    options.apply_features(&mut app.world.resource_mut::<Features>());
+
+    // Runs through the benchmark populations, saves the timings, and quits
+    if bench {
+        app.world
+            .resource_mut::<benchmark::BenchmarkMode>()
+            .exit_when_done = true;
+        app.world
+            .resource_mut::<Events<benchmark::StartBenchmarkEvent>>()
+            .send(benchmark::StartBenchmarkEvent);
+    }
+
+    // Opt-in panics for when systems disagree with each other
+    app.add_plugin(invariants::InvariantsPlugin { strict });
+
+    #[cfg(feature = "inspector")]
+    app.add_plugin(bevy_inspector_egui::quick::WorldInspectorPlugin::new());
+
+    // F12 for a picture
+    app.add_plugin(screenshot::ScreenshotPlugin);
 
     // Diagnostic stuff (FPS printing, etc)
-    app.add_plugin(FrameTimeDiagnosticsPlugin::default())
+    app.add_plugin(FrameTimeDiagnosticsPlugin)
         .add_plugin(LogDiagnosticsPlugin {
             wait_duration: Duration::from_secs(2),
             ..Default::default()
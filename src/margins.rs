@@ -0,0 +1,81 @@
+// A band along the edges of the world where creatures get turned back inwards,
+// harder the deeper in they are, so they swing around near the border instead
+// of popping out the other side. Anything that makes it all the way through
+// still wraps around like before.
+
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    ApplyForceEvent, CreatureType, ForceKind, FrozenTypes, SimState, SystemStages, WorldBounds,
+};
+
+#[derive(Debug, Clone, Resource)]
+pub struct MarginSettings {
+    pub enabled: bool,
+    /// How far in from each edge the band goes
+    pub margin: f32,
+    /// How hard creatures get turned at the very edge. It fades out to
+    /// nothing at the inside of the band.
+    pub strength: f32,
+}
+
+impl Default for MarginSettings {
+    fn default() -> Self {
+        MarginSettings {
+            enabled: false,
+            margin: 100.0,
+            strength: 20.0,
+        }
+    }
+}
+
+fn margin_system(
+    settings: Res<MarginSettings>,
+    world_bounds: Res<WorldBounds>,
+    frozen_types: Res<FrozenTypes>,
+    creature_query: Query<(Entity, &Transform, &CreatureType)>,
+    mut force_writer: EventWriter<ApplyForceEvent>,
+) {
+    if !settings.enabled || settings.strength <= 0.0 {
+        return;
+    }
+    // Can't be more than half the world or the bands would overlap
+    let margin = settings
+        .margin
+        .min(world_bounds.size().min_element() / 2.0)
+        .max(1.0);
+    let (min, max) = (world_bounds.min(), world_bounds.max());
+    for (entity, transform, creature_type) in creature_query.iter() {
+        if frozen_types.contains(creature_type) {
+            continue;
+        }
+        let position = transform.translation.xy();
+        // How far into the band it is on each side, out of the whole band
+        let low = ((min + margin - position) / margin).clamp(Vec2::ZERO, Vec2::ONE);
+        let high = ((position - (max - margin)) / margin).clamp(Vec2::ZERO, Vec2::ONE);
+        let inward = low - high;
+        let depth = inward.abs().max_element();
+        if depth <= 0.0 {
+            continue;
+        }
+        force_writer.send(ApplyForceEvent(
+            entity,
+            inward.normalize(),
+            settings.strength * depth,
+            ForceKind::Margin,
+        ));
+    }
+}
+
+#[derive(Default)]
+pub struct MarginPlugin;
+
+impl Plugin for MarginPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MarginSettings>().add_system(
+            margin_system
+                .in_set(SystemStages::Calculate)
+                .in_set(OnUpdate(SimState::Running)),
+        );
+    }
+}
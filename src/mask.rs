@@ -0,0 +1,173 @@
+// Turns a black and white image into obstacles stretched over the whole world,
+// so creatures can be boxed into a logo or a map. The image gets chopped into
+// a grid and runs of dark squares get merged into as few boxes as possible
+// since every obstacle costs something for every creature.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    files::load_file,
+    obstacles::{ObstacleBuilder, ObstacleShape},
+    WorldBounds,
+};
+
+#[derive(Debug, Clone, Resource)]
+pub struct MaskSettings {
+    /// Relative to where the sim was started from
+    pub path: String,
+    /// World units across each square of the grid the image gets chopped into
+    pub resolution: f32,
+    /// Pixels darker than this (0 to 1) are walls
+    pub threshold: f32,
+    /// Light pixels are walls instead
+    pub invert: bool,
+    /// What happened last time something was loaded
+    pub status: Option<String>,
+}
+
+impl Default for MaskSettings {
+    fn default() -> Self {
+        MaskSettings {
+            path: "mask.png".to_string(),
+            resolution: 8.0,
+            threshold: 0.5,
+            invert: false,
+            status: None,
+        }
+    }
+}
+
+/// Loads the image at `MaskSettings::path`, replacing the last mask
+pub struct LoadMaskEvent;
+
+pub struct ClearMaskEvent;
+
+/// Obstacles that came from the mask, so clearing it leaves the rest alone
+#[derive(Debug, Clone, Copy, Component)]
+pub struct MaskObstacle;
+
+/// Greedily merges the walls into boxes, returned as (min, max) corners in
+/// grid squares with the max exclusive. Rows of the same span get stacked.
+fn merge_walls(walls: &[bool], columns: usize, rows: usize) -> Vec<(UVec2, UVec2)> {
+    let mut boxes = vec![];
+    // Spans still growing downwards and the row they started on
+    let mut open = BTreeMap::<(usize, usize), usize>::new();
+    let mut spans = vec![];
+    for row in 0..=rows {
+        spans.clear();
+        if row < rows {
+            let mut column = 0;
+            while column < columns {
+                if !walls[row * columns + column] {
+                    column += 1;
+                    continue;
+                }
+                let start = column;
+                while column < columns && walls[row * columns + column] {
+                    column += 1;
+                }
+                spans.push((start, column));
+            }
+        }
+        open.retain(|&(start, end), &mut first_row| {
+            let continues = spans.contains(&(start, end));
+            if !continues {
+                boxes.push((
+                    UVec2::new(start as u32, first_row as u32),
+                    UVec2::new(end as u32, row as u32),
+                ));
+            }
+            continues
+        });
+        for &span in spans.iter() {
+            open.entry(span).or_insert(row);
+        }
+    }
+    boxes
+}
+
+fn load_mask_system(
+    mut builder: ObstacleBuilder,
+    mut settings: ResMut<MaskSettings>,
+    world_bounds: Res<WorldBounds>,
+    mut load_events: EventReader<LoadMaskEvent>,
+    mut clear_events: EventReader<ClearMaskEvent>,
+    mask_query: Query<Entity, With<MaskObstacle>>,
+) {
+    let load = load_events.iter().count() > 0;
+    if !load && clear_events.iter().count() == 0 {
+        return;
+    }
+    for entity in mask_query.iter() {
+        builder.commands.entity(entity).despawn();
+    }
+    if !load {
+        settings.status = None;
+        return;
+    }
+
+    let image = match load_file(&settings.path)
+        .and_then(|bytes| image::load_from_memory(&bytes).map_err(|error| error.to_string()))
+    {
+        Ok(image) => image.to_luma_alpha8(),
+        Err(error) => {
+            settings.status = Some(format!("Couldn't load it: {}", error));
+            return;
+        }
+    };
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        settings.status = Some("The image is empty".to_string());
+        return;
+    }
+
+    // Each square of the grid goes off of the pixel under its middle
+    let size = world_bounds.size();
+    let resolution = settings.resolution.max(1.0);
+    let columns = (size.x / resolution).ceil().max(1.0) as usize;
+    let rows = (size.y / resolution).ceil().max(1.0) as usize;
+    let mut walls = vec![false; columns * rows];
+    for row in 0..rows {
+        for column in 0..columns {
+            let x = ((column as f32 + 0.5) / columns as f32 * width as f32) as u32;
+            let y = ((row as f32 + 0.5) / rows as f32 * height as f32) as u32;
+            let [luma, alpha] = image.get_pixel(x.min(width - 1), y.min(height - 1)).0;
+            // See through counts as open either way
+            let dark = (luma as f32) < settings.threshold * 255.0;
+            walls[row * columns + column] = alpha > 127 && dark != settings.invert;
+        }
+    }
+
+    // Images go top down but the world goes bottom up
+    let square = size / Vec2::new(columns as f32, rows as f32);
+    let top_left = Vec2::new(world_bounds.min().x, world_bounds.max().y);
+    let boxes = merge_walls(&walls, columns, rows);
+    for &(min, max) in boxes.iter() {
+        let (min, max) = (min.as_vec2() * square, max.as_vec2() * square);
+        let center = top_left + Vec2::new(min.x + max.x, -(min.y + max.y)) / 2.0;
+        let shape = ObstacleShape::Rect {
+            half_size: (max - min) / 2.0,
+        };
+        builder.spawn(center, shape).insert(MaskObstacle);
+    }
+    settings.status = Some(format!(
+        "{}x{} image turned into {} obstacles",
+        width,
+        height,
+        boxes.len()
+    ));
+}
+
+#[derive(Default)]
+pub struct MaskPlugin;
+
+impl Plugin for MaskPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MaskSettings>()
+            .add_event::<LoadMaskEvent>()
+            .add_event::<ClearMaskEvent>()
+            .add_system(load_mask_system);
+    }
+}
@@ -0,0 +1,182 @@
+// Fills the world with obstacles in one go, either a maze of corridors or a
+// field of scattered pillars. Same seed and settings give the same layout.
+// Generating throws out every obstacle that was there before.
+
+use bevy::prelude::*;
+use rand::{prelude::*, rngs::StdRng};
+
+use crate::{
+    obstacles::{Obstacle, ObstacleBuilder, ObstacleShape},
+    WorldBounds,
+};
+
+/// Walls are this much of a cell thick
+const WALL_THICKNESS: f32 = 0.15;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MazeLayout {
+    #[default]
+    Maze,
+    Pillars,
+}
+
+impl MazeLayout {
+    pub const ALL: [MazeLayout; 2] = [MazeLayout::Maze, MazeLayout::Pillars];
+}
+
+impl std::fmt::Display for MazeLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct MazeSettings {
+    pub layout: MazeLayout,
+    /// How wide corridors are, or how far apart pillars are
+    pub cell_size: f32,
+    /// For mazes, how many of the walls stay up (1 has exactly one way between
+    /// any two spots). For pillars, how many cells get one.
+    pub density: f32,
+    pub seed: u64,
+}
+
+impl Default for MazeSettings {
+    fn default() -> Self {
+        MazeSettings {
+            layout: MazeLayout::Maze,
+            cell_size: 100.0,
+            density: 0.6,
+            seed: 0,
+        }
+    }
+}
+
+/// Replaces all the obstacles with a new maze or pillars
+pub struct GenerateMazeEvent;
+
+/// Carves a maze out of a full grid of walls with a depth first search.
+/// Returns which walls are still up on the east and north side of each cell.
+fn carve_maze(columns: usize, rows: usize, rng: &mut StdRng) -> (Vec<bool>, Vec<bool>) {
+    let mut east = vec![true; columns * rows];
+    let mut north = vec![true; columns * rows];
+    let mut visited = vec![false; columns * rows];
+    let mut stack = vec![0];
+    visited[0] = true;
+    while let Some(&cell) = stack.last() {
+        let (column, row) = (cell % columns, cell / columns);
+        let mut unvisited = Vec::with_capacity(4);
+        if column + 1 < columns && !visited[cell + 1] {
+            unvisited.push(cell + 1);
+        }
+        if column > 0 && !visited[cell - 1] {
+            unvisited.push(cell - 1);
+        }
+        if row + 1 < rows && !visited[cell + columns] {
+            unvisited.push(cell + columns);
+        }
+        if row > 0 && !visited[cell - columns] {
+            unvisited.push(cell - columns);
+        }
+        let Some(&next) = unvisited.choose(rng) else {
+            stack.pop();
+            continue;
+        };
+        // Knock down the wall between them, which belongs to the lower one
+        let lower = cell.min(next);
+        if next / columns == row {
+            east[lower] = false;
+        } else {
+            north[lower] = false;
+        }
+        visited[next] = true;
+        stack.push(next);
+    }
+    (east, north)
+}
+
+fn generate_maze_system(
+    mut builder: ObstacleBuilder,
+    settings: Res<MazeSettings>,
+    world_bounds: Res<WorldBounds>,
+    mut generate_events: EventReader<GenerateMazeEvent>,
+    obstacle_query: Query<Entity, With<Obstacle>>,
+) {
+    if generate_events.iter().count() == 0 {
+        return;
+    }
+    for entity in obstacle_query.iter() {
+        builder.commands.entity(entity).despawn();
+    }
+
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+    let cell_size = settings.cell_size.max(10.0);
+    let size = world_bounds.size();
+    let columns = ((size.x / cell_size) as usize).max(1);
+    let rows = ((size.y / cell_size) as usize).max(1);
+    // Centered with whatever doesn't fit split around the edges
+    let origin = -Vec2::new(columns as f32, rows as f32) * cell_size / 2.0;
+    let cell_min =
+        |column: usize, row: usize| origin + Vec2::new(column as f32, row as f32) * cell_size;
+    let mut obstacles = vec![];
+
+    match settings.layout {
+        MazeLayout::Maze => {
+            let (east, north) = carve_maze(columns, rows, &mut rng);
+            let thickness = cell_size * WALL_THICKNESS;
+            // Walls run a bit past the cell so corners don't have gaps
+            let half_length = (cell_size + thickness) / 2.0;
+            for row in 0..rows {
+                for column in 0..columns {
+                    let cell = row * columns + column;
+                    let min = cell_min(column, row);
+                    // The outside edge stays open so the world can still wrap
+                    if column + 1 < columns && east[cell] && rng.gen::<f32>() < settings.density {
+                        let center = min + Vec2::new(cell_size, cell_size / 2.0);
+                        let half_size = Vec2::new(thickness / 2.0, half_length);
+                        obstacles.push((center, ObstacleShape::Rect { half_size }));
+                    }
+                    if row + 1 < rows && north[cell] && rng.gen::<f32>() < settings.density {
+                        let center = min + Vec2::new(cell_size / 2.0, cell_size);
+                        let half_size = Vec2::new(half_length, thickness / 2.0);
+                        obstacles.push((center, ObstacleShape::Rect { half_size }));
+                    }
+                }
+            }
+        }
+        MazeLayout::Pillars => {
+            for row in 0..rows {
+                for column in 0..columns {
+                    if rng.gen::<f32>() >= settings.density {
+                        continue;
+                    }
+                    let radius = cell_size * rng.gen_range(0.15..0.3);
+                    // Jittered so it doesn't look like a grid
+                    let jitter = cell_size / 2.0 - radius;
+                    let center = cell_min(column, row)
+                        + Vec2::splat(cell_size / 2.0)
+                        + Vec2::new(
+                            rng.gen_range(-jitter..=jitter),
+                            rng.gen_range(-jitter..=jitter),
+                        );
+                    obstacles.push((center, ObstacleShape::Circle { radius }));
+                }
+            }
+        }
+    }
+
+    for (center, shape) in obstacles {
+        builder.spawn(center, shape);
+    }
+}
+
+#[derive(Default)]
+pub struct MazePlugin;
+
+impl Plugin for MazePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MazeSettings>()
+            .add_event::<GenerateMazeEvent>()
+            .add_system(generate_maze_system);
+    }
+}
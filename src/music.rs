@@ -0,0 +1,145 @@
+// Two music loops of the same length play the whole time and get crossfaded
+// between. The calm one plays when not much is going on and the intense one
+// takes over as more creatures chase and get eaten, so a big hunt can be heard.
+
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    lifecycle::{BoidKilled, DeathCause},
+    sound::SoundSettings,
+    ApplyForceEvent, ForceKind, SimState,
+};
+
+/// Seconds of kills that count towards the kill rate
+const KILL_WINDOW: f32 = 5.0;
+/// This many chasing at once is as intense as it gets (on its own)
+const FULL_CHASERS: f32 = 40.0;
+/// This many kills a second is as intense as it gets (on its own)
+const FULL_KILL_RATE: f32 = 2.0;
+/// How fast the music can get more intense and calm back down (per second)
+const RISE_SPEED: f32 = 0.5;
+const FALL_SPEED: f32 = 0.15;
+
+/// How intense things are, from 0 (calm) to 1 (all out hunt).
+#[derive(Debug, Default, Resource)]
+pub struct MusicIntensity {
+    pub intensity: f32,
+    /// Creatures chasing something on the last tick
+    pub chasers: usize,
+    /// Creatures eaten per second lately
+    pub kill_rate: f32,
+    kills: VecDeque<f32>,
+}
+
+#[derive(Debug, Resource)]
+struct MusicSinks {
+    calm: Handle<AudioSink>,
+    intense: Handle<AudioSink>,
+}
+
+fn setup_music_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+) {
+    // Both start silent and the volume system fades them in
+    let play = |path: &str| {
+        let sink = audio.play_with_settings(
+            asset_server.load(path),
+            PlaybackSettings::LOOP.with_volume(0.0),
+        );
+        audio_sinks.get_handle(sink)
+    };
+    let sinks = MusicSinks {
+        calm: play("sounds/music_calm.wav"),
+        intense: play("sounds/music_intense.wav"),
+    };
+    commands.insert_resource(sinks);
+}
+
+fn music_intensity_system(
+    time: Res<Time>,
+    sim_state: Res<State<SimState>>,
+    mut music: ResMut<MusicIntensity>,
+    mut force_reader: EventReader<ApplyForceEvent>,
+    mut killed_reader: EventReader<BoidKilled>,
+) {
+    let now = time.raw_elapsed_seconds();
+    let kills = killed_reader
+        .iter()
+        .filter(|killed| matches!(killed.cause, DeathCause::Eaten { .. }))
+        .count();
+    music.kills.extend(std::iter::repeat_n(now, kills));
+    while music
+        .kills
+        .front()
+        .is_some_and(|&killed_at| now - killed_at > KILL_WINDOW)
+    {
+        music.kills.pop_front();
+    }
+    music.kill_rate = music.kills.len() as f32 / KILL_WINDOW;
+
+    // Flocking runs on a fixed timestep so frames without any forces keep the
+    // last count instead of dropping to nothing
+    let mut chasers = HashSet::default();
+    let mut any_forces = false;
+    for ApplyForceEvent(entity, _, _, kind) in force_reader.iter() {
+        any_forces = true;
+        if *kind == ForceKind::Chase {
+            chasers.insert(*entity);
+        }
+    }
+    if sim_state.0 == SimState::Paused {
+        music.chasers = 0;
+    } else if any_forces {
+        music.chasers = chasers.len();
+    }
+
+    let target =
+        (music.chasers as f32 / FULL_CHASERS + music.kill_rate / FULL_KILL_RATE).clamp(0.0, 1.0);
+    let delta_seconds = time.raw_delta_seconds();
+    music.intensity = if target > music.intensity {
+        (music.intensity + RISE_SPEED * delta_seconds).min(target)
+    } else {
+        (music.intensity - FALL_SPEED * delta_seconds).max(target)
+    };
+}
+
+fn music_volume_system(
+    settings: Res<SoundSettings>,
+    music: Res<MusicIntensity>,
+    sinks: Option<Res<MusicSinks>>,
+    audio_sinks: Res<Assets<AudioSink>>,
+) {
+    let Some(sinks) = sinks else {
+        return;
+    };
+    // Equal power so the middle of the fade isn't quieter than the ends
+    let volume = settings.volume(settings.music_volume);
+    let angle = music.intensity * std::f32::consts::FRAC_PI_2;
+    // The sinks only show up once the loops have loaded and started playing
+    if let Some(calm) = audio_sinks.get(&sinks.calm) {
+        calm.set_volume(volume * angle.cos());
+    }
+    if let Some(intense) = audio_sinks.get(&sinks.intense) {
+        intense.set_volume(volume * angle.sin());
+    }
+}
+
+#[derive(Default)]
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MusicIntensity::default())
+            .add_startup_system(setup_music_system)
+            .add_systems(
+                (music_intensity_system, music_volume_system)
+                    .chain()
+                    .in_base_set(CoreSet::PostUpdate),
+            );
+    }
+}
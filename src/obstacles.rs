@@ -0,0 +1,816 @@
+// Walls for the creatures to find their way around. Obstacles can be boxes,
+// circles, or any convex polygon. Creatures look ahead along where they're
+// heading and turn away from whatever they'd run into, get shoved back out if
+// they end up inside one anyway, and can't see each other through them.
+//
+// Alt+left drag draws a box or circle. With the polygon tool each Alt+left click
+// adds a corner, and clicking the first corner again (or Enter) finishes it.
+// Escape throws away a half drawn shape.
+
+use bevy::{
+    ecs::system::{EntityCommands, SystemParam},
+    input::mouse::MouseButtonInput,
+    math::Vec3Swizzles,
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+    sprite::MaterialMesh2dBundle,
+    utils::HashMap,
+    window::PrimaryWindow,
+};
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    gust::distance_to_segment, move_system, overlays::WorldToScreen, ui::overlays_shown,
+    variation::Variation, wrap_borders_system, ApplyForceEvent, CreatureType, Cursor, Direction,
+    FactorInfo, ForceKind, FrozenTypes, MainCamera, SimState, SystemStages,
+};
+
+const OBSTACLE_COLOR: Color = Color::rgb(0.35, 0.35, 0.4);
+/// Circles get drawn as this many sided polygons
+const CIRCLE_SEGMENTS: usize = 32;
+/// Shapes smaller than this across are probably misclicks
+const MIN_SIZE: f32 = 4.0;
+/// Clicking this close to the first corner closes the polygon
+const CLOSE_RADIUS: f32 = 8.0;
+/// How far outside the edge creatures get put when they're shoved out
+const PUSH_MARGIN: f32 = 0.5;
+/// World units across each square obstacles get filed into for looking them up
+const OBSTACLE_CELL: f32 = 100.0;
+
+/// Shapes are relative to the obstacle's position. Polygons are convex and go
+/// counterclockwise.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObstacleShape {
+    Rect { half_size: Vec2 },
+    Circle { radius: f32 },
+    Polygon { points: Vec<Vec2> },
+}
+
+impl ObstacleShape {
+    /// Nothing further than this from the middle is part of the shape
+    pub fn bounding_radius(&self) -> f32 {
+        match self {
+            ObstacleShape::Rect { half_size } => half_size.length(),
+            ObstacleShape::Circle { radius } => *radius,
+            ObstacleShape::Polygon { points } => points
+                .iter()
+                .map(|point| point.length())
+                .fold(0.0, f32::max),
+        }
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        match self {
+            ObstacleShape::Rect { half_size } => polygon_contains(&rect_corners(*half_size), point),
+            ObstacleShape::Circle { radius } => point.length_squared() <= radius * radius,
+            ObstacleShape::Polygon { points } => polygon_contains(points, point),
+        }
+    }
+
+    /// Closest point on the outline, from inside or out
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        match self {
+            ObstacleShape::Rect { half_size } => {
+                polygon_closest_point(&rect_corners(*half_size), point)
+            }
+            ObstacleShape::Circle { radius } => point.try_normalize().unwrap_or(Vec2::X) * *radius,
+            ObstacleShape::Polygon { points } => polygon_closest_point(points, point),
+        }
+    }
+
+    /// How far along `direction` (normalized) a ray from `origin` goes before
+    /// hitting the shape and which way the surface it hit faces. Starting
+    /// inside counts as hitting right away.
+    pub fn raycast(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> Option<(f32, Vec2)> {
+        match self {
+            ObstacleShape::Rect { half_size } => {
+                polygon_raycast(&rect_corners(*half_size), origin, direction, max_distance)
+            }
+            ObstacleShape::Circle { radius } => {
+                circle_raycast(*radius, origin, direction, max_distance)
+            }
+            ObstacleShape::Polygon { points } => {
+                polygon_raycast(points, origin, direction, max_distance)
+            }
+        }
+    }
+
+    /// The outline as points going counterclockwise, for drawing
+    pub fn outline(&self) -> Vec<Vec2> {
+        match self {
+            ObstacleShape::Rect { half_size } => rect_corners(*half_size).to_vec(),
+            ObstacleShape::Circle { radius } => (0..CIRCLE_SEGMENTS)
+                .map(|index| {
+                    let angle = index as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                    Vec2::from_angle(angle) * *radius
+                })
+                .collect(),
+            ObstacleShape::Polygon { points } => points.clone(),
+        }
+    }
+}
+
+fn rect_corners(half_size: Vec2) -> [Vec2; 4] {
+    [
+        Vec2::new(-half_size.x, -half_size.y),
+        Vec2::new(half_size.x, -half_size.y),
+        Vec2::new(half_size.x, half_size.y),
+        Vec2::new(-half_size.x, half_size.y),
+    ]
+}
+
+fn edges(points: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(&start, &end)| (start, end))
+}
+
+fn polygon_contains(points: &[Vec2], point: Vec2) -> bool {
+    points.len() >= 3
+        && edges(points).all(|(start, end)| (end - start).perp_dot(point - start) >= 0.0)
+}
+
+fn polygon_closest_point(points: &[Vec2], point: Vec2) -> Vec2 {
+    edges(points)
+        .map(|(start, end)| {
+            let segment = end - start;
+            let t = ((point - start).dot(segment) / segment.length_squared().max(f32::EPSILON))
+                .clamp(0.0, 1.0);
+            start + segment * t
+        })
+        .min_by(|a, b| {
+            a.distance_squared(point)
+                .total_cmp(&b.distance_squared(point))
+        })
+        .unwrap_or(Vec2::ZERO)
+}
+
+/// Clips the ray against every edge, which works for any convex polygon
+fn polygon_raycast(
+    points: &[Vec2],
+    origin: Vec2,
+    direction: Vec2,
+    max_distance: f32,
+) -> Option<(f32, Vec2)> {
+    if points.len() < 3 {
+        return None;
+    }
+    let (mut enter, mut exit) = (0.0, max_distance);
+    let mut normal = None;
+    for (start, end) in edges(points) {
+        let edge = end - start;
+        // Counterclockwise so the right hand side is outside
+        let outward = Vec2::new(edge.y, -edge.x);
+        let facing = outward.dot(direction);
+        let distance = outward.dot(start - origin);
+        if facing.abs() < f32::EPSILON {
+            // Running alongside this edge on the outside never gets in
+            if distance < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let t = distance / facing;
+        if facing < 0.0 {
+            if t > enter {
+                enter = t;
+                normal = Some(outward);
+            }
+        } else {
+            exit = f32::min(exit, t);
+        }
+        if enter > exit {
+            return None;
+        }
+    }
+    let normal = normal.map_or(-direction, |normal| normal.normalize_or_zero());
+    Some((enter, normal))
+}
+
+fn circle_raycast(
+    radius: f32,
+    origin: Vec2,
+    direction: Vec2,
+    max_distance: f32,
+) -> Option<(f32, Vec2)> {
+    let along = origin.dot(direction);
+    let outside = origin.length_squared() - radius * radius;
+    if outside <= 0.0 {
+        return Some((0.0, origin.try_normalize().unwrap_or(-direction)));
+    }
+    // Heading away
+    if along > 0.0 {
+        return None;
+    }
+    let discriminant = along * along - outside;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t = -along - discriminant.sqrt();
+    (t <= max_distance).then(|| (t, (origin + direction * t).normalize_or_zero()))
+}
+
+/// Turns a bunch of clicked points into a convex polygon going counterclockwise
+pub fn convex_hull(mut points: Vec<Vec2>) -> Vec<Vec2> {
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    // Andrew's monotone chain, bottom half then top half
+    let half_hull = |points: &mut dyn Iterator<Item = Vec2>| {
+        let mut half: Vec<Vec2> = Vec::new();
+        for point in points {
+            while let [.., a, b] = half[..] {
+                if (b - a).perp_dot(point - a) > 0.0 {
+                    break;
+                }
+                half.pop();
+            }
+            half.push(point);
+        }
+        // The last point of each half is the first of the other
+        half.pop();
+        half
+    };
+    let mut hull = half_hull(&mut points.iter().copied());
+    hull.extend(half_hull(&mut points.iter().rev().copied()));
+    hull
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct Obstacle(pub ObstacleShape);
+
+#[derive(Debug, Clone)]
+struct PlacedObstacle {
+    center: Vec2,
+    shape: ObstacleShape,
+    bounding_radius: f32,
+}
+
+fn obstacle_cell(position: Vec2) -> (i32, i32) {
+    let cell = (position / OBSTACLE_CELL).floor();
+    (cell.x as i32, cell.y as i32)
+}
+
+/// Every obstacle in world space for the systems (like flocking) that can't
+/// easily query them. They're filed into a grid since image masks can make a
+/// lot of them.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct ObstacleSet {
+    obstacles: Vec<PlacedObstacle>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl ObstacleSet {
+    fn rebuild(&mut self, obstacles: Vec<PlacedObstacle>) {
+        self.cells.clear();
+        for (index, obstacle) in obstacles.iter().enumerate() {
+            let reach = Vec2::splat(obstacle.bounding_radius);
+            let (min, max) = (
+                obstacle_cell(obstacle.center - reach),
+                obstacle_cell(obstacle.center + reach),
+            );
+            for x in min.0..=max.0 {
+                for y in min.1..=max.1 {
+                    self.cells.entry((x, y)).or_default().push(index);
+                }
+            }
+        }
+        self.obstacles = obstacles;
+    }
+
+    /// Obstacles that might be in the box. Big ones can come up more than once.
+    fn nearby(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = &PlacedObstacle> {
+        let (min, max) = (obstacle_cell(min), obstacle_cell(max));
+        (min.0..=max.0)
+            .flat_map(move |x| (min.1..=max.1).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .map(|&index| &self.obstacles[index])
+    }
+
+    pub fn len(&self) -> usize {
+        self.obstacles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.obstacles.is_empty()
+    }
+
+    /// Closest thing a ray runs into, how far away it is, and which way it faces
+    pub fn raycast(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> Option<(f32, Vec2)> {
+        let end = origin + direction * max_distance;
+        self.nearby(origin.min(end), origin.max(end))
+            .filter(|obstacle| {
+                distance_to_segment(obstacle.center, origin, end) <= obstacle.bounding_radius
+            })
+            .filter_map(|obstacle| {
+                obstacle
+                    .shape
+                    .raycast(origin - obstacle.center, direction, max_distance)
+            })
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+    }
+
+    pub fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        let offset = to - from;
+        let distance = offset.length();
+        distance < f32::EPSILON || self.raycast(from, offset / distance, distance).is_none()
+    }
+
+    /// Where to put something that's ended up inside an obstacle
+    pub fn push_out(&self, point: Vec2) -> Option<Vec2> {
+        let obstacle = self.nearby(point, point).find(|obstacle| {
+            obstacle.center.distance_squared(point) <= obstacle.bounding_radius.powi(2)
+                && obstacle.shape.contains(point - obstacle.center)
+        })?;
+        let local = point - obstacle.center;
+        let edge = obstacle.shape.closest_point(local);
+        let outward = (edge - local).try_normalize().unwrap_or(Vec2::X);
+        Some(obstacle.center + edge + outward * PUSH_MARGIN)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ObstacleTool {
+    #[default]
+    Rect,
+    Circle,
+    Polygon,
+}
+
+impl ObstacleTool {
+    pub const ALL: [ObstacleTool; 3] = [
+        ObstacleTool::Rect,
+        ObstacleTool::Circle,
+        ObstacleTool::Polygon,
+    ];
+}
+
+impl std::fmt::Display for ObstacleTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct ObstacleSettings {
+    pub tool: ObstacleTool,
+    /// How hard creatures turn away from a wall right in front of them
+    pub avoidance: f32,
+    /// How far ahead creatures look for walls, as a fraction of their vision
+    pub look_ahead: f32,
+    /// Obstacles block creatures from seeing each other
+    pub blocks_sight: bool,
+}
+
+impl Default for ObstacleSettings {
+    fn default() -> Self {
+        ObstacleSettings {
+            tool: ObstacleTool::Rect,
+            avoidance: 20.0,
+            look_ahead: 0.5,
+            blocks_sight: true,
+        }
+    }
+}
+
+/// A shape that's still being drawn
+#[derive(Debug, Clone, Default, Resource)]
+pub struct ObstacleDraft {
+    /// Where a box or circle drag started
+    pub drag_start: Option<Vec2>,
+    /// Corners clicked so far for a polygon
+    pub points: Vec<Vec2>,
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct ObstacleMaterial(pub Handle<ColorMaterial>);
+
+impl FromWorld for ObstacleMaterial {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        ObstacleMaterial(materials.add(ColorMaterial::from(OBSTACLE_COLOR)))
+    }
+}
+
+fn outline_mesh(outline: &[Vec2]) -> Mesh {
+    // Convex so a fan from the first corner covers it
+    let positions = outline
+        .iter()
+        .map(|point| [point.x, point.y, 0.0])
+        .collect::<Vec<_>>();
+    let normals = vec![[0.0, 0.0, 1.0]; outline.len()];
+    let uvs = vec![[0.0, 0.0]; outline.len()];
+    let indices = (1..outline.len().saturating_sub(1) as u32)
+        .flat_map(|index| [0, index, index + 1])
+        .collect::<Vec<_>>();
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+pub fn spawn_obstacle(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    material: &ObstacleMaterial,
+    center: Vec2,
+    shape: ObstacleShape,
+) -> Entity {
+    commands
+        .spawn((
+            MaterialMesh2dBundle {
+                mesh: meshes.add(outline_mesh(&shape.outline())).into(),
+                material: material.0.clone(),
+                // Under the creatures
+                transform: Transform::from_translation(center.extend(-0.5)),
+                ..default()
+            },
+            Obstacle(shape),
+        ))
+        .id()
+}
+
+/// Everything [`spawn_obstacle`] needs, for systems putting down a bunch of them
+#[derive(SystemParam)]
+pub(crate) struct ObstacleBuilder<'w, 's> {
+    pub(crate) commands: Commands<'w, 's>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    material: Res<'w, ObstacleMaterial>,
+}
+
+impl<'w, 's> ObstacleBuilder<'w, 's> {
+    pub(crate) fn spawn(
+        &mut self,
+        center: Vec2,
+        shape: ObstacleShape,
+    ) -> EntityCommands<'w, 's, '_> {
+        let entity = spawn_obstacle(
+            &mut self.commands,
+            &mut self.meshes,
+            &self.material,
+            center,
+            shape,
+        );
+        self.commands.entity(entity)
+    }
+}
+
+fn draw_obstacle_system(
+    mut builder: ObstacleBuilder,
+    cursor: Res<Cursor>,
+    keys: Res<Input<KeyCode>>,
+    settings: Res<ObstacleSettings>,
+    mut draft: ResMut<ObstacleDraft>,
+    mut mouse_button_events: EventReader<MouseButtonInput>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        *draft = ObstacleDraft::default();
+    }
+    let mut finished = None;
+    for event in mouse_button_events.iter() {
+        if event.button != MouseButton::Left || !keys.any_pressed([KeyCode::LAlt, KeyCode::RAlt]) {
+            continue;
+        }
+        match (settings.tool, event.state.is_pressed()) {
+            (ObstacleTool::Rect | ObstacleTool::Circle, true) => {
+                draft.drag_start = Some(cursor.position);
+            }
+            (ObstacleTool::Rect, false) => {
+                let Some(start) = draft.drag_start.take() else {
+                    continue;
+                };
+                let half_size = (cursor.position - start).abs() / 2.0;
+                if half_size.min_element() * 2.0 >= MIN_SIZE {
+                    let center = (start + cursor.position) / 2.0;
+                    finished = Some((center, ObstacleShape::Rect { half_size }));
+                }
+            }
+            (ObstacleTool::Circle, false) => {
+                let Some(center) = draft.drag_start.take() else {
+                    continue;
+                };
+                let radius = center.distance(cursor.position);
+                if radius * 2.0 >= MIN_SIZE {
+                    finished = Some((center, ObstacleShape::Circle { radius }));
+                }
+            }
+            (ObstacleTool::Polygon, false) => {
+                let closing = draft.points.len() >= 3
+                    && draft.points[0].distance(cursor.position) <= CLOSE_RADIUS;
+                if closing {
+                    finished = finish_polygon(&mut draft);
+                } else {
+                    draft.points.push(cursor.position);
+                }
+            }
+            (ObstacleTool::Polygon, true) => {}
+        }
+    }
+    if keys.just_pressed(KeyCode::Return) && settings.tool == ObstacleTool::Polygon {
+        finished = finish_polygon(&mut draft);
+    }
+
+    if let Some((center, shape)) = finished {
+        builder.spawn(center, shape);
+    }
+}
+
+fn finish_polygon(draft: &mut ObstacleDraft) -> Option<(Vec2, ObstacleShape)> {
+    let hull = convex_hull(std::mem::take(&mut draft.points));
+    if hull.len() < 3 {
+        return None;
+    }
+    let center = hull.iter().sum::<Vec2>() / hull.len() as f32;
+    let points = hull.into_iter().map(|point| point - center).collect();
+    Some((center, ObstacleShape::Polygon { points }))
+}
+
+fn draft_preview_system(
+    mut egui_context: EguiContexts,
+    cursor: Res<Cursor>,
+    settings: Res<ObstacleSettings>,
+    draft: Res<ObstacleDraft>,
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let outline = match (settings.tool, draft.drag_start) {
+        (ObstacleTool::Rect, Some(start)) => {
+            let (min, max) = (start.min(cursor.position), start.max(cursor.position));
+            vec![min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)]
+        }
+        (ObstacleTool::Circle, Some(center)) => ObstacleShape::Circle {
+            radius: center.distance(cursor.position),
+        }
+        .outline()
+        .into_iter()
+        .map(|point| center + point)
+        .collect(),
+        // Open until it's finished so it runs through the cursor instead
+        (ObstacleTool::Polygon, _) if !draft.points.is_empty() => {
+            let mut points = draft.points.clone();
+            points.push(cursor.position);
+            points
+        }
+        _ => return,
+    };
+    let Ok(window) = primary_query.get_single() else {
+        return;
+    };
+    let (camera, camera_transform) = camera_query.single();
+    let to_screen = WorldToScreen::new(camera, camera_transform, window);
+    let points = outline
+        .into_iter()
+        .filter_map(|point| to_screen.point(point))
+        .collect::<Vec<_>>();
+
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    let stroke = egui::Stroke::new(1.5, egui::Color32::LIGHT_GRAY);
+    if settings.tool == ObstacleTool::Polygon {
+        painter.add(egui::Shape::line(points, stroke));
+    } else {
+        painter.add(egui::Shape::closed_line(points, stroke));
+    }
+}
+
+type ChangedObstacle = (With<Obstacle>, Or<(Changed<Obstacle>, Changed<Transform>)>);
+
+fn collect_obstacles_system(
+    mut obstacle_set: ResMut<ObstacleSet>,
+    changed_query: Query<(), ChangedObstacle>,
+    mut removed: RemovedComponents<Obstacle>,
+    obstacle_query: Query<(&Transform, &Obstacle)>,
+) {
+    // Only bother when something's different since obstacles hardly ever change
+    let removed = removed.iter().count() > 0;
+    if changed_query.is_empty() && !removed {
+        return;
+    }
+    obstacle_set.rebuild(
+        obstacle_query
+            .iter()
+            .map(|(transform, obstacle)| PlacedObstacle {
+                center: transform.translation.xy(),
+                bounding_radius: obstacle.0.bounding_radius(),
+                shape: obstacle.0.clone(),
+            })
+            .collect(),
+    );
+}
+
+fn avoidance_system(
+    settings: Res<ObstacleSettings>,
+    obstacle_set: Res<ObstacleSet>,
+    factor_info: Res<FactorInfo>,
+    frozen_types: Res<FrozenTypes>,
+    creature_query: Query<(
+        Entity,
+        &Transform,
+        &Direction,
+        &CreatureType,
+        Option<&Variation>,
+    )>,
+    mut force_writer: EventWriter<ApplyForceEvent>,
+) {
+    if obstacle_set.is_empty() {
+        return;
+    }
+    for (entity, transform, direction, creature_type, variation) in creature_query.iter() {
+        let Some(factors) = factor_info.factors.get(creature_type) else {
+            continue;
+        };
+        let Some(heading) = direction.0.try_normalize() else {
+            continue;
+        };
+        if frozen_types.contains(creature_type) {
+            continue;
+        }
+        let look_ahead =
+            variation.copied().unwrap_or_default().vision(factors) * settings.look_ahead;
+        let position = transform.translation.xy();
+        let Some((distance, normal)) = obstacle_set.raycast(position, heading, look_ahead) else {
+            continue;
+        };
+        // Away from the wall and along it so they slide around instead of
+        // bouncing straight back
+        let along = heading - normal * heading.dot(normal);
+        let away = (normal + along.normalize_or_zero()).normalize_or_zero();
+        let closeness = 1.0 - distance / look_ahead.max(f32::EPSILON);
+        force_writer.send(ApplyForceEvent(
+            entity,
+            away,
+            settings.avoidance * closeness,
+            ForceKind::Obstacle,
+        ));
+    }
+}
+
+/// Turning away doesn't always work in time so anything that got in gets put
+/// back on the outside
+fn push_out_system(
+    obstacle_set: Res<ObstacleSet>,
+    mut creature_query: Query<&mut Transform, With<CreatureType>>,
+) {
+    if obstacle_set.is_empty() {
+        return;
+    }
+    for mut transform in creature_query.iter_mut() {
+        if let Some(position) = obstacle_set.push_out(transform.translation.xy()) {
+            transform.translation = position.extend(transform.translation.z);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ObstaclePlugin;
+
+impl Plugin for ObstaclePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ObstacleSettings>()
+            .init_resource::<ObstacleSet>()
+            .init_resource::<ObstacleDraft>()
+            .init_resource::<ObstacleMaterial>()
+            .add_system(collect_obstacles_system.in_base_set(CoreSet::PreUpdate))
+            .add_system(draw_obstacle_system)
+            .add_system(draft_preview_system.run_if(overlays_shown))
+            .add_system(
+                avoidance_system
+                    .in_set(SystemStages::Calculate)
+                    .in_set(OnUpdate(SimState::Running)),
+            )
+            .add_system(
+                push_out_system
+                    .after(move_system)
+                    .before(wrap_borders_system)
+                    .in_set(SystemStages::Act)
+                    .in_set(OnUpdate(SimState::Running)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Vec<Vec2> {
+        rect_corners(Vec2::splat(10.0)).to_vec()
+    }
+
+    #[test]
+    fn convex_hull_drops_inner_points_and_goes_counterclockwise() {
+        let hull = convex_hull(vec![
+            Vec2::new(10.0, 10.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(-10.0, 10.0),
+            Vec2::new(-10.0, -10.0),
+            Vec2::new(10.0, -10.0),
+            Vec2::new(2.0, -3.0),
+            Vec2::new(10.0, 10.0),
+        ]);
+        assert_eq!(hull, square());
+    }
+
+    #[test]
+    fn convex_hull_keeps_too_few_points_as_is() {
+        assert_eq!(
+            convex_hull(vec![Vec2::ONE, Vec2::ZERO, Vec2::ONE]),
+            vec![Vec2::ZERO, Vec2::ONE]
+        );
+    }
+
+    #[test]
+    fn polygon_contains_inside_edges_and_not_outside() {
+        let square = square();
+        assert!(polygon_contains(&square, Vec2::ZERO));
+        assert!(polygon_contains(&square, Vec2::new(10.0, 3.0)));
+        assert!(!polygon_contains(&square, Vec2::new(10.5, 3.0)));
+        assert!(!polygon_contains(&square, Vec2::new(-3.0, -11.0)));
+        assert!(!polygon_contains(&square[..2], Vec2::ZERO));
+    }
+
+    #[test]
+    fn polygon_raycast_hits_the_near_edge() {
+        let (distance, normal) = polygon_raycast(&square(), Vec2::new(-30.0, 0.0), Vec2::X, 100.0)
+            .expect("should hit the left side");
+        assert!((distance - 20.0).abs() < 1e-4);
+        assert_eq!(normal, Vec2::NEG_X);
+    }
+
+    #[test]
+    fn polygon_raycast_misses() {
+        let square = square();
+        // Too short
+        assert!(polygon_raycast(&square, Vec2::new(-30.0, 0.0), Vec2::X, 15.0).is_none());
+        // Heading away
+        assert!(polygon_raycast(&square, Vec2::new(-30.0, 0.0), Vec2::NEG_X, 100.0).is_none());
+        // Passing alongside
+        assert!(polygon_raycast(&square, Vec2::new(-30.0, 11.0), Vec2::X, 100.0).is_none());
+    }
+
+    #[test]
+    fn polygon_raycast_from_inside_hits_right_away() {
+        let (distance, _) = polygon_raycast(&square(), Vec2::ZERO, Vec2::Y, 100.0).unwrap();
+        assert_eq!(distance, 0.0);
+    }
+
+    #[test]
+    fn circle_raycast_hits_the_near_side() {
+        let (distance, normal) =
+            circle_raycast(5.0, Vec2::new(0.0, 20.0), Vec2::NEG_Y, 100.0).unwrap();
+        assert!((distance - 15.0).abs() < 1e-4);
+        assert!(normal.distance(Vec2::Y) < 1e-4);
+    }
+
+    #[test]
+    fn circle_raycast_misses() {
+        // Too short, heading away, and passing by
+        assert!(circle_raycast(5.0, Vec2::new(0.0, 20.0), Vec2::NEG_Y, 10.0).is_none());
+        assert!(circle_raycast(5.0, Vec2::new(0.0, 20.0), Vec2::Y, 100.0).is_none());
+        assert!(circle_raycast(5.0, Vec2::new(6.0, 20.0), Vec2::NEG_Y, 100.0).is_none());
+    }
+
+    #[test]
+    fn circle_raycast_from_inside_hits_right_away() {
+        let (distance, normal) = circle_raycast(5.0, Vec2::new(3.0, 0.0), Vec2::Y, 100.0).unwrap();
+        assert_eq!(distance, 0.0);
+        assert_eq!(normal, Vec2::X);
+    }
+
+    fn obstacle_set(center: Vec2, shape: ObstacleShape) -> ObstacleSet {
+        let mut obstacle_set = ObstacleSet::default();
+        obstacle_set.rebuild(vec![PlacedObstacle {
+            center,
+            bounding_radius: shape.bounding_radius(),
+            shape,
+        }]);
+        obstacle_set
+    }
+
+    #[test]
+    fn push_out_moves_to_the_nearest_edge() {
+        let obstacle_set = obstacle_set(
+            Vec2::new(200.0, 100.0),
+            ObstacleShape::Rect {
+                half_size: Vec2::splat(10.0),
+            },
+        );
+        let pushed = obstacle_set.push_out(Vec2::new(207.0, 101.0)).unwrap();
+        assert!(pushed.distance(Vec2::new(210.0 + PUSH_MARGIN, 101.0)) < 1e-4);
+        assert!(obstacle_set.push_out(pushed).is_none());
+    }
+
+    #[test]
+    fn push_out_leaves_outside_points_alone() {
+        let obstacle_set = obstacle_set(Vec2::ZERO, ObstacleShape::Circle { radius: 10.0 });
+        assert!(obstacle_set.push_out(Vec2::new(8.0, 8.0)).is_none());
+        let pushed = obstacle_set.push_out(Vec2::new(0.0, -4.0)).unwrap();
+        assert!(pushed.distance(Vec2::new(0.0, -10.0 - PUSH_MARGIN)) < 1e-4);
+    }
+}
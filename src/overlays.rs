@@ -0,0 +1,648 @@
+// Debug drawings on top of the sim. Bevy doesn't have a way to draw lines yet
+// so these go through egui's painter behind all the windows.
+
+use bevy::{ecs::system::SystemParam, math::Vec3Swizzles, prelude::*, window::PrimaryWindow};
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    flow::{FlowImage, FlowPattern, FlowSettings},
+    rgba_u8,
+    selection::{CreaturePicker, Selected},
+    spatial::SpatialQuery,
+    temperature::{warmth_color, TemperatureField, TemperatureSettings},
+    ui::overlays_shown,
+    variation::Variation,
+    ApplyForceEvent, CreatureType, Cursor, DespawnProperties, Energy, FactorInfo, Features,
+    ForceKind, HashGrid, MainCamera, SpawnProperties, WorldBounds,
+};
+
+/// World units across each square of the temperature overlay
+const TEMPERATURE_CELL: f32 = 25.0;
+/// World units between the arrows of the flow overlay
+const FLOW_CELL: f32 = 50.0;
+
+#[derive(Debug, Clone, Resource)]
+pub struct DebugOverlays {
+    /// Vision and flocking radii
+    pub vision_circles: bool,
+    /// Lines to every neighbor a creature is reacting to
+    pub neighbor_lines: bool,
+    /// Everything gets really busy with every creature drawing circles and lines
+    pub only_selected: bool,
+    /// Occupied hash grid chunks with how many creatures are in them
+    pub hash_grid: bool,
+    /// Every force pushing the selected creature, one arrow per kind
+    pub force_arrows: bool,
+    /// World units of arrow per unit of force factor
+    pub force_arrow_scale: f32,
+    /// Tints the world blue where it's cold and red where it's hot
+    pub temperature: bool,
+    /// Arrows showing which way the current goes
+    pub flow: bool,
+}
+
+impl Default for DebugOverlays {
+    fn default() -> Self {
+        DebugOverlays {
+            vision_circles: false,
+            neighbor_lines: false,
+            only_selected: true,
+            hash_grid: false,
+            force_arrows: false,
+            force_arrow_scale: 4.0,
+            temperature: false,
+            flow: false,
+        }
+    }
+}
+
+/// Turns world positions into egui's screen space.
+pub(crate) struct WorldToScreen<'a> {
+    camera: &'a Camera,
+    camera_transform: &'a GlobalTransform,
+    window_height: f32,
+}
+
+impl<'a> WorldToScreen<'a> {
+    pub(crate) fn new(
+        camera: &'a Camera,
+        camera_transform: &'a GlobalTransform,
+        window: &Window,
+    ) -> Self {
+        WorldToScreen {
+            camera,
+            camera_transform,
+            window_height: window.height(),
+        }
+    }
+
+    pub(crate) fn point(&self, position: Vec2) -> Option<egui::Pos2> {
+        let viewport = self
+            .camera
+            .world_to_viewport(self.camera_transform, position.extend(0.0))?;
+        // The viewport starts at the bottom left but egui starts at the top left
+        Some(egui::pos2(viewport.x, self.window_height - viewport.y))
+    }
+
+    pub(crate) fn length(&self, position: Vec2, length: f32) -> Option<f32> {
+        let start = self.point(position)?;
+        let end = self.point(position + Vec2::X * length)?;
+        Some((end - start).length())
+    }
+}
+
+/// The window and main camera, which is everything [`WorldToScreen`] needs.
+#[derive(SystemParam)]
+pub(crate) struct ScreenView<'w, 's> {
+    primary_query: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    camera_query: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<MainCamera>>,
+}
+
+impl ScreenView<'_, '_> {
+    pub(crate) fn window(&self) -> Option<&Window> {
+        self.primary_query.get_single().ok()
+    }
+
+    /// Nothing to draw on without a window
+    pub(crate) fn to_screen(&self) -> Option<WorldToScreen<'_>> {
+        let window = self.window()?;
+        let (camera, camera_transform) = self.camera_query.single();
+        Some(WorldToScreen::new(camera, camera_transform, window))
+    }
+}
+
+fn to_color32(color: Color, alpha: f32) -> egui::Color32 {
+    let [r, g, b, _] = rgba_u8(color);
+    egui::Color32::from_rgba_unmultiplied(r, g, b, (alpha * 255.0) as u8)
+}
+
+fn vision_circles_system(
+    mut egui_context: EguiContexts,
+    overlays: Res<DebugOverlays>,
+    factor_info: Res<FactorInfo>,
+    screen_view: ScreenView,
+    creature_query: Query<(
+        &Transform,
+        &CreatureType,
+        Option<&Selected>,
+        Option<&Variation>,
+    )>,
+) {
+    if !overlays.vision_circles {
+        return;
+    }
+    let Some(to_screen) = screen_view.to_screen() else {
+        return;
+    };
+
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    let screen = painter.clip_rect();
+
+    for (transform, creature_type, selected, variation) in creature_query.iter() {
+        if overlays.only_selected && selected.is_none() {
+            continue;
+        }
+        let factors = factor_info.factors.get(creature_type).unwrap();
+        let variation = variation.copied().unwrap_or_default();
+        let position = transform.translation.xy();
+        let (Some(center), Some(reach)) = (
+            to_screen.point(position),
+            to_screen.length(position, variation.reach(factors)),
+        ) else {
+            continue;
+        };
+        if !screen.expand(reach).contains(center) {
+            continue;
+        }
+
+        // Vision in the type's color and the flocking radii in the force colors
+        let circles = [
+            (variation.vision(factors), factors.color, 0.6),
+            (
+                variation.radius(factors.cohesion_radius),
+                force_color(ForceKind::Cohesion),
+                0.4,
+            ),
+            (
+                variation.radius(factors.alignment_radius),
+                force_color(ForceKind::Alignment),
+                0.4,
+            ),
+            (
+                variation.radius(factors.separation_radius),
+                force_color(ForceKind::Separation),
+                0.4,
+            ),
+        ];
+        for (radius, color, alpha) in circles {
+            if let Some(radius) = to_screen.length(position, radius) {
+                painter.circle_stroke(
+                    center,
+                    radius,
+                    egui::Stroke::new(1.0, to_color32(color, alpha)),
+                );
+            }
+        }
+    }
+}
+
+fn hash_grid_overlay_system(
+    mut egui_context: EguiContexts,
+    overlays: Res<DebugOverlays>,
+    hash_grid: Res<HashGrid>,
+    screen_view: ScreenView,
+) {
+    if !overlays.hash_grid {
+        return;
+    }
+    let Some(to_screen) = screen_view.to_screen() else {
+        return;
+    };
+
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    let screen = painter.clip_rect();
+    let busiest = hash_grid.cells().map(|(_, count)| count).max().unwrap_or(1);
+
+    for (cell, count) in hash_grid.cells() {
+        let (min, max) = hash_grid.cell_bounds(cell);
+        // Screen y is flipped so the corners swap
+        let (Some(top_left), Some(bottom_right)) = (
+            to_screen.point(Vec2::new(min.x, max.y)),
+            to_screen.point(Vec2::new(max.x, min.y)),
+        ) else {
+            continue;
+        };
+        let rect = egui::Rect::from_min_max(top_left, bottom_right);
+        if !screen.intersects(rect) {
+            continue;
+        }
+
+        // Busier chunks are more opaque
+        let alpha = 0.1 + 0.4 * count as f32 / busiest as f32;
+        painter.rect(
+            rect,
+            0.0,
+            to_color32(Color::ORANGE, alpha),
+            egui::Stroke::new(1.0, to_color32(Color::ORANGE, 0.6)),
+        );
+        // Too small to read when zoomed out
+        if rect.height() >= 14.0 {
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                count.to_string(),
+                egui::FontId::monospace(10.0),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+}
+
+fn temperature_overlay_system(
+    mut egui_context: EguiContexts,
+    overlays: Res<DebugOverlays>,
+    settings: Res<TemperatureSettings>,
+    field: Res<TemperatureField>,
+    world_bounds: Res<WorldBounds>,
+    screen_view: ScreenView,
+) {
+    if !overlays.temperature || !settings.enabled {
+        return;
+    }
+    let Some(to_screen) = screen_view.to_screen() else {
+        return;
+    };
+
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    let screen = painter.clip_rect();
+
+    let (min, size) = (world_bounds.min(), world_bounds.size());
+    let columns = (size.x / TEMPERATURE_CELL).ceil() as usize;
+    let rows = (size.y / TEMPERATURE_CELL).ceil() as usize;
+    for row in 0..rows {
+        for column in 0..columns {
+            let cell_min = min + Vec2::new(column as f32, row as f32) * TEMPERATURE_CELL;
+            let cell_max = (cell_min + Vec2::splat(TEMPERATURE_CELL)).min(world_bounds.max());
+            // Screen y is flipped so the corners swap
+            let (Some(top_left), Some(bottom_right)) = (
+                to_screen.point(Vec2::new(cell_min.x, cell_max.y)),
+                to_screen.point(Vec2::new(cell_max.x, cell_min.y)),
+            ) else {
+                continue;
+            };
+            let rect = egui::Rect::from_min_max(top_left, bottom_right);
+            if !screen.intersects(rect) {
+                continue;
+            }
+            let warmth = field.warmth(&settings, &world_bounds, (cell_min + cell_max) / 2.0);
+            painter.rect_filled(rect, 0.0, to_color32(warmth_color(warmth), 0.25));
+        }
+    }
+}
+
+fn flow_overlay_system(
+    mut egui_context: EguiContexts,
+    overlays: Res<DebugOverlays>,
+    settings: Res<FlowSettings>,
+    image: Res<FlowImage>,
+    world_bounds: Res<WorldBounds>,
+    screen_view: ScreenView,
+) {
+    if !overlays.flow || settings.pattern == FlowPattern::Off {
+        return;
+    }
+    let Some(to_screen) = screen_view.to_screen() else {
+        return;
+    };
+
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    let screen = painter.clip_rect();
+    let stroke = egui::Stroke::new(1.0, to_color32(force_color(ForceKind::Flow), 0.5));
+
+    let (min, size) = (world_bounds.min(), world_bounds.size());
+    let columns = (size.x / FLOW_CELL).ceil() as usize;
+    let rows = (size.y / FLOW_CELL).ceil() as usize;
+    for row in 0..rows {
+        for column in 0..columns {
+            let center = min + (Vec2::new(column as f32, row as f32) + 0.5) * FLOW_CELL;
+            let flow = settings.at(&image, &world_bounds, center);
+            // Full strength reaches most of the way to the next arrow
+            let tip = center + flow * FLOW_CELL * 0.8;
+            let (Some(start), Some(end)) = (to_screen.point(center), to_screen.point(tip)) else {
+                continue;
+            };
+            if !screen.contains(start) || start.distance(end) < 1.0 {
+                continue;
+            }
+            painter.arrow(start, end - start, stroke);
+        }
+    }
+}
+
+pub(crate) fn force_color(kind: ForceKind) -> Color {
+    match kind {
+        ForceKind::Cohesion => Color::GREEN,
+        ForceKind::Alignment => Color::CYAN,
+        ForceKind::Separation => Color::YELLOW,
+        ForceKind::CollisionAvoidance => Color::ORANGE,
+        ForceKind::Chase => Color::RED,
+        ForceKind::Scare => Color::PURPLE,
+        ForceKind::Mob => Color::MAROON,
+        ForceKind::Gust => Color::WHITE,
+        ForceKind::Comfort => Color::TEAL,
+        ForceKind::Gravity => Color::VIOLET,
+        ForceKind::Flow => Color::AZURE,
+        ForceKind::Goal => Color::LIME_GREEN,
+        ForceKind::Path => Color::SEA_GREEN,
+        ForceKind::Obstacle => Color::GRAY,
+        ForceKind::Margin => Color::SILVER,
+        ForceKind::Scripted => Color::PINK,
+        ForceKind::Custom => Color::GOLD,
+    }
+}
+
+type SelectedCreature = (With<Selected>, With<CreatureType>);
+
+fn force_arrows_system(
+    mut egui_context: EguiContexts,
+    overlays: Res<DebugOverlays>,
+    mut force_reader: EventReader<ApplyForceEvent>,
+    // Flocking doesn't run every frame so the arrows stick around until new forces come in
+    mut last_forces: Local<Option<(Entity, [Vec2; ForceKind::ALL.len()])>>,
+    screen_view: ScreenView,
+    selected_query: Query<(Entity, &Transform), SelectedCreature>,
+) {
+    if !overlays.force_arrows {
+        return;
+    }
+    let Ok((selected_entity, transform)) = selected_query.get_single() else {
+        *last_forces = None;
+        return;
+    };
+
+    let mut forces = [Vec2::ZERO; ForceKind::ALL.len()];
+    let mut got_forces = false;
+    for ApplyForceEvent(entity, force, factor, kind) in force_reader.iter() {
+        if *entity != selected_entity {
+            continue;
+        }
+        let index = ForceKind::ALL
+            .iter()
+            .position(|other| other == kind)
+            .unwrap();
+        forces[index] += *force * *factor;
+        got_forces = true;
+    }
+    let is_new_selection = last_forces.is_none_or(|(entity, _)| entity != selected_entity);
+    if got_forces || is_new_selection {
+        *last_forces = Some((selected_entity, forces));
+    }
+    let Some((_, forces)) = *last_forces else {
+        return;
+    };
+
+    let Some(to_screen) = screen_view.to_screen() else {
+        return;
+    };
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+
+    let position = transform.translation.xy();
+    let Some(origin) = to_screen.point(position) else {
+        return;
+    };
+    for (kind, force) in ForceKind::ALL.iter().zip(forces) {
+        if force == Vec2::ZERO {
+            continue;
+        }
+        let Some(tip) = to_screen.point(position + force * overlays.force_arrow_scale) else {
+            continue;
+        };
+        let color = to_color32(force_color(*kind), 0.9);
+        painter.arrow(origin, tip - origin, egui::Stroke::new(2.0, color));
+        painter.text(
+            tip,
+            egui::Align2::LEFT_BOTTOM,
+            kind.to_string(),
+            egui::FontId::proportional(10.0),
+            color,
+        );
+    }
+}
+
+/// Past this many lines it's an unreadable mess anyway and just slows things down
+const MAX_NEIGHBOR_LINES: usize = 3000;
+
+/// Why `creature_type` cares about `other_type`, as the color of the line between them.
+fn neighbor_color(
+    creature_type: &CreatureType,
+    other_type: &CreatureType,
+    factor_info: &FactorInfo,
+    features: &Features,
+) -> Option<Color> {
+    let factors = factor_info.factors.get(creature_type)?;
+    let other_factors = factor_info.factors.get(other_type)?;
+    if creature_type == other_type {
+        features.flocking.then_some(factors.color)
+    } else if factors.predator_of.contains(other_type) {
+        features.chasing.then_some(Color::RED)
+    } else if other_factors.predator_of.contains(creature_type) {
+        features.running.then_some(Color::YELLOW)
+    } else {
+        None
+    }
+}
+
+fn neighbor_lines_system(
+    mut egui_context: EguiContexts,
+    overlays: Res<DebugOverlays>,
+    features: Res<Features>,
+    spatial_query: SpatialQuery,
+    factor_info: Res<FactorInfo>,
+    screen_view: ScreenView,
+    creature_query: Query<(Entity, &Transform, &CreatureType, Option<&Selected>)>,
+) {
+    if !overlays.neighbor_lines {
+        return;
+    }
+    let Some(to_screen) = screen_view.to_screen() else {
+        return;
+    };
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+
+    let mut line_count = 0;
+    for (entity, transform, creature_type, selected) in creature_query.iter() {
+        if overlays.only_selected && selected.is_none() {
+            continue;
+        }
+        let reach = factor_info.factors.get(creature_type).unwrap().reach();
+        let position = transform.translation.xy();
+        let Some(start) = to_screen.point(position) else {
+            continue;
+        };
+
+        for other_entity in spatial_query.get_nearby_entities(position, reach) {
+            if other_entity == entity {
+                continue;
+            }
+            let Ok((_, other_transform, other_type, _)) = creature_query.get(other_entity) else {
+                continue;
+            };
+            let other_position = other_transform.translation.xy();
+            if position.distance(other_position) > reach {
+                continue;
+            }
+            let Some(color) = neighbor_color(creature_type, other_type, &factor_info, &features)
+            else {
+                continue;
+            };
+            let Some(end) = to_screen.point(other_position) else {
+                continue;
+            };
+
+            painter.line_segment([start, end], egui::Stroke::new(1.0, to_color32(color, 0.5)));
+            line_count += 1;
+            if line_count >= MAX_NEIGHBOR_LINES {
+                return;
+            }
+        }
+    }
+}
+
+/// Rings around the hovered and selected creatures plus a tooltip for the hovered one.
+fn highlight_system(
+    mut egui_context: EguiContexts,
+    creature_picker: CreaturePicker,
+    screen_view: ScreenView,
+    energy_query: Query<&Energy>,
+    selected_query: Query<Entity, (With<Selected>, With<CreatureType>)>,
+) {
+    let (Some(window), Some(to_screen)) = (screen_view.window(), screen_view.to_screen()) else {
+        return;
+    };
+    let CreaturePicker {
+        factor_info,
+        creature_query,
+        ..
+    } = &creature_picker;
+    let ctx = egui_context.ctx_mut();
+    let painter = ctx.layer_painter(egui::LayerId::background());
+
+    let ring = |entity: Entity, padding: f32, stroke: egui::Stroke| {
+        let (transform, creature_type) = creature_query.get(entity).ok()?;
+        let size = factor_info.factors.get(creature_type)?.size;
+        let position = transform.translation.xy();
+        let center = to_screen.point(position)?;
+        let radius = to_screen.length(position, size * 0.75)?;
+        painter.circle_stroke(center, radius + padding, stroke);
+        Some(())
+    };
+
+    for entity in selected_query.iter() {
+        ring(entity, 2.0, egui::Stroke::new(2.0, egui::Color32::WHITE));
+    }
+
+    // Hovering a window over a creature shouldn't count
+    if ctx.is_pointer_over_area() || window.cursor_position().is_none() {
+        return;
+    }
+    let Some(hovered) = creature_picker.hovered() else {
+        return;
+    };
+    ring(
+        hovered,
+        4.0,
+        egui::Stroke::new(1.0, egui::Color32::LIGHT_YELLOW),
+    );
+
+    let Ok((_, creature_type)) = creature_query.get(hovered) else {
+        return;
+    };
+    let max_energy = factor_info
+        .factors
+        .get(creature_type)
+        .map_or(0.0, |factors| factors.max_energy);
+    let energy = energy_query.get(hovered).map_or(0.0, |energy| energy.0);
+    egui::show_tooltip_at_pointer(ctx, egui::Id::new("creature_hover"), |ui| {
+        ui.label(format!("Type: {}", creature_type));
+        ui.label(format!("Energy: {:.1} / {:.1}", energy, max_energy));
+    });
+}
+
+/// How big the Shift (spawn) and Ctrl (despawn) clicks are.
+#[derive(SystemParam)]
+struct BrushProperties<'w> {
+    spawn: Res<'w, SpawnProperties>,
+    despawn: Res<'w, DespawnProperties>,
+}
+
+/// Shows the area a Shift (spawn) or Ctrl (despawn) click is going to hit.
+fn brush_preview_system(
+    mut egui_context: EguiContexts,
+    cursor: Res<Cursor>,
+    keys: Res<Input<KeyCode>>,
+    factor_info: Res<FactorInfo>,
+    brushes: BrushProperties,
+    selected_creature_type: Res<CreatureType>,
+    screen_view: ScreenView,
+) {
+    let (radius, fill) = if keys.pressed(KeyCode::LShift) {
+        (brushes.spawn.radius, true)
+    } else if keys.pressed(KeyCode::LControl) {
+        (brushes.despawn.radius, false)
+    } else {
+        return;
+    };
+    let (Some(window), Some(to_screen)) = (screen_view.window(), screen_view.to_screen()) else {
+        return;
+    };
+    if window.cursor_position().is_none() {
+        return;
+    }
+    let (Some(center), Some(radius)) = (
+        to_screen.point(cursor.position),
+        to_screen.length(cursor.position, radius),
+    ) else {
+        return;
+    };
+
+    let color = factor_info
+        .factors
+        .get(&selected_creature_type)
+        .map_or(Color::WHITE, |factors| factors.color);
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    let stroke = egui::Stroke::new(1.5, to_color32(color, 0.9));
+    if fill {
+        painter.circle(center, radius, to_color32(color, 0.1), stroke);
+    } else {
+        // Despawning is drawn hollow with a cross so it reads as erasing
+        painter.circle_stroke(center, radius, stroke);
+        let arm = radius * std::f32::consts::FRAC_1_SQRT_2;
+        painter.line_segment(
+            [center - egui::vec2(arm, arm), center + egui::vec2(arm, arm)],
+            stroke,
+        );
+        painter.line_segment(
+            [
+                center - egui::vec2(arm, -arm),
+                center + egui::vec2(arm, -arm),
+            ],
+            stroke,
+        );
+    }
+}
+
+/// Only added with the UI since everything here is drawn with egui.
+#[derive(Default)]
+pub struct OverlayPlugin;
+
+impl Plugin for OverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DebugOverlays::default()).add_systems(
+            (
+                temperature_overlay_system,
+                flow_overlay_system,
+                vision_circles_system,
+                hash_grid_overlay_system,
+                force_arrows_system,
+                neighbor_lines_system,
+                highlight_system,
+                brush_preview_system,
+            )
+                .distributive_run_if(overlays_shown),
+        );
+    }
+}
@@ -0,0 +1,357 @@
+// Optional fast path for the built in steering behaviors. Neighbors get packed into separate
+// arrays for each coordinate so distances and the flocking sums can be done four
+// at a time with `Vec4`, which glam backs with SIMD registers where it can.
+// The forces that come out match the normal path, give or take some rounding.
+
+use bevy::{
+    math::{BVec4A, Vec4},
+    prelude::*,
+    utils::Instant,
+};
+use rand::{prelude::*, rngs::StdRng};
+
+use crate::{
+    personality::Personality,
+    steering::{steering_forces, SteeringBehaviors, SteeringRules, SteeringScratch},
+    variation::Variation,
+    ApplyForceEvent, ChunkResolution, CreatureType, FactorInfo, Features, FlockSums, ForceKind,
+    HashGrid, Neighbor, SpatialIndex, WorldBounds,
+};
+
+/// Use the packed fast path for flocking. Only used when arena tiling is off and
+/// nobody's changed the steering behaviors.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct PackedFlocking(pub bool);
+
+/// Who hunts who as a flat table so the hot loop doesn't hash anything.
+pub(crate) struct TypeRelations {
+    type_count: usize,
+    hunts: Vec<bool>,
+}
+
+impl TypeRelations {
+    pub(crate) fn new(factor_info: &FactorInfo) -> Self {
+        let type_count = factor_info.factors.len();
+        let mut hunts = vec![false; type_count * type_count];
+        for (predator, factors) in factor_info.factors.iter() {
+            for prey in factors.predator_of.iter() {
+                if predator.0 < type_count && prey.0 < type_count {
+                    hunts[predator.0 * type_count + prey.0] = true;
+                }
+            }
+        }
+        TypeRelations { type_count, hunts }
+    }
+
+    fn hunts(&self, predator: CreatureType, prey: CreatureType) -> bool {
+        predator.0 < self.type_count
+            && prey.0 < self.type_count
+            && self.hunts[predator.0 * self.type_count + prey.0]
+    }
+}
+
+/// Neighbors of a single creature laid out column by column. Reused between
+/// creatures so it only allocates while it's growing.
+#[derive(Default)]
+pub(crate) struct PackedNeighbors {
+    types: Vec<CreatureType>,
+    xs: Vec<f32>,
+    ys: Vec<f32>,
+    direction_xs: Vec<f32>,
+    direction_ys: Vec<f32>,
+    distances_squared: Vec<f32>,
+}
+
+impl PackedNeighbors {
+    fn fill(&mut self, creature: &Neighbor, neighbors: impl Iterator<Item = Neighbor>) {
+        self.types.clear();
+        self.xs.clear();
+        self.ys.clear();
+        self.direction_xs.clear();
+        self.direction_ys.clear();
+        for neighbor in neighbors {
+            if neighbor.entity == creature.entity {
+                continue;
+            }
+            self.types.push(neighbor.creature_type);
+            self.xs.push(neighbor.position.x);
+            self.ys.push(neighbor.position.y);
+            self.direction_xs.push(neighbor.direction.x);
+            self.direction_ys.push(neighbor.direction.y);
+        }
+
+        // Pad out the last batch with neighbors infinitely far away so they never count
+        while !self.xs.len().is_multiple_of(4) {
+            self.xs.push(f32::INFINITY);
+            self.ys.push(f32::INFINITY);
+            self.direction_xs.push(0.0);
+            self.direction_ys.push(0.0);
+        }
+        self.distances_squared.resize(self.xs.len(), 0.0);
+    }
+}
+
+fn sum_lanes(lanes: Vec4) -> f32 {
+    lanes.dot(Vec4::ONE)
+}
+
+/// Same forces as `steering_forces` with the built in behaviors.
+pub(crate) fn packed_flocking_forces(
+    creature: &Neighbor,
+    neighbors: impl Iterator<Item = Neighbor>,
+    packed: &mut PackedNeighbors,
+    relations: &TypeRelations,
+    factor_info: &FactorInfo,
+    features: &Features,
+    events: &mut Vec<ApplyForceEvent>,
+) {
+    let entity_a = creature.entity;
+    let type_a = creature.creature_type;
+    let factors_a = factor_info.factors.get(&type_a).unwrap();
+    let position_a = creature.position;
+    packed.fill(creature, neighbors);
+
+    let x_a = Vec4::splat(position_a.x);
+    let y_a = Vec4::splat(position_a.y);
+    let variation = creature.variation;
+    let radius_squared = |radius| Vec4::splat(variation.radius(radius).powi(2));
+    let cohesion_squared = radius_squared(factors_a.cohesion_radius);
+    let alignment_squared = radius_squared(factors_a.alignment_radius);
+    let separation_squared = radius_squared(factors_a.separation_radius);
+
+    let mut position_x_sum = Vec4::ZERO;
+    let mut position_y_sum = Vec4::ZERO;
+    let mut cohesion_count = Vec4::ZERO;
+    let mut direction_x_sum = Vec4::ZERO;
+    let mut direction_y_sum = Vec4::ZERO;
+    let mut alignment_count = Vec4::ZERO;
+    let mut close_x_sum = Vec4::ZERO;
+    let mut close_y_sum = Vec4::ZERO;
+    let mut separation_count = Vec4::ZERO;
+
+    for start in (0..packed.xs.len()).step_by(4) {
+        let xs = Vec4::from_slice(&packed.xs[start..]);
+        let ys = Vec4::from_slice(&packed.ys[start..]);
+        let offset_x = xs - x_a;
+        let offset_y = ys - y_a;
+        let distance_squared = offset_x * offset_x + offset_y * offset_y;
+        distance_squared.write_to_slice(&mut packed.distances_squared[start..]);
+
+        if !features.flocking {
+            continue;
+        }
+        let is_same_type = |lane| packed.types.get(start + lane) == Some(&type_a);
+        let same_type = BVec4A::new(
+            is_same_type(0),
+            is_same_type(1),
+            is_same_type(2),
+            is_same_type(3),
+        );
+
+        // Selects instead of multiplying by a 0/1 mask since the padding is infinite
+        let in_cohesion = distance_squared.cmple(cohesion_squared) & same_type;
+        position_x_sum += Vec4::select(in_cohesion, xs, Vec4::ZERO);
+        position_y_sum += Vec4::select(in_cohesion, ys, Vec4::ZERO);
+        cohesion_count += Vec4::select(in_cohesion, Vec4::ONE, Vec4::ZERO);
+
+        let in_alignment = distance_squared.cmple(alignment_squared) & same_type;
+        direction_x_sum += Vec4::select(
+            in_alignment,
+            Vec4::from_slice(&packed.direction_xs[start..]),
+            Vec4::ZERO,
+        );
+        direction_y_sum += Vec4::select(
+            in_alignment,
+            Vec4::from_slice(&packed.direction_ys[start..]),
+            Vec4::ZERO,
+        );
+        alignment_count += Vec4::select(in_alignment, Vec4::ONE, Vec4::ZERO);
+
+        let in_separation = distance_squared.cmple(separation_squared) & same_type;
+        close_x_sum += Vec4::select(in_separation, xs, Vec4::ZERO);
+        close_y_sum += Vec4::select(in_separation, ys, Vec4::ZERO);
+        separation_count += Vec4::select(in_separation, Vec4::ONE, Vec4::ZERO);
+    }
+
+    let mut sums = FlockSums {
+        position_sum: Vec2::new(sum_lanes(position_x_sum), sum_lanes(position_y_sum)),
+        cohesion_count: sum_lanes(cohesion_count) as usize,
+        direction_sum: Vec2::new(sum_lanes(direction_x_sum), sum_lanes(direction_y_sum)),
+        alignment_count: sum_lanes(alignment_count) as usize,
+        close_position_sum: Vec2::new(sum_lanes(close_x_sum), sum_lanes(close_y_sum)),
+        separation_count: sum_lanes(separation_count) as usize,
+        closest_target: None,
+    };
+
+    // Whatever's left makes one force per neighbor so it goes one at a time,
+    // but only for the few neighbors close enough to matter.
+    let vision = variation.vision(factors_a);
+    let collision_distance = variation.size(factors_a) * 2.0;
+    let reach = vision.max(collision_distance);
+    let reach_squared = reach * reach;
+    let flee_range = vision * creature.personality.flee_range_scale();
+    let collision_squared = collision_distance.powi(2);
+    let scare = factors_a.scare * creature.personality.scare_scale();
+    let mut sees_predator = false;
+    for (index, &type_b) in packed.types.iter().enumerate() {
+        let distance_squared = packed.distances_squared[index];
+        if distance_squared > reach_squared {
+            continue;
+        }
+        let position_b = Vec2::new(packed.xs[index], packed.ys[index]);
+
+        if features.flocking && type_b == type_a {
+            if distance_squared <= collision_squared {
+                events.push(ApplyForceEvent(
+                    entity_a,
+                    (position_a - position_b).normalize(),
+                    factors_a.collision_avoidance,
+                    ForceKind::CollisionAvoidance,
+                ));
+            }
+            continue;
+        }
+
+        let distance = distance_squared.sqrt();
+        if distance > vision {
+            continue;
+        }
+        if features.chasing && !creature.satiated && relations.hunts(type_a, type_b) {
+            sums.consider_target(distance, position_b);
+        }
+        if features.running && relations.hunts(type_b, type_a) && distance <= flee_range {
+            sees_predator = true;
+            let away = (position_a - position_b).normalize();
+            events.push(ApplyForceEvent(
+                entity_a,
+                factors_a.flee_direction(away, distance, creature.evasion_phase),
+                scare,
+                ForceKind::Scare,
+            ));
+        }
+    }
+    if let Some(fear) = creature.fear.filter(|_| features.running && !sees_predator) {
+        events.push(ApplyForceEvent(
+            entity_a,
+            (position_a - fear.from).normalize_or_zero(),
+            scare * fear.level(),
+            ForceKind::Scare,
+        ));
+    }
+
+    sums.push_events(creature, factors_a, features, events);
+}
+
+/// How many boids the flocking benchmark throws into the world
+const BENCHMARK_POPULATION: usize = 50_000;
+
+/// Timings from the last flocking benchmark.
+#[derive(Debug, Default, Resource)]
+pub struct FlockingBenchmark {
+    pub population: usize,
+    pub normal_millis: f32,
+    pub packed_millis: f32,
+    /// Both paths should come up with the same number of forces
+    pub normal_forces: usize,
+    pub packed_forces: usize,
+}
+
+pub struct RunFlockingBenchmarkEvent;
+
+/// Runs every creature through both flocking paths on a single thread so the
+/// timings only compare the math and not the scheduling.
+fn flocking_benchmark_system(
+    mut benchmark_events: EventReader<RunFlockingBenchmarkEvent>,
+    mut benchmark: ResMut<FlockingBenchmark>,
+    world_bounds: Res<WorldBounds>,
+    factor_info: Res<FactorInfo>,
+    features: Res<Features>,
+    chunk_resolution: Res<ChunkResolution>,
+) {
+    if benchmark_events.iter().count() == 0 || factor_info.factors.is_empty() {
+        return;
+    }
+
+    // Same boids every time so runs can be compared
+    let mut rng = StdRng::seed_from_u64(0);
+    let type_count = factor_info.factors.len();
+    let creatures = (0..BENCHMARK_POPULATION)
+        .map(|index| Neighbor {
+            entity: Entity::from_raw(index as u32),
+            position: Vec2::new(
+                rng.gen_range(world_bounds.min().x..world_bounds.max().x),
+                rng.gen_range(world_bounds.min().y..world_bounds.max().y),
+            ),
+            direction: Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU)),
+            creature_type: CreatureType(rng.gen_range(0..type_count)),
+            variation: Variation::default(),
+            personality: Personality::default(),
+            satiated: false,
+            fear: None,
+            evasion_phase: 0.0,
+        })
+        .collect::<Vec<_>>();
+    let mut hash_grid = HashGrid::new(chunk_resolution.0);
+    for creature in creatures.iter() {
+        hash_grid.update_entity(creature.entity, creature.position);
+    }
+    let neighbors_of = |creature: &Neighbor| {
+        let reach = factor_info.factors[&creature.creature_type].reach();
+        hash_grid
+            .get_nearby_entities(creature.position, reach)
+            .into_iter()
+            .map(|entity| creatures[entity.index() as usize])
+    };
+
+    let mut events = vec![];
+    let behaviors = SteeringBehaviors::default();
+    let rules = SteeringRules {
+        behaviors: &behaviors,
+        factor_info: &factor_info,
+        features: &features,
+    };
+    let mut scratch = SteeringScratch::default();
+    let normal_start = Instant::now();
+    for creature in creatures.iter() {
+        steering_forces(
+            creature,
+            neighbors_of(creature),
+            rules,
+            &mut scratch,
+            &mut events,
+        );
+    }
+    benchmark.normal_millis = normal_start.elapsed().as_secs_f32() * 1000.0;
+    benchmark.normal_forces = events.len();
+
+    events.clear();
+    let relations = TypeRelations::new(&factor_info);
+    let mut packed = PackedNeighbors::default();
+    let packed_start = Instant::now();
+    for creature in creatures.iter() {
+        packed_flocking_forces(
+            creature,
+            neighbors_of(creature),
+            &mut packed,
+            &relations,
+            &factor_info,
+            &features,
+            &mut events,
+        );
+    }
+    benchmark.packed_millis = packed_start.elapsed().as_secs_f32() * 1000.0;
+    benchmark.packed_forces = events.len();
+    benchmark.population = BENCHMARK_POPULATION;
+}
+
+#[derive(Default)]
+pub struct PackedFlockingPlugin;
+
+impl Plugin for PackedFlockingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PackedFlocking::default())
+            .insert_resource(FlockingBenchmark::default())
+            .add_event::<RunFlockingBenchmarkEvent>()
+            .add_system(flocking_benchmark_system);
+    }
+}
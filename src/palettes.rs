@@ -0,0 +1,103 @@
+// Sets of colors that go together for giving every type its own color in one
+// go. New types take the next color from whichever palette is picked.
+
+use bevy::prelude::*;
+
+use crate::{rgba_u8, CreatureType, FactorInfo};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub enum ColorPalette {
+    #[default]
+    Pastel,
+    Neon,
+    /// Okabe-Ito, which stays tellable apart with the common kinds of color
+    /// blindness. Black swapped for gray since the background is black.
+    ColorblindSafe,
+}
+
+const PASTEL: [[u8; 3]; 8] = [
+    [255, 179, 186],
+    [186, 225, 255],
+    [186, 255, 201],
+    [255, 223, 186],
+    [215, 186, 255],
+    [255, 255, 186],
+    [255, 186, 242],
+    [201, 240, 228],
+];
+
+const NEON: [[u8; 3]; 8] = [
+    [255, 32, 121],
+    [0, 240, 255],
+    [57, 255, 20],
+    [255, 240, 31],
+    [188, 19, 254],
+    [255, 95, 31],
+    [4, 217, 255],
+    [254, 1, 154],
+];
+
+const COLORBLIND_SAFE: [[u8; 3]; 8] = [
+    [230, 159, 0],
+    [86, 180, 233],
+    [0, 158, 115],
+    [240, 228, 66],
+    [0, 114, 178],
+    [213, 94, 0],
+    [204, 121, 167],
+    [187, 187, 187],
+];
+
+impl ColorPalette {
+    pub const ALL: [ColorPalette; 3] = [
+        ColorPalette::Pastel,
+        ColorPalette::Neon,
+        ColorPalette::ColorblindSafe,
+    ];
+
+    pub fn colors(&self) -> impl Iterator<Item = Color> {
+        let colors = match self {
+            ColorPalette::Pastel => PASTEL,
+            ColorPalette::Neon => NEON,
+            ColorPalette::ColorblindSafe => COLORBLIND_SAFE,
+        };
+        colors.into_iter().map(|[r, g, b]| Color::rgb_u8(r, g, b))
+    }
+
+    /// The `index`th color, going back around once they run out
+    pub fn color(&self, index: usize) -> Color {
+        let colors = self.colors().collect::<Vec<_>>();
+        colors[index % colors.len()]
+    }
+
+    /// The first color no type has yet, or the next one around if they're all
+    /// taken
+    pub fn next_color(&self, factor_info: &FactorInfo) -> Color {
+        self.colors()
+            .find(|&color| {
+                factor_info
+                    .factors
+                    .values()
+                    .all(|factors| rgba_u8(factors.color) != rgba_u8(color))
+            })
+            .unwrap_or_else(|| self.color(factor_info.factors.len()))
+    }
+
+    /// Gives every type a color from this palette in order
+    pub fn apply(&self, factor_info: &mut FactorInfo) {
+        for index in 0..factor_info.factors.len() {
+            if let Some(factors) = factor_info.factors.get_mut(&CreatureType(index)) {
+                factors.color = self.color(index);
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ColorPalette {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ColorPalette::ColorblindSafe => write!(f, "Colorblind Safe"),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
@@ -0,0 +1,323 @@
+// Loops for a type to patrol around while still flocking with each other. A
+// path gets clicked out point by point after hitting Draw in Edit Factors and
+// belongs to whichever type was selected. Creatures head for a spot a little
+// further along it than where they are and get pulled back in when they drift
+// out of its corridor. Smooth paths curve through the points (Catmull-Rom) so
+// flocks swing around corners instead of cutting them.
+
+use bevy::{
+    input::mouse::MouseButtonInput, math::Vec3Swizzles, prelude::*, utils::HashMap,
+    window::PrimaryWindow,
+};
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    overlays::WorldToScreen,
+    rgba_u8,
+    ui::{has_ui, overlays_shown},
+    ApplyForceEvent, CreatureType, Cursor, FactorInfo, ForceKind, FrozenTypes, MainCamera,
+    SimState, SystemStages,
+};
+
+/// Clicking this close to the first point closes the loop
+const CLOSE_RADIUS: f32 = 10.0;
+/// How many straight bits each curve between two points gets broken into
+const SAMPLES_PER_SEGMENT: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct PatrolPath {
+    /// Goes back around to the first one after the last. Call `resample`
+    /// after changing these.
+    pub points: Vec<Vec2>,
+    /// How far off the path creatures can wander before getting pulled back
+    pub corridor: f32,
+    /// Curves through the points instead of going straight between them
+    pub smooth: bool,
+    /// The path chopped into straight bits, ending back on the first one
+    samples: Vec<Vec2>,
+    /// How far along the path each sample is, so spots on it can be found by
+    /// distance instead of by which points they're between
+    distances: Vec<f32>,
+}
+
+impl PatrolPath {
+    pub const DEFAULT_CORRIDOR: f32 = 40.0;
+
+    pub fn new(points: Vec<Vec2>, corridor: f32, smooth: bool) -> Self {
+        let mut path = PatrolPath {
+            points,
+            corridor,
+            smooth,
+            samples: vec![],
+            distances: vec![],
+        };
+        path.resample();
+        path
+    }
+
+    /// Rebuilds the samples from the points
+    pub fn resample(&mut self) {
+        let count = self.points.len();
+        let point = |i: usize| self.points[i % count];
+        let mut samples = vec![];
+        for i in 0..count {
+            if !self.smooth {
+                samples.push(point(i));
+                continue;
+            }
+            let (p0, p1, p2, p3) = (point(i + count - 1), point(i), point(i + 1), point(i + 2));
+            for step in 0..SAMPLES_PER_SEGMENT {
+                let t = step as f32 / SAMPLES_PER_SEGMENT as f32;
+                let (t2, t3) = (t * t, t * t * t);
+                samples.push(
+                    0.5 * (2.0 * p1
+                        + (p2 - p0) * t
+                        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+                        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3),
+                );
+            }
+        }
+        if let Some(&first) = samples.first() {
+            samples.push(first);
+        }
+        let mut total = 0.0;
+        self.distances = samples
+            .iter()
+            .enumerate()
+            .map(|(i, &sample)| {
+                if i > 0 {
+                    total += sample.distance(samples[i - 1]);
+                }
+                total
+            })
+            .collect();
+        self.samples = samples;
+    }
+
+    /// All the way around
+    pub fn length(&self) -> f32 {
+        self.distances.last().copied().unwrap_or(0.0)
+    }
+
+    pub fn samples(&self) -> &[Vec2] {
+        &self.samples
+    }
+
+    /// How far along the path the closest spot to `position` is, that spot,
+    /// and how far away it is
+    pub fn closest(&self, position: Vec2) -> (f32, Vec2, f32) {
+        self.samples
+            .windows(2)
+            .enumerate()
+            .map(|(i, pair)| {
+                let along = pair[1] - pair[0];
+                let length = along.length();
+                let t = ((position - pair[0]).dot(along) / length.max(f32::EPSILON).powi(2))
+                    .clamp(0.0, 1.0);
+                let point = pair[0] + along * t;
+                (
+                    self.distances[i] + length * t,
+                    point,
+                    point.distance(position),
+                )
+            })
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+            .unwrap_or((0.0, position, 0.0))
+    }
+
+    /// The spot this far along the path, going around as many times as it takes
+    pub fn point_at(&self, distance: f32) -> Vec2 {
+        let length = self.length();
+        if length <= 0.0 {
+            return self.samples.first().copied().unwrap_or_default();
+        }
+        let distance = distance.rem_euclid(length);
+        let i = self
+            .distances
+            .partition_point(|&sample| sample <= distance)
+            .clamp(1, self.samples.len() - 1);
+        let (start, end) = (self.distances[i - 1], self.distances[i]);
+        let t = (distance - start) / (end - start).max(f32::EPSILON);
+        self.samples[i - 1].lerp(self.samples[i], t)
+    }
+
+    /// Where a creature here should be heading, `lookahead` further along the
+    /// path than it is or straight back to it if it's outside the corridor
+    pub fn target(&self, position: Vec2, lookahead: f32) -> Vec2 {
+        let (along, closest, distance) = self.closest(position);
+        if distance > self.corridor {
+            closest
+        } else {
+            self.point_at(along + lookahead)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Resource)]
+pub struct PatrolPaths(pub HashMap<CreatureType, PatrolPath>);
+
+/// The path being clicked out, if there is one. Plain left clicks add to it
+/// instead of selecting while it's going.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct PathEditor {
+    pub drawing: bool,
+    pub points: Vec<Vec2>,
+}
+
+fn draw_path_system(
+    mut egui_context: EguiContexts,
+    cursor: Res<Cursor>,
+    keys: Res<Input<KeyCode>>,
+    selected_creature_type: Res<CreatureType>,
+    mut editor: ResMut<PathEditor>,
+    mut paths: ResMut<PatrolPaths>,
+    mut mouse_button_events: EventReader<MouseButtonInput>,
+) {
+    if !editor.drawing {
+        return;
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        *editor = PathEditor::default();
+        return;
+    }
+    let over_ui = egui_context.ctx_mut().is_pointer_over_area();
+    let mut finished = keys.just_pressed(KeyCode::Return);
+    for event in mouse_button_events.iter() {
+        if event.button != MouseButton::Left || event.state.is_pressed() || over_ui {
+            continue;
+        }
+        if editor.points.len() >= 3 && editor.points[0].distance(cursor.position) <= CLOSE_RADIUS {
+            finished = true;
+        } else {
+            editor.points.push(cursor.position);
+        }
+    }
+    // Anything less than a triangle isn't much of a loop
+    if !finished || editor.points.len() < 3 {
+        return;
+    }
+    // Redrawing keeps the old path's settings
+    let (corridor, smooth) = paths
+        .0
+        .get(&*selected_creature_type)
+        .map_or((PatrolPath::DEFAULT_CORRIDOR, true), |path| {
+            (path.corridor, path.smooth)
+        });
+    let points = std::mem::take(&mut editor.points);
+    paths.0.insert(
+        *selected_creature_type,
+        PatrolPath::new(points, corridor, smooth),
+    );
+    editor.drawing = false;
+}
+
+fn path_follow_system(
+    paths: Res<PatrolPaths>,
+    factor_info: Res<FactorInfo>,
+    frozen_types: Res<FrozenTypes>,
+    creature_query: Query<(Entity, &Transform, &CreatureType)>,
+    mut force_writer: EventWriter<ApplyForceEvent>,
+) {
+    if paths.0.is_empty() {
+        return;
+    }
+    for (entity, transform, creature_type) in creature_query.iter() {
+        let (Some(path), Some(factors)) = (
+            paths.0.get(creature_type),
+            factor_info.factors.get(creature_type),
+        ) else {
+            continue;
+        };
+        if factors.path_follow <= 0.0 || frozen_types.contains(creature_type) {
+            continue;
+        }
+        let position = transform.translation.xy();
+        let target = path.target(position, factors.path_lookahead);
+        let direction = (target - position).normalize_or_zero();
+        if direction == Vec2::ZERO {
+            continue;
+        }
+        force_writer.send(ApplyForceEvent(
+            entity,
+            direction,
+            factors.path_follow,
+            ForceKind::Path,
+        ));
+    }
+}
+
+/// Each path in its type's color with its corridor drawn faintly on either
+/// side, plus whatever's being drawn
+fn path_overlay_system(
+    mut egui_context: EguiContexts,
+    cursor: Res<Cursor>,
+    paths: Res<PatrolPaths>,
+    editor: Res<PathEditor>,
+    factor_info: Res<FactorInfo>,
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    if paths.0.is_empty() && !editor.drawing {
+        return;
+    }
+    let Ok(window) = primary_query.get_single() else {
+        return;
+    };
+    let (camera, camera_transform) = camera_query.single();
+    let to_screen = WorldToScreen::new(camera, camera_transform, window);
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    let to_points = |points: &[Vec2]| {
+        points
+            .iter()
+            .filter_map(|&point| to_screen.point(point))
+            .collect::<Vec<_>>()
+    };
+
+    for (creature_type, path) in paths.0.iter() {
+        let Some(factors) = factor_info.factors.get(creature_type) else {
+            continue;
+        };
+        let [r, g, b, _] = rgba_u8(factors.color);
+        let color = egui::Color32::from_rgb(r, g, b);
+        let samples = to_points(path.samples());
+        let corridor = to_screen
+            .length(path.points[0], path.corridor)
+            .unwrap_or(0.0);
+        painter.add(egui::Shape::line(
+            samples.clone(),
+            egui::Stroke::new(corridor * 2.0, color.linear_multiply(0.08)),
+        ));
+        painter.add(egui::Shape::line(samples, egui::Stroke::new(1.5, color)));
+        for point in to_points(&path.points) {
+            painter.circle_filled(point, 3.0, color);
+        }
+    }
+
+    if editor.drawing && !editor.points.is_empty() {
+        let mut points = editor.points.clone();
+        points.push(cursor.position);
+        painter.add(egui::Shape::line(
+            to_points(&points),
+            egui::Stroke::new(1.5, egui::Color32::LIGHT_GRAY),
+        ));
+    }
+}
+
+#[derive(Default)]
+pub struct PathPlugin;
+
+impl Plugin for PathPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PatrolPaths>()
+            .init_resource::<PathEditor>()
+            .add_system(draw_path_system.run_if(has_ui))
+            .add_system(path_overlay_system.run_if(overlays_shown))
+            .add_system(
+                path_follow_system
+                    .in_set(SystemStages::Calculate)
+                    .in_set(OnUpdate(SimState::Running)),
+            );
+    }
+}
@@ -0,0 +1,337 @@
+// Remembers the settings panel between page loads on the web build. Natively
+// the storage functions are no-ops so nothing here does anything there.
+
+use bevy::{ecs::system::SystemParam, prelude::*, window::PrimaryWindow};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collision::CollisionSettings,
+    dock::{self, DockLayout},
+    files::{local_storage_get, local_storage_remove, local_storage_set},
+    food::FoodSettings,
+    growth::GrowthSettings,
+    satiation::SatiationSettings,
+    temperature::TemperatureSettings,
+    theme::UiTheme,
+    weather::WeatherSettings,
+    window_mode::WindowModeSettings,
+    CreatureType, DespawnProperties, FactorInfo, Factors, Features, ReplaceFactors,
+    SpawnProperties, SteeringCombination, WorldBounds,
+};
+
+const STORAGE_KEY: &str = "rusty-boids-settings";
+
+/// How long to wait after the last change before writing again. Dragging a
+/// slider changes things every frame and storage writes aren't free.
+const SAVE_DELAY: f32 = 1.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedSettings {
+    features: Features,
+    /// Indexed by creature type
+    factors: Vec<Factors>,
+    spawn: SpawnProperties,
+    despawn: DespawnProperties,
+    window_size: Option<(f32, f32)>,
+    // Settings saved before the world had its own size won't have this
+    #[serde(default)]
+    world_bounds: Option<WorldBounds>,
+    #[serde(default)]
+    steering: SteeringCombination,
+    #[serde(default)]
+    satiation: SatiationSettings,
+    #[serde(default)]
+    food: FoodSettings,
+    #[serde(default)]
+    temperature: TemperatureSettings,
+    #[serde(default)]
+    weather: WeatherSettings,
+    #[serde(default)]
+    collision: CollisionSettings,
+    #[serde(default)]
+    growth: GrowthSettings,
+    #[serde(default)]
+    theme: UiTheme,
+    #[serde(default, deserialize_with = "dock::deserialize_or_default")]
+    dock: DockLayout,
+}
+
+impl SavedSettings {
+    fn load() -> Option<SavedSettings> {
+        let json = local_storage_get(STORAGE_KEY)?;
+        match serde_json::from_str(&json) {
+            Ok(settings) => Some(settings),
+            Err(error) => {
+                warn!("Ignoring saved settings: {}", error);
+                None
+            }
+        }
+    }
+
+    fn factors_to_map(&self) -> bevy::utils::HashMap<CreatureType, Factors> {
+        self.factors
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(index, factors)| (CreatureType(index), factors))
+            .collect()
+    }
+}
+
+/// Whatever the app started with before anything was restored, for "Reset to defaults".
+#[derive(Debug, Clone, Resource)]
+struct DefaultSettings {
+    features: Features,
+    factor_info: FactorInfo,
+    spawn: SpawnProperties,
+    despawn: DespawnProperties,
+    world_bounds: WorldBounds,
+    steering: SteeringCombination,
+    satiation: SatiationSettings,
+    food: FoodSettings,
+    temperature: TemperatureSettings,
+    weather: WeatherSettings,
+    collision: CollisionSettings,
+    growth: GrowthSettings,
+}
+
+pub struct ResetSettingsEvent;
+
+#[derive(Debug, Default, Resource)]
+struct PendingSave {
+    since_change: Option<f32>,
+}
+
+fn restore_window_size_system(mut primary_query: Query<&mut Window, With<PrimaryWindow>>) {
+    let Some((width, height)) = SavedSettings::load().and_then(|settings| settings.window_size)
+    else {
+        return;
+    };
+    if let Ok(mut window) = primary_query.get_single_mut() {
+        window.resolution.set(width, height);
+    }
+}
+
+/// Everything saved besides the factors and interface, grouped to keep the systems
+/// below under Bevy's parameter limit.
+#[derive(SystemParam)]
+struct SimSettings<'w> {
+    features: Res<'w, Features>,
+    spawn: Res<'w, SpawnProperties>,
+    despawn: Res<'w, DespawnProperties>,
+    world_bounds: Res<'w, WorldBounds>,
+    steering: Res<'w, SteeringCombination>,
+    satiation: Res<'w, SatiationSettings>,
+    food: Res<'w, FoodSettings>,
+    temperature: Res<'w, TemperatureSettings>,
+    weather: Res<'w, WeatherSettings>,
+    collision: Res<'w, CollisionSettings>,
+    growth: Res<'w, GrowthSettings>,
+}
+
+impl SimSettings<'_> {
+    fn is_changed(&self) -> bool {
+        self.features.is_changed()
+            || self.spawn.is_changed()
+            || self.despawn.is_changed()
+            || self.world_bounds.is_changed()
+            || self.steering.is_changed()
+            || self.satiation.is_changed()
+            || self.food.is_changed()
+            || self.temperature.is_changed()
+            || self.weather.is_changed()
+            || self.collision.is_changed()
+            || self.growth.is_changed()
+    }
+}
+
+/// How the windows look and where they're docked. Either can be missing when
+/// the app we're in doesn't have the UI plugin.
+#[derive(SystemParam)]
+struct InterfaceSettings<'w> {
+    theme: Option<Res<'w, UiTheme>>,
+    dock: Option<Res<'w, DockLayout>>,
+}
+
+impl InterfaceSettings<'_> {
+    fn is_changed(&self) -> bool {
+        self.theme.as_ref().is_some_and(|theme| theme.is_changed())
+            || self.dock.as_ref().is_some_and(|dock| dock.is_changed())
+    }
+}
+
+#[derive(SystemParam)]
+struct SimSettingsMut<'w> {
+    features: ResMut<'w, Features>,
+    spawn: ResMut<'w, SpawnProperties>,
+    despawn: ResMut<'w, DespawnProperties>,
+    world_bounds: ResMut<'w, WorldBounds>,
+    steering: ResMut<'w, SteeringCombination>,
+    satiation: ResMut<'w, SatiationSettings>,
+    food: ResMut<'w, FoodSettings>,
+    temperature: ResMut<'w, TemperatureSettings>,
+    weather: ResMut<'w, WeatherSettings>,
+    collision: ResMut<'w, CollisionSettings>,
+    growth: ResMut<'w, GrowthSettings>,
+}
+
+fn save_settings_system(
+    timer: Res<Time>,
+    mut pending_save: ResMut<PendingSave>,
+    factor_info: Res<FactorInfo>,
+    sim_settings: SimSettings,
+    primary_query: Query<Ref<Window>, With<PrimaryWindow>>,
+    window_mode_settings: Option<Res<WindowModeSettings>>,
+    interface: InterfaceSettings,
+) {
+    let window = primary_query.get_single().ok();
+    let changed = factor_info.is_changed()
+        || sim_settings.is_changed()
+        || interface.is_changed()
+        || window.as_ref().is_some_and(|window| window.is_changed());
+    if changed {
+        pending_save.since_change = Some(0.0);
+    }
+
+    let Some(since_change) = pending_save.since_change.as_mut() else {
+        return;
+    };
+    *since_change += timer.delta_seconds();
+    if *since_change < SAVE_DELAY {
+        return;
+    }
+    pending_save.since_change = None;
+
+    let mut factors = factor_info.factors.iter().collect::<Vec<_>>();
+    factors.sort_by_key(|(creature_type, _)| creature_type.0);
+    let settings = SavedSettings {
+        features: sim_settings.features.clone(),
+        factors: factors
+            .into_iter()
+            .map(|(_, factors)| factors.clone())
+            .collect(),
+        spawn: sim_settings.spawn.clone(),
+        despawn: sim_settings.despawn.clone(),
+        // Coming back to a fullscreen sized window would be weird
+        window_size: window.map(|window| {
+            let size = window_mode_settings
+                .as_ref()
+                .map_or(Vec2::new(window.width(), window.height()), |settings| {
+                    settings.windowed_size(&window)
+                });
+            (size.x, size.y)
+        }),
+        world_bounds: Some(*sim_settings.world_bounds),
+        steering: *sim_settings.steering,
+        satiation: *sim_settings.satiation,
+        food: *sim_settings.food,
+        temperature: *sim_settings.temperature,
+        weather: *sim_settings.weather,
+        collision: *sim_settings.collision,
+        growth: *sim_settings.growth,
+        theme: interface
+            .theme
+            .map_or_else(UiTheme::default, |theme| (*theme).clone()),
+        dock: interface
+            .dock
+            .map_or_else(DockLayout::default, |dock| (*dock).clone()),
+    };
+    let result = serde_json::to_string(&settings)
+        .map_err(|error| error.to_string())
+        .and_then(|json| local_storage_set(STORAGE_KEY, &json));
+    if let Err(error) = result {
+        warn!("Couldn't save settings: {}", error);
+    }
+}
+
+fn reset_settings_system(
+    defaults: Res<DefaultSettings>,
+    mut replace_factors: ReplaceFactors,
+    mut sim_settings: SimSettingsMut,
+    theme: Option<ResMut<UiTheme>>,
+    dock: Option<ResMut<DockLayout>>,
+    mut pending_save: ResMut<PendingSave>,
+    mut reset_events: EventReader<ResetSettingsEvent>,
+) {
+    if reset_events.iter().last().is_none() {
+        return;
+    }
+
+    *sim_settings.features = defaults.features.clone();
+    *sim_settings.spawn = defaults.spawn.clone();
+    *sim_settings.despawn = defaults.despawn.clone();
+    *sim_settings.world_bounds = defaults.world_bounds;
+    *sim_settings.steering = defaults.steering;
+    *sim_settings.satiation = defaults.satiation;
+    *sim_settings.food = defaults.food;
+    *sim_settings.temperature = defaults.temperature;
+    *sim_settings.weather = defaults.weather;
+    *sim_settings.collision = defaults.collision;
+    *sim_settings.growth = defaults.growth;
+    if let Some(mut theme) = theme {
+        *theme = UiTheme::default();
+    }
+    if let Some(mut dock) = dock {
+        *dock = DockLayout::default();
+    }
+    replace_factors.replace(defaults.factor_info.factors.clone());
+
+    local_storage_remove(STORAGE_KEY);
+    // Resetting changes everything above which would just save it all again
+    pending_save.since_change = None;
+}
+
+/// Needs to be added after the settings resources exist so it can overwrite them.
+#[derive(Default)]
+pub struct PersistencePlugin;
+
+impl Plugin for PersistencePlugin {
+    fn build(&self, app: &mut App) {
+        let world = &app.world;
+        let defaults = DefaultSettings {
+            features: world.resource::<Features>().clone(),
+            factor_info: world.resource::<FactorInfo>().clone(),
+            spawn: world.resource::<SpawnProperties>().clone(),
+            despawn: world.resource::<DespawnProperties>().clone(),
+            world_bounds: *world.resource::<WorldBounds>(),
+            steering: *world.resource::<SteeringCombination>(),
+            satiation: *world.resource::<SatiationSettings>(),
+            food: *world.resource::<FoodSettings>(),
+            temperature: *world.resource::<TemperatureSettings>(),
+            weather: *world.resource::<WeatherSettings>(),
+            collision: *world.resource::<CollisionSettings>(),
+            growth: *world.resource::<GrowthSettings>(),
+        };
+        app.insert_resource(defaults);
+
+        // Restored right away so the very first creatures already use them
+        if let Some(settings) = SavedSettings::load() {
+            app.insert_resource(FactorInfo {
+                factors: settings.factors_to_map(),
+            })
+            .insert_resource(settings.features)
+            .insert_resource(settings.spawn)
+            .insert_resource(settings.despawn)
+            .insert_resource(settings.steering)
+            .insert_resource(settings.satiation)
+            .insert_resource(settings.food)
+            .insert_resource(settings.temperature)
+            .insert_resource(settings.weather)
+            .insert_resource(settings.collision)
+            .insert_resource(settings.growth)
+            // The UI plugin comes after this so it keeps the saved ones
+            .insert_resource(settings.theme)
+            .insert_resource(settings.dock);
+            if let Some(world_bounds) = settings.world_bounds {
+                app.insert_resource(world_bounds);
+            }
+        }
+
+        app.insert_resource(PendingSave::default())
+            .add_event::<ResetSettingsEvent>()
+            // After the binary's own startup systems have set their window size
+            .add_startup_system(restore_window_size_system.in_base_set(StartupSet::PostStartup))
+            .add_system(save_settings_system.in_base_set(CoreSet::PostUpdate))
+            .add_system(reset_settings_system.in_base_set(CoreSet::PreUpdate));
+    }
+}
@@ -0,0 +1,77 @@
+// Some creatures are braver or friendlier than others. Boldness turns down how
+// hard (and how early) they run from predators and sociability turns up how
+// hard they pull towards their flock. Both go from 0 to 1 and 0.5 acts exactly
+// like the type's factors say.
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{CreatureType, SimRng, SpawnProperties};
+
+#[derive(Debug, Clone, Copy, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Personality {
+    pub boldness: f32,
+    pub sociability: f32,
+}
+
+impl Default for Personality {
+    fn default() -> Self {
+        Personality {
+            boldness: 0.5,
+            sociability: 0.5,
+        }
+    }
+}
+
+impl Personality {
+    /// Traits spread out from the middle by up to `spread` (1 can be anything)
+    pub fn random(rng: &mut impl Rng, spread: f32) -> Self {
+        let spread = spread.clamp(0.0, 1.0) / 2.0;
+        let mut roll = || 0.5 + rng.gen_range(-spread..=spread);
+        Personality {
+            boldness: roll(),
+            sociability: roll(),
+        }
+    }
+
+    /// Multiplies the scare factor, from 1.5 for the most timid to 0.5 for the boldest
+    pub fn scare_scale(&self) -> f32 {
+        1.5 - self.boldness
+    }
+
+    /// Multiplies vision when looking out for predators so bold ones let
+    /// them get closer before running. Timid ones can't see any farther though.
+    pub fn flee_range_scale(&self) -> f32 {
+        self.scare_scale().min(1.0)
+    }
+
+    /// Multiplies the cohesion factor, from 0.5 for loners to 1.5 for the clingiest
+    pub fn cohesion_scale(&self) -> f32 {
+        0.5 + self.sociability
+    }
+}
+
+/// Same idea as the variations, new creatures get theirs as they show up.
+fn assign_personalities_system(
+    mut commands: Commands,
+    mut rng: ResMut<SimRng>,
+    spawn_properties: Res<SpawnProperties>,
+    creature_query: Query<Entity, (Added<CreatureType>, Without<Personality>)>,
+) {
+    for entity in creature_query.iter() {
+        let personality = Personality::random(rng.as_mut(), spawn_properties.personality_spread);
+        commands.entity(entity).insert(personality);
+    }
+}
+
+#[derive(Default)]
+pub struct PersonalityPlugin;
+
+impl Plugin for PersonalityPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Personality>()
+            .add_system(assign_personalities_system.in_base_set(CoreSet::PostUpdate));
+    }
+}
@@ -0,0 +1,165 @@
+// Everything needed to reproduce a bug in one JSON file: the settings, the
+// seed, a snapshot of every type, the recent event log, and what it ran on.
+// The snapshot is the same format as saved survivors so it can be dropped in
+// as the survivors file and warm started from.
+
+use bevy::{
+    diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    ecs::system::SystemParam,
+    prelude::*,
+    window::PrimaryWindow,
+};
+use serde::Serialize;
+
+use crate::{
+    event_log::{EventLog, LogEntry},
+    files::{save_file, timestamp},
+    snapshot::SurvivorSnapshot,
+    CreatureType, DespawnProperties, DeterministicMode, FactorInfo, Features, SimRng,
+    SpawnProperties, WorldBounds, IS_WASM,
+};
+
+pub struct CreateReportEvent;
+
+/// Where the last report went (or why it didn't) so the UI can tell the user.
+#[derive(Debug, Default, Resource)]
+pub struct ReportStatus(pub Option<String>);
+
+#[derive(Serialize)]
+struct SystemInfo {
+    version: &'static str,
+    os: &'static str,
+    arch: &'static str,
+    wasm: bool,
+    /// `None` when there's no window, like a headless sim
+    window: Option<(f32, f32)>,
+    fps: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct Reproduction {
+    seed: u64,
+    deterministic: bool,
+}
+
+#[derive(Serialize)]
+struct Config<'a> {
+    world_bounds: WorldBounds,
+    features: &'a Features,
+    spawn: &'a SpawnProperties,
+    despawn: &'a DespawnProperties,
+}
+
+#[derive(Serialize)]
+struct ReportBundle<'a> {
+    system: SystemInfo,
+    reproduction: Reproduction,
+    config: Config<'a>,
+    /// Factors and current population of every type
+    snapshot: SurvivorSnapshot,
+    /// Oldest first, ignoring the Event Log window's filters. Empty without
+    /// the UI since that's what keeps the log.
+    event_log: Vec<&'a LogEntry>,
+}
+
+/// What the sim is running on
+#[derive(SystemParam)]
+struct Machine<'w, 's> {
+    diagnostics: Res<'w, Diagnostics>,
+    primary_query: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+}
+
+impl Machine<'_, '_> {
+    fn info(&self) -> SystemInfo {
+        SystemInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            wasm: IS_WASM,
+            window: self
+                .primary_query
+                .get_single()
+                .ok()
+                .map(|window| (window.width(), window.height())),
+            fps: self
+                .diagnostics
+                .get(FrameTimeDiagnosticsPlugin::FPS)
+                .and_then(|fps| fps.average()),
+        }
+    }
+}
+
+/// What it takes to set the sim up the same way again
+#[derive(SystemParam)]
+struct ReportSettings<'w> {
+    features: Res<'w, Features>,
+    spawn_properties: Res<'w, SpawnProperties>,
+    despawn_properties: Res<'w, DespawnProperties>,
+    world_bounds: Res<'w, WorldBounds>,
+    rng: Res<'w, SimRng>,
+    deterministic_mode: Res<'w, DeterministicMode>,
+}
+
+impl ReportSettings<'_> {
+    fn reproduction(&self) -> Reproduction {
+        Reproduction {
+            seed: self.rng.seed(),
+            deterministic: self.deterministic_mode.0,
+        }
+    }
+
+    fn config(&self) -> Config<'_> {
+        Config {
+            world_bounds: *self.world_bounds,
+            features: &self.features,
+            spawn: &self.spawn_properties,
+            despawn: &self.despawn_properties,
+        }
+    }
+}
+
+fn create_report_system(
+    machine: Machine,
+    settings: ReportSettings,
+    factor_info: Res<FactorInfo>,
+    creature_query: Query<&CreatureType>,
+    event_log: Option<Res<EventLog>>,
+    mut report_status: ResMut<ReportStatus>,
+    mut create_report_events: EventReader<CreateReportEvent>,
+) {
+    if create_report_events.iter().count() == 0 {
+        return;
+    }
+
+    let report = ReportBundle {
+        system: machine.info(),
+        reproduction: settings.reproduction(),
+        config: settings.config(),
+        snapshot: SurvivorSnapshot::capture(&factor_info, creature_query.iter()),
+        event_log: event_log
+            .as_ref()
+            .map_or(vec![], |event_log| event_log.entries.iter().collect()),
+    };
+
+    let file_name = format!("rusty-boids-report-{}.json", timestamp());
+    report_status.0 = Some(
+        match serde_json::to_vec_pretty(&report)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| save_file(&file_name, &contents, "application/json"))
+        {
+            Ok(path) => format!("Saved report to {}", path),
+            Err(error) => format!("Couldn't save report: {}", error),
+        },
+    );
+}
+
+#[derive(Default)]
+pub struct ReportPlugin;
+
+impl Plugin for ReportPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ReportStatus::default())
+            .add_event::<CreateReportEvent>()
+            .add_system(create_report_system);
+    }
+}
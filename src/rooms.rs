@@ -0,0 +1,328 @@
+// Splits the world into a grid of rooms walled off from each other except for
+// a gap (a portal) in each wall between neighbors, for seeing how flocks get
+// through bottlenecks. The walls are plain obstacles so everything that avoids
+// those avoids these. Goals in another room get reached by heading through
+// whichever portals lead there.
+
+use std::collections::VecDeque;
+
+use bevy::{math::Vec3Swizzles, prelude::*, window::PrimaryWindow};
+use bevy_egui::{egui, EguiContexts};
+use rand::{prelude::*, rngs::StdRng};
+
+use crate::{
+    obstacles::{ObstacleBuilder, ObstacleShape},
+    overlays::WorldToScreen,
+    ui::overlays_shown,
+    CreatureType, MainCamera, WorldBounds,
+};
+
+#[derive(Debug, Clone, Resource)]
+pub struct RoomSettings {
+    pub columns: usize,
+    pub rows: usize,
+    /// How wide the gap through each wall is
+    pub portal_width: f32,
+    pub wall_thickness: f32,
+    /// Walls off the edges of the world too so nothing wraps into another room
+    pub closed_edges: bool,
+    /// Where along each wall its portal goes. 0 puts them all in the middle.
+    pub seed: u64,
+}
+
+impl Default for RoomSettings {
+    fn default() -> Self {
+        RoomSettings {
+            columns: 2,
+            rows: 1,
+            portal_width: 60.0,
+            wall_thickness: 12.0,
+            closed_edges: true,
+            seed: 0,
+        }
+    }
+}
+
+/// Replaces the rooms with new ones from `RoomSettings`
+pub struct GenerateRoomsEvent;
+
+pub struct ClearRoomsEvent;
+
+/// Obstacles that make up the rooms, so clearing them leaves the rest alone
+#[derive(Debug, Clone, Copy, Component)]
+pub struct RoomWall;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Portal {
+    pub center: Vec2,
+    /// Sideways along the wall it's in
+    pub along: Vec2,
+    pub width: f32,
+    /// The rooms on either side
+    pub rooms: (usize, usize),
+}
+
+/// The rooms that are up right now, numbered across then up from the bottom
+/// left. Without any there's just the one room that's the whole world.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct Rooms {
+    pub columns: usize,
+    pub rows: usize,
+    min: Vec2,
+    room_size: Vec2,
+    pub portals: Vec<Portal>,
+    /// How many creatures are in each room, as of the last frame
+    pub census: Vec<usize>,
+}
+
+impl Rooms {
+    pub fn is_empty(&self) -> bool {
+        self.columns * self.rows <= 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    pub fn room_at(&self, position: Vec2) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+        let cell = ((position - self.min) / self.room_size).floor();
+        let column = (cell.x.max(0.0) as usize).min(self.columns - 1);
+        let row = (cell.y.max(0.0) as usize).min(self.rows - 1);
+        Some(row * self.columns + column)
+    }
+
+    pub fn room_center(&self, room: usize) -> Vec2 {
+        let cell = Vec2::new((room % self.columns) as f32, (room / self.columns) as f32);
+        self.min + (cell + 0.5) * self.room_size
+    }
+
+    /// Where to head from `position` to get to `target`, which is the first
+    /// portal along the way if it's in another room. Fewest portals wins.
+    pub fn waypoint(&self, position: Vec2, target: Vec2) -> Vec2 {
+        let (Some(from), Some(to)) = (self.room_at(position), self.room_at(target)) else {
+            return target;
+        };
+        if from == to {
+            return target;
+        }
+        // Breadth first from the target's room so each room knows which
+        // portal takes it one step closer
+        let mut towards = vec![None; self.len()];
+        let mut visited = vec![false; self.len()];
+        let mut queue = VecDeque::from([to]);
+        visited[to] = true;
+        while let Some(room) = queue.pop_front() {
+            for (i, portal) in self.portals.iter().enumerate() {
+                let other = match portal.rooms {
+                    (a, b) if a == room => b,
+                    (a, b) if b == room => a,
+                    _ => continue,
+                };
+                if !visited[other] {
+                    visited[other] = true;
+                    towards[other] = Some(i);
+                    queue.push_back(other);
+                }
+            }
+        }
+        towards[from].map_or(target, |i| self.portals[i].center)
+    }
+}
+
+fn generate_rooms_system(
+    mut builder: ObstacleBuilder,
+    settings: Res<RoomSettings>,
+    world_bounds: Res<WorldBounds>,
+    mut rooms: ResMut<Rooms>,
+    mut generate_events: EventReader<GenerateRoomsEvent>,
+    mut clear_events: EventReader<ClearRoomsEvent>,
+    wall_query: Query<Entity, With<RoomWall>>,
+) {
+    let generate = generate_events.iter().count() > 0;
+    if !generate && clear_events.iter().count() == 0 {
+        return;
+    }
+    for entity in wall_query.iter() {
+        builder.commands.entity(entity).despawn();
+    }
+    *rooms = Rooms::default();
+    if !generate {
+        return;
+    }
+
+    let (columns, rows) = (settings.columns.max(1), settings.rows.max(1));
+    let room_size = world_bounds.size() / Vec2::new(columns as f32, rows as f32);
+    let (min, max) = (world_bounds.min(), world_bounds.max());
+    let thickness = settings.wall_thickness.max(1.0);
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+    let mut walls = vec![];
+    let mut portals = vec![];
+
+    // Every wall between two rooms gets split around its portal
+    let mut add_wall = |start: Vec2, end: Vec2, between: (usize, usize)| {
+        let along = (end - start).normalize();
+        let length = start.distance(end);
+        let width = settings.portal_width.min(length - thickness * 2.0).max(0.0);
+        let slack = (length - width) / 2.0 - thickness;
+        let offset = if settings.seed == 0 || slack <= 0.0 {
+            0.0
+        } else {
+            rng.gen_range(-slack..=slack)
+        };
+        let center = (start + end) / 2.0 + along * offset;
+        walls.push((start, center - along * width / 2.0));
+        walls.push((center + along * width / 2.0, end));
+        portals.push(Portal {
+            center,
+            along,
+            width,
+            rooms: between,
+        });
+    };
+    for row in 0..rows {
+        for column in 1..columns {
+            let x = min.x + column as f32 * room_size.x;
+            let y = min.y + row as f32 * room_size.y;
+            let room = row * columns + column;
+            add_wall(
+                Vec2::new(x, y),
+                Vec2::new(x, y + room_size.y),
+                (room - 1, room),
+            );
+        }
+    }
+    for row in 1..rows {
+        for column in 0..columns {
+            let y = min.y + row as f32 * room_size.y;
+            let x = min.x + column as f32 * room_size.x;
+            let room = row * columns + column;
+            add_wall(
+                Vec2::new(x, y),
+                Vec2::new(x + room_size.x, y),
+                (room - columns, room),
+            );
+        }
+    }
+    if settings.closed_edges {
+        // Pulled in a little so they're entirely inside the world
+        let inset = thickness / 2.0;
+        let corners = [
+            Vec2::new(min.x + inset, min.y + inset),
+            Vec2::new(max.x - inset, min.y + inset),
+            Vec2::new(max.x - inset, max.y - inset),
+            Vec2::new(min.x + inset, max.y - inset),
+        ];
+        for (i, &corner) in corners.iter().enumerate() {
+            walls.push((corner, corners[(i + 1) % corners.len()]));
+        }
+    }
+
+    for (start, end) in walls {
+        let length = start.distance(end);
+        if length <= 0.0 {
+            continue;
+        }
+        // Long enough to cover the corners where walls meet
+        let (along, across) = ((length + thickness) / 2.0, thickness / 2.0);
+        let half_size = if (end - start).x.abs() > (end - start).y.abs() {
+            Vec2::new(along, across)
+        } else {
+            Vec2::new(across, along)
+        };
+        let shape = ObstacleShape::Rect { half_size };
+        let center = (start + end) / 2.0;
+        builder.spawn(center, shape).insert(RoomWall);
+    }
+
+    *rooms = Rooms {
+        columns,
+        rows,
+        min,
+        room_size,
+        portals,
+        census: vec![0; columns * rows],
+    };
+}
+
+fn room_census_system(
+    mut rooms: ResMut<Rooms>,
+    creature_query: Query<&Transform, With<CreatureType>>,
+) {
+    if rooms.is_empty() {
+        return;
+    }
+    let mut census = vec![0; rooms.len()];
+    for transform in creature_query.iter() {
+        if let Some(room) = rooms.room_at(transform.translation.xy()) {
+            census[room] += 1;
+        }
+    }
+    rooms.census = census;
+}
+
+/// Each portal's gap outlined and each room's number and head count in the
+/// middle of it
+fn room_overlay_system(
+    mut egui_context: EguiContexts,
+    rooms: Res<Rooms>,
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    if rooms.is_empty() {
+        return;
+    }
+    let Ok(window) = primary_query.get_single() else {
+        return;
+    };
+    let (camera, camera_transform) = camera_query.single();
+    let to_screen = WorldToScreen::new(camera, camera_transform, window);
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    let color = egui::Color32::from_rgba_unmultiplied(120, 200, 255, 160);
+
+    for portal in rooms.portals.iter() {
+        let half = portal.along * portal.width / 2.0;
+        let (Some(start), Some(end)) = (
+            to_screen.point(portal.center - half),
+            to_screen.point(portal.center + half),
+        ) else {
+            continue;
+        };
+        painter.line_segment([start, end], egui::Stroke::new(2.0, color));
+    }
+    for room in 0..rooms.len() {
+        let Some(center) = to_screen.point(rooms.room_center(room)) else {
+            continue;
+        };
+        painter.text(
+            center,
+            egui::Align2::CENTER_CENTER,
+            format!(
+                "{}: {}",
+                room + 1,
+                rooms.census.get(room).copied().unwrap_or(0)
+            ),
+            egui::FontId::proportional(16.0),
+            color.linear_multiply(0.6),
+        );
+    }
+}
+
+#[derive(Default)]
+pub struct RoomPlugin;
+
+impl Plugin for RoomPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RoomSettings>()
+            .init_resource::<Rooms>()
+            .add_event::<GenerateRoomsEvent>()
+            .add_event::<ClearRoomsEvent>()
+            .add_system(generate_rooms_system)
+            .add_system(room_census_system)
+            .add_system(room_overlay_system.run_if(overlays_shown));
+    }
+}
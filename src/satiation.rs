@@ -0,0 +1,60 @@
+// Predators that just ate take a break. While they're full they don't chase or
+// eat anything and just drift along slower with their flock, which gives prey
+// a chance to get away instead of getting eaten one after another.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{SimState, SimTime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SatiationSettings {
+    /// Seconds a predator stays full after a kill. 0 turns it off.
+    pub duration: f32,
+    /// Multiplies the speed of full predators
+    pub speed_scale: f32,
+}
+
+impl Default for SatiationSettings {
+    fn default() -> Self {
+        SatiationSettings {
+            duration: 2.0,
+            speed_scale: 0.5,
+        }
+    }
+}
+
+/// On a predator that ate recently. Goes away when the timer's done.
+#[derive(Debug, Clone, Component)]
+pub struct Satiated(pub Timer);
+
+impl Satiated {
+    pub fn new(duration: f32) -> Self {
+        Satiated(Timer::from_seconds(duration, TimerMode::Once))
+    }
+}
+
+fn satiation_system(
+    mut commands: Commands,
+    sim_time: SimTime,
+    mut satiated_query: Query<(Entity, &mut Satiated)>,
+) {
+    let delta = Duration::from_secs_f32(sim_time.delta_seconds());
+    for (entity, mut satiated) in satiated_query.iter_mut() {
+        if satiated.0.tick(delta).finished() {
+            commands.entity(entity).remove::<Satiated>();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SatiationPlugin;
+
+impl Plugin for SatiationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(satiation_system.in_set(OnUpdate(SimState::Running)));
+    }
+}
@@ -0,0 +1,304 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    CreatureType, Factors, Features, InitialPopulations, ReplaceFactors, INITIAL_POPULATIONS,
+    IS_WASM,
+};
+
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub features: Features,
+    pub factors: HashMap<CreatureType, Factors>,
+    pub populations: Vec<usize>,
+}
+
+impl Scenario {
+    pub fn all() -> Vec<Scenario> {
+        vec![
+            Self::classic(),
+            Self::predator_prey(),
+            Self::food_chain(),
+            Self::stress_test(),
+        ]
+    }
+
+    pub fn classic() -> Self {
+        let mut factors = HashMap::default();
+        factors.insert(
+            CreatureType(0),
+            Factors {
+                name: "Boids".to_string(),
+                description: "Flocks and that's it.".to_string(),
+                color: Color::CYAN,
+                speed: 70.0,
+                vision: 20.0,
+                cohesion_radius: 20.0,
+                alignment_radius: 20.0,
+                separation_radius: 10.0,
+                size: 2.0,
+                cohesion: 6.0,
+                separation: 12.0,
+                alignment: 16.0,
+                collision_avoidance: 6.0,
+                scare: 0.0,
+                chase: 0.0,
+                ..Default::default()
+            },
+        );
+
+        Self {
+            name: "Classic Boids",
+            description: "A single species doing nothing but flocking.",
+            features: Features {
+                chasing: false,
+                running: false,
+                killing: false,
+                flocking: true,
+                energy_draining: false,
+            },
+            factors,
+            populations: vec![if IS_WASM { 800 } else { 1500 }],
+        }
+    }
+
+    pub fn predator_prey() -> Self {
+        let mut factors = HashMap::default();
+        factors.insert(
+            CreatureType(0),
+            Factors {
+                name: "Prey".to_string(),
+                description: "Schools up and runs from the predators.".to_string(),
+                color: Color::CYAN,
+                speed: 70.0,
+                vision: 15.0,
+                cohesion_radius: 15.0,
+                alignment_radius: 15.0,
+                separation_radius: 7.5,
+                size: 1.0,
+                cohesion: 6.0,
+                separation: 12.0,
+                alignment: 16.0,
+                collision_avoidance: 6.0,
+                scare: 30.0,
+                chase: 0.0,
+                max_energy: 50.0,
+                ..Default::default()
+            },
+        );
+
+        let mut predator_of = HashSet::default();
+        predator_of.insert(CreatureType(0));
+        factors.insert(
+            CreatureType(1),
+            Factors {
+                name: "Predators".to_string(),
+                description: "Hunts the prey in small packs.".to_string(),
+                color: Color::RED,
+                speed: 60.0,
+                vision: 35.0,
+                cohesion_radius: 35.0,
+                alignment_radius: 35.0,
+                separation_radius: 17.5,
+                size: 3.0,
+                cohesion: 2.0,
+                separation: 10.0,
+                alignment: 6.0,
+                collision_avoidance: 2.0,
+                scare: 0.0,
+                chase: 15.0,
+                max_energy: 35.0,
+                predator_of,
+                ..Default::default()
+            },
+        );
+
+        Self {
+            name: "Predator/Prey",
+            description: "One school of prey hunted by a smaller group of predators.",
+            features: Features {
+                killing: true,
+                ..Default::default()
+            },
+            factors,
+            populations: vec![
+                if IS_WASM { 500 } else { 1000 },
+                if IS_WASM { 20 } else { 60 },
+            ],
+        }
+    }
+
+    pub fn food_chain() -> Self {
+        let mut factors = HashMap::default();
+
+        factors.insert(
+            CreatureType(0),
+            Factors {
+                name: "Minnows".to_string(),
+                description: "Bottom of the food chain. Everyone eats them.".to_string(),
+                color: Color::CYAN,
+                speed: 70.0,
+                vision: 15.0,
+                cohesion_radius: 15.0,
+                alignment_radius: 15.0,
+                separation_radius: 7.5,
+                size: 1.0,
+                cohesion: 6.0,
+                separation: 12.0,
+                alignment: 16.0,
+                collision_avoidance: 6.0,
+                scare: 30.0,
+                chase: 0.0,
+                max_energy: 50.0,
+                predator_of: HashSet::default(),
+                ..Default::default()
+            },
+        );
+
+        let mut b_predator_of = HashSet::default();
+        b_predator_of.insert(CreatureType(0));
+        b_predator_of.insert(CreatureType(2));
+        factors.insert(
+            CreatureType(1),
+            Factors {
+                name: "Sharks".to_string(),
+                description: "Top of the food chain. Eats everyone.".to_string(),
+                color: Color::RED,
+                speed: 55.0,
+                vision: 30.0,
+                cohesion_radius: 30.0,
+                alignment_radius: 30.0,
+                separation_radius: 15.0,
+                size: 3.0,
+                cohesion: 3.0,
+                separation: 10.0,
+                alignment: 10.0,
+                collision_avoidance: 2.0,
+                scare: 0.0,
+                chase: 15.0,
+                max_energy: 35.0,
+                predator_of: b_predator_of,
+                ..Default::default()
+            },
+        );
+
+        let mut c_predator_of = HashSet::default();
+        c_predator_of.insert(CreatureType(0));
+        factors.insert(
+            CreatureType(2),
+            Factors {
+                name: "Barracudas".to_string(),
+                description: "Eats minnows and runs from sharks.".to_string(),
+                color: Color::WHITE,
+                speed: 64.0,
+                vision: 25.0,
+                cohesion_radius: 25.0,
+                alignment_radius: 25.0,
+                separation_radius: 12.5,
+                size: 2.0,
+                cohesion: 4.0,
+                separation: 8.0,
+                alignment: 10.0,
+                collision_avoidance: 3.0,
+                scare: 10.0,
+                chase: 10.0,
+                max_energy: 50.0,
+                predator_of: c_predator_of,
+                ..Default::default()
+            },
+        );
+
+        Self {
+            name: "Food Chain",
+            description: "Three species where the reds eat everyone and the whites eat the cyans.",
+            features: Features::default(),
+            factors,
+            populations: INITIAL_POPULATIONS.to_vec(),
+        }
+    }
+
+    pub fn stress_test() -> Self {
+        let mut factors = HashMap::default();
+        factors.insert(
+            CreatureType(0),
+            Factors {
+                name: "Swarm".to_string(),
+                description: "Flocks, just a lot of them.".to_string(),
+                color: Color::YELLOW,
+                speed: 80.0,
+                vision: 12.0,
+                cohesion_radius: 12.0,
+                alignment_radius: 12.0,
+                separation_radius: 6.0,
+                size: 1.0,
+                cohesion: 4.0,
+                separation: 10.0,
+                alignment: 12.0,
+                collision_avoidance: 4.0,
+                scare: 0.0,
+                chase: 0.0,
+                ..Default::default()
+            },
+        );
+
+        Self {
+            name: "Swarm Stress Test",
+            description: "As many flocking boids as we can throw at the hash grid.",
+            features: Features {
+                chasing: false,
+                running: false,
+                killing: false,
+                flocking: true,
+                energy_draining: false,
+            },
+            factors,
+            populations: vec![if IS_WASM { 3000 } else { 8000 }],
+        }
+    }
+}
+
+#[derive(Debug, Resource)]
+pub struct Scenarios(pub Vec<Scenario>);
+
+impl Default for Scenarios {
+    fn default() -> Self {
+        Scenarios(Scenario::all())
+    }
+}
+
+pub struct LoadScenarioEvent(pub usize);
+
+fn load_scenario_system(
+    scenarios: Res<Scenarios>,
+    mut features: ResMut<Features>,
+    mut replace_factors: ReplaceFactors,
+    mut initial_populations: ResMut<InitialPopulations>,
+    mut load_scenario_events: EventReader<LoadScenarioEvent>,
+) {
+    // Only the latest request matters since each load wipes the previous one
+    let Some(LoadScenarioEvent(index)) = load_scenario_events.iter().last() else {
+        return;
+    };
+    let Some(scenario) = scenarios.0.get(*index) else {
+        return;
+    };
+
+    *features = scenario.features.clone();
+    initial_populations.0 = scenario.populations.clone();
+    replace_factors.replace(scenario.factors.clone());
+}
+
+#[derive(Default)]
+pub struct ScenarioPlugin;
+
+impl Plugin for ScenarioPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Scenarios::default())
+            .add_event::<LoadScenarioEvent>()
+            .add_system(load_scenario_system.in_base_set(CoreSet::PreUpdate));
+    }
+}
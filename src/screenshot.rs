@@ -0,0 +1,291 @@
+// The window's own frame can't be read back from the GPU in this version of
+// Bevy, so a screenshot is a second camera lined up with the main one that
+// draws into an image for a frame, which then gets copied back and saved.
+// Everything the main camera draws shows up (textures, trails, labels, the
+// long exposure) but the egui windows and overlays don't since egui draws
+// straight onto the window. That's exactly what you want for a clean shot.
+
+use std::sync::{
+    mpsc::{channel, Receiver, Sender},
+    Mutex,
+};
+
+use bevy::{
+    asset::HandleId,
+    core_pipeline::tonemapping::Tonemapping,
+    prelude::*,
+    render::{
+        camera::{ExtractedCamera, NormalizedRenderTarget, RenderTarget},
+        render_asset::RenderAssets,
+        render_resource::{
+            BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageDataLayout, MapMode,
+            TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        renderer::{render_system, RenderDevice, RenderQueue},
+        Extract, ExtractSchedule, RenderApp, RenderSet,
+    },
+    window::PrimaryWindow,
+};
+use image::{codecs::png::PngEncoder, ImageEncoder};
+
+use crate::{
+    files::{save_file, timestamp},
+    MainCamera,
+};
+
+pub const SCREENSHOT_KEY: KeyCode = KeyCode::F12;
+
+/// After the main camera and anything else already drawing to the window
+const SCREENSHOT_CAMERA_ORDER: isize = 10;
+
+/// The camera drawing the screenshot and the image it draws into, for as long
+/// as it takes to come back from the GPU
+#[derive(Debug, Clone, Resource)]
+struct PendingScreenshot {
+    camera: Entity,
+    image: Handle<Image>,
+}
+
+/// Tightly packed RGBA rows of a finished screenshot
+struct Pixels {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+/// Receiving end in the main world. The mutex is only there to make it `Sync`.
+#[derive(Resource)]
+struct PixelsReceiver(Mutex<Receiver<Pixels>>);
+
+/// Sending end in the render world
+#[derive(Resource)]
+struct PixelsSender(Mutex<Sender<Pixels>>);
+
+/// The image of the screenshot being taken, mirrored into the render world
+#[derive(Debug, Default, Resource)]
+struct ExtractedScreenshot(Option<Handle<Image>>);
+
+fn screenshot_image(width: u32, height: u32) -> Image {
+    let size = Extent3d {
+        width: width.max(1),
+        height: height.max(1),
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("screenshot"),
+            size,
+            dimension: TextureDimension::D2,
+            // Same bytes a PNG wants so nothing needs converting afterwards
+            format: TextureFormat::Rgba8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    image
+}
+
+fn take_screenshot_system(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    pending: Option<Res<PendingScreenshot>>,
+    mut images: ResMut<Assets<Image>>,
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<
+        (
+            &Transform,
+            &OrthographicProjection,
+            &Camera2d,
+            Option<&Tonemapping>,
+        ),
+        With<MainCamera>,
+    >,
+) {
+    if !keys.just_pressed(SCREENSHOT_KEY) || pending.is_some() {
+        return;
+    }
+    let (Ok(window), Ok((transform, projection, camera_2d, tonemapping))) =
+        (primary_query.get_single(), camera_query.get_single())
+    else {
+        warn!("Couldn't take screenshot: there's no window or camera to take it from");
+        return;
+    };
+
+    let image = images.add(screenshot_image(
+        window.physical_width(),
+        window.physical_height(),
+    ));
+    let camera = commands
+        .spawn(Camera2dBundle {
+            camera: Camera {
+                order: SCREENSHOT_CAMERA_ORDER,
+                target: RenderTarget::Image(image.clone()),
+                ..default()
+            },
+            camera_2d: camera_2d.clone(),
+            tonemapping: tonemapping.copied().unwrap_or_default(),
+            transform: *transform,
+            // The image is in physical pixels, which the projection takes as
+            // logical
+            projection: OrthographicProjection {
+                scale: projection.scale / window.scale_factor() as f32,
+                ..projection.clone()
+            },
+            ..default()
+        })
+        .id();
+    commands.insert_resource(PendingScreenshot { camera, image });
+}
+
+fn save_screenshot_system(
+    mut commands: Commands,
+    pending: Option<Res<PendingScreenshot>>,
+    receiver: Res<PixelsReceiver>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some(pending) = pending else {
+        return;
+    };
+    let Ok(pixels) = receiver.0.lock().unwrap().try_recv() else {
+        return;
+    };
+    commands.entity(pending.camera).despawn();
+    images.remove(&pending.image);
+    commands.remove_resource::<PendingScreenshot>();
+
+    let mut png = vec![];
+    let encoded = PngEncoder::new(&mut png).write_image(
+        &pixels.data,
+        pixels.width,
+        pixels.height,
+        image::ColorType::Rgba8,
+    );
+    let result = encoded.map_err(|error| error.to_string()).and_then(|_| {
+        save_file(
+            &format!("rusty-boids-{}.png", timestamp()),
+            &png,
+            "image/png",
+        )
+    });
+    match result {
+        Ok(path) => info!("Saved screenshot to {}", path),
+        Err(error) => warn!("Couldn't save screenshot: {}", error),
+    }
+}
+
+fn extract_screenshot_system(
+    mut extracted: ResMut<ExtractedScreenshot>,
+    pending: Extract<Option<Res<PendingScreenshot>>>,
+) {
+    extracted.0 = pending.as_ref().map(|pending| pending.image.clone());
+}
+
+/// Copies the screenshot image into a buffer once it's been drawn, and sends
+/// the pixels back to the main world when the buffer's ready to read
+fn copy_screenshot_system(
+    extracted: Res<ExtractedScreenshot>,
+    gpu_images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    sender: Res<PixelsSender>,
+    camera_query: Query<&ExtractedCamera>,
+    mut copied: Local<Option<HandleId>>,
+) {
+    let Some(handle) = extracted.0.as_ref() else {
+        return;
+    };
+    if *copied == Some(handle.id()) {
+        return;
+    }
+    // Nothing's been drawn into it until its camera makes it over
+    let target = Some(NormalizedRenderTarget::Image(handle.clone()));
+    let drawn = camera_query.iter().any(|camera| camera.target == target);
+    let (true, Some(gpu_image)) = (drawn, gpu_images.get(handle)) else {
+        return;
+    };
+    *copied = Some(handle.id());
+
+    let (width, height) = (gpu_image.size.x as u32, gpu_image.size.y as u32);
+    let row_bytes = width as usize * 4;
+    // Rows in the buffer have to line up to the copy alignment
+    let padded_row_bytes = RenderDevice::align_copy_bytes_per_row(row_bytes);
+
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: Some("screenshot_buffer"),
+        size: (padded_row_bytes * height as usize) as u64,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = render_device.create_command_encoder(&default());
+    encoder.copy_texture_to_buffer(
+        gpu_image.texture.as_image_copy(),
+        ImageCopyBuffer {
+            buffer: &buffer,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(padded_row_bytes as u32),
+                rows_per_image: None,
+            },
+        },
+        Extent3d {
+            width,
+            height,
+            ..default()
+        },
+    );
+    render_queue.submit([encoder.finish()]);
+
+    let sender = sender.0.lock().unwrap().clone();
+    let mapped = buffer.clone();
+    render_device.map_buffer(&buffer.slice(..), MapMode::Read, move |result| {
+        if let Err(error) = result {
+            warn!("Couldn't read screenshot back: {}", error);
+            return;
+        }
+        let data = mapped
+            .slice(..)
+            .get_mapped_range()
+            .chunks(padded_row_bytes)
+            .flat_map(|row| &row[..row_bytes])
+            .copied()
+            .collect();
+        mapped.unmap();
+        // Nothing's listening anymore if the app is shutting down
+        let _ = sender.send(Pixels {
+            width,
+            height,
+            data,
+        });
+    });
+}
+
+#[derive(Default)]
+pub struct ScreenshotPlugin;
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = channel();
+        app.insert_resource(PixelsReceiver(Mutex::new(receiver)))
+            .add_system(take_screenshot_system)
+            .add_system(save_screenshot_system);
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .insert_resource(PixelsSender(Mutex::new(sender)))
+            .init_resource::<ExtractedScreenshot>()
+            .add_system(extract_screenshot_system.in_schedule(ExtractSchedule))
+            .add_system(
+                copy_screenshot_system
+                    .in_set(RenderSet::Render)
+                    .after(render_system),
+            );
+    }
+}
@@ -0,0 +1,320 @@
+// Custom steering written in rhai so behaviors can be tried out without
+// recompiling. Drop a `type_<n>.rhai` file with a `steer(me)` function into
+// `scripts/` and every creature of type n gets the force it returns on top of
+// the usual flocking. The folder gets checked every second so scripts can be
+// edited while the sim is running. See `scripts/examples` for a few to copy.
+
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use bevy::{math::Vec3Swizzles, prelude::*, utils::HashMap};
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST, FLOAT, INT};
+
+use crate::{
+    spatial::SpatialQuery, ApplyForceEvent, CreatureType, Direction, FactorInfo, ForceKind,
+    FrozenTypes, SimState, SimTime, SystemStages,
+};
+
+pub const SCRIPTS_FOLDER: &str = "scripts";
+/// Seconds between checking the scripts folder for changes
+const RELOAD_INTERVAL: f32 = 1.0;
+/// Keeps an accidental infinite loop from freezing the whole sim
+const MAX_OPERATIONS: u64 = 100_000;
+
+pub struct CreatureScript {
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+    ast: Option<AST>,
+    /// Compile or runtime error. A broken script is skipped until it changes.
+    pub error: Option<String>,
+}
+
+#[derive(Resource)]
+pub struct Scripting {
+    pub enabled: bool,
+    pub scripts: HashMap<CreatureType, CreatureScript>,
+    engine: Engine,
+    since_reload: f32,
+    /// Sim seconds handed to scripts as `time`, so pausing or speeding up the
+    /// sim does the same to anything a script animates with it
+    elapsed: f32,
+}
+
+impl Default for Scripting {
+    fn default() -> Self {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        Scripting {
+            enabled: true,
+            scripts: HashMap::default(),
+            engine,
+            // Load right away instead of waiting a second
+            since_reload: RELOAD_INTERVAL,
+            elapsed: 0.0,
+        }
+    }
+}
+
+/// `scripts/type_2.rhai` -> Type 2
+fn script_creature_type(path: &Path) -> Option<CreatureType> {
+    if path.extension()? != "rhai" {
+        return None;
+    }
+    let index = path.file_stem()?.to_str()?.strip_prefix("type_")?;
+    Some(CreatureType(index.parse().ok()?))
+}
+
+fn script_reload_system(time: Res<Time>, mut scripting: ResMut<Scripting>) {
+    scripting.since_reload += time.raw_delta_seconds();
+    if scripting.since_reload < RELOAD_INTERVAL {
+        return;
+    }
+    scripting.since_reload = 0.0;
+
+    // No folder (or no file system on the web) just means no scripts
+    let found = std::fs::read_dir(SCRIPTS_FOLDER)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let path = entry.path();
+                    let modified = entry.metadata().and_then(|data| data.modified()).ok();
+                    Some((script_creature_type(&path)?, (path, modified)))
+                })
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_default();
+
+    let Scripting {
+        scripts, engine, ..
+    } = &mut *scripting;
+    scripts.retain(|creature_type, _| found.contains_key(creature_type));
+    for (creature_type, (path, modified)) in found {
+        if let Some(script) = scripts.get(&creature_type) {
+            if script.path == path && script.modified == modified {
+                continue;
+            }
+        }
+
+        let compiled = std::fs::read_to_string(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|source| engine.compile(source).map_err(|error| error.to_string()));
+        let (ast, error) = match compiled {
+            Ok(ast) => (Some(ast), None),
+            Err(error) => (None, Some(error)),
+        };
+        match &error {
+            Some(error) => warn!("Couldn't load {}: {}", path.display(), error),
+            None => info!("Loaded {} for {}", path.display(), creature_type),
+        }
+        scripts.insert(
+            creature_type,
+            CreatureScript {
+                path,
+                modified,
+                ast,
+                error,
+            },
+        );
+    }
+}
+
+fn vec2_fields(map: &mut Map, name: &str, value: Vec2) {
+    map.insert(format!("{}_x", name).into(), Dynamic::from_float(value.x));
+    map.insert(format!("{}_y", name).into(), Dynamic::from_float(value.y));
+}
+
+fn as_float(value: &Dynamic) -> Option<f32> {
+    value
+        .as_float()
+        .ok()
+        .or_else(|| value.as_int().ok().map(|int| int as FLOAT))
+}
+
+/// Scripts can return either `[x, y]` or `#{ x: .., y: .. }`.
+fn force_from_dynamic(value: Dynamic) -> Option<Vec2> {
+    if value.is::<Array>() {
+        let array = value.cast::<Array>();
+        return match array.as_slice() {
+            [x, y] => Some(Vec2::new(as_float(x)?, as_float(y)?)),
+            _ => None,
+        };
+    }
+    if value.is::<Map>() {
+        let map = value.cast::<Map>();
+        return Some(Vec2::new(
+            as_float(map.get("x")?)?,
+            as_float(map.get("y")?)?,
+        ));
+    }
+    None
+}
+
+/// Runs each creature's script with a summary of what it can see. The length of
+/// the returned force is how hard it steers, same as the factors for flocking.
+fn scripted_steering_system(
+    sim_time: SimTime,
+    mut scripting: ResMut<Scripting>,
+    factor_info: Res<FactorInfo>,
+    frozen_types: Res<FrozenTypes>,
+    spatial_query: SpatialQuery,
+    creature_query: Query<(Entity, &Transform, &Direction, &CreatureType)>,
+    mut force_writer: EventWriter<ApplyForceEvent>,
+) {
+    scripting.elapsed += sim_time.delta_seconds();
+    let Scripting {
+        enabled,
+        scripts,
+        engine,
+        elapsed,
+        ..
+    } = &mut *scripting;
+    if !*enabled || scripts.values().all(|script| script.ast.is_none()) {
+        return;
+    }
+
+    for (entity, transform, direction, creature_type) in creature_query.iter() {
+        let Some(script) = scripts.get_mut(creature_type) else {
+            continue;
+        };
+        let Some(ast) = &script.ast else {
+            continue;
+        };
+        if frozen_types.contains(creature_type) {
+            continue;
+        }
+        let Some(factors) = factor_info.factors.get(creature_type) else {
+            continue;
+        };
+
+        let position = transform.translation.xy();
+        let mut flock_count = 0;
+        let mut flock_center = Vec2::ZERO;
+        let mut flock_heading = Vec2::ZERO;
+        let mut nearest_predator: Option<(f32, Vec2)> = None;
+        let mut nearest_prey: Option<(f32, Vec2)> = None;
+        for other in spatial_query.get_nearby_entities(position, factors.vision) {
+            if other == entity {
+                continue;
+            }
+            let Ok((_, other_transform, other_direction, other_type)) = creature_query.get(other)
+            else {
+                continue;
+            };
+            let other_position = other_transform.translation.xy();
+            let distance = position.distance(other_position);
+            if distance > factors.vision {
+                continue;
+            }
+
+            let closer = |nearest: Option<(f32, Vec2)>| {
+                nearest.is_none_or(|(nearest_distance, _)| distance < nearest_distance)
+            };
+            if other_type == creature_type {
+                flock_count += 1;
+                flock_center += other_position;
+                flock_heading += other_direction.0;
+            } else if factors.predator_of.contains(other_type) {
+                if closer(nearest_prey) {
+                    nearest_prey = Some((distance, other_position));
+                }
+            } else if factor_info
+                .factors
+                .get(other_type)
+                .is_some_and(|other_factors| other_factors.predator_of.contains(creature_type))
+                && closer(nearest_predator)
+            {
+                nearest_predator = Some((distance, other_position));
+            }
+        }
+
+        let mut me = Map::new();
+        vec2_fields(&mut me, "position", position);
+        vec2_fields(&mut me, "direction", direction.0);
+        me.insert("time".into(), Dynamic::from_float(*elapsed));
+        me.insert("vision".into(), Dynamic::from_float(factors.vision));
+        me.insert(
+            "cohesion_radius".into(),
+            Dynamic::from_float(factors.cohesion_radius),
+        );
+        me.insert(
+            "alignment_radius".into(),
+            Dynamic::from_float(factors.alignment_radius),
+        );
+        me.insert(
+            "separation_radius".into(),
+            Dynamic::from_float(factors.separation_radius),
+        );
+        me.insert("flock_count".into(), Dynamic::from_int(flock_count as INT));
+        // With nobody around the center is just where the creature already is
+        let (center, heading) = if flock_count > 0 {
+            (
+                flock_center / flock_count as f32,
+                flock_heading / flock_count as f32,
+            )
+        } else {
+            (position, direction.0)
+        };
+        vec2_fields(&mut me, "flock_center", center);
+        vec2_fields(&mut me, "flock_heading", heading);
+        me.insert(
+            "has_predator".into(),
+            Dynamic::from_bool(nearest_predator.is_some()),
+        );
+        vec2_fields(
+            &mut me,
+            "predator",
+            nearest_predator.map_or(position, |(_, position)| position),
+        );
+        me.insert(
+            "has_prey".into(),
+            Dynamic::from_bool(nearest_prey.is_some()),
+        );
+        vec2_fields(
+            &mut me,
+            "prey",
+            nearest_prey.map_or(position, |(_, position)| position),
+        );
+
+        let result = engine.call_fn::<Dynamic>(&mut Scope::new(), ast, "steer", (me,));
+        let force = match result {
+            Ok(value) => force_from_dynamic(value)
+                .ok_or_else(|| "steer should return [x, y] or #{ x: .., y: .. }".to_string()),
+            Err(error) => Err(error.to_string()),
+        };
+        match force {
+            Ok(force) => {
+                if force.length_squared() > 0.0 && force.is_finite() {
+                    force_writer.send(ApplyForceEvent(
+                        entity,
+                        force.normalize(),
+                        force.length(),
+                        ForceKind::Scripted,
+                    ));
+                }
+            }
+            Err(error) => {
+                warn!("{} stopped: {}", script.path.display(), error);
+                script.ast = None;
+                script.error = Some(error);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Scripting::default())
+            .add_system(script_reload_system.before(SystemStages::Calculate))
+            .add_system(
+                scripted_steering_system
+                    .in_set(SystemStages::Calculate)
+                    .in_set(OnUpdate(SimState::Running)),
+            );
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::{
+    ecs::system::SystemParam, input::mouse::MouseButtonInput, math::Vec3Swizzles, prelude::*,
+};
+use bevy_egui::EguiContexts;
+
+use crate::{
+    paths::PathEditor, spatial::SpatialQuery, ui::has_ui, CreatureType, Cursor, FactorInfo,
+    MainCamera, SimState, SystemStages,
+};
+
+/// The creature picked with a plain left click. There's only ever one of these.
+#[derive(Debug, Default, Clone, Component)]
+pub struct Selected;
+
+/// Ride along with the selected creature.
+#[derive(Debug, Clone, Resource)]
+pub struct CameraFollow {
+    pub enabled: bool,
+    /// Higher catches up faster. Roughly how many times per second the gap closes.
+    pub smoothing: f32,
+}
+
+impl Default for CameraFollow {
+    fn default() -> Self {
+        CameraFollow {
+            enabled: false,
+            smoothing: 5.0,
+        }
+    }
+}
+
+/// A creature moving further than this in one frame wrapped around the screen.
+/// The camera cuts instead of sweeping across the whole world.
+const FOLLOW_SNAP_DISTANCE: f32 = 200.0;
+
+/// Clicks that land a little outside a creature still count since they're tiny
+const PICK_LEEWAY: f32 = 4.0;
+
+/// Everything it takes to find the creature under the cursor.
+#[derive(SystemParam)]
+pub(crate) struct CreaturePicker<'w, 's> {
+    cursor: Res<'w, Cursor>,
+    spatial_query: SpatialQuery<'w>,
+    pub(crate) factor_info: Res<'w, FactorInfo>,
+    pub(crate) creature_query: Query<'w, 's, (&'static Transform, &'static CreatureType)>,
+}
+
+impl CreaturePicker<'_, '_> {
+    /// The creature under the cursor, if any. Closest wins when they overlap.
+    pub(crate) fn hovered(&self) -> Option<Entity> {
+        let position = self.cursor.position;
+        let largest_size = self
+            .factor_info
+            .factors
+            .values()
+            .map(|factors| factors.size)
+            .fold(0.0, f32::max);
+        self.spatial_query
+            .get_nearby_entities(position, largest_size / 2.0 + PICK_LEEWAY)
+            .into_iter()
+            .filter_map(|entity| {
+                let (transform, creature_type) = self.creature_query.get(entity).ok()?;
+                let distance = transform.translation.xy().distance(position);
+                let size = self.factor_info.factors.get(creature_type)?.size;
+                (distance <= size / 2.0 + PICK_LEEWAY).then_some((entity, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(entity, _)| entity)
+    }
+}
+
+fn select_system(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    creature_picker: CreaturePicker,
+    mut mouse_button_events: EventReader<MouseButtonInput>,
+    selected_query: Query<Entity, With<Selected>>,
+    path_editor: Res<PathEditor>,
+) {
+    for event in mouse_button_events.iter() {
+        // Shift and Ctrl clicks belong to spawning and despawning, Alt to obstacles,
+        // and all of them to a path that's being drawn
+        if path_editor.drawing
+            || event.button != MouseButton::Left
+            || event.state.is_pressed()
+            || keys.any_pressed([
+                KeyCode::LShift,
+                KeyCode::LControl,
+                KeyCode::LAlt,
+                KeyCode::RAlt,
+            ])
+        {
+            continue;
+        }
+
+        // Clicking on nothing keeps the old selection so panning around doesn't lose it
+        let Some(entity) = creature_picker.hovered() else {
+            continue;
+        };
+        for selected_entity in selected_query.iter() {
+            commands.entity(selected_entity).remove::<Selected>();
+        }
+        commands.entity(entity).insert(Selected);
+    }
+}
+
+/// Dying creatures aren't creatures anymore so they can't stay selected either
+fn deselect_dead_system(
+    mut commands: Commands,
+    selected_query: Query<Entity, (With<Selected>, Without<CreatureType>)>,
+) {
+    for entity in selected_query.iter() {
+        commands.entity(entity).remove::<Selected>();
+    }
+}
+
+/// F turns following on and off, unless it's being typed into a text box
+fn toggle_follow_system(
+    keys: Res<Input<KeyCode>>,
+    mut egui_context: EguiContexts,
+    mut camera_follow: ResMut<CameraFollow>,
+) {
+    if keys.just_pressed(KeyCode::F) && !egui_context.ctx_mut().wants_keyboard_input() {
+        camera_follow.enabled = !camera_follow.enabled;
+    }
+}
+
+type FollowedCreature = (With<Selected>, Without<MainCamera>);
+
+fn camera_follow_system(
+    timer: Res<Time>,
+    camera_follow: Res<CameraFollow>,
+    mut last_target: Local<Option<(Entity, Vec2)>>,
+    selected_query: Query<(Entity, &Transform), FollowedCreature>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    if !camera_follow.enabled {
+        *last_target = None;
+        return;
+    }
+    // Losing the selection leaves the camera wherever it ended up
+    let Ok((entity, selected_transform)) = selected_query.get_single() else {
+        *last_target = None;
+        return;
+    };
+    let Ok(mut camera_transform) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    let target = selected_transform.translation.xy();
+    let position = camera_transform.translation.xy();
+    // Switching to a different creature should still glide over to it
+    let wrapped = last_target
+        .replace((entity, target))
+        .is_some_and(|(last_entity, last)| {
+            last_entity == entity && last.distance(target) > FOLLOW_SNAP_DISTANCE
+        });
+    let new_position = if wrapped {
+        target
+    } else {
+        // Frame rate independent easing
+        let t = 1.0 - (-camera_follow.smoothing * timer.delta_seconds()).exp();
+        position.lerp(target, t)
+    };
+    camera_transform.translation = new_position.extend(camera_transform.translation.z);
+}
+
+#[derive(Default)]
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CameraFollow::default())
+            .add_system(select_system.in_set(SystemStages::Spawn))
+            .add_system(toggle_follow_system.run_if(has_ui))
+            .add_system(
+                camera_follow_system
+                    .after(toggle_follow_system)
+                    .after(SystemStages::Act),
+            )
+            .add_system(deselect_dead_system.in_set(OnUpdate(SimState::Running)));
+    }
+}
@@ -0,0 +1,226 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    files::{load_file, save_file},
+    CreatureType, FactorInfo, Factors, InitialPopulations, ReplaceFactors,
+};
+
+pub const SURVIVORS_FILE_NAME: &str = "rusty-boids-survivors.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SurvivingType {
+    pub factors: Factors,
+    pub survivors: usize,
+}
+
+/// The factors of every type at the end of a run along with how many of them made it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SurvivorSnapshot {
+    pub types: Vec<SurvivingType>,
+}
+
+impl SurvivorSnapshot {
+    /// Every type's factors along with how many of it are alive right now
+    pub fn capture<'a>(
+        factor_info: &FactorInfo,
+        creature_types: impl Iterator<Item = &'a CreatureType>,
+    ) -> Self {
+        let populations = creature_types.fold(
+            vec![0; factor_info.factors.len()],
+            |mut populations, &creature_type| {
+                populations[creature_type.0] += 1;
+                populations
+            },
+        );
+
+        SurvivorSnapshot {
+            types: populations
+                .into_iter()
+                .enumerate()
+                .map(|(index, survivors)| SurvivingType {
+                    factors: factor_info.factors[&CreatureType(index)].clone(),
+                    survivors,
+                })
+                .collect(),
+        }
+    }
+
+    /// Keeps only the types that survived, squashing their indices together and
+    /// splitting `total_population` between them by how well they did.
+    pub fn warm_start(
+        &self,
+        total_population: usize,
+    ) -> (HashMap<CreatureType, Factors>, Vec<usize>) {
+        let mut new_indices = HashMap::default();
+        for (old_index, surviving_type) in self.types.iter().enumerate() {
+            if surviving_type.survivors > 0 {
+                new_indices.insert(CreatureType(old_index), CreatureType(new_indices.len()));
+            }
+        }
+
+        let total_survivors = self
+            .types
+            .iter()
+            .map(|surviving_type| surviving_type.survivors)
+            .sum::<usize>()
+            .max(1);
+
+        let mut all_factors = HashMap::default();
+        let mut populations = vec![0; new_indices.len()];
+        for (old_index, surviving_type) in self.types.iter().enumerate() {
+            let Some(&new_type) = new_indices.get(&CreatureType(old_index)) else {
+                continue;
+            };
+
+            let mut factors = surviving_type.factors.clone();
+            factors.predator_of = factors
+                .predator_of
+                .iter()
+                .filter_map(|prey| new_indices.get(prey).copied())
+                .collect::<HashSet<_>>();
+            all_factors.insert(new_type, factors);
+
+            populations[new_type.0] =
+                (total_population * surviving_type.survivors / total_survivors).max(1);
+        }
+
+        (all_factors, populations)
+    }
+}
+
+pub struct SaveSurvivorsEvent;
+
+pub struct WarmStartEvent;
+
+#[derive(Debug, Default, Resource)]
+pub struct SnapshotStatus(pub Option<String>);
+
+fn save_survivors_system(
+    factor_info: Res<FactorInfo>,
+    creature_query: Query<&CreatureType>,
+    mut snapshot_status: ResMut<SnapshotStatus>,
+    mut save_survivors_events: EventReader<SaveSurvivorsEvent>,
+) {
+    if save_survivors_events.iter().count() == 0 {
+        return;
+    }
+
+    let snapshot = SurvivorSnapshot::capture(&factor_info, creature_query.iter());
+
+    snapshot_status.0 = Some(
+        match serde_json::to_vec_pretty(&snapshot)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| save_file(SURVIVORS_FILE_NAME, &contents, "application/json"))
+        {
+            Ok(path) => format!("Saved survivors to {}", path),
+            Err(error) => format!("Couldn't save survivors: {}", error),
+        },
+    );
+}
+
+fn warm_start_system(
+    mut replace_factors: ReplaceFactors,
+    mut snapshot_status: ResMut<SnapshotStatus>,
+    mut warm_start_events: EventReader<WarmStartEvent>,
+    mut initial_populations: ResMut<InitialPopulations>,
+) {
+    if warm_start_events.iter().count() == 0 {
+        return;
+    }
+
+    let snapshot = match load_file(SURVIVORS_FILE_NAME).and_then(|contents| {
+        serde_json::from_slice::<SurvivorSnapshot>(&contents).map_err(|error| error.to_string())
+    }) {
+        Ok(snapshot) => snapshot,
+        Err(error) => {
+            snapshot_status.0 = Some(format!("Couldn't load survivors: {}", error));
+            return;
+        }
+    };
+
+    let total_population = initial_populations.0.iter().sum();
+    let (all_factors, populations) = snapshot.warm_start(total_population);
+    if all_factors.is_empty() {
+        snapshot_status.0 = Some("Nobody survived last time :(".to_string());
+        return;
+    }
+
+    initial_populations.0 = populations;
+    replace_factors.replace(all_factors);
+    snapshot_status.0 = Some("Warm started from the last survivors".to_string());
+}
+
+#[derive(Default)]
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SnapshotStatus::default())
+            .add_event::<SaveSurvivorsEvent>()
+            .add_event::<WarmStartEvent>()
+            .add_system(save_survivors_system)
+            .add_system(warm_start_system.in_base_set(CoreSet::PreUpdate));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn surviving_type(survivors: usize, prey: &[usize]) -> SurvivingType {
+        SurvivingType {
+            factors: Factors {
+                predator_of: prey.iter().map(|&index| CreatureType(index)).collect(),
+                ..Factors::default()
+            },
+            survivors,
+        }
+    }
+
+    #[test]
+    fn warm_start_squashes_out_extinct_types() {
+        let snapshot = SurvivorSnapshot {
+            types: vec![
+                surviving_type(30, &[1, 2]),
+                surviving_type(0, &[]),
+                surviving_type(10, &[0, 1]),
+            ],
+        };
+        let (all_factors, populations) = snapshot.warm_start(100);
+
+        assert_eq!(all_factors.len(), 2);
+        assert_eq!(populations, vec![75, 25]);
+        // Prey that died out is dropped and the rest follows the new indices
+        assert_eq!(
+            all_factors[&CreatureType(0)].predator_of,
+            HashSet::from_iter([CreatureType(1)])
+        );
+        assert_eq!(
+            all_factors[&CreatureType(1)].predator_of,
+            HashSet::from_iter([CreatureType(0)])
+        );
+    }
+
+    #[test]
+    fn warm_start_gives_every_survivor_at_least_one() {
+        let snapshot = SurvivorSnapshot {
+            types: vec![surviving_type(1000, &[]), surviving_type(1, &[])],
+        };
+        let (_, populations) = snapshot.warm_start(10);
+        assert_eq!(populations, vec![9, 1]);
+    }
+
+    #[test]
+    fn warm_start_with_no_survivors_is_empty() {
+        let snapshot = SurvivorSnapshot {
+            types: vec![surviving_type(0, &[]), surviving_type(0, &[])],
+        };
+        let (all_factors, populations) = snapshot.warm_start(100);
+        assert!(all_factors.is_empty());
+        assert!(populations.is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+// A quiet ambient loop with little sounds for creatures dying, spawning, and
+// flocks joining up. The sounds come from wherever the thing happened on the
+// screen. When lots happens at once each sound gets quieter and only the ones
+// closest to the middle of the screen play so a big hunt doesn't turn into noise.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    camera::CameraView,
+    flocks::FlocksMerged,
+    lifecycle::{BoidKilled, BoidSpawned},
+};
+
+/// Most of each sound that can start in one frame
+const MAX_VOICES_PER_FRAME: usize = 2;
+/// Events per second of one kind before that kind starts getting quieter
+const CALM_RATE: f32 = 4.0;
+/// How fast the event rates forget what happened (per second)
+const RATE_DECAY: f32 = 2.0;
+/// How far (in listener units) the edge of the screen is from the listener.
+/// Sounds fall off with distance so this sets how quiet the edges are.
+const SCREEN_EDGE_DISTANCE: f32 = 1.5;
+const EAR_GAP: f32 = 1.0;
+
+#[derive(Debug, Clone, Resource)]
+pub struct SoundSettings {
+    pub muted: bool,
+    pub master_volume: f32,
+    pub ambient_volume: f32,
+    pub effects_volume: f32,
+    pub music_volume: f32,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        SoundSettings {
+            muted: false,
+            master_volume: 0.7,
+            ambient_volume: 0.4,
+            effects_volume: 0.6,
+            music_volume: 0.5,
+        }
+    }
+}
+
+impl SoundSettings {
+    pub(crate) fn volume(&self, volume: f32) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master_volume * volume
+        }
+    }
+}
+
+#[derive(Debug, Resource)]
+struct SoundAssets {
+    ambient: Handle<AudioSource>,
+    kill: Handle<AudioSource>,
+    spawn: Handle<AudioSource>,
+    merge: Handle<AudioSource>,
+}
+
+#[derive(Debug, Default, Resource)]
+struct AmbientSink(Option<Handle<AudioSink>>);
+
+/// Roughly how many of each sound's events happened per second lately.
+#[derive(Debug, Default, Resource)]
+struct EventRates {
+    kill: f32,
+    spawn: f32,
+    merge: f32,
+}
+
+fn setup_sounds_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    settings: Res<SoundSettings>,
+) {
+    let sounds = SoundAssets {
+        ambient: asset_server.load("sounds/ambient.wav"),
+        kill: asset_server.load("sounds/kill.wav"),
+        spawn: asset_server.load("sounds/spawn.wav"),
+        merge: asset_server.load("sounds/merge.wav"),
+    };
+    let ambient = audio.play_with_settings(
+        sounds.ambient.clone(),
+        PlaybackSettings::LOOP.with_volume(settings.volume(settings.ambient_volume)),
+    );
+    // What `play` gives back is weak so it has to be made strong to keep it around
+    commands.insert_resource(AmbientSink(Some(audio_sinks.get_handle(ambient))));
+    commands.insert_resource(sounds);
+}
+
+fn ambient_volume_system(
+    settings: Res<SoundSettings>,
+    ambient_sink: Res<AmbientSink>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    mut applied: Local<bool>,
+) {
+    if settings.is_changed() {
+        *applied = false;
+    }
+    if *applied {
+        return;
+    }
+    // The sink only shows up once the sound has loaded and started playing
+    let Some(sink) = ambient_sink
+        .0
+        .as_ref()
+        .and_then(|handle| audio_sinks.get(handle))
+    else {
+        return;
+    };
+    sink.set_volume(settings.volume(settings.ambient_volume));
+    *applied = true;
+}
+
+struct SoundEvent {
+    position: Vec2,
+    /// Multiplies the effects volume
+    loudness: f32,
+}
+
+/// Plays the events closest to the middle of the screen at most, quieter the
+/// more of them there have been lately.
+fn play_events(
+    audio: &Audio,
+    source: &Handle<AudioSource>,
+    mut events: Vec<SoundEvent>,
+    rate: &mut f32,
+    volume: f32,
+    view: Rect,
+) {
+    *rate += events.len() as f32;
+    if volume <= 0.0 {
+        return;
+    }
+    let half_size = view.half_size();
+    // Way off screen isn't worth hearing
+    events.retain(|event| {
+        ((event.position - view.center()) / half_size)
+            .abs()
+            .max_element()
+            <= 1.5
+    });
+    events.sort_by(|a, b| {
+        let distance = |event: &SoundEvent| event.position.distance_squared(view.center());
+        distance(a).total_cmp(&distance(b))
+    });
+    let crowd_volume = 1.0 / (1.0 + *rate / CALM_RATE).sqrt();
+    for event in events.into_iter().take(MAX_VOICES_PER_FRAME) {
+        let emitter = (event.position - view.center()) / half_size * SCREEN_EDGE_DISTANCE;
+        audio.play_spatial_with_settings(
+            source.clone(),
+            PlaybackSettings::ONCE.with_volume(volume * event.loudness * crowd_volume),
+            Transform::IDENTITY,
+            EAR_GAP,
+            emitter.extend(0.0),
+        );
+    }
+}
+
+/// Everything that happened this frame worth making a sound for
+#[derive(SystemParam)]
+struct SoundEvents<'w, 's> {
+    killed_reader: EventReader<'w, 's, BoidKilled>,
+    spawned_reader: EventReader<'w, 's, BoidSpawned>,
+    merged_reader: EventReader<'w, 's, FlocksMerged>,
+}
+
+fn event_sounds_system(
+    time: Res<Time>,
+    audio: Res<Audio>,
+    settings: Res<SoundSettings>,
+    sounds: Option<Res<SoundAssets>>,
+    mut rates: ResMut<EventRates>,
+    sound_events: SoundEvents,
+    camera_view: CameraView,
+) {
+    let SoundEvents {
+        mut killed_reader,
+        mut spawned_reader,
+        mut merged_reader,
+    } = sound_events;
+    let decay = (-RATE_DECAY * time.raw_delta_seconds()).exp();
+    rates.kill *= decay;
+    rates.spawn *= decay;
+    rates.merge *= decay;
+
+    let kills = killed_reader
+        .iter()
+        .map(|killed| SoundEvent {
+            position: killed.position,
+            loudness: 1.0,
+        })
+        .collect::<Vec<_>>();
+    let spawns = spawned_reader
+        .iter()
+        .map(|spawned| SoundEvent {
+            position: spawned.position,
+            loudness: 0.5,
+        })
+        .collect::<Vec<_>>();
+    // Bigger flocks make a bigger whoosh
+    let merges = merged_reader
+        .iter()
+        .map(|merged| SoundEvent {
+            position: merged.position,
+            loudness: (merged.size as f32).log10().clamp(1.0, 3.0) / 2.0,
+        })
+        .collect::<Vec<_>>();
+
+    let (Some(sounds), Some(view)) = (sounds, camera_view.visible_area()) else {
+        return;
+    };
+    let volume = settings.volume(settings.effects_volume);
+    play_events(&audio, &sounds.kill, kills, &mut rates.kill, volume, view);
+    play_events(
+        &audio,
+        &sounds.spawn,
+        spawns,
+        &mut rates.spawn,
+        volume,
+        view,
+    );
+    play_events(
+        &audio,
+        &sounds.merge,
+        merges,
+        &mut rates.merge,
+        volume,
+        view,
+    );
+}
+
+#[derive(Default)]
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SoundSettings::default())
+            .insert_resource(AmbientSink::default())
+            .insert_resource(EventRates::default())
+            .add_startup_system(setup_sounds_system)
+            .add_system(ambient_volume_system)
+            .add_system(event_sounds_system.in_base_set(CoreSet::PostUpdate));
+    }
+}
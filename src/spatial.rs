@@ -0,0 +1,404 @@
+// Neighbor lookups go through `SpatialIndex` so the structure behind them can
+// be swapped at runtime. The hash grid is great for dense swarms in a small
+// world but a quadtree doesn't care how big or empty the world is.
+
+use bevy::{
+    ecs::system::SystemParam,
+    math::Vec3Swizzles,
+    prelude::*,
+    utils::{HashMap, Instant},
+};
+use rand::{prelude::*, rngs::StdRng};
+
+use crate::{
+    ChunkResolution, CreatureType, FactorInfo, Factors, HashGrid, SystemStages, WorldBounds,
+};
+
+pub(crate) trait SpatialIndex: Send + Sync {
+    /// Everything that might be within `radius` of `position`. This can include
+    /// entities a bit farther away (or already dead) so callers still need to check.
+    fn get_nearby_entities(&self, position: Vec2, radius: f32) -> Vec<Entity>;
+}
+
+/// Which structure answers neighbor queries. The hash grid is always kept up to
+/// date anyways since the grid overlay and colors are based on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Resource)]
+pub enum SpatialIndexKind {
+    #[default]
+    HashGrid,
+    Quadtree,
+}
+
+impl SpatialIndexKind {
+    pub const ALL: [SpatialIndexKind; 2] = [SpatialIndexKind::HashGrid, SpatialIndexKind::Quadtree];
+}
+
+impl std::fmt::Display for SpatialIndexKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SpatialIndexKind::HashGrid => write!(f, "Hash Grid"),
+            SpatialIndexKind::Quadtree => write!(f, "Quadtree"),
+        }
+    }
+}
+
+/// Leaves split once they hold more than this
+const NODE_CAPACITY: usize = 8;
+/// Stops creatures stacked on the exact same spot from splitting forever
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug)]
+struct QuadNode {
+    min: Vec2,
+    max: Vec2,
+    points: Vec<(Entity, Vec2)>,
+    /// Bottom left, bottom right, top left, top right
+    children: Option<[usize; 4]>,
+}
+
+impl QuadNode {
+    fn new(min: Vec2, max: Vec2) -> Self {
+        QuadNode {
+            min,
+            max,
+            points: vec![],
+            children: None,
+        }
+    }
+
+    fn quadrant(&self, position: Vec2) -> usize {
+        let center = (self.min + self.max) / 2.0;
+        (position.x >= center.x) as usize + 2 * (position.y >= center.y) as usize
+    }
+
+    fn overlaps(&self, min: Vec2, max: Vec2) -> bool {
+        self.min.x <= max.x && self.max.x >= min.x && self.min.y <= max.y && self.max.y >= min.y
+    }
+}
+
+/// Rebuilt from scratch every frame. Moving things around in place isn't worth
+/// it when nearly every creature changes position each tick.
+#[derive(Debug, Default, Resource)]
+pub(crate) struct Quadtree {
+    nodes: Vec<QuadNode>,
+}
+
+impl Quadtree {
+    pub(crate) fn rebuild(&mut self, points: &[(Entity, Vec2)]) {
+        self.nodes.clear();
+        if points.is_empty() {
+            return;
+        }
+
+        let (min, max) = points.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), (_, position)| (min.min(*position), max.max(*position)),
+        );
+        self.nodes.push(QuadNode::new(min, max));
+        for &(entity, position) in points {
+            self.insert(entity, position);
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    fn insert(&mut self, entity: Entity, position: Vec2) {
+        let mut index = 0;
+        let mut depth = 0;
+        while let Some(children) = self.nodes[index].children {
+            index = children[self.nodes[index].quadrant(position)];
+            depth += 1;
+        }
+
+        self.nodes[index].points.push((entity, position));
+        if self.nodes[index].points.len() > NODE_CAPACITY && depth < MAX_DEPTH {
+            self.split(index);
+        }
+    }
+
+    fn split(&mut self, index: usize) {
+        let (min, max) = (self.nodes[index].min, self.nodes[index].max);
+        let center = (min + max) / 2.0;
+        let first_child = self.nodes.len();
+        for quadrant in 0..4 {
+            let (min_x, max_x) = if quadrant & 1 == 0 {
+                (min.x, center.x)
+            } else {
+                (center.x, max.x)
+            };
+            let (min_y, max_y) = if quadrant & 2 == 0 {
+                (min.y, center.y)
+            } else {
+                (center.y, max.y)
+            };
+            self.nodes.push(QuadNode::new(
+                Vec2::new(min_x, min_y),
+                Vec2::new(max_x, max_y),
+            ));
+        }
+
+        let children = [
+            first_child,
+            first_child + 1,
+            first_child + 2,
+            first_child + 3,
+        ];
+        let points = std::mem::take(&mut self.nodes[index].points);
+        for (entity, position) in points {
+            let quadrant = self.nodes[index].quadrant(position);
+            self.nodes[children[quadrant]]
+                .points
+                .push((entity, position));
+        }
+        self.nodes[index].children = Some(children);
+    }
+}
+
+impl SpatialIndex for Quadtree {
+    fn get_nearby_entities(&self, position: Vec2, radius: f32) -> Vec<Entity> {
+        let mut result = vec![];
+        if self.nodes.is_empty() {
+            return result;
+        }
+
+        let min = position - Vec2::splat(radius);
+        let max = position + Vec2::splat(radius);
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.overlaps(min, max) {
+                continue;
+            }
+            match node.children {
+                Some(children) => stack.extend(children),
+                None => result.extend(
+                    node.points
+                        .iter()
+                        .filter(|(_, point)| point.cmpge(min).all() && point.cmple(max).all())
+                        .map(|(entity, _)| *entity),
+                ),
+            }
+        }
+        result
+    }
+}
+
+/// Whichever spatial index is currently picked.
+#[derive(SystemParam)]
+pub(crate) struct SpatialQuery<'w> {
+    kind: Res<'w, SpatialIndexKind>,
+    hash_grid: Res<'w, HashGrid>,
+    quadtree: Res<'w, Quadtree>,
+}
+
+impl SpatialQuery<'_> {
+    pub(crate) fn index(&self) -> &dyn SpatialIndex {
+        match *self.kind {
+            SpatialIndexKind::HashGrid => self.hash_grid.as_ref(),
+            SpatialIndexKind::Quadtree => self.quadtree.as_ref(),
+        }
+    }
+
+    pub(crate) fn get_nearby_entities(&self, position: Vec2, radius: f32) -> Vec<Entity> {
+        self.index().get_nearby_entities(position, radius)
+    }
+}
+
+fn quadtree_update_system(
+    kind: Res<SpatialIndexKind>,
+    mut quadtree: ResMut<Quadtree>,
+    creature_query: Query<(Entity, &Transform), With<CreatureType>>,
+) {
+    if *kind != SpatialIndexKind::Quadtree {
+        // Don't hang on to a stale tree nobody is reading
+        if !quadtree.is_empty() {
+            *quadtree = Quadtree::default();
+        }
+        return;
+    }
+
+    let points = creature_query
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation.xy()))
+        .collect::<Vec<_>>();
+    quadtree.rebuild(&points);
+}
+
+/// How many random points get thrown into the world for each benchmark round
+const BENCHMARK_POPULATIONS: [usize; 4] = [500, 2_000, 8_000, 20_000];
+
+#[derive(Debug, Clone)]
+pub struct BenchmarkResult {
+    pub kind: SpatialIndexKind,
+    pub population: usize,
+    pub build_millis: f32,
+    pub query_millis: f32,
+    /// Neighbors actually within the radius, after the same distance check
+    /// flocking does. Every index has to find exactly as many or it's broken.
+    pub neighbors_found: usize,
+}
+
+/// Results of the last spatial index benchmark.
+#[derive(Debug, Default, Resource)]
+pub struct SpatialBenchmark {
+    pub results: Vec<BenchmarkResult>,
+    /// Creatures per 100x100 area for each population in the world it ran in
+    pub densities: Vec<f32>,
+}
+
+pub struct RunSpatialBenchmarkEvent;
+
+fn time_index(
+    kind: SpatialIndexKind,
+    points: &[(Entity, Vec2)],
+    radius: f32,
+    chunk_size: f32,
+) -> BenchmarkResult {
+    let build_start = Instant::now();
+    let mut hash_grid = HashGrid::new(chunk_size);
+    let mut quadtree = Quadtree::default();
+    let index: &dyn SpatialIndex = match kind {
+        SpatialIndexKind::HashGrid => {
+            for &(entity, position) in points {
+                hash_grid.update_entity(entity, position);
+            }
+            &hash_grid
+        }
+        SpatialIndexKind::Quadtree => {
+            quadtree.rebuild(points);
+            &quadtree
+        }
+    };
+    let build_millis = build_start.elapsed().as_secs_f32() * 1000.0;
+
+    let positions = points.iter().copied().collect::<HashMap<_, _>>();
+    let query_start = Instant::now();
+    let neighbors_found = points
+        .iter()
+        .map(|&(_, position)| {
+            index
+                .get_nearby_entities(position, radius)
+                .into_iter()
+                .filter(|entity| positions[entity].distance_squared(position) <= radius * radius)
+                .count()
+        })
+        .sum();
+    let query_millis = query_start.elapsed().as_secs_f32() * 1000.0;
+
+    BenchmarkResult {
+        kind,
+        population: points.len(),
+        build_millis,
+        query_millis,
+        neighbors_found,
+    }
+}
+
+/// Times building each index and querying around every point in it, the same
+/// thing flocking does every tick.
+fn spatial_benchmark_system(
+    mut benchmark_events: EventReader<RunSpatialBenchmarkEvent>,
+    mut benchmark: ResMut<SpatialBenchmark>,
+    world_bounds: Res<WorldBounds>,
+    factor_info: Res<FactorInfo>,
+    chunk_resolution: Res<ChunkResolution>,
+) {
+    if benchmark_events.iter().count() == 0 {
+        return;
+    }
+
+    let radius = factor_info
+        .factors
+        .values()
+        .map(Factors::reach)
+        .sum::<f32>()
+        / factor_info.factors.len().max(1) as f32;
+    let area = world_bounds.width * world_bounds.height;
+
+    // Same points every time so runs can be compared
+    let mut rng = StdRng::seed_from_u64(0);
+    benchmark.results.clear();
+    benchmark.densities.clear();
+    for population in BENCHMARK_POPULATIONS {
+        let points = (0..population)
+            .map(|index| {
+                let position = Vec2::new(
+                    rng.gen_range(world_bounds.min().x..world_bounds.max().x),
+                    rng.gen_range(world_bounds.min().y..world_bounds.max().y),
+                );
+                (Entity::from_raw(index as u32), position)
+            })
+            .collect::<Vec<_>>();
+        for kind in SpatialIndexKind::ALL {
+            benchmark
+                .results
+                .push(time_index(kind, &points, radius, chunk_resolution.0));
+        }
+        benchmark
+            .densities
+            .push(population as f32 / area * 100.0 * 100.0);
+    }
+}
+
+#[derive(Default)]
+pub struct SpatialPlugin;
+
+impl Plugin for SpatialPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SpatialIndexKind::default())
+            .insert_resource(Quadtree::default())
+            .insert_resource(SpatialBenchmark::default())
+            .add_event::<RunSpatialBenchmarkEvent>()
+            .add_system(quadtree_update_system.in_set(SystemStages::Cache))
+            .add_system(spatial_benchmark_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn within(
+        index: &dyn SpatialIndex,
+        points: &[(Entity, Vec2)],
+        position: Vec2,
+        radius: f32,
+    ) -> Vec<Entity> {
+        let mut found = index
+            .get_nearby_entities(position, radius)
+            .into_iter()
+            .filter(|entity| points[entity.index() as usize].1.distance(position) <= radius)
+            .collect::<Vec<_>>();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn quadtree_and_hash_grid_find_the_same_neighbors() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let points = (0..500)
+            .map(|index| {
+                let position =
+                    Vec2::new(rng.gen_range(-400.0..400.0), rng.gen_range(-300.0..300.0));
+                (Entity::from_raw(index), position)
+            })
+            .collect::<Vec<_>>();
+
+        let mut hash_grid = HashGrid::new(32.0);
+        for &(entity, position) in &points {
+            hash_grid.update_entity(entity, position);
+        }
+        let mut quadtree = Quadtree::default();
+        quadtree.rebuild(&points);
+
+        for radius in [5.0, 40.0, 150.0] {
+            for &(_, position) in &points {
+                let from_grid = within(&hash_grid, &points, position, radius);
+                assert!(!from_grid.is_empty(), "should at least find itself");
+                assert_eq!(from_grid, within(&quadtree, &points, position, radius));
+            }
+        }
+    }
+}
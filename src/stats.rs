@@ -0,0 +1,197 @@
+// Running totals of what's been happening to each creature type, from the
+// lifecycle events. Counts are kept since the last reset (or restart) and for
+// the last minute of sim time, next to the average energy and speed right now.
+
+use std::collections::VecDeque;
+
+use bevy::{math::Vec3Swizzles, prelude::*, utils::HashMap};
+
+use crate::{
+    lifecycle::{BoidKilled, BoidSpawned, DeathCause},
+    CreatureType, Energy, RestartEvent, SimState, SimTime, WorldBounds,
+};
+
+/// Seconds of sim time the per minute counts look back over
+const RECENT_WINDOW: f32 = 60.0;
+/// Seconds of sim time between measuring speeds
+const SPEED_INTERVAL: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatKind {
+    /// Put there by a click, restart, scenario, or snapshot
+    Spawned,
+    Starved,
+    /// Got eaten (counted for the one that got eaten)
+    Eaten,
+    /// Ate something (counted for the one that ate)
+    Kill,
+}
+
+impl StatKind {
+    pub const ALL: [StatKind; 4] = [
+        StatKind::Spawned,
+        StatKind::Starved,
+        StatKind::Eaten,
+        StatKind::Kill,
+    ];
+}
+
+impl std::fmt::Display for StatKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StatKind::Kill => write!(f, "Kills"),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TypeStats {
+    totals: [usize; StatKind::ALL.len()],
+    /// When (in sim seconds) each of the recent ones happened
+    recent: VecDeque<(f32, StatKind)>,
+    pub average_energy: f32,
+    /// World units per second, measured from how far they actually went
+    pub average_speed: f32,
+}
+
+impl TypeStats {
+    pub fn total(&self, kind: StatKind) -> usize {
+        self.totals[kind as usize]
+    }
+
+    /// How many in the last minute of sim time
+    pub fn per_minute(&self, kind: StatKind) -> usize {
+        self.recent
+            .iter()
+            .filter(|(_, recent_kind)| *recent_kind == kind)
+            .count()
+    }
+}
+
+#[derive(Debug, Default, Resource)]
+pub struct SimStats {
+    pub types: HashMap<CreatureType, TypeStats>,
+    /// Sim seconds since the stats were reset
+    pub elapsed: f32,
+}
+
+impl SimStats {
+    pub fn reset(&mut self) {
+        *self = SimStats::default();
+    }
+
+    fn count(&mut self, creature_type: CreatureType, kind: StatKind) {
+        let elapsed = self.elapsed;
+        let stats = self.types.entry(creature_type).or_default();
+        stats.totals[kind as usize] += 1;
+        stats.recent.push_back((elapsed, kind));
+    }
+}
+
+fn stats_events_system(
+    mut stats: ResMut<SimStats>,
+    mut restart_events: EventReader<RestartEvent>,
+    mut spawned_reader: EventReader<BoidSpawned>,
+    mut killed_reader: EventReader<BoidKilled>,
+    creature_query: Query<&CreatureType>,
+) {
+    if restart_events.iter().count() > 0 {
+        stats.reset();
+    }
+    for spawned in spawned_reader.iter() {
+        stats.count(spawned.creature_type, StatKind::Spawned);
+    }
+    for killed in killed_reader.iter() {
+        match killed.cause {
+            DeathCause::Starved => stats.count(killed.creature_type, StatKind::Starved),
+            DeathCause::Eaten { by } => {
+                stats.count(killed.creature_type, StatKind::Eaten);
+                if let Ok(&killer_type) = creature_query.get(by) {
+                    stats.count(killer_type, StatKind::Kill);
+                }
+            }
+            DeathCause::Despawned => {}
+        }
+    }
+}
+
+fn stats_averages_system(
+    sim_time: SimTime,
+    world_bounds: Res<WorldBounds>,
+    mut stats: ResMut<SimStats>,
+    creature_query: Query<(Entity, &CreatureType, &Transform, &Energy)>,
+    mut last_positions: Local<HashMap<Entity, Vec2>>,
+    mut since_speeds: Local<f32>,
+) {
+    let delta_seconds = sim_time.delta_seconds();
+    stats.elapsed += delta_seconds;
+    let elapsed = stats.elapsed;
+    for type_stats in stats.types.values_mut() {
+        while type_stats
+            .recent
+            .front()
+            .is_some_and(|&(at, _)| elapsed - at > RECENT_WINDOW)
+        {
+            type_stats.recent.pop_front();
+        }
+    }
+
+    let mut energies = HashMap::<CreatureType, (f32, usize)>::default();
+    for (_, creature_type, _, energy) in creature_query.iter() {
+        let (sum, count) = energies.entry(*creature_type).or_default();
+        *sum += energy.0;
+        *count += 1;
+    }
+    // Types that haven't had anything happen yet still get averages
+    for creature_type in energies.keys() {
+        stats.types.entry(*creature_type).or_default();
+    }
+    for (creature_type, type_stats) in stats.types.iter_mut() {
+        type_stats.average_energy = energies
+            .get(creature_type)
+            .map_or(0.0, |&(sum, count)| sum / count as f32);
+    }
+
+    *since_speeds += delta_seconds;
+    if *since_speeds < SPEED_INTERVAL {
+        return;
+    }
+    // Going across the edge of the world jumps to the other side so anything
+    // that moved more than half of it wrapped and doesn't count
+    let max_distance = world_bounds.size().min_element() / 2.0;
+    let mut speeds = HashMap::<CreatureType, (f32, usize)>::default();
+    let mut positions = HashMap::default();
+    for (entity, creature_type, transform, _) in creature_query.iter() {
+        let position = transform.translation.xy();
+        positions.insert(entity, position);
+        let Some(last_position) = last_positions.get(&entity) else {
+            continue;
+        };
+        let distance = position.distance(*last_position);
+        if distance > max_distance {
+            continue;
+        }
+        let (sum, count) = speeds.entry(*creature_type).or_default();
+        *sum += distance / *since_speeds;
+        *count += 1;
+    }
+    for (creature_type, type_stats) in stats.types.iter_mut() {
+        type_stats.average_speed = speeds
+            .get(creature_type)
+            .map_or(0.0, |&(sum, count)| sum / count as f32);
+    }
+    *last_positions = positions;
+    *since_speeds = 0.0;
+}
+
+#[derive(Default)]
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SimStats::default())
+            .add_system(stats_events_system.in_base_set(CoreSet::PostUpdate))
+            .add_system(stats_averages_system.in_set(OnUpdate(SimState::Running)));
+    }
+}
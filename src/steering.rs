@@ -0,0 +1,587 @@
+// Flocking is built out of small steering behaviors that each look at what a
+// creature can see and push some forces. Every creature type has its own list
+// of behaviors with a weight for each, so other crates can add their own
+// without forking the flocking loop:
+//
+// app.world
+//     .resource_mut::<SteeringBehaviors>()
+//     .add_for(CreatureType(1), Wander, 0.5);
+
+use std::sync::Arc;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{ApplyForceEvent, CreatureType, FactorInfo, Factors, Features, ForceKind, Neighbor};
+
+/// What a behavior knows about the creature it's steering.
+pub struct SteeringContext<'a> {
+    pub creature: &'a Neighbor,
+    pub factors: &'a Factors,
+    pub factor_info: &'a FactorInfo,
+    pub features: &'a Features,
+}
+
+impl SteeringContext<'_> {
+    /// This creature's own vision, with its variation
+    pub fn vision(&self) -> f32 {
+        self.creature.variation.vision(self.factors)
+    }
+
+    /// This creature's own size, with its variation
+    pub fn size(&self) -> f32 {
+        self.creature.variation.size(self.factors)
+    }
+
+    /// One of the type's flocking radii as this creature sees it
+    pub fn radius(&self, radius: f32) -> f32 {
+        self.creature.variation.radius(radius)
+    }
+
+    pub fn distance_to(&self, neighbor: &Neighbor) -> f32 {
+        self.creature.position.distance(neighbor.position)
+    }
+
+    pub fn is_flockmate(&self, neighbor: &Neighbor) -> bool {
+        neighbor.creature_type == self.creature.creature_type
+    }
+
+    pub fn hunts(&self, neighbor: &Neighbor) -> bool {
+        self.factors.predator_of.contains(&neighbor.creature_type)
+    }
+
+    pub fn is_hunted_by(&self, neighbor: &Neighbor) -> bool {
+        self.factor_info
+            .factors
+            .get(&neighbor.creature_type)
+            .is_some_and(|factors| factors.predator_of.contains(&self.creature.creature_type))
+    }
+
+    /// Where the predator is when it's the only one in sight and there's
+    /// enough of the flock around to gang up on it
+    pub fn mob_target(&self, neighbors: &[Neighbor]) -> Option<Vec2> {
+        if self.factors.mob_threshold == 0 {
+            return None;
+        }
+        let vision = self.vision();
+        let mut predators = neighbors
+            .iter()
+            .filter(|neighbor| self.is_hunted_by(neighbor) && self.distance_to(neighbor) <= vision);
+        let predator = predators.next()?;
+        if predators.next().is_some() {
+            return None;
+        }
+        // Counting itself
+        let group_size = 1 + self.flockmates_within(neighbors, vision).count();
+        self.factors
+            .mobs(group_size, 1)
+            .then_some(predator.position)
+    }
+
+    /// The middle of a group of prey big enough to gang up on this creature,
+    /// if none of the other predators after them are around to help
+    pub fn mobbed_by(&self, neighbors: &[Neighbor]) -> Option<Vec2> {
+        let vision = self.vision();
+        for prey_type in self.factors.predator_of.iter() {
+            let Some(prey_factors) = self.factor_info.factors.get(prey_type) else {
+                continue;
+            };
+            if prey_factors.mob_threshold == 0 {
+                continue;
+            }
+            let mut group = (Vec2::ZERO, 0);
+            // Counting itself
+            let mut predators = 1;
+            for neighbor in neighbors {
+                if self.distance_to(neighbor) > vision {
+                    continue;
+                }
+                if neighbor.creature_type == *prey_type {
+                    group = (group.0 + neighbor.position, group.1 + 1);
+                } else if self
+                    .factor_info
+                    .factors
+                    .get(&neighbor.creature_type)
+                    .is_some_and(|factors| factors.predator_of.contains(prey_type))
+                {
+                    predators += 1;
+                }
+            }
+            if prey_factors.mobs(group.1, predators) {
+                return Some(group.0 / group.1 as f32);
+            }
+        }
+        None
+    }
+
+    /// Same type neighbors within `radius`
+    pub fn flockmates_within<'a>(
+        &'a self,
+        neighbors: &'a [Neighbor],
+        radius: f32,
+    ) -> impl Iterator<Item = &'a Neighbor> + 'a {
+        neighbors
+            .iter()
+            .filter(move |neighbor| self.is_flockmate(neighbor))
+            .filter(move |neighbor| self.distance_to(neighbor) <= radius)
+    }
+}
+
+pub trait SteeringBehavior: Send + Sync + 'static {
+    /// What the forces show up as in the overlays and timeline
+    fn kind(&self) -> ForceKind {
+        ForceKind::Custom
+    }
+
+    /// Pushes a `(direction, strength)` for every force this wants to apply.
+    /// `neighbors` is everything within the creature's reach (its vision, flocking
+    /// radii, or twice its size, whichever's farthest) and never includes the
+    /// creature itself.
+    fn steer(
+        &self,
+        context: &SteeringContext,
+        neighbors: &[Neighbor],
+        forces: &mut Vec<(Vec2, f32)>,
+    );
+}
+
+/// Steer towards the middle of the flock.
+pub struct Cohesion;
+
+impl SteeringBehavior for Cohesion {
+    fn kind(&self) -> ForceKind {
+        ForceKind::Cohesion
+    }
+
+    fn steer(
+        &self,
+        context: &SteeringContext,
+        neighbors: &[Neighbor],
+        forces: &mut Vec<(Vec2, f32)>,
+    ) {
+        if !context.features.flocking {
+            return;
+        }
+        let (sum, count) = context
+            .flockmates_within(neighbors, context.radius(context.factors.cohesion_radius))
+            .fold((Vec2::ZERO, 0), |(sum, count), neighbor| {
+                (sum + neighbor.position, count + 1)
+            });
+        if count > 0 {
+            let average_position = sum / count as f32;
+            forces.push((
+                (average_position - context.creature.position).normalize(),
+                context.factors.cohesion * context.creature.personality.cohesion_scale(),
+            ));
+        }
+    }
+}
+
+/// Steer the same way as the flock.
+pub struct Alignment;
+
+impl SteeringBehavior for Alignment {
+    fn kind(&self) -> ForceKind {
+        ForceKind::Alignment
+    }
+
+    fn steer(
+        &self,
+        context: &SteeringContext,
+        neighbors: &[Neighbor],
+        forces: &mut Vec<(Vec2, f32)>,
+    ) {
+        if !context.features.flocking {
+            return;
+        }
+        let (sum, count) = context
+            .flockmates_within(neighbors, context.radius(context.factors.alignment_radius))
+            .fold((Vec2::ZERO, 0), |(sum, count), neighbor| {
+                (sum + neighbor.direction, count + 1)
+            });
+        if count > 0 {
+            let average_direction = sum / count as f32;
+            forces.push((average_direction.normalize(), context.factors.alignment));
+        }
+    }
+}
+
+/// Steer away from the flockmates that are getting a little too close.
+pub struct Separation;
+
+impl SteeringBehavior for Separation {
+    fn kind(&self) -> ForceKind {
+        ForceKind::Separation
+    }
+
+    fn steer(
+        &self,
+        context: &SteeringContext,
+        neighbors: &[Neighbor],
+        forces: &mut Vec<(Vec2, f32)>,
+    ) {
+        if !context.features.flocking {
+            return;
+        }
+        let (sum, count) = context
+            .flockmates_within(neighbors, context.radius(context.factors.separation_radius))
+            .fold((Vec2::ZERO, 0), |(sum, count), neighbor| {
+                (sum + neighbor.position, count + 1)
+            });
+        if count > 0 {
+            let average_close_position = sum / count as f32;
+            forces.push((
+                (context.creature.position - average_close_position).normalize(),
+                context.factors.separation,
+            ));
+        }
+    }
+}
+
+/// Get away from every flockmate that's basically touching.
+pub struct CollisionAvoidance;
+
+impl SteeringBehavior for CollisionAvoidance {
+    fn kind(&self) -> ForceKind {
+        ForceKind::CollisionAvoidance
+    }
+
+    fn steer(
+        &self,
+        context: &SteeringContext,
+        neighbors: &[Neighbor],
+        forces: &mut Vec<(Vec2, f32)>,
+    ) {
+        if !context.features.flocking {
+            return;
+        }
+        for neighbor in context.flockmates_within(neighbors, context.size() * 2.0) {
+            forces.push((
+                (context.creature.position - neighbor.position).normalize(),
+                context.factors.collision_avoidance,
+            ));
+        }
+    }
+}
+
+/// How far off to the side of the prey pack hunters aim, as a fraction of how
+/// far away it still is. They close in on it as they get there.
+const FLANK_SPREAD: f32 = 0.5;
+
+/// Go after the closest prey. Pack hunters all go after the prey closest to
+/// the middle of their pack instead and come at it from the sides.
+pub struct Chase;
+
+impl SteeringBehavior for Chase {
+    fn kind(&self) -> ForceKind {
+        ForceKind::Chase
+    }
+
+    fn steer(
+        &self,
+        context: &SteeringContext,
+        neighbors: &[Neighbor],
+        forces: &mut Vec<(Vec2, f32)>,
+    ) {
+        if !context.features.chasing
+            || context.creature.satiated
+            || context.mobbed_by(neighbors).is_some()
+        {
+            return;
+        }
+        let position = context.creature.position;
+        let vision = context.vision();
+        let prey = neighbors.iter().filter(|neighbor| {
+            !context.is_flockmate(neighbor)
+                && context.hunts(neighbor)
+                && context.distance_to(neighbor) <= vision
+        });
+
+        // Hungry packmates it can see, counting itself
+        let (pack_sum, pack_size) = if context.factors.pack_hunter {
+            context
+                .flockmates_within(neighbors, vision)
+                .filter(|packmate| !packmate.satiated)
+                .fold((position, 1), |(sum, count), packmate| {
+                    (sum + packmate.position, count + 1)
+                })
+        } else {
+            (position, 1)
+        };
+        if pack_size == 1 {
+            let closest_prey = prey
+                .map(|neighbor| (context.distance_to(neighbor), neighbor.position))
+                .min_by(|(a, _), (b, _)| a.total_cmp(b));
+            if let Some((_, prey_position)) = closest_prey {
+                forces.push((
+                    (prey_position - position).normalize(),
+                    context.factors.chase,
+                ));
+            }
+            return;
+        }
+
+        // Everyone in the pack picks about the same prey this way
+        let pack_center = pack_sum / pack_size as f32;
+        let Some(target) = prey
+            .map(|neighbor| neighbor.position)
+            .min_by(|a, b| a.distance(pack_center).total_cmp(&b.distance(pack_center)))
+        else {
+            return;
+        };
+        // Whichever side of the pack it's on is the side it comes in from
+        let across = (target - pack_center).perp().normalize_or_zero();
+        let side = ((position - pack_center).dot(across) / vision).clamp(-1.0, 1.0);
+        let aim = target + across * side * position.distance(target) * FLANK_SPREAD;
+        forces.push(((aim - position).normalize_or_zero(), context.factors.chase));
+    }
+}
+
+/// Run from every predator in sight.
+pub struct Flee;
+
+impl SteeringBehavior for Flee {
+    fn kind(&self) -> ForceKind {
+        ForceKind::Scare
+    }
+
+    fn steer(
+        &self,
+        context: &SteeringContext,
+        neighbors: &[Neighbor],
+        forces: &mut Vec<(Vec2, f32)>,
+    ) {
+        if !context.features.running || context.mob_target(neighbors).is_some() {
+            return;
+        }
+        let scare = context.factors.scare * context.creature.personality.scare_scale();
+        let mut sees_predator = false;
+        for neighbor in neighbors {
+            if context.is_flockmate(neighbor)
+                || !context.is_hunted_by(neighbor)
+                || context.distance_to(neighbor)
+                    > context.vision() * context.creature.personality.flee_range_scale()
+            {
+                continue;
+            }
+            sees_predator = true;
+            let away = (context.creature.position - neighbor.position).normalize();
+            forces.push((
+                context.factors.flee_direction(
+                    away,
+                    context.distance_to(neighbor),
+                    context.creature.evasion_phase,
+                ),
+                scare,
+            ));
+        }
+        // Out of sight isn't out of mind just yet
+        if let Some(fear) = context.creature.fear.filter(|_| !sees_predator) {
+            forces.push((
+                (context.creature.position - fear.from).normalize_or_zero(),
+                scare * fear.level(),
+            ));
+        }
+    }
+}
+
+/// Gang up on a predator that's alone when there's enough of the flock around
+/// (with the strength it'd otherwise run away with), and back off from a gang
+/// like that as the predator.
+pub struct Mob;
+
+impl SteeringBehavior for Mob {
+    fn kind(&self) -> ForceKind {
+        ForceKind::Mob
+    }
+
+    fn steer(
+        &self,
+        context: &SteeringContext,
+        neighbors: &[Neighbor],
+        forces: &mut Vec<(Vec2, f32)>,
+    ) {
+        let position = context.creature.position;
+        if context.features.running {
+            if let Some(predator_position) = context.mob_target(neighbors) {
+                forces.push((
+                    (predator_position - position).normalize(),
+                    context.factors.scare * context.creature.personality.scare_scale(),
+                ));
+            }
+        }
+        if context.features.chasing {
+            if let Some(mob_position) = context.mobbed_by(neighbors) {
+                forces.push(((position - mob_position).normalize(), context.factors.chase));
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct WeightedBehavior {
+    pub behavior: Arc<dyn SteeringBehavior>,
+    /// Multiplies the strength of every force the behavior pushes
+    pub weight: f32,
+}
+
+impl WeightedBehavior {
+    pub fn new(behavior: impl SteeringBehavior, weight: f32) -> Self {
+        WeightedBehavior {
+            behavior: Arc::new(behavior),
+            weight,
+        }
+    }
+}
+
+/// Which behaviors steer each creature type. Types without their own list use
+/// the shared one, which starts out as the built in flocking behaviors.
+#[derive(Clone, Resource)]
+pub struct SteeringBehaviors {
+    shared: Vec<WeightedBehavior>,
+    per_type: HashMap<CreatureType, Vec<WeightedBehavior>>,
+    /// Whether anything's different from the built in behaviors, which the
+    /// packed flocking path assumes
+    customized: bool,
+}
+
+impl Default for SteeringBehaviors {
+    fn default() -> Self {
+        SteeringBehaviors {
+            shared: SteeringBehaviors::built_in(),
+            per_type: HashMap::default(),
+            customized: false,
+        }
+    }
+}
+
+impl SteeringBehaviors {
+    pub fn built_in() -> Vec<WeightedBehavior> {
+        vec![
+            WeightedBehavior::new(Cohesion, 1.0),
+            WeightedBehavior::new(Alignment, 1.0),
+            WeightedBehavior::new(Separation, 1.0),
+            WeightedBehavior::new(CollisionAvoidance, 1.0),
+            WeightedBehavior::new(Chase, 1.0),
+            WeightedBehavior::new(Flee, 1.0),
+            WeightedBehavior::new(Mob, 1.0),
+        ]
+    }
+
+    pub fn for_type(&self, creature_type: CreatureType) -> &[WeightedBehavior] {
+        self.per_type
+            .get(&creature_type)
+            .unwrap_or(&self.shared)
+            .as_slice()
+    }
+
+    /// Adds a behavior to every type
+    pub fn add(&mut self, behavior: impl SteeringBehavior, weight: f32) -> &mut Self {
+        let weighted = WeightedBehavior::new(behavior, weight);
+        self.shared.push(weighted.clone());
+        for behaviors in self.per_type.values_mut() {
+            behaviors.push(weighted.clone());
+        }
+        self.customized = true;
+        self
+    }
+
+    /// Adds a behavior to just one type
+    pub fn add_for(
+        &mut self,
+        creature_type: CreatureType,
+        behavior: impl SteeringBehavior,
+        weight: f32,
+    ) -> &mut Self {
+        self.type_mut(creature_type)
+            .push(WeightedBehavior::new(behavior, weight));
+        self
+    }
+
+    /// Replaces everything steering a type, like to take away a built in behavior
+    pub fn set_for(
+        &mut self,
+        creature_type: CreatureType,
+        behaviors: Vec<WeightedBehavior>,
+    ) -> &mut Self {
+        *self.type_mut(creature_type) = behaviors;
+        self
+    }
+
+    /// Reweighs every behavior of a type that pushes `kind` forces
+    pub fn set_weight(
+        &mut self,
+        creature_type: CreatureType,
+        kind: ForceKind,
+        weight: f32,
+    ) -> &mut Self {
+        for weighted in self.type_mut(creature_type).iter_mut() {
+            if weighted.behavior.kind() == kind {
+                weighted.weight = weight;
+            }
+        }
+        self
+    }
+
+    fn type_mut(&mut self, creature_type: CreatureType) -> &mut Vec<WeightedBehavior> {
+        self.customized = true;
+        self.per_type
+            .entry(creature_type)
+            .or_insert_with(|| self.shared.clone())
+    }
+
+    pub(crate) fn is_built_in(&self) -> bool {
+        !self.customized
+    }
+}
+
+/// Reused between creatures so the flocking loop only allocates while these grow.
+#[derive(Default)]
+pub(crate) struct SteeringScratch {
+    nearby: Vec<Neighbor>,
+    forces: Vec<(Vec2, f32)>,
+}
+
+/// Everything besides the neighbors that decides how creatures steer.
+#[derive(Clone, Copy)]
+pub(crate) struct SteeringRules<'a> {
+    pub(crate) behaviors: &'a SteeringBehaviors,
+    pub(crate) factor_info: &'a FactorInfo,
+    pub(crate) features: &'a Features,
+}
+
+/// Runs every behavior of the creature's type over its neighbors.
+pub(crate) fn steering_forces(
+    creature: &Neighbor,
+    neighbors: impl Iterator<Item = Neighbor>,
+    rules: SteeringRules,
+    scratch: &mut SteeringScratch,
+    events: &mut Vec<ApplyForceEvent>,
+) {
+    let SteeringRules {
+        behaviors,
+        factor_info,
+        features,
+    } = rules;
+    let factors = factor_info.factors.get(&creature.creature_type).unwrap();
+    let reach = creature.variation.reach(factors);
+    let SteeringScratch { nearby, forces } = scratch;
+    nearby.clear();
+    nearby.extend(neighbors.filter(|neighbor| {
+        neighbor.entity != creature.entity && creature.position.distance(neighbor.position) <= reach
+    }));
+
+    let context = SteeringContext {
+        creature,
+        factors,
+        factor_info,
+        features,
+    };
+    for weighted in behaviors.for_type(creature.creature_type) {
+        forces.clear();
+        weighted.behavior.steer(&context, nearby, forces);
+        let kind = weighted.behavior.kind();
+        events.extend(forces.drain(..).map(|(direction, strength)| {
+            ApplyForceEvent(creature.entity, direction, strength * weighted.weight, kind)
+        }));
+    }
+}
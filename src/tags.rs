@@ -0,0 +1,81 @@
+// Naming a creature makes it notable: it gets its name floating over it, the
+// despawn brush leaves it alone, and the Event Log keeps tabs on what it gets
+// up to (see `event_log_system`).
+
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+const LABEL_FONT_SIZE: f32 = 14.0;
+/// How far above the creature the name sits
+const LABEL_OFFSET: f32 = 12.0;
+const LABEL_COLOR: Color = Color::WHITE;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component)]
+pub struct Tagged(pub String);
+
+/// The floating name for a tagged creature
+#[derive(Debug, Clone, Copy, Component)]
+pub struct TagLabel {
+    pub target: Entity,
+}
+
+fn new_label_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    tagged_query: Query<(Entity, &Tagged, &Transform), Added<Tagged>>,
+) {
+    for (entity, tagged, transform) in tagged_query.iter() {
+        let style = TextStyle {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size: LABEL_FONT_SIZE,
+            color: LABEL_COLOR,
+        };
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(tagged.0.clone(), style)
+                    .with_alignment(TextAlignment::Center),
+                transform: Transform::from_translation(
+                    (transform.translation.xy() + Vec2::Y * LABEL_OFFSET).extend(10.0),
+                ),
+                ..default()
+            },
+            TagLabel { target: entity },
+        ));
+    }
+}
+
+/// Keeps labels over their creatures and cleans them up once the creature is
+/// gone or isn't tagged anymore
+fn label_system(
+    mut commands: Commands,
+    tagged_query: Query<(&Tagged, &Transform), Without<TagLabel>>,
+    mut label_query: Query<(Entity, &TagLabel, &mut Transform, &mut Text)>,
+) {
+    for (entity, label, mut transform, mut text) in label_query.iter_mut() {
+        let Ok((tagged, target_transform)) = tagged_query.get(label.target) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        let position = target_transform.translation.xy() + Vec2::Y * LABEL_OFFSET;
+        if transform.translation.xy() != position {
+            transform.translation = position.extend(transform.translation.z);
+        }
+        // Renaming just swaps the text
+        if text.sections[0].value != tagged.0 {
+            text.sections[0].value = tagged.0.clone();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TagsPlugin;
+
+impl Plugin for TagsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Tagged>().add_systems(
+            (new_label_system, label_system)
+                .chain()
+                .in_base_set(CoreSet::PostUpdate),
+        );
+    }
+}
@@ -0,0 +1,212 @@
+// A temperature over the whole world, colder to the south and warmer to the
+// north with some lumpy warm and cold spots mixed in. Every type has a range it's
+// comfortable in and anything outside of it burns energy faster and gets nudged
+// towards somewhere nicer.
+
+use std::f32::consts::TAU;
+
+use bevy::{ecs::system::SystemParam, math::Vec3Swizzles, prelude::*};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ApplyForceEvent, CreatureType, EnergyChangeEvent, FactorInfo, Features, ForceKind, FrozenTypes,
+    RestartEvent, SimRng, SimState, SimTime, SystemStages, WorldBounds,
+};
+
+/// How far to look on each side when working out which way is warmer
+const GRADIENT_STEP: f32 = 5.0;
+/// Degrees out of range it takes to get pushed with the full comfort steering
+const FULL_DISCOMFORT: f32 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemperatureSettings {
+    pub enabled: bool,
+    /// Degrees at the coldest spot
+    pub cold: f32,
+    /// Degrees at the hottest spot
+    pub hot: f32,
+    /// 0 is a smooth south to north gradient, 1 is all warm and cold spots
+    pub noisiness: f32,
+    /// World units across a warm or cold spot
+    pub noise_scale: f32,
+    /// Extra energy burned per second for each degree out of range
+    pub discomfort_drain: f32,
+    /// How hard uncomfortable creatures steer towards their range
+    pub comfort_steering: f32,
+}
+
+impl Default for TemperatureSettings {
+    fn default() -> Self {
+        TemperatureSettings {
+            enabled: false,
+            cold: 0.0,
+            hot: 40.0,
+            noisiness: 0.3,
+            noise_scale: 300.0,
+            discomfort_drain: 0.2,
+            comfort_steering: 2.0,
+        }
+    }
+}
+
+/// The waves the warm and cold spots come from. Rolled again on restart.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct TemperatureField {
+    /// Direction and phase of each wave
+    waves: [(Vec2, f32); 3],
+}
+
+impl TemperatureField {
+    fn roll(rng: &mut impl Rng) -> Self {
+        let mut waves = [(Vec2::X, 0.0); 3];
+        for wave in waves.iter_mut() {
+            *wave = (
+                Vec2::from_angle(rng.gen_range(0.0..TAU)),
+                rng.gen_range(0.0..TAU),
+            );
+        }
+        TemperatureField { waves }
+    }
+
+    /// 0 at the coldest up to 1 at the hottest
+    pub fn warmth(
+        &self,
+        settings: &TemperatureSettings,
+        bounds: &WorldBounds,
+        position: Vec2,
+    ) -> f32 {
+        let gradient = ((position.y - bounds.min().y) / bounds.size().y).clamp(0.0, 1.0);
+        let scale = settings.noise_scale.max(1.0);
+        let noise = self
+            .waves
+            .iter()
+            .map(|(direction, phase)| (direction.dot(position) * TAU / scale + phase).sin())
+            .sum::<f32>()
+            / self.waves.len() as f32;
+        let noise = (noise + 1.0) / 2.0;
+        gradient + (noise - gradient) * settings.noisiness.clamp(0.0, 1.0)
+    }
+
+    /// In degrees
+    pub fn at(&self, settings: &TemperatureSettings, bounds: &WorldBounds, position: Vec2) -> f32 {
+        let warmth = self.warmth(settings, bounds, position);
+        settings.cold + (settings.hot - settings.cold) * warmth
+    }
+}
+
+/// Blue for cold through to red for hot.
+pub fn warmth_color(warmth: f32) -> Color {
+    Color::hsl(240.0 * (1.0 - warmth.clamp(0.0, 1.0)), 0.8, 0.5)
+}
+
+fn roll_temperature_system(
+    mut rng: ResMut<SimRng>,
+    mut field: ResMut<TemperatureField>,
+    mut restart_events: EventReader<RestartEvent>,
+    mut rolled: Local<bool>,
+) {
+    let restarted = restart_events.iter().count() > 0;
+    if *rolled && !restarted {
+        return;
+    }
+    *rolled = true;
+    *field = TemperatureField::roll(rng.as_mut());
+}
+
+/// The temperature anywhere in the world
+#[derive(SystemParam)]
+struct Thermometer<'w> {
+    settings: Res<'w, TemperatureSettings>,
+    field: Res<'w, TemperatureField>,
+    world_bounds: Res<'w, WorldBounds>,
+}
+
+impl Thermometer<'_> {
+    fn at(&self, position: Vec2) -> f32 {
+        self.field.at(&self.settings, &self.world_bounds, position)
+    }
+}
+
+/// Being too hot or cold burns energy and sends creatures looking for better
+#[derive(SystemParam)]
+struct Discomfort<'w> {
+    force_writer: EventWriter<'w, ApplyForceEvent>,
+    energy_change_writer: EventWriter<'w, EnergyChangeEvent>,
+}
+
+fn comfort_system(
+    sim_time: SimTime,
+    thermometer: Thermometer,
+    features: Res<Features>,
+    factor_info: Res<FactorInfo>,
+    frozen_types: Res<FrozenTypes>,
+    creature_query: Query<(Entity, &Transform, &CreatureType)>,
+    discomfort: Discomfort,
+) {
+    let settings = &thermometer.settings;
+    let Discomfort {
+        mut force_writer,
+        mut energy_change_writer,
+    } = discomfort;
+    if !settings.enabled {
+        return;
+    }
+    let delta_seconds = sim_time.delta_seconds();
+    for (entity, transform, creature_type) in creature_query.iter() {
+        if frozen_types.contains(creature_type) {
+            continue;
+        }
+        let Some(factors) = factor_info.factors.get(creature_type) else {
+            continue;
+        };
+        let position = transform.translation.xy();
+        let temperature = thermometer.at(position);
+        let (discomfort, towards_warmth) = if temperature < factors.comfort_min {
+            (factors.comfort_min - temperature, 1.0)
+        } else if temperature > factors.comfort_max {
+            (temperature - factors.comfort_max, -1.0)
+        } else {
+            continue;
+        };
+
+        if features.energy_draining {
+            energy_change_writer.send(EnergyChangeEvent(
+                entity,
+                -discomfort * settings.discomfort_drain * delta_seconds,
+            ));
+        }
+
+        let at = |offset: Vec2| thermometer.at(position + offset);
+        let warmer = Vec2::new(
+            at(Vec2::X * GRADIENT_STEP) - at(-Vec2::X * GRADIENT_STEP),
+            at(Vec2::Y * GRADIENT_STEP) - at(-Vec2::Y * GRADIENT_STEP),
+        )
+        .normalize_or_zero();
+        if warmer == Vec2::ZERO {
+            continue;
+        }
+        force_writer.send(ApplyForceEvent(
+            entity,
+            warmer * towards_warmth,
+            settings.comfort_steering * (discomfort / FULL_DISCOMFORT).min(1.0),
+            ForceKind::Comfort,
+        ));
+    }
+}
+
+#[derive(Default)]
+pub struct TemperaturePlugin;
+
+impl Plugin for TemperaturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TemperatureField>()
+            .add_system(roll_temperature_system.in_set(SystemStages::Cache))
+            .add_system(
+                comfort_system
+                    .in_set(SystemStages::Calculate)
+                    .in_set(OnUpdate(SimState::Running)),
+            );
+    }
+}
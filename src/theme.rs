@@ -0,0 +1,82 @@
+// How the egui windows look. Light mode is easier to read on a projector and
+// the scale makes everything bigger (or smaller) on top of whatever the
+// window's own DPI scaling already is.
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiSettings};
+use serde::{Deserialize, Serialize};
+
+pub const MIN_UI_SCALE: f32 = 0.5;
+pub const MAX_UI_SCALE: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ThemeMode {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl ThemeMode {
+    pub const ALL: [ThemeMode; 2] = [ThemeMode::Dark, ThemeMode::Light];
+}
+
+impl std::fmt::Display for ThemeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiTheme {
+    pub mode: ThemeMode,
+    /// Selected things, checked boxes, slider fills, and links
+    pub accent: [u8; 3],
+    pub scale: f32,
+}
+
+impl Default for UiTheme {
+    fn default() -> Self {
+        UiTheme {
+            mode: ThemeMode::Dark,
+            // egui's own blue
+            accent: [0, 92, 128],
+            scale: 1.0,
+        }
+    }
+}
+
+fn apply_theme_system(
+    theme: Res<UiTheme>,
+    mut egui_context: EguiContexts,
+    mut egui_settings: ResMut<EguiSettings>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+    let mut visuals = match theme.mode {
+        ThemeMode::Dark => egui::Visuals::dark(),
+        ThemeMode::Light => egui::Visuals::light(),
+    };
+    let [r, g, b] = theme.accent;
+    let accent = egui::Color32::from_rgb(r, g, b);
+    visuals.selection.bg_fill = accent;
+    visuals.hyperlink_color = accent;
+    egui_context.ctx_mut().set_visuals(visuals);
+
+    let scale = theme.scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE) as f64;
+    if egui_settings.scale_factor != scale {
+        egui_settings.scale_factor = scale;
+    }
+}
+
+/// Added by the UI plugin. Saved settings can have put a theme in already.
+#[derive(Default)]
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UiTheme>()
+            .add_system(apply_theme_system);
+    }
+}
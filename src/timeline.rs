@@ -0,0 +1,99 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{ApplyForceEvent, CreatureType, ForceKind, SimState, SystemStages};
+
+/// The one creature whose every push and pull gets written down.
+#[derive(Debug, Default, Resource)]
+pub struct TrackedCreature(pub Option<Entity>);
+
+#[derive(Debug, Clone)]
+pub struct TimelineEntry {
+    pub tick: u64,
+    /// Summed magnitude per [`ForceKind`], in the same order as [`ForceKind::ALL`].
+    pub forces: [f32; ForceKind::ALL.len()],
+}
+
+#[derive(Debug, Resource)]
+pub struct ForceTimeline {
+    pub capacity: usize,
+    pub entries: VecDeque<TimelineEntry>,
+    tick: u64,
+}
+
+impl Default for ForceTimeline {
+    fn default() -> Self {
+        ForceTimeline {
+            capacity: 300,
+            entries: VecDeque::default(),
+            tick: 0,
+        }
+    }
+}
+
+impl ForceTimeline {
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.tick = 0;
+    }
+}
+
+fn record_timeline_system(
+    mut timeline: ResMut<ForceTimeline>,
+    mut tracked_creature: ResMut<TrackedCreature>,
+    mut force_reader: EventReader<ApplyForceEvent>,
+    creature_query: Query<Entity, With<CreatureType>>,
+) {
+    let Some(tracked_entity) = tracked_creature.0 else {
+        return;
+    };
+    if creature_query.get(tracked_entity).is_err() {
+        tracked_creature.0 = None;
+        return;
+    }
+
+    let mut forces = [0.0; ForceKind::ALL.len()];
+    for ApplyForceEvent(entity, force, factor, kind) in force_reader.iter() {
+        if *entity != tracked_entity {
+            continue;
+        }
+        let index = ForceKind::ALL
+            .iter()
+            .position(|other| other == kind)
+            .unwrap();
+        forces[index] += force.length() * factor;
+    }
+
+    let tick = timeline.tick;
+    timeline.tick += 1;
+    timeline.entries.push_back(TimelineEntry { tick, forces });
+    while timeline.entries.len() > timeline.capacity {
+        timeline.entries.pop_front();
+    }
+}
+
+fn reset_timeline_system(
+    tracked_creature: Res<TrackedCreature>,
+    mut timeline: ResMut<ForceTimeline>,
+) {
+    if tracked_creature.is_changed() {
+        timeline.clear();
+    }
+}
+
+#[derive(Default)]
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TrackedCreature::default())
+            .insert_resource(ForceTimeline::default())
+            .add_system(reset_timeline_system.before(record_timeline_system))
+            .add_system(
+                record_timeline_system
+                    .in_set(SystemStages::Apply)
+                    .in_set(OnUpdate(SimState::Running)),
+            );
+    }
+}
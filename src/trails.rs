@@ -0,0 +1,164 @@
+// Trails are made of plain sprites stretched between the last few positions of
+// each creature. There's no line drawing in Bevy yet and this way they also
+// show up in screenshots.
+
+use std::collections::VecDeque;
+
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{CreatureType, SimState, SystemStages, WorldBounds};
+
+#[derive(Debug, Clone, Resource)]
+pub struct TrailSettings {
+    /// Off by default since every creature gets `length` extra sprites
+    pub enabled: bool,
+    /// How many past positions to remember
+    pub length: usize,
+    pub opacity: f32,
+    pub thickness: f32,
+}
+
+impl Default for TrailSettings {
+    fn default() -> Self {
+        TrailSettings {
+            enabled: false,
+            length: 20,
+            opacity: 0.5,
+            thickness: 1.5,
+        }
+    }
+}
+
+/// Recent positions of a creature, newest first, and the sprites connecting them.
+#[derive(Debug, Default, Component)]
+pub struct Trail {
+    pub points: VecDeque<Vec2>,
+    segments: Vec<Entity>,
+}
+
+#[derive(Debug, Component)]
+struct TrailSegment {
+    owner: Entity,
+}
+
+fn record_trails_system(
+    mut commands: Commands,
+    trail_settings: Res<TrailSettings>,
+    mut creature_query: Query<(Entity, &Transform, Option<&mut Trail>), With<CreatureType>>,
+) {
+    if !trail_settings.enabled {
+        return;
+    }
+
+    for (entity, transform, trail) in creature_query.iter_mut() {
+        let Some(mut trail) = trail else {
+            commands.entity(entity).insert(Trail::default());
+            continue;
+        };
+        trail.points.push_front(transform.translation.xy());
+        trail.points.truncate(trail_settings.length.max(2));
+
+        let wanted_segments = trail.points.len() - 1;
+        while trail.segments.len() > wanted_segments {
+            if let Some(segment) = trail.segments.pop() {
+                commands.entity(segment).despawn();
+            }
+        }
+        while trail.segments.len() < wanted_segments {
+            let segment = commands
+                .spawn(SpriteBundle {
+                    // Shown once it's been placed next frame
+                    visibility: Visibility::Hidden,
+                    ..SpriteBundle::default()
+                })
+                .insert(TrailSegment { owner: entity })
+                .id();
+            trail.segments.push(segment);
+        }
+    }
+}
+
+type SegmentParts<'a> = (&'a mut Transform, &'a mut Sprite, &'a mut Visibility);
+
+fn render_trails_system(
+    world_bounds: Res<WorldBounds>,
+    trail_settings: Res<TrailSettings>,
+    creature_query: Query<(&Trail, &Sprite), With<CreatureType>>,
+    mut segment_query: Query<SegmentParts, (With<TrailSegment>, Without<CreatureType>)>,
+) {
+    // Anything longer than this jumped across the world when wrapping
+    let max_segment_length = world_bounds.width.min(world_bounds.height) / 2.0;
+
+    for (trail, creature_sprite) in creature_query.iter() {
+        let segment_count = trail.segments.len();
+        for (index, &segment) in trail.segments.iter().enumerate() {
+            let Ok((mut transform, mut sprite, mut visibility)) = segment_query.get_mut(segment)
+            else {
+                continue;
+            };
+            let (Some(&start), Some(&end)) = (trail.points.get(index), trail.points.get(index + 1))
+            else {
+                *visibility = Visibility::Hidden;
+                continue;
+            };
+
+            let offset = end - start;
+            let length = offset.length();
+            if length > max_segment_length {
+                *visibility = Visibility::Hidden;
+                continue;
+            }
+            *visibility = Visibility::Inherited;
+
+            // Older segments fade out
+            let age = index as f32 / segment_count as f32;
+            let mut color = creature_sprite.color;
+            color.set_a(trail_settings.opacity * (1.0 - age));
+            sprite.color = color;
+            sprite.custom_size = Some(Vec2::new(length, trail_settings.thickness));
+
+            // Behind the creatures but in front of the world boundary
+            transform.translation = ((start + end) / 2.0).extend(-0.5);
+            transform.rotation = Quat::from_rotation_z(offset.y.atan2(offset.x));
+        }
+    }
+}
+
+/// Gets rid of segments whose creature died and everything when trails get turned off.
+fn cleanup_trails_system(
+    mut commands: Commands,
+    trail_settings: Res<TrailSettings>,
+    owner_query: Query<(), With<CreatureType>>,
+    trail_query: Query<Entity, With<Trail>>,
+    segment_query: Query<(Entity, &TrailSegment)>,
+) {
+    if !trail_settings.enabled {
+        for entity in trail_query.iter() {
+            commands.entity(entity).remove::<Trail>();
+        }
+    }
+
+    for (entity, segment) in segment_query.iter() {
+        if !trail_settings.enabled || owner_query.get(segment.owner).is_err() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TrailPlugin;
+
+impl Plugin for TrailPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TrailSettings::default()).add_systems(
+            (
+                // Paused creatures would just fill their trail with the same point
+                record_trails_system.in_set(OnUpdate(SimState::Running)),
+                render_trails_system,
+                cleanup_trails_system,
+            )
+                .chain()
+                .after(SystemStages::Act),
+        );
+    }
+}
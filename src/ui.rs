@@ -1,21 +1,157 @@
 use bevy::{
     diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    ecs::system::{SystemParam, SystemState},
+    input::mouse::MouseWheel,
     prelude::*,
     window::{PrimaryWindow, WindowResolution},
 };
 use bevy_egui::{
-    egui::{self, color_picker::color_edit_button_rgb, Rgba},
-    EguiContexts, EguiPlugin,
+    egui::{
+        self,
+        color_picker::color_edit_button_rgb,
+        plot::{Legend, Line, Plot},
+        Rgba,
+    },
+    EguiContext, EguiContexts, EguiPlugin,
 };
+use egui_dock::TabViewer;
+use rand::seq::SliceRandom;
 
 use crate::{
-    boids::{DespawnProperties, Features, SpawnProperties},
-    CreatureType, FactorInfo, Factors, IS_WASM,
+    benchmark::{BenchmarkMode, StartBenchmarkEvent, STAGE_NAMES},
+    camera::CameraControls,
+    collision::CollisionSettings,
+    coloring::{CreatureColorMode, DebugColorMode},
+    comparison::Comparison,
+    data_log::DataLog,
+    death::{DeathAnimation, DeathAnimationSettings},
+    dock::{DockLayout, Panel},
+    duplicate_creature_type,
+    event_log::{EventLog, LogKind},
+    evolution::{EvolutionHistory, TraitKind},
+    exposure::LongExposure,
+    factors_from_json, factors_to_json,
+    flow::{FlowPattern, FlowSettings, LoadFlowImageEvent},
+    food::{FoodPatch, FoodSettings, ReseedFoodEvent},
+    gamepad::{button_name, GamepadBindings, BINDABLE_BUTTONS},
+    goals::Goals,
+    gravity::{GravityWell, NewGravityWell},
+    growth::{Age, BodySize, GrowthSettings},
+    gust::GustProperties,
+    history::{restore_factors, FactorEdit, FactorHistory, HistoryAction},
+    labels::{LabelContent, LabelSettings},
+    margins::MarginSettings,
+    mask::{ClearMaskEvent, LoadMaskEvent, MaskSettings},
+    maze::{GenerateMazeEvent, MazeLayout, MazeSettings},
+    music::MusicIntensity,
+    obstacles::{Obstacle, ObstacleSettings, ObstacleTool},
+    overlays::{DebugOverlays, OverlayPlugin},
+    packed::{FlockingBenchmark, PackedFlocking, RunFlockingBenchmarkEvent},
+    palettes::ColorPalette,
+    paths::{PathEditor, PatrolPaths},
+    persistence::ResetSettingsEvent,
+    personality::Personality,
+    randomize_prey, remove_creature_type,
+    report::{CreateReportEvent, ReportStatus},
+    rgba_u8,
+    rooms::{ClearRoomsEvent, GenerateRoomsEvent, RoomSettings, Rooms},
+    satiation::SatiationSettings,
+    scenarios::{LoadScenarioEvent, Scenarios},
+    scripting::{Scripting, SCRIPTS_FOLDER},
+    selection::{CameraFollow, Selected},
+    smear::MotionSmear,
+    snapshot::{SaveSurvivorsEvent, SnapshotStatus, WarmStartEvent},
+    sound::SoundSettings,
+    spatial::{RunSpatialBenchmarkEvent, SpatialBenchmark, SpatialIndexKind},
+    stats::{SimStats, StatKind, TypeStats},
+    tags::Tagged,
+    temperature::TemperatureSettings,
+    theme::{ThemeMode, ThemePlugin, UiTheme, MAX_UI_SCALE, MIN_UI_SCALE},
+    timeline::{ForceTimeline, TrackedCreature},
+    trails::TrailSettings,
+    weather::{ChangeWeatherEvent, Weather, WeatherKind, WeatherSettings},
+    window_mode::{window_mode_name, window_modes, WindowModeSettings, WINDOW_MODE_KEY},
+    ArenaTiling, ChunkResolution, CreatureType, DespawnProperties, DeterministicMode, Direction,
+    Energy, FactorInfo, Factors, Features, ForceKind, FrozenTypes, HashGrid, LevelOfDetail,
+    MainCamera, NeighborCache, PastedFactors, RestartEvent, SimRng, SimState, SimStep, SimTicks,
+    SpawnProperties, SteeringCombination, TimeScale, WorldBounds, CREATURE_TEXTURES, IS_WASM,
+    MAX_LAYER,
 };
 
 #[derive(Component)]
 struct FPSText;
 
+/// Tab or H hides the UI, Shift with either goes straight to photo mode
+pub const HIDE_UI_KEYS: [KeyCode; 2] = [KeyCode::Tab, KeyCode::H];
+
+/// Big enough to scroll around in for a while
+const MAX_WORLD_SIZE: f32 = 20000.0;
+
+/// How much gets drawn on top of the sim. Input works the same either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub enum UiVisibility {
+    #[default]
+    Shown,
+    /// No windows or FPS but debug overlays and brush previews still show
+    Hidden,
+    /// Nothing but the creatures, for recording clean clips
+    Photo,
+}
+
+impl UiVisibility {
+    pub fn windows_shown(&self) -> bool {
+        *self == UiVisibility::Shown
+    }
+
+    pub fn overlays_shown(&self) -> bool {
+        *self != UiVisibility::Photo
+    }
+}
+
+fn windows_shown(visibility: Res<UiVisibility>) -> bool {
+    visibility.windows_shown()
+}
+
+/// Sims running without the UI (like the right side of a comparison) don't
+/// have any of it to show overlays in
+pub(crate) fn overlays_shown(visibility: Option<Res<UiVisibility>>) -> bool {
+    visibility.is_some_and(|visibility| visibility.overlays_shown())
+}
+
+/// For systems outside of the UI that still need egui, like mouse tools that
+/// shouldn't go off while typing
+pub(crate) fn has_ui(visibility: Option<Res<UiVisibility>>) -> bool {
+    visibility.is_some()
+}
+
+fn ui_visibility_system(
+    keys: Res<Input<KeyCode>>,
+    mut egui_context: EguiContexts,
+    mut visibility: ResMut<UiVisibility>,
+    mut fps_query: Query<&mut Visibility, With<FPSText>>,
+) {
+    // Tab moves between text boxes and H is a letter so typing gets them
+    let typing = egui_context.ctx_mut().wants_keyboard_input();
+    if !typing && keys.any_just_pressed(HIDE_UI_KEYS) {
+        let shift = keys.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+        *visibility = match (*visibility, shift) {
+            (UiVisibility::Shown, false) => UiVisibility::Hidden,
+            (UiVisibility::Shown, true) => UiVisibility::Photo,
+            _ => UiVisibility::Shown,
+        };
+    }
+    if !visibility.is_changed() {
+        return;
+    }
+    for mut fps_visibility in fps_query.iter_mut() {
+        *fps_visibility = if visibility.windows_shown() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
 fn fps_text_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands
         .spawn(TextBundle {
@@ -74,306 +210,2616 @@ fn fps_text_update_system(
     }
 }
 
-fn statistics_system(
-    creature_query: Query<&CreatureType>,
+const TYPE_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// 1-9 picks a creature type and Alt+Scroll goes through them
+fn type_hotkeys_system(
+    keys: Res<Input<KeyCode>>,
+    mut wheel_events: EventReader<MouseWheel>,
     mut egui_context: EguiContexts,
-    all_factors: Res<FactorInfo>,
+    factor_info: Res<FactorInfo>,
+    mut selected_creature_type: ResMut<CreatureType>,
 ) {
-    let population_information = creature_query.iter().fold(
-        vec![0; all_factors.factors.len()],
-        |mut population_information, &creature_type| {
-            population_information[creature_type.0] += 1;
-            population_information
-        },
-    );
+    let scroll = wheel_events.iter().map(|wheel| wheel.y).sum::<f32>();
+    let ctx = egui_context.ctx_mut();
+    // Typing a 2 into a name shouldn't switch types
+    let typing = ctx.wants_keyboard_input();
+    let type_count = factor_info.factors.len();
+    if type_count == 0 {
+        return;
+    }
+    if !typing {
+        if let Some(index) = TYPE_KEYS.iter().position(|&key| keys.just_pressed(key)) {
+            if index < type_count {
+                selected_creature_type.0 = index;
+            }
+        }
+    }
+    let alt = keys.any_pressed([KeyCode::LAlt, KeyCode::RAlt]);
+    if alt && scroll != 0.0 && !ctx.is_pointer_over_area() {
+        // Scrolling up goes back a type like it would in a list
+        selected_creature_type.0 = if scroll > 0.0 {
+            (selected_creature_type.0 + type_count - 1) % type_count
+        } else {
+            (selected_creature_type.0 + 1) % type_count
+        };
+    }
+}
 
-    egui::Window::new("Statistics")
-        .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
-        .vscroll(true)
+/// Shows which type is selected next to the FPS so it's clear what spawning will make
+fn weather_indicator_system(mut egui_context: EguiContexts, weather: Res<Weather>) {
+    let Some(current) = weather.current else {
+        return;
+    };
+    egui::Area::new("weather_indicator")
+        .anchor(egui::Align2::CENTER_TOP, egui::Vec2::new(0.0, 20.0))
+        .interactable(false)
         .show(egui_context.ctx_mut(), |ui| {
-            egui::CollapsingHeader::new("Populations")
-                .default_open(true)
-                .show(ui, |ui| {
-                    population_information
-                        .iter()
-                        .enumerate()
-                        .for_each(|(index, count)| {
-                            ui.label(format!("{}: {}", CreatureType(index), count));
-                        });
-                });
+            ui.label(
+                egui::RichText::new(current.kind.to_string())
+                    .color(egui::Color32::WHITE)
+                    .strong(),
+            );
         });
 }
 
-fn settings_system(
-    keys: Res<Input<KeyCode>>,
-    mut features: ResMut<Features>,
+/// Dims the scene a little while paused, with a banner saying so under the
+/// weather
+fn pause_overlay_system(
     mut egui_context: EguiContexts,
-    selected_creature_type: Res<CreatureType>,
-    mut spawn_properties: ResMut<SpawnProperties>,
-    mut despawn_properties: ResMut<DespawnProperties>,
-    mut primary_query: Query<&mut Window, With<PrimaryWindow>>,
+    sim_state: Res<State<SimState>>,
+    sim_step: Res<SimStep>,
+    ticks: Res<SimTicks>,
 ) {
-    egui::Window::new("Settings")
-        .anchor(egui::Align2::LEFT_BOTTOM, [10.0, -10.0])
-        .vscroll(true)
-        .show(egui_context.ctx_mut(), |ui| {
-            if IS_WASM {
-                ui.collapsing("⚠ Web Warning ⚠", |ui| {
-                    ui.label(concat!(
-                        "LShift and LCtrl detection are a little buggy on the web. ",
-                        "The sim can keep keys pressed when you click out. ",
-                        "Just click Ctrl and Shift while focused on the sim to reset input."
-                    ));
-                });
-            }
-
-            ui.collapsing(
-                format!(
-                    "Spawning Type {} (LShift+Click to Spawn)",
-                    selected_creature_type.0
-                ),
-                |ui| {
-                    ui.add(
-                        egui::Slider::new(&mut spawn_properties.radius, 5.0..=500.0).text("Radius"),
-                    );
-                    ui.add(
-                        egui::Slider::new(&mut spawn_properties.amount, 0..=100)
-                            .text("Amount Per Click"),
-                    );
-                },
-            );
-
-            ui.collapsing(
-                format!(
-                    "Despawn Type {} (LCtrl+Click to Despawn)",
-                    selected_creature_type.0
-                ),
-                |ui| {
-                    ui.add(
-                        egui::Slider::new(&mut despawn_properties.radius, 5.0..=500.0)
-                            .text("Radius"),
-                    );
-                },
-            );
-
-            ui.collapsing("Features", |ui| {
-                ui.label("Enable or Disable Simulation Features");
-                ui.checkbox(&mut features.chasing, "Chasing");
-                ui.checkbox(&mut features.running, "Running");
-                ui.checkbox(&mut features.flocking, "Flocking");
-                ui.checkbox(&mut features.killing, "Killing");
-                ui.checkbox(&mut features.energy_draining, "Energy Draining");
-            });
-
-            let mut window = primary_query.get_single_mut().unwrap();
-            let is_shift = keys.pressed(KeyCode::LShift);
-            let is_ctrl = keys.pressed(KeyCode::LControl);
-            ui.collapsing("Screen", |ui| {
+    // Steps run for a frame without really unpausing
+    if sim_state.0 != SimState::Paused && !sim_step.is_stepping() {
+        return;
+    }
+    let ctx = egui_context.ctx_mut();
+    ctx.layer_painter(egui::LayerId::background()).rect_filled(
+        ctx.screen_rect(),
+        0.0,
+        egui::Color32::from_black_alpha(80),
+    );
+    egui::Area::new("pause_banner")
+        .anchor(egui::Align2::CENTER_TOP, egui::Vec2::new(0.0, 50.0))
+        .interactable(false)
+        .show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
                 ui.label(
-                    "Click to Increase. LCtrl+Click to Decrease. LShift+<> to increase change.",
+                    egui::RichText::new("Paused")
+                        .color(egui::Color32::WHITE)
+                        .size(28.0)
+                        .strong(),
+                );
+                ui.label(
+                    egui::RichText::new(format!("Tick {}", ticks.0))
+                        .color(egui::Color32::LIGHT_GRAY),
                 );
-                let change = if is_shift { 500 } else { 50 };
-                let change = if is_ctrl { -change } else { change };
-                let change = change as f32;
-                if ui.button("Width").clicked() {
-                    let new_width = (window.width() + change).max(500.0);
-                    window.resolution = WindowResolution::new(new_width, window.height());
-                }
-                if ui.button("Height").clicked() {
-                    let new_height = (window.height() + change).max(500.0);
-                    window.resolution = WindowResolution::new(window.width(), new_height);
-                }
             });
         });
 }
 
-fn factors_system(
-    mut commands: Commands,
+fn selected_type_indicator_system(
     mut egui_context: EguiContexts,
-    mut all_factors: ResMut<FactorInfo>,
-    mut selected_creature_type: ResMut<CreatureType>,
-    mut creature_query: Query<(Entity, &mut CreatureType)>,
+    factor_info: Res<FactorInfo>,
+    selected_creature_type: Res<CreatureType>,
 ) {
-    egui::Window::new("Edit Factors")
-        .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
-        .vscroll(true)
+    let Some(factors) = factor_info.factors.get(&selected_creature_type) else {
+        return;
+    };
+    egui::Area::new("selected_type_indicator")
+        .anchor(egui::Align2::LEFT_TOP, egui::Vec2::new(150.0, 20.0))
+        .interactable(false)
         .show(egui_context.ctx_mut(), |ui| {
-            let mut selected_type_index = selected_creature_type.0;
+            ui.horizontal(|ui| {
+                color_swatch(ui, factors.color);
+                ui.label(
+                    egui::RichText::new(factor_info.name_of(*selected_creature_type))
+                        .color(egui::Color32::WHITE)
+                        .strong(),
+                );
+            });
+        });
+}
 
-            egui::ComboBox::from_label("Select")
-                .selected_text(format!("{}", CreatureType(selected_type_index)))
-                .show_ui(ui, |ui| {
-                    (0..all_factors.factors.len()).for_each(|creature_index| {
-                        ui.horizontal(|ui| {
-                            let factors = all_factors
-                                .factors
-                                .get(&CreatureType(creature_index))
-                                .unwrap();
-                            let color = Rgba::from_rgb(
-                                factors.color.r(),
-                                factors.color.g(),
-                                factors.color.b(),
-                            );
-                            ui.selectable_value(
-                                &mut selected_type_index,
-                                creature_index,
-                                CreatureType(creature_index).to_string(),
-                            );
-                            egui::widgets::color_picker::show_color(
-                                ui,
-                                color,
-                                egui::Vec2::new(10.0, 10.0),
-                            );
-                        });
+/// Little square of a creature type's color to go next to its name
+fn color_swatch(ui: &mut egui::Ui, color: Color) {
+    egui::widgets::color_picker::show_color(
+        ui,
+        Rgba::from_rgb(color.r(), color.g(), color.b()),
+        egui::Vec2::new(10.0, 10.0),
+    );
+}
+
+/// What the Statistics tab counts up, logs, and resets.
+#[derive(SystemParam)]
+struct StatisticsPanel<'w, 's> {
+    creature_query: Query<'w, 's, &'static CreatureType>,
+    all_factors: Res<'w, FactorInfo>,
+    data_log: ResMut<'w, DataLog>,
+    sim_stats: ResMut<'w, SimStats>,
+}
+
+fn statistics_panel(ui: &mut egui::Ui, panel: StatisticsPanel) {
+    let StatisticsPanel {
+        creature_query,
+        all_factors,
+        mut data_log,
+        mut sim_stats,
+    } = panel;
+    let population_information = creature_query.iter().fold(
+        vec![0; all_factors.factors.len()],
+        |mut population_information, &creature_type| {
+            population_information[creature_type.0] += 1;
+            population_information
+        },
+    );
+
+    egui::CollapsingHeader::new("Populations")
+        .default_open(true)
+        .show(ui, |ui| {
+            population_information
+                .iter()
+                .enumerate()
+                .for_each(|(index, count)| {
+                    let creature_type = CreatureType(index);
+                    let Some(factors) = all_factors.factors.get(&creature_type) else {
+                        return;
+                    };
+                    ui.horizontal(|ui| {
+                        color_swatch(ui, factors.color);
+                        let label =
+                            ui.label(format!("{}: {}", all_factors.name_of(creature_type), count));
+                        if !factors.description.is_empty() {
+                            label.on_hover_text(factors.description.as_str());
+                        }
                     });
                 });
+        });
 
-            selected_creature_type.0 = selected_type_index;
+    ui.collapsing("Lifecycle", |ui| {
+        ui.label(format!(
+            "Totals over {:.0}s of sim time (last minute in brackets).",
+            sim_stats.elapsed
+        ));
+        // Types nothing has happened to yet
+        let no_stats = TypeStats::default();
+        egui::Grid::new("lifecycle_stats")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("");
+                for kind in StatKind::ALL {
+                    ui.label(kind.to_string());
+                }
+                ui.label("Energy");
+                ui.label("Speed");
+                ui.end_row();
 
-            ui.horizontal(|ui| {
-                // This is so hacky. I hate this. I'm so sorry.
-                if all_factors.factors.len() > 1 && ui.button("Remove Selected").clicked() {
-                    let selected_index = selected_creature_type.0;
-                    for (entity, mut creature_type) in creature_query.iter_mut() {
-                        if *creature_type.as_ref() == *selected_creature_type {
-                            commands.entity(entity).despawn();
-                        } else if creature_type.0 > selected_index {
-                            creature_type.0 -= 1;
-                        }
+                for index in 0..all_factors.factors.len() {
+                    let creature_type = CreatureType(index);
+                    let Some(factors) = all_factors.factors.get(&creature_type) else {
+                        continue;
+                    };
+                    let type_stats = sim_stats.types.get(&creature_type).unwrap_or(&no_stats);
+                    ui.horizontal(|ui| {
+                        color_swatch(ui, factors.color);
+                        ui.label(all_factors.name_of(creature_type));
+                    });
+                    for kind in StatKind::ALL {
+                        ui.label(format!(
+                            "{} ({})",
+                            type_stats.total(kind),
+                            type_stats.per_minute(kind)
+                        ));
                     }
+                    ui.label(format!("{:.1}", type_stats.average_energy));
+                    ui.label(format!("{:.0}", type_stats.average_speed));
+                    ui.end_row();
+                }
+            });
+        if ui
+            .button("Reset")
+            .on_hover_text("Restarting resets these too")
+            .clicked()
+        {
+            sim_stats.reset();
+        }
+    });
 
-                    for (mut creature_type, mut factors) in
-                        all_factors.factors.drain().collect::<Vec<_>>()
-                    {
-                        if creature_type == *selected_creature_type {
-                            continue;
-                        } else if creature_type.0 > selected_index {
-                            creature_type.0 -= 1;
-                        }
-                        factors.predator_of.remove(&selected_creature_type);
-                        for mut prey in factors.predator_of.drain().collect::<Vec<_>>() {
-                            if prey == *selected_creature_type {
-                                continue;
-                            } else if prey.0 > selected_index {
-                                prey.0 -= 1;
-                            }
-                            factors.predator_of.insert(prey);
+    ui.collapsing("Data Logging", |ui| {
+        ui.add(egui::Slider::new(&mut data_log.interval, 0.1..=10.0).text("Sample Interval (s)"));
+        ui.label(format!("{} samples recorded", data_log.samples.len()));
+        ui.horizontal(|ui| {
+            if ui.button("Export CSV").clicked() {
+                data_log.export_csv();
+            }
+            if ui.button("Clear").clicked() {
+                data_log.clear();
+            }
+        });
+        if let Some(status) = &data_log.status {
+            ui.label(status);
+        }
+    });
+}
+
+/// Scenarios to load and survivors to save or start over with.
+#[derive(SystemParam)]
+struct ScenariosPanel<'w> {
+    scenarios: Res<'w, Scenarios>,
+    load_scenario_events: EventWriter<'w, LoadScenarioEvent>,
+    snapshot_status: Res<'w, SnapshotStatus>,
+    save_survivors_events: EventWriter<'w, SaveSurvivorsEvent>,
+    warm_start_events: EventWriter<'w, WarmStartEvent>,
+}
+
+fn scenarios_panel(ui: &mut egui::Ui, panel: ScenariosPanel) {
+    let ScenariosPanel {
+        scenarios,
+        mut load_scenario_events,
+        snapshot_status,
+        mut save_survivors_events,
+        mut warm_start_events,
+    } = panel;
+    ui.label("Loading a scenario resets the whole simulation.");
+    ui.separator();
+    for (index, scenario) in scenarios.0.iter().enumerate() {
+        if ui
+            .button(scenario.name)
+            .on_hover_text(scenario.description)
+            .clicked()
+        {
+            load_scenario_events.send(LoadScenarioEvent(index));
+        }
+    }
+
+    ui.separator();
+    ui.collapsing("Survivors", |ui| {
+        ui.label(concat!(
+            "Save the types still alive at the end of a run, ",
+            "then warm start a new run with only those types."
+        ));
+        ui.horizontal(|ui| {
+            if ui.button("Save Survivors").clicked() {
+                save_survivors_events.send(SaveSurvivorsEvent);
+            }
+            if ui.button("Warm Start").clicked() {
+                warm_start_events.send(WarmStartEvent);
+            }
+        });
+        if let Some(status) = &snapshot_status.0 {
+            ui.label(status);
+        }
+    });
+}
+
+/// Everything the mouse tools can be tweaked with.
+#[derive(SystemParam)]
+struct ToolProperties<'w> {
+    spawn: ResMut<'w, SpawnProperties>,
+    despawn: ResMut<'w, DespawnProperties>,
+    gust: ResMut<'w, GustProperties>,
+    /// For the name of what's being spawned
+    factor_info: Res<'w, FactorInfo>,
+    selected_creature_type: Res<'w, CreatureType>,
+}
+
+/// How things look without changing what they do.
+#[derive(SystemParam)]
+struct DisplayProperties<'w, 's> {
+    death_animation: ResMut<'w, DeathAnimationSettings>,
+    trails: ResMut<'w, TrailSettings>,
+    color_mode: ResMut<'w, CreatureColorMode>,
+    debug_color_mode: ResMut<'w, DebugColorMode>,
+    overlays: ResMut<'w, DebugOverlays>,
+    labels: ResMut<'w, LabelSettings>,
+    smear: ResMut<'w, MotionSmear>,
+    exposure: ResMut<'w, LongExposure>,
+    window_mode: ResMut<'w, WindowModeSettings>,
+    ui_visibility: ResMut<'w, UiVisibility>,
+    theme: ResMut<'w, UiTheme>,
+    ui_scale_input: Local<'s, Option<f32>>,
+}
+
+/// Knobs for how the sim itself is run rather than what's in it.
+#[derive(SystemParam)]
+struct RunControls<'w, 's> {
+    features: ResMut<'w, Features>,
+    rng: ResMut<'w, SimRng>,
+    seed_input: Local<'s, Option<u64>>,
+    deterministic_mode: ResMut<'w, DeterministicMode>,
+    restart_events: EventWriter<'w, RestartEvent>,
+    world_bounds: ResMut<'w, WorldBounds>,
+    time_scale: ResMut<'w, TimeScale>,
+    sim_state: Res<'w, State<SimState>>,
+    next_sim_state: ResMut<'w, NextState<SimState>>,
+    sim_step: ResMut<'w, SimStep>,
+    ticks: Res<'w, SimTicks>,
+    steering: ResMut<'w, SteeringCombination>,
+    satiation: ResMut<'w, SatiationSettings>,
+    collision: ResMut<'w, CollisionSettings>,
+    growth: ResMut<'w, GrowthSettings>,
+    margins: ResMut<'w, MarginSettings>,
+    camera: ResMut<'w, CameraControls>,
+}
+
+/// Trading accuracy and memory for speed.
+#[derive(SystemParam)]
+struct PerformanceSettings<'w> {
+    arena_tiling: ResMut<'w, ArenaTiling>,
+    spatial_index: ResMut<'w, SpatialIndexKind>,
+    spatial_benchmark: Res<'w, SpatialBenchmark>,
+    benchmark_events: EventWriter<'w, RunSpatialBenchmarkEvent>,
+    chunk_resolution: ResMut<'w, ChunkResolution>,
+    level_of_detail: ResMut<'w, LevelOfDetail>,
+    neighbor_cache: ResMut<'w, NeighborCache>,
+    packed_flocking: ResMut<'w, PackedFlocking>,
+    flocking_benchmark: Res<'w, FlockingBenchmark>,
+    flocking_benchmark_events: EventWriter<'w, RunFlockingBenchmarkEvent>,
+    benchmark: Res<'w, BenchmarkMode>,
+    start_benchmark_events: EventWriter<'w, StartBenchmarkEvent>,
+}
+
+/// Extra ways to drive, watch, or listen to the sim that don't change what's in it.
+#[derive(SystemParam)]
+struct ExtraSettings<'w> {
+    scripting: ResMut<'w, Scripting>,
+    comparison: ResMut<'w, Comparison>,
+    gamepad_bindings: ResMut<'w, GamepadBindings>,
+    sound: ResMut<'w, SoundSettings>,
+    music: Res<'w, MusicIntensity>,
+    reset_settings_events: EventWriter<'w, ResetSettingsEvent>,
+    report_status: Res<'w, ReportStatus>,
+    create_report_events: EventWriter<'w, CreateReportEvent>,
+}
+
+/// The window, and the camera for centering it, in the Screen section.
+#[derive(SystemParam)]
+struct ScreenControls<'w, 's> {
+    keys: Res<'w, Input<KeyCode>>,
+    primary_query: Query<'w, 's, &'static mut Window, With<PrimaryWindow>>,
+    camera_follow: ResMut<'w, CameraFollow>,
+    camera_query: Query<'w, 's, &'static mut Transform, With<MainCamera>>,
+}
+
+fn event_log_window_system(
+    mut egui_context: EguiContexts,
+    mut event_log: ResMut<EventLog>,
+    factor_info: Res<FactorInfo>,
+) {
+    egui::Window::new("Event Log")
+        .default_open(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for kind in LogKind::ALL {
+                    let mut shown = event_log.shown_kinds.contains(&kind);
+                    if ui.checkbox(&mut shown, kind.to_string()).changed() {
+                        if shown {
+                            event_log.shown_kinds.push(kind);
+                        } else {
+                            event_log
+                                .shown_kinds
+                                .retain(|&shown_kind| shown_kind != kind);
                         }
-                        all_factors.factors.insert(creature_type, factors);
                     }
-                    selected_creature_type.0 =
-                        selected_creature_type.0.min(all_factors.factors.len() - 1);
                 }
-
-                if ui.button("Add New").clicked() {
-                    let new_creature_type = CreatureType(all_factors.factors.len());
-                    all_factors
-                        .factors
-                        .insert(new_creature_type, Factors::default());
-                    selected_creature_type.0 = new_creature_type.0;
+            });
+            ui.horizontal(|ui| {
+                let selected_text = event_log
+                    .type_filter
+                    .map_or("All Types".to_string(), |creature_type| {
+                        factor_info.name_of(creature_type)
+                    });
+                egui::ComboBox::from_id_source("event_log_type")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut event_log.type_filter, None, "All Types");
+                        for index in 0..factor_info.factors.len() {
+                            let creature_type = CreatureType(index);
+                            ui.selectable_value(
+                                &mut event_log.type_filter,
+                                Some(creature_type),
+                                factor_info.name_of(creature_type),
+                            );
+                        }
+                    });
+                if ui.button("Clear").clicked() {
+                    event_log.clear();
                 }
             });
-
             ui.separator();
 
-            let selected_creature_type = *selected_creature_type.as_ref();
-            let all_creature_types = (0..all_factors.factors.len())
-                .map(|creature_index| CreatureType(creature_index))
-                .collect::<Vec<_>>();
-            let factors = all_factors
-                .factors
-                .get_mut(&selected_creature_type)
-                .unwrap();
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .stick_to_bottom(true)
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    for entry in event_log.entries.iter() {
+                        if !event_log.is_shown(entry) {
+                            continue;
+                        }
+                        ui.horizontal(|ui| {
+                            // Colored like whoever the line is about first
+                            let color = entry
+                                .creature_types
+                                .first()
+                                .and_then(|creature_type| factor_info.factors.get(creature_type))
+                                .map_or(Color::WHITE, |factors| factors.color);
+                            color_swatch(ui, color);
+                            ui.label(egui::RichText::new(format!("{:.1}s", entry.time)).weak());
+                            ui.label(entry.text.as_str());
+                        });
+                    }
+                });
+        });
+}
 
-            ui.horizontal(|ui| {
-                let mut color = [factors.color.r(), factors.color.g(), factors.color.b()];
-                color_edit_button_rgb(ui, &mut color);
-                factors.color = color.into();
-                ui.label("Color");
-            });
+/// Everything the Obstacles section of the Environment window needs.
+#[derive(SystemParam)]
+struct ObstacleControls<'w, 's> {
+    settings: ResMut<'w, ObstacleSettings>,
+    query: Query<'w, 's, Entity, With<Obstacle>>,
+    maze: ResMut<'w, MazeSettings>,
+    generate_maze_events: EventWriter<'w, GenerateMazeEvent>,
+    mask: ResMut<'w, MaskSettings>,
+    load_mask_events: EventWriter<'w, LoadMaskEvent>,
+    clear_mask_events: EventWriter<'w, ClearMaskEvent>,
+    rooms: Res<'w, Rooms>,
+    room_settings: ResMut<'w, RoomSettings>,
+    generate_rooms_events: EventWriter<'w, GenerateRoomsEvent>,
+    clear_rooms_events: EventWriter<'w, ClearRoomsEvent>,
+}
+
+/// Everything the Food Patches, Temperature, and Weather sections of the
+/// Environment window need.
+#[derive(SystemParam)]
+struct ClimateControls<'w, 's> {
+    food: ResMut<'w, FoodSettings>,
+    reseed_food_events: EventWriter<'w, ReseedFoodEvent>,
+    patch_query: Query<'w, 's, &'static FoodPatch>,
+    temperature: ResMut<'w, TemperatureSettings>,
+    weather_settings: ResMut<'w, WeatherSettings>,
+    weather: Res<'w, Weather>,
+    change_weather_events: EventWriter<'w, ChangeWeatherEvent>,
+}
 
-            ui.add(egui::Slider::new(&mut factors.speed, 5.0..=200.0).text("Speed"));
-            ui.add(egui::Slider::new(&mut factors.vision, 5.0..=100.0).text("Vision"));
-            ui.add(egui::Slider::new(&mut factors.size, 0.5..=10.0).text("Size"));
-            ui.add(egui::Slider::new(&mut factors.max_energy, 20.0..=200.0).text("Max Energy"));
+/// Everything the Flow Field and Gravity Wells sections of the Environment
+/// window need.
+#[derive(SystemParam)]
+struct ForceControls<'w, 's> {
+    flow: ResMut<'w, FlowSettings>,
+    load_flow_image_events: EventWriter<'w, LoadFlowImageEvent>,
+    new_well: ResMut<'w, NewGravityWell>,
+    well_query: Query<'w, 's, (Entity, &'static mut GravityWell)>,
+}
 
-            ui.collapsing("Boids System", |ui| {
-                ui.add(egui::Slider::new(&mut factors.alignment, 0.0..=50.0).text("Alignment"));
-                ui.add(egui::Slider::new(&mut factors.cohesion, 0.0..=50.0).text("Cohesion"));
-                ui.add(egui::Slider::new(&mut factors.separation, 0.0..=50.0).text("Separation"));
+/// Things in the world besides the creatures.
+fn environment_system(
+    mut egui_context: EguiContexts,
+    mut commands: Commands,
+    factor_info: Res<FactorInfo>,
+    climate: ClimateControls,
+    forces: ForceControls,
+    mut obstacles: ObstacleControls,
+) {
+    let ClimateControls {
+        mut food,
+        mut reseed_food_events,
+        patch_query,
+        mut temperature,
+        mut weather_settings,
+        weather,
+        mut change_weather_events,
+    } = climate;
+    let ForceControls {
+        mut flow,
+        mut load_flow_image_events,
+        mut new_well,
+        mut well_query,
+    } = forces;
+    egui::Window::new("Environment")
+        .default_open(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.collapsing("Food Patches", |ui| {
+                ui.label("Grazers (set in Edit Factors) get energy from eating in these.");
+                ui.checkbox(&mut food.enabled, "Enabled");
+                let (biomass, capacity) =
+                    patch_query
+                        .iter()
+                        .fold((0.0, 0.0), |(biomass, capacity), patch| {
+                            (biomass + patch.biomass, capacity + patch.capacity)
+                        });
+                if capacity > 0.0 {
+                    ui.label(format!(
+                        "{:.0} / {:.0} biomass ({:.0}%)",
+                        biomass,
+                        capacity,
+                        biomass / capacity * 100.0
+                    ));
+                }
+                ui.add(egui::Slider::new(&mut food.regrowth_rate, 0.0..=1.0).text("Regrowth Rate"));
+                ui.add(egui::Slider::new(&mut food.graze_rate, 0.0..=20.0).text("Graze Rate"));
+                ui.add(
+                    egui::Slider::new(&mut food.energy_per_biomass, 0.0..=5.0)
+                        .text("Energy Per Biomass"),
+                );
+                ui.label("These take effect on reseeding:");
+                ui.add(egui::Slider::new(&mut food.clusters, 1..=20).text("Clusters"));
+                ui.add(
+                    egui::Slider::new(&mut food.patches_per_cluster, 1..=20)
+                        .text("Patches Per Cluster"),
+                );
                 ui.add(
-                    egui::Slider::new(&mut factors.collision_avoidance, 0.0..=50.0)
-                        .text("Collision Avoidance"),
+                    egui::Slider::new(&mut food.cluster_spread, 0.0..=500.0).text("Cluster Spread"),
                 );
+                ui.add(egui::Slider::new(&mut food.patch_radius, 5.0..=100.0).text("Patch Radius"));
+                ui.add(egui::Slider::new(&mut food.capacity, 10.0..=500.0).text("Capacity"));
+                if ui
+                    .add_enabled(food.enabled, egui::Button::new("Reseed"))
+                    .clicked()
+                {
+                    reseed_food_events.send(ReseedFoodEvent);
+                }
             });
 
-            drop(factors);
-            ui.collapsing("Predator/Prey (Chase/Run) System", |ui| {
-                let factors = all_factors
-                    .factors
-                    .get_mut(&selected_creature_type)
-                    .unwrap();
-
+            ui.collapsing("Temperature", |ui| {
                 ui.label(concat!(
-                    "You can be both predator and prey to another type. ",
-                    "If both, running/chasing is determined by your run/chase factor. ",
-                    "On contact and w/ killing, whomever has more energy shall prevail."
+                    "Colder to the south and warmer to the north. Types outside of ",
+                    "their comfort range (in Edit Factors) drain energy faster and ",
+                    "head somewhere nicer."
                 ));
+                ui.checkbox(&mut temperature.enabled, "Enabled");
+                ui.add(egui::Slider::new(&mut temperature.cold, -20.0..=60.0).text("Coldest (°)"));
+                ui.add(egui::Slider::new(&mut temperature.hot, -20.0..=60.0).text("Hottest (°)"));
+                temperature.hot = temperature.hot.max(temperature.cold);
+                ui.add(egui::Slider::new(&mut temperature.noisiness, 0.0..=1.0).text("Noisiness"))
+                    .on_hover_text("0 is a smooth gradient, 1 is all warm and cold spots");
+                ui.add(
+                    egui::Slider::new(&mut temperature.noise_scale, 50.0..=1000.0)
+                        .text("Spot Size"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut temperature.discomfort_drain, 0.0..=2.0)
+                        .text("Drain Per Degree"),
+                )
+                .on_hover_text("Only while energy draining is on");
+                ui.add(
+                    egui::Slider::new(&mut temperature.comfort_steering, 0.0..=10.0)
+                        .text("Comfort Steering"),
+                );
+            });
 
-                ui.add(egui::Slider::new(&mut factors.chase, 0.0..=50.0).text("Chase"));
-                ui.add(egui::Slider::new(&mut factors.scare, 0.0..=50.0).text("Scare"));
-
-                ui.collapsing("Predator of", |ui| {
-                    for &other_creature_type in all_creature_types.iter() {
-                        if selected_creature_type == other_creature_type {
-                            continue;
-                        }
-                        let mut is_predator_of_other =
-                            factors.predator_of.contains(&other_creature_type);
-                        ui.checkbox(&mut is_predator_of_other, other_creature_type.to_string());
-                        if is_predator_of_other {
-                            factors.predator_of.insert(other_creature_type.clone());
-                        } else {
-                            factors.predator_of.remove(&other_creature_type);
+            ui.collapsing("Weather", |ui| {
+                ui.label(match weather.current {
+                    Some(current) => format!("{} ({:.0}s left)", current.kind, current.remaining()),
+                    None => "Clear".to_string(),
+                });
+                ui.horizontal(|ui| {
+                    for kind in WeatherKind::ALL {
+                        if ui.button(kind.to_string()).clicked() {
+                            change_weather_events.send(ChangeWeatherEvent(Some(kind)));
                         }
                     }
+                    if ui.button("Clear").clicked() {
+                        change_weather_events.send(ChangeWeatherEvent(None));
+                    }
                 });
+                ui.checkbox(&mut weather_settings.enabled, "Random Weather")
+                    .on_hover_text("Storms, rain, and fog every so often on their own");
+                ui.add(
+                    egui::Slider::new(&mut weather_settings.frequency, 0.0..=10.0)
+                        .text("Per Minute"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut weather_settings.duration, 2.0..=60.0)
+                        .text("Duration (s)"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut weather_settings.storm_strength, 0.0..=10.0)
+                        .text("Storm Strength"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut weather_settings.rain_speed_scale, 0.0..=1.0)
+                        .text("Rain Speed"),
+                )
+                .on_hover_text("Speed of the types slowed by rain (in Edit Factors)");
+                ui.add(
+                    egui::Slider::new(&mut weather_settings.fog_vision_scale, 0.1..=1.0)
+                        .text("Fog Vision"),
+                );
+            });
 
-                ui.collapsing("Prey of", |ui| {
-                    for &other_creature_type in all_creature_types.iter() {
-                        let other_factors =
-                            all_factors.factors.get_mut(&other_creature_type).unwrap();
-                        if selected_creature_type == other_creature_type {
-                            continue;
-                        }
-                        let mut is_scared_of_other =
-                            other_factors.predator_of.contains(&selected_creature_type);
-                        ui.checkbox(&mut is_scared_of_other, other_creature_type.to_string());
-                        if is_scared_of_other {
-                            other_factors.predator_of.insert(selected_creature_type);
-                        } else {
-                            other_factors.predator_of.remove(&selected_creature_type);
+            ui.collapsing("Flow Field", |ui| {
+                ui.label("A current that carries creatures along with it.");
+                egui::ComboBox::from_label("Pattern")
+                    .selected_text(flow.pattern.to_string())
+                    .show_ui(ui, |ui| {
+                        for pattern in FlowPattern::ALL {
+                            ui.selectable_value(&mut flow.pattern, pattern, pattern.to_string());
                         }
+                    });
+                ui.add(egui::Slider::new(&mut flow.strength, 0.0..=20.0).text("Strength"));
+                ui.add_enabled(
+                    matches!(flow.pattern, FlowPattern::Shear | FlowPattern::Waves),
+                    egui::Slider::new(&mut flow.scale, 20.0..=1000.0).text("Scale"),
+                );
+                ui.label("Image (hue is the direction, brightness is the strength):")
+                    .on_hover_text("Red flows right, then around through green and blue");
+                ui.horizontal(|ui| {
+                    ui.label("Path");
+                    ui.text_edit_singleline(&mut flow.image_path);
+                    if ui.button("Load").clicked() {
+                        load_flow_image_events.send(LoadFlowImageEvent);
                     }
                 });
+                if let Some(status) = &flow.status {
+                    ui.label(status);
+                }
             });
-        });
-}
-
-#[derive(Default)]
-pub struct UiPlugin;
+            ui.collapsing("Gravity Wells", |ui| {
+                ui.label("Middle click to place a well or to remove one.");
+                ui.label("New wells:");
+                gravity_well_controls(ui, &mut new_well.0, &factor_info, "new_well");
+                let mut wells = well_query.iter_mut().collect::<Vec<_>>();
+                wells.sort_by_key(|(entity, _)| *entity);
+                for (index, (entity, well)) in wells.iter_mut().enumerate() {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Well {}", index + 1));
+                        if ui.button("Remove").clicked() {
+                            commands.entity(*entity).despawn();
+                        }
+                    });
+                    gravity_well_controls(ui, well, &factor_info, *entity);
+                }
+                if !wells.is_empty() {
+                    ui.separator();
+                    if ui.button("Remove All").clicked() {
+                        for (entity, _) in wells.iter() {
+                            commands.entity(*entity).despawn();
+                        }
+                    }
+                }
+            });
+            ui.collapsing("Obstacles", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Draw:");
+                    for tool in ObstacleTool::ALL {
+                        ui.selectable_value(&mut obstacles.settings.tool, tool, tool.to_string());
+                    }
+                });
+                ui.label(match obstacles.settings.tool {
+                    ObstacleTool::Polygon => {
+                        "Alt+click to add corners, click the first one or Enter to finish."
+                    }
+                    _ => "Alt+drag to draw one.",
+                });
+                ui.add(
+                    egui::Slider::new(&mut obstacles.settings.avoidance, 0.0..=50.0)
+                        .text("Avoidance"),
+                )
+                .on_hover_text("How hard creatures turn away from walls ahead of them");
+                ui.add(
+                    egui::Slider::new(&mut obstacles.settings.look_ahead, 0.1..=1.0)
+                        .text("Look Ahead"),
+                )
+                .on_hover_text("How far ahead they look for walls, out of their vision");
+                ui.checkbox(&mut obstacles.settings.blocks_sight, "Block Line of Sight");
+                let count = obstacles.query.iter().count();
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} obstacles", count));
+                    if ui
+                        .add_enabled(count > 0, egui::Button::new("Remove All"))
+                        .clicked()
+                    {
+                        for entity in obstacles.query.iter() {
+                            commands.entity(entity).despawn();
+                        }
+                    }
+                });
 
-impl Plugin for UiPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_plugin(EguiPlugin)
-            .add_startup_system(fps_text_setup);
+                ui.separator();
+                ui.label("Generate (replaces every obstacle):");
+                ui.horizontal(|ui| {
+                    for layout in MazeLayout::ALL {
+                        ui.selectable_value(&mut obstacles.maze.layout, layout, layout.to_string());
+                    }
+                });
+                ui.add(
+                    egui::Slider::new(&mut obstacles.maze.cell_size, 30.0..=300.0)
+                        .text("Cell Size"),
+                );
+                ui.add(egui::Slider::new(&mut obstacles.maze.density, 0.0..=1.0).text("Density"))
+                    .on_hover_text(match obstacles.maze.layout {
+                        MazeLayout::Maze => "How many walls stay up, 1 is a perfect maze",
+                        MazeLayout::Pillars => "How many cells get a pillar",
+                    });
+                ui.horizontal(|ui| {
+                    ui.label("Seed");
+                    ui.add(egui::DragValue::new(&mut obstacles.maze.seed).speed(1.0));
+                    if ui.button("🎲").on_hover_text("Random Seed").clicked() {
+                        obstacles.maze.seed = rand::random();
+                    }
+                });
+                if ui.button("Generate").clicked() {
+                    obstacles.generate_maze_events.send(GenerateMazeEvent);
+                }
 
-        app.add_system(
-            factors_system, // .label("despawning")
-        );
+                ui.separator();
+                ui.label("Mask Image (dark pixels become walls, stretched over the world):");
+                ui.horizontal(|ui| {
+                    ui.label("Path");
+                    ui.text_edit_singleline(&mut obstacles.mask.path);
+                });
+                ui.add(
+                    egui::Slider::new(&mut obstacles.mask.resolution, 2.0..=50.0)
+                        .text("Resolution"),
+                )
+                .on_hover_text("World units across each square the image gets chopped into");
+                ui.add(
+                    egui::Slider::new(&mut obstacles.mask.threshold, 0.0..=1.0).text("Threshold"),
+                );
+                ui.checkbox(&mut obstacles.mask.invert, "Light Pixels Are Walls");
+                ui.horizontal(|ui| {
+                    if ui.button("Load").clicked() {
+                        obstacles.load_mask_events.send(LoadMaskEvent);
+                    }
+                    if ui.button("Clear").clicked() {
+                        obstacles.clear_mask_events.send(ClearMaskEvent);
+                    }
+                });
+                if let Some(status) = &obstacles.mask.status {
+                    ui.label(status);
+                }
+            });
+
+            ui.collapsing("Rooms", |ui| {
+                ui.label("Walls the world into a grid of rooms with a portal between neighbors.");
+                let settings = &mut obstacles.room_settings;
+                ui.add(egui::Slider::new(&mut settings.columns, 1..=6).text("Columns"));
+                ui.add(egui::Slider::new(&mut settings.rows, 1..=6).text("Rows"));
+                ui.add(
+                    egui::Slider::new(&mut settings.portal_width, 0.0..=300.0).text("Portal Width"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut settings.wall_thickness, 2.0..=50.0)
+                        .text("Wall Thickness"),
+                );
+                ui.checkbox(&mut settings.closed_edges, "Wall Off the Edges")
+                    .on_hover_text("Otherwise creatures can wrap around into another room");
+                ui.horizontal(|ui| {
+                    ui.label("Seed");
+                    ui.add(egui::DragValue::new(&mut settings.seed).speed(1.0))
+                        .on_hover_text("Where along the walls the portals go, 0 is the middle");
+                    if ui.button("🎲").on_hover_text("Random Seed").clicked() {
+                        settings.seed = rand::random();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Generate").clicked() {
+                        obstacles.generate_rooms_events.send(GenerateRoomsEvent);
+                    }
+                    if ui
+                        .add_enabled(!obstacles.rooms.is_empty(), egui::Button::new("Clear"))
+                        .clicked()
+                    {
+                        obstacles.clear_rooms_events.send(ClearRoomsEvent);
+                    }
+                });
+                if !obstacles.rooms.is_empty() {
+                    ui.label(
+                        obstacles
+                            .rooms
+                            .census
+                            .iter()
+                            .enumerate()
+                            .map(|(room, count)| format!("{}: {}", room + 1, count))
+                            .collect::<Vec<_>>()
+                            .join("  "),
+                    );
+                }
+            });
+        });
+}
+
+fn gravity_well_controls(
+    ui: &mut egui::Ui,
+    well: &mut GravityWell,
+    factor_info: &FactorInfo,
+    id: impl std::hash::Hash,
+) {
+    ui.add(egui::Slider::new(&mut well.mass, 0.0..=20000.0).text("Mass"));
+    ui.add(egui::Slider::new(&mut well.falloff, 0.5..=3.0).text("Falloff"))
+        .on_hover_text("2 is real gravity, lower reaches further");
+    let mut creature_types = factor_info.factors.keys().copied().collect::<Vec<_>>();
+    creature_types.sort_by_key(|creature_type| creature_type.0);
+    ui.push_id(id, |ui| {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Pulls:");
+            for creature_type in creature_types {
+                let mut affected = well.affected_types.contains(&creature_type);
+                if ui
+                    .checkbox(&mut affected, factor_info.name_of(creature_type))
+                    .changed()
+                {
+                    if affected {
+                        well.affected_types.insert(creature_type);
+                    } else {
+                        well.affected_types.remove(&creature_type);
+                    }
+                }
+            }
+        })
+        .response
+        .on_hover_text("Every type when none are checked");
+    });
+}
+
+/// How each type's traits have shifted over time.
+fn evolution_system(
+    mut egui_context: EguiContexts,
+    factor_info: Res<FactorInfo>,
+    mut history: ResMut<EvolutionHistory>,
+    mut shown_trait: Local<Option<TraitKind>>,
+) {
+    egui::Window::new("Evolution")
+        .default_open(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label("Averages as multipliers of each type's own factors.");
+            let shown = shown_trait.get_or_insert(TraitKind::Speed);
+            ui.horizontal(|ui| {
+                for kind in TraitKind::ALL {
+                    ui.selectable_value(shown, kind, kind.to_string());
+                }
+            });
+            let index = TraitKind::ALL
+                .iter()
+                .position(|kind| kind == shown)
+                .unwrap();
+
+            let mut creature_types = history.types.keys().copied().collect::<Vec<_>>();
+            creature_types.sort_by_key(|creature_type| creature_type.0);
+            let lines = creature_types
+                .iter()
+                .filter_map(|creature_type| {
+                    let factors = factor_info.factors.get(creature_type)?;
+                    let points = history.types[creature_type]
+                        .iter()
+                        .map(|sample| [sample.time as f64, sample.means[index] as f64])
+                        .collect::<Vec<_>>();
+                    let [r, g, b, _] = rgba_u8(factors.color);
+                    Some(
+                        Line::new(points)
+                            .name(factor_info.name_of(*creature_type))
+                            .color(egui::Color32::from_rgb(r, g, b)),
+                    )
+                })
+                .collect::<Vec<_>>();
+            Plot::new("Evolution")
+                .height(200.0)
+                .legend(Legend::default())
+                .show(ui, |plot_ui| {
+                    for line in lines {
+                        plot_ui.line(line);
+                    }
+                });
+
+            egui::Grid::new("evolution_stats")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.label("Mean");
+                    ui.label("Variance");
+                    ui.label("Count");
+                    ui.end_row();
+                    for creature_type in creature_types.iter() {
+                        let (Some(factors), Some(sample)) = (
+                            factor_info.factors.get(creature_type),
+                            history.latest(creature_type),
+                        ) else {
+                            continue;
+                        };
+                        ui.horizontal(|ui| {
+                            color_swatch(ui, factors.color);
+                            ui.label(factor_info.name_of(*creature_type));
+                        });
+                        ui.label(format!("{:.3}", sample.means[index]));
+                        ui.label(format!("{:.4}", sample.variances[index]));
+                        ui.label(sample.count.to_string());
+                        ui.end_row();
+                    }
+                });
+            ui.add(egui::Slider::new(&mut history.interval, 0.1..=10.0).text("Sample Every (s)"));
+            if ui.button("Clear").clicked() {
+                history.clear();
+            }
+        });
+}
+
+fn timeline_system(
+    mut egui_context: EguiContexts,
+    timeline: Res<ForceTimeline>,
+    mut tracked_creature: ResMut<TrackedCreature>,
+    creature_query: Query<(Entity, &CreatureType)>,
+) {
+    egui::Window::new("Creature Timeline")
+        .default_open(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label("Every force pushing on one creature, tick by tick.");
+            ui.horizontal(|ui| {
+                if ui.button("Track Random").clicked() {
+                    let count = creature_query.iter().count();
+                    if count > 0 {
+                        tracked_creature.0 = creature_query
+                            .iter()
+                            .nth(rand::random::<usize>() % count)
+                            .map(|(entity, _)| entity);
+                    }
+                }
+                if tracked_creature.0.is_some() && ui.button("Stop").clicked() {
+                    tracked_creature.0 = None;
+                }
+            });
+
+            let Some(entity) = tracked_creature.0 else {
+                ui.label("Not tracking anything.");
+                return;
+            };
+            if let Ok((_, creature_type)) = creature_query.get(entity) {
+                ui.label(format!("Tracking {:?} ({})", entity, creature_type));
+            }
+
+            // Stack each kind on top of the ones before it
+            let mut totals = vec![0.0; timeline.entries.len()];
+            let lines = ForceKind::ALL
+                .iter()
+                .enumerate()
+                .map(|(kind_index, kind)| {
+                    let points = timeline
+                        .entries
+                        .iter()
+                        .zip(totals.iter_mut())
+                        .map(|(entry, total)| {
+                            *total += entry.forces[kind_index] as f64;
+                            [entry.tick as f64, *total]
+                        })
+                        .collect::<Vec<_>>();
+                    Line::new(points).name(kind.to_string()).fill(0.0)
+                })
+                .collect::<Vec<_>>();
+
+            Plot::new("Force Timeline")
+                .height(200.0)
+                .legend(Legend::default())
+                .show(ui, |plot_ui| {
+                    // Drawn in reverse so the smaller stacks sit on top
+                    for line in lines.into_iter().rev() {
+                        plot_ui.line(line);
+                    }
+                });
+        });
+}
+
+type InspectedCreature<'a> = (
+    Entity,
+    &'a CreatureType,
+    &'a Energy,
+    &'a Direction,
+    &'a Transform,
+    Option<&'a Personality>,
+    Option<&'a Age>,
+    Option<&'a BodySize>,
+    Option<&'a Tagged>,
+);
+
+/// Keeping an eye on the selected creature in the timeline or with the camera
+#[derive(SystemParam)]
+struct Watching<'w> {
+    tracked_creature: ResMut<'w, TrackedCreature>,
+    camera_follow: ResMut<'w, CameraFollow>,
+}
+
+fn inspector_system(
+    mut commands: Commands,
+    mut egui_context: EguiContexts,
+    hash_grid: Res<HashGrid>,
+    factor_info: Res<FactorInfo>,
+    watching: Watching,
+    mut name_input: Local<String>,
+    selected_query: Query<InspectedCreature, With<Selected>>,
+) {
+    let Watching {
+        mut tracked_creature,
+        mut camera_follow,
+    } = watching;
+    let Ok((
+        entity,
+        creature_type,
+        energy,
+        direction,
+        transform,
+        personality,
+        age,
+        body_size,
+        tagged,
+    )) = selected_query.get_single()
+    else {
+        return;
+    };
+
+    egui::Window::new("Inspector")
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            if let Some(tagged) = tagged {
+                ui.heading(&tagged.0);
+            }
+            ui.label(format!("Entity: {:?}", entity));
+            ui.label(format!("Type: {}", creature_type));
+            let max_energy = factor_info
+                .factors
+                .get(creature_type)
+                .map_or(0.0, |factors| factors.max_energy);
+            ui.label(format!("Energy: {:.2} / {:.2}", energy.0, max_energy));
+            ui.label(format!(
+                "Position: ({:.1}, {:.1})",
+                transform.translation.x, transform.translation.y
+            ));
+            ui.label(format!(
+                "Direction: ({:.2}, {:.2}) at {:.1} speed",
+                direction.0.x,
+                direction.0.y,
+                direction.0.length()
+            ));
+            if let Some(personality) = personality {
+                ui.label(format!(
+                    "Boldness: {:.2}, Sociability: {:.2}",
+                    personality.boldness, personality.sociability
+                ));
+            }
+            if let (Some(age), Some(body_size)) = (age, body_size) {
+                ui.label(format!("Age: {:.0}s, Size: {:.1}", age.0, body_size.0));
+            }
+            match hash_grid.cell_of(entity) {
+                Some((row, column)) => ui.label(format!("Grid Cell: ({}, {})", row, column)),
+                None => ui.label("Grid Cell: Not Filed Yet"),
+            };
+
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut *name_input).hint_text("Name"));
+                let name = name_input.trim().to_string();
+                let label = if tagged.is_some() { "Rename" } else { "Tag" };
+                if ui
+                    .add_enabled(!name.is_empty(), egui::Button::new(label))
+                    .on_hover_text("Named ones get a label, dodge the brush, and get logged")
+                    .clicked()
+                {
+                    commands.entity(entity).insert(Tagged(name));
+                    name_input.clear();
+                }
+                if tagged.is_some() && ui.button("Untag").clicked() {
+                    commands.entity(entity).remove::<Tagged>();
+                }
+            });
+            ui.checkbox(&mut camera_follow.enabled, "Follow With Camera (F)");
+            ui.add_enabled(
+                camera_follow.enabled,
+                egui::Slider::new(&mut camera_follow.smoothing, 1.0..=20.0).text("Smoothing"),
+            );
+
+            ui.horizontal(|ui| {
+                if ui.button("Track in Timeline").clicked() {
+                    tracked_creature.0 = Some(entity);
+                }
+                if ui.button("Deselect").clicked() {
+                    commands.entity(entity).remove::<Selected>();
+                }
+                if ui.button("Despawn").clicked() {
+                    commands.entity(entity).despawn();
+                }
+            });
+        });
+}
+
+/// Every section of the Settings tab.
+#[derive(SystemParam)]
+struct SettingsPanel<'w, 's> {
+    tools: ToolProperties<'w>,
+    run_controls: RunControls<'w, 's>,
+    performance: PerformanceSettings<'w>,
+    display: DisplayProperties<'w, 's>,
+    extras: ExtraSettings<'w>,
+    screen: ScreenControls<'w, 's>,
+}
+
+fn settings_panel(ui: &mut egui::Ui, panel: SettingsPanel) {
+    let SettingsPanel {
+        mut tools,
+        mut run_controls,
+        mut performance,
+        mut display,
+        mut extras,
+        screen,
+    } = panel;
+    let ScreenControls {
+        keys,
+        mut primary_query,
+        mut camera_follow,
+        mut camera_query,
+    } = screen;
+    if IS_WASM {
+        ui.collapsing("⚠ Web Warning ⚠", |ui| {
+            ui.label(concat!(
+                "LShift and LCtrl detection are a little buggy on the web. ",
+                "The sim can keep keys pressed when you click out. ",
+                "Just click Ctrl and Shift while focused on the sim to reset input."
+            ));
+        });
+    }
+
+    ui.horizontal(|ui| {
+        let paused =
+            run_controls.sim_state.0 == SimState::Paused || run_controls.sim_step.is_stepping();
+        let (label, next) = if paused {
+            ("Resume", SimState::Running)
+        } else {
+            ("Pause", SimState::Paused)
+        };
+        if ui.button(label).on_hover_text("P").clicked() {
+            run_controls.next_sim_state.set(next);
+        }
+        if ui
+            .add_enabled(paused, egui::Button::new("Step"))
+            .on_hover_text(". runs one tick while paused")
+            .clicked()
+        {
+            run_controls.sim_step.request();
+        }
+        ui.label(format!(
+            "{} at tick {}",
+            if paused { "Paused" } else { "Running" },
+            run_controls.ticks.0
+        ));
+    });
+
+    let selected_name = tools.factor_info.name_of(*tools.selected_creature_type);
+    // Ids are set so renaming the type doesn't close these
+    egui::CollapsingHeader::new(format!(
+        "Spawning {} (LShift+Click/Drag to Spawn)",
+        selected_name
+    ))
+    .id_source("spawning")
+    .show(ui, |ui| {
+        ui.add(egui::Slider::new(&mut tools.spawn.radius, 5.0..=500.0).text("Radius"));
+        ui.add(egui::Slider::new(&mut tools.spawn.amount, 0..=100).text("Amount Per Click"));
+        ui.add(
+            egui::Slider::new(&mut tools.spawn.paint_rate, 1.0..=500.0)
+                .text("Per Second While Dragging"),
+        );
+        let variation_text = format!(
+            "Individual Variation ±{:.0}%",
+            tools.spawn.variation * 100.0
+        );
+        ui.add(
+            egui::Slider::new(&mut tools.spawn.variation, 0.0..=0.5)
+                .show_value(false)
+                .text(variation_text),
+        )
+        .on_hover_text("How much each one's speed, vision, and size can differ");
+        ui.add(
+            egui::Slider::new(&mut tools.spawn.personality_spread, 0.0..=1.0)
+                .text("Personality Spread"),
+        )
+        .on_hover_text("How far boldness and sociability can stray from average");
+    });
+
+    egui::CollapsingHeader::new(format!(
+        "Despawn {} (LCtrl+Click/Drag to Despawn)",
+        selected_name
+    ))
+    .id_source("despawning")
+    .show(ui, |ui| {
+        ui.add(egui::Slider::new(&mut tools.despawn.radius, 5.0..=500.0).text("Radius"));
+    });
+
+    ui.collapsing("Wind Gust (Right Click+Drag to Swipe)", |ui| {
+        ui.add(egui::Slider::new(&mut tools.gust.radius, 5.0..=200.0).text("Radius"));
+        ui.add(egui::Slider::new(&mut tools.gust.strength, 0.1..=10.0).text("Strength"));
+    });
+
+    ui.collapsing(
+        format!("Time Scale ({:.2}x)", run_controls.time_scale.0),
+        |ui| {
+            ui.label("[ to halve, ] to double, \\ to reset.");
+            let mut scale = run_controls.time_scale.0;
+            ui.add(
+                egui::Slider::new(&mut scale, TimeScale::MIN..=TimeScale::MAX)
+                    .logarithmic(true)
+                    .text("Speed"),
+            );
+            if scale != run_controls.time_scale.0 {
+                run_controls.time_scale.set(scale);
+            }
+        },
+    );
+
+    ui.collapsing(format!("Seed ({})", run_controls.rng.seed()), |ui| {
+        ui.label("The same seed in deterministic mode reproduces the same run from launch.");
+        let seed = run_controls
+            .seed_input
+            .get_or_insert(run_controls.rng.seed());
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(seed).speed(1.0));
+            if ui.button("🎲").on_hover_text("Random Seed").clicked() {
+                *seed = rand::random();
+            }
+        });
+        ui.checkbox(
+            &mut run_controls.deterministic_mode.0,
+            "Deterministic (Fixed Time Step)",
+        );
+        if ui.button("Restart With Seed").clicked() {
+            run_controls.rng.reseed(*seed);
+            run_controls.restart_events.send(RestartEvent);
+        }
+    });
+
+    ui.collapsing("Features", |ui| {
+        ui.label("Enable or Disable Simulation Features");
+        ui.checkbox(&mut run_controls.features.chasing, "Chasing");
+        ui.checkbox(&mut run_controls.features.running, "Running");
+        ui.checkbox(&mut run_controls.features.flocking, "Flocking");
+        ui.checkbox(&mut run_controls.features.killing, "Killing");
+        ui.checkbox(
+            &mut run_controls.features.energy_draining,
+            "Energy Draining",
+        );
+        ui.separator();
+        ui.checkbox(
+            &mut run_controls.steering.prioritized,
+            "Prioritized Steering",
+        )
+        .on_hover_text(
+            "Avoiding, separating, and running away get first dibs on turning \
+                    and flocking only gets what's left",
+        );
+        ui.add_enabled(
+            run_controls.steering.prioritized,
+            egui::Slider::new(&mut run_controls.steering.priority_budget, 1.0..=50.0)
+                .text("Steering Budget"),
+        );
+        ui.separator();
+        ui.add(
+            egui::Slider::new(&mut run_controls.satiation.duration, 0.0..=20.0)
+                .text("Full After Eating (s)"),
+        )
+        .on_hover_text("Predators stop chasing and slow down for a bit after a kill");
+        ui.add_enabled(
+            run_controls.satiation.duration > 0.0,
+            egui::Slider::new(&mut run_controls.satiation.speed_scale, 0.0..=1.0)
+                .text("Full Speed Scale"),
+        );
+        ui.separator();
+        ui.checkbox(&mut run_controls.collision.enabled, "Hard Collisions")
+            .on_hover_text("Pushes overlapping creatures apart so they can't pile up");
+        ui.add_enabled(
+            run_controls.collision.enabled,
+            egui::Slider::new(&mut run_controls.collision.stiffness, 0.1..=1.0).text("Stiffness"),
+        );
+        ui.add_enabled(
+            run_controls.collision.enabled,
+            egui::Slider::new(&mut run_controls.collision.iterations, 1..=8).text("Passes"),
+        );
+        ui.separator();
+        ui.checkbox(&mut run_controls.growth.enabled, "Grow With Age")
+            .on_hover_text("Older creatures get bigger and hungry ones shrink a bit");
+        ui.add_enabled_ui(run_controls.growth.enabled, |ui| {
+            ui.add(
+                egui::Slider::new(&mut run_controls.growth.max_age_scale, 1.0..=3.0)
+                    .text("Oldest Size Scale"),
+            );
+            ui.add(
+                egui::Slider::new(&mut run_controls.growth.growth_time, 5.0..=300.0)
+                    .text("Growth Time (s)"),
+            );
+            ui.add(
+                egui::Slider::new(&mut run_controls.growth.energy_influence, 0.0..=1.0)
+                    .text("Energy Influence"),
+            );
+        });
+        ui.add(
+            egui::Slider::new(&mut run_controls.growth.predation_size_ratio, 0.0..=2.0)
+                .text("Max Prey Size Ratio"),
+        )
+        .on_hover_text(
+            "Predators can only eat prey up to this many times their own size. \
+                    0 lets them eat anything.",
+        );
+    });
+
+    ui.collapsing("Display", |ui| {
+        egui::ComboBox::from_label("Color By")
+            .selected_text(display.color_mode.to_string())
+            .show_ui(ui, |ui| {
+                for color_mode in CreatureColorMode::ALL {
+                    ui.selectable_value(
+                        &mut *display.color_mode,
+                        color_mode,
+                        color_mode.to_string(),
+                    );
+                }
+            });
+        egui::ComboBox::from_label("Debug Colors")
+            .selected_text(display.debug_color_mode.to_string())
+            .show_ui(ui, |ui| {
+                for debug_color_mode in DebugColorMode::ALL {
+                    ui.selectable_value(
+                        &mut *display.debug_color_mode,
+                        debug_color_mode,
+                        debug_color_mode.to_string(),
+                    );
+                }
+            })
+            .response
+            .on_hover_text("Overrides Color By while it's not None");
+
+        ui.checkbox(&mut display.overlays.vision_circles, "Vision Circles")
+            .on_hover_text(
+                "Vision in the type's color and the cohesion, alignment, \
+                    and separation radii in their force colors",
+            );
+        ui.checkbox(&mut display.overlays.neighbor_lines, "Neighbor Lines")
+            .on_hover_text(concat!(
+                "Lines to every neighbor in reach that matters. ",
+                "Flockmates in their color, prey in red, predators in yellow."
+            ));
+        ui.add_enabled(
+            display.overlays.vision_circles || display.overlays.neighbor_lines,
+            egui::Checkbox::new(&mut display.overlays.only_selected, "Only Selected"),
+        );
+        ui.checkbox(&mut display.overlays.hash_grid, "Hash Grid")
+            .on_hover_text("Occupied chunks with how many creatures are in each");
+        ui.checkbox(&mut display.overlays.temperature, "Temperature")
+            .on_hover_text("Blue where it's cold and red where it's hot");
+        ui.checkbox(&mut display.overlays.flow, "Flow Field")
+            .on_hover_text("Which way the current goes");
+        ui.checkbox(&mut display.overlays.force_arrows, "Force Arrows")
+            .on_hover_text("Forces pushing on the selected creature");
+        ui.add_enabled(
+            display.overlays.force_arrows,
+            egui::Slider::new(&mut display.overlays.force_arrow_scale, 0.5..=20.0)
+                .text("Arrow Scale"),
+        );
+
+        ui.checkbox(&mut display.labels.enabled, "Labels")
+            .on_hover_text("Text over creatures on screen");
+        ui.add_enabled_ui(display.labels.enabled, |ui| {
+            egui::ComboBox::from_label("Label Shows")
+                .selected_text(display.labels.content.to_string())
+                .show_ui(ui, |ui| {
+                    for content in LabelContent::ALL {
+                        ui.selectable_value(
+                            &mut display.labels.content,
+                            content,
+                            content.to_string(),
+                        );
+                    }
+                });
+            ui.checkbox(&mut display.labels.only_selected, "Only Selected Labeled");
+            ui.add(egui::Slider::new(&mut display.labels.max_labels, 10..=500).text("Max Labels"))
+                .on_hover_text("Past this only the ones closest to the middle get one");
+            if display.labels.candidates > display.labels.max_labels {
+                ui.label(format!(
+                    "Labeling {} of {} on screen",
+                    display.labels.max_labels, display.labels.candidates
+                ));
+            }
+        });
+
+        ui.checkbox(&mut display.death_animation.enabled, "Death Animations");
+        ui.add_enabled(
+            display.death_animation.enabled,
+            egui::Slider::new(&mut display.death_animation.duration, 0.1..=2.0)
+                .text("Death Duration (s)"),
+        );
+
+        ui.checkbox(&mut display.trails.enabled, "Trails")
+            .on_hover_text("Can get slow with a lot of creatures");
+        ui.add_enabled_ui(display.trails.enabled, |ui| {
+            ui.add(egui::Slider::new(&mut display.trails.length, 2..=100).text("Trail Length"));
+            ui.add(
+                egui::Slider::new(&mut display.trails.opacity, 0.05..=1.0).text("Trail Opacity"),
+            );
+        });
+
+        ui.checkbox(&mut display.smear.enabled, "Motion Smear")
+            .on_hover_text("Stretches creatures along where they're going, more for faster types");
+        ui.add_enabled(
+            display.smear.enabled,
+            egui::Slider::new(&mut display.smear.amount, 0.1..=3.0).text("Smear Amount"),
+        );
+
+        ui.checkbox(&mut display.exposure.enabled, "Long Exposure")
+            .on_hover_text("Leaves glowing streaks wherever creatures have been");
+        ui.add_enabled(
+            display.exposure.enabled,
+            egui::Slider::new(&mut display.exposure.half_life, 0.1..=10.0)
+                .logarithmic(true)
+                .text("Fade Half Life (s)"),
+        );
+    });
+
+    ui.collapsing("Interface", |ui| {
+        egui::ComboBox::from_label("Theme")
+            .selected_text(display.theme.mode.to_string())
+            .show_ui(ui, |ui| {
+                for mode in ThemeMode::ALL {
+                    ui.selectable_value(&mut display.theme.mode, mode, mode.to_string());
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.color_edit_button_srgb(&mut display.theme.accent);
+            ui.label("Accent");
+        });
+        // Rescaling while dragging moves the slider out from under the
+        // mouse so the change waits for the drag to end
+        let scale = display.ui_scale_input.get_or_insert(display.theme.scale);
+        let response =
+            ui.add(egui::Slider::new(scale, MIN_UI_SCALE..=MAX_UI_SCALE).text("UI Scale"));
+        if !response.dragged() {
+            let scale = display.ui_scale_input.take().unwrap();
+            if scale != display.theme.scale {
+                display.theme.scale = scale;
+            }
+        }
+        if ui.button("Reset Look").clicked() {
+            *display.theme = UiTheme::default();
+        }
+    });
+
+    ui.collapsing("Performance", |ui| {
+        match performance.benchmark.current_population() {
+            Some(population) => {
+                ui.label(format!("Benchmarking {} boids...", population));
+            }
+            None => {
+                if ui
+                    .button("Run Benchmark")
+                    .on_hover_text(concat!(
+                        "Restarts with more and more boids and times each stage. ",
+                        "Everything goes back to how it was after."
+                    ))
+                    .clicked()
+                {
+                    performance.start_benchmark_events.send(StartBenchmarkEvent);
+                }
+            }
+        }
+        if !performance.benchmark.results.is_empty() {
+            egui::Grid::new("stage_benchmark")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Boids");
+                    ui.label("Frame (ms)");
+                    for stage in STAGE_NAMES {
+                        ui.label(format!("{} (ms)", stage));
+                    }
+                    ui.end_row();
+                    for round in performance.benchmark.results.iter() {
+                        ui.label(round.population.to_string());
+                        ui.label(format!("{:.2}", round.frame_millis));
+                        for millis in round.stage_millis {
+                            ui.label(format!("{:.2}", millis));
+                        }
+                        ui.end_row();
+                    }
+                });
+        }
+        if let Some(status) = &performance.benchmark.status {
+            ui.label(status);
+        }
+        ui.separator();
+
+        ui.checkbox(&mut performance.arena_tiling.enabled, "Arena Tiling")
+            .on_hover_text(concat!(
+                "Split the world into tiles simulated on separate threads. ",
+                "Only creatures near tile borders are shared between them."
+            ));
+        ui.add_enabled_ui(performance.arena_tiling.enabled, |ui| {
+            ui.add(
+                egui::Slider::new(&mut performance.arena_tiling.columns, 1..=16).text("Columns"),
+            );
+            ui.add(egui::Slider::new(&mut performance.arena_tiling.rows, 1..=16).text("Rows"));
+        });
+
+        ui.checkbox(
+            &mut performance.level_of_detail.enabled,
+            "Slow Down Off-Screen Creatures",
+        )
+        .on_hover_text(concat!(
+            "Creatures the camera can't see only flock every few ticks. ",
+            "Ignored in deterministic mode."
+        ));
+        ui.add_enabled_ui(performance.level_of_detail.enabled, |ui| {
+            ui.add(
+                egui::Slider::new(&mut performance.level_of_detail.off_screen_interval, 2..=16)
+                    .text("Off-Screen Interval (Ticks)"),
+            );
+        });
+
+        ui.checkbox(&mut performance.neighbor_cache.enabled, "Cache Neighbors")
+            .on_hover_text(concat!(
+                "Reuse who each creature is flocking with for a few ticks. ",
+                "Faster but new neighbors take a moment to get noticed."
+            ));
+        ui.add_enabled_ui(performance.neighbor_cache.enabled, |ui| {
+            ui.add(
+                egui::Slider::new(&mut performance.neighbor_cache.refresh_interval, 2..=30)
+                    .text("Neighbor Refresh (Ticks)"),
+            );
+        });
+
+        ui.checkbox(&mut performance.packed_flocking.0, "Packed (SIMD) Flocking")
+            .on_hover_text("Works out four neighbors at once. Ignored with arena tiling.");
+        if ui
+            .button("Benchmark Flocking")
+            .on_hover_text("Runs 50k boids through both ways of flocking. Takes a bit.")
+            .clicked()
+        {
+            performance
+                .flocking_benchmark_events
+                .send(RunFlockingBenchmarkEvent);
+        }
+        let flocking_benchmark = &performance.flocking_benchmark;
+        if flocking_benchmark.population > 0 {
+            ui.label(format!(
+                "{} boids: {:.1} ms normal, {:.1} ms packed ({:.2}x)",
+                flocking_benchmark.population,
+                flocking_benchmark.normal_millis,
+                flocking_benchmark.packed_millis,
+                flocking_benchmark.normal_millis
+                    / flocking_benchmark.packed_millis.max(f32::EPSILON)
+            ));
+            if flocking_benchmark.normal_forces != flocking_benchmark.packed_forces {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!(
+                        "Force counts differ: {} vs {}",
+                        flocking_benchmark.normal_forces, flocking_benchmark.packed_forces
+                    ),
+                );
+            }
+        }
+
+        ui.separator();
+        egui::ComboBox::from_label("Spatial Index")
+            .selected_text(performance.spatial_index.to_string())
+            .show_ui(ui, |ui| {
+                for kind in SpatialIndexKind::ALL {
+                    ui.selectable_value(&mut *performance.spatial_index, kind, kind.to_string());
+                }
+            });
+        // Still worth tuning with the quadtree since the grid overlay and colors use it
+        ui.add(
+            egui::Slider::new(
+                &mut performance.chunk_resolution.0,
+                ChunkResolution::MIN..=ChunkResolution::MAX,
+            )
+            .text("Grid Chunk Size"),
+        )
+        .on_hover_text("Around the vision of most creatures is usually fastest");
+        if ui
+            .button("Benchmark Indices")
+            .on_hover_text("Might freeze things for a second")
+            .clicked()
+        {
+            performance.benchmark_events.send(RunSpatialBenchmarkEvent);
+        }
+        let benchmark = &performance.spatial_benchmark;
+        if !benchmark.results.is_empty() {
+            egui::Grid::new("spatial_benchmark")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Creatures");
+                    ui.label("Index");
+                    ui.label("Build (ms)");
+                    ui.label("Query (ms)");
+                    ui.label("Found");
+                    ui.end_row();
+                    for result in benchmark.results.iter() {
+                        ui.label(result.population.to_string());
+                        ui.label(result.kind.to_string());
+                        ui.label(format!("{:.2}", result.build_millis));
+                        ui.label(format!("{:.2}", result.query_millis));
+                        ui.label(result.neighbors_found.to_string());
+                        ui.end_row();
+                    }
+                });
+            ui.label(format!(
+                "Densities per 100x100: {}",
+                benchmark
+                    .densities
+                    .iter()
+                    .map(|density| format!("{:.1}", density))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    });
+
+    ui.collapsing("Scripts", |ui| {
+        ui.label(format!(
+            "Put a `steer(me)` function in {}/type_<n>.rhai to steer that type.",
+            SCRIPTS_FOLDER
+        ));
+        ui.checkbox(&mut extras.scripting.enabled, "Run Scripts");
+        let mut scripts = extras.scripting.scripts.iter().collect::<Vec<_>>();
+        scripts.sort_by_key(|(creature_type, _)| creature_type.0);
+        if scripts.is_empty() {
+            ui.label("No scripts found.");
+        }
+        for (creature_type, script) in scripts {
+            match &script.error {
+                Some(error) => {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!("{}: {}", creature_type, error),
+                    );
+                }
+                None => {
+                    ui.label(format!("{}: {}", creature_type, script.path.display()));
+                }
+            }
+        }
+    });
+
+    ui.collapsing("Compare", |ui| {
+        ui.label(concat!(
+            "Runs a second copy of the sim on the right half of the screen. ",
+            "It starts with the same settings and seed, then has its own settings."
+        ));
+        ui.checkbox(&mut extras.comparison.enabled, "Split Screen");
+    });
+
+    ui.collapsing("Sound", |ui| {
+        let sound = extras.sound.as_mut();
+        ui.checkbox(&mut sound.muted, "Mute");
+        ui.add_enabled_ui(!sound.muted, |ui| {
+            ui.add(egui::Slider::new(&mut sound.master_volume, 0.0..=1.0).text("Volume"));
+            ui.add(egui::Slider::new(&mut sound.ambient_volume, 0.0..=1.0).text("Ambient"));
+            ui.add(egui::Slider::new(&mut sound.effects_volume, 0.0..=1.0).text("Effects"));
+            ui.add(egui::Slider::new(&mut sound.music_volume, 0.0..=1.0).text("Music"));
+        });
+        let music = extras.music.as_ref();
+        ui.label(format!(
+            "{} chasing, {:.1} eaten per second",
+            music.chasers, music.kill_rate
+        ));
+        ui.add(
+            egui::ProgressBar::new(music.intensity)
+                .text(format!("Music Intensity {:.0}%", music.intensity * 100.0)),
+        );
+    });
+
+    ui.collapsing("Gamepad", |ui| {
+        let bindings = extras.gamepad_bindings.as_mut();
+        ui.checkbox(&mut bindings.enabled, "Use Gamepads");
+        ui.add(egui::Slider::new(&mut bindings.pan_speed, 100.0..=2000.0).text("Pan Speed"));
+        ui.add(egui::Slider::new(&mut bindings.zoom_speed, 1.1..=4.0).text("Zoom Speed"));
+        egui::Grid::new("gamepad_bindings")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Action");
+                ui.strong("Button");
+                ui.end_row();
+                ui.label("Pan Camera");
+                ui.label("Left Stick");
+                ui.end_row();
+                ui.label("Zoom");
+                ui.label("Right Stick");
+                ui.end_row();
+                for (action, button) in bindings.buttons.iter_mut() {
+                    ui.label(action.to_string());
+                    egui::ComboBox::from_id_source(("gamepad_binding", *action))
+                        .selected_text(button_name(*button))
+                        .show_ui(ui, |ui| {
+                            for option in BINDABLE_BUTTONS {
+                                ui.selectable_value(button, option, button_name(option));
+                            }
+                        });
+                    ui.end_row();
+                }
+            });
+        if ui.button("Reset Bindings").clicked() {
+            bindings.buttons = GamepadBindings::default().buttons;
+        }
+    });
+
+    let mut window = primary_query.get_single_mut().unwrap();
+    ui.collapsing("World", |ui| {
+        ui.label("Creatures wrap around at the edges of the world, not the window.");
+        ui.label("Arrow keys or WASD pan, scroll zooms, and Home fits the whole world.");
+        // Only touch the resource when something actually changes so
+        // things listening for changes don't run every frame
+        let mut world_bounds = *run_controls.world_bounds;
+        ui.add(
+            egui::Slider::new(&mut world_bounds.width, 200.0..=MAX_WORLD_SIZE)
+                .logarithmic(true)
+                .text("Width"),
+        );
+        ui.add(
+            egui::Slider::new(&mut world_bounds.height, 200.0..=MAX_WORLD_SIZE)
+                .logarithmic(true)
+                .text("Height"),
+        );
+        if ui.button("Match Window").clicked() {
+            world_bounds = WorldBounds::new(window.width(), window.height());
+        }
+        if world_bounds != *run_controls.world_bounds {
+            *run_controls.world_bounds = world_bounds;
+        }
+        ui.checkbox(
+            &mut run_controls.camera.cull_off_screen,
+            "Hide Off-Screen Creatures",
+        )
+        .on_hover_text("Skips drawing what the camera can't see, for big worlds");
+        ui.horizontal(|ui| {
+            let camera = &mut run_controls.camera;
+            ui.checkbox(&mut camera.constant_size, "Constant Screen Size")
+                .on_hover_text("Same size on screen at any zoom so far off flocks stay visible");
+            ui.add_enabled(
+                camera.constant_size,
+                egui::DragValue::new(&mut camera.constant_size_pixels)
+                    .clamp_range(1.0..=50.0)
+                    .suffix(" px"),
+            );
+        });
+        let margins = &mut run_controls.margins;
+        ui.checkbox(&mut margins.enabled, "Soft Edges")
+            .on_hover_text("Turn creatures back before they reach the edge");
+        ui.add_enabled(
+            margins.enabled,
+            egui::Slider::new(&mut margins.margin, 10.0..=500.0).text("Margin"),
+        );
+        ui.add_enabled(
+            margins.enabled,
+            egui::Slider::new(&mut margins.strength, 0.0..=100.0).text("Turn Strength"),
+        );
+    });
+
+    let is_shift = keys.pressed(KeyCode::LShift);
+    let is_ctrl = keys.pressed(KeyCode::LControl);
+    ui.collapsing("Screen", |ui| {
+        ui.horizontal(|ui| {
+            for &mode in window_modes() {
+                if ui
+                    .selectable_label(window.mode == mode, window_mode_name(mode))
+                    .clicked()
+                {
+                    display.window_mode.set_mode(&mut window, mode);
+                }
+            }
+        });
+        ui.label(format!("{:?} switches between these too.", WINDOW_MODE_KEY));
+        ui.checkbox(
+            &mut display.window_mode.world_follows_window,
+            "World Follows Window",
+        )
+        .on_hover_text("Resize the world with the window when they were the same size");
+        ui.horizontal(|ui| {
+            if ui.button("Hide UI").clicked() {
+                *display.ui_visibility = UiVisibility::Hidden;
+            }
+            if ui
+                .button("Photo Mode")
+                .on_hover_text("Hides the debug overlays and brush too")
+                .clicked()
+            {
+                *display.ui_visibility = UiVisibility::Photo;
+            }
+        });
+        ui.label("Tab or H brings the UI back (Shift+Tab for photo mode).");
+        ui.label("Click to Increase. LCtrl+Click to Decrease. LShift+<> to increase change.");
+        let change = if is_shift { 500 } else { 50 };
+        let change = if is_ctrl { -change } else { change };
+        let change = change as f32;
+        if ui.button("Width").clicked() {
+            let new_width = (window.width() + change).max(500.0);
+            window.resolution = WindowResolution::new(new_width, window.height());
+        }
+        if ui.button("Height").clicked() {
+            let new_height = (window.height() + change).max(500.0);
+            window.resolution = WindowResolution::new(window.width(), new_height);
+        }
+        if ui.button("Center Camera").clicked() {
+            camera_follow.enabled = false;
+            let mut camera_transform = camera_query.single_mut();
+            camera_transform.translation.x = 0.0;
+            camera_transform.translation.y = 0.0;
+        }
+    });
+
+    if IS_WASM {
+        ui.label("Settings are remembered by your browser.");
+    }
+    if ui
+        .button("Reset to Defaults")
+        .on_hover_text("Forget saved settings and restart with the defaults")
+        .clicked()
+    {
+        extras.reset_settings_events.send(ResetSettingsEvent);
+    }
+
+    ui.collapsing("Report a Bug", |ui| {
+        ui.label(concat!(
+            "Saves the current config, seed, a snapshot of every type, the recent ",
+            "event log, and system info into one file. ",
+            "Attach it to your GitHub issue so the weird behavior can be reproduced."
+        ));
+        if ui.button("Create Report Bundle").clicked() {
+            extras.create_report_events.send(CreateReportEvent);
+        }
+        if let Some(status) = &extras.report_status.0 {
+            ui.label(status);
+        }
+    });
+}
+
+/// Pasted JSON waiting to be put in and how the last copy or paste went
+#[derive(Default)]
+struct FactorClipboard {
+    text: String,
+    status: Option<String>,
+}
+
+/// What the Edit Factors panel remembers between frames
+#[derive(Default)]
+struct FactorsPanelState {
+    convert_percent: f32,
+    randomize_prey_too: bool,
+    clipboard: FactorClipboard,
+}
+
+/// The factors along with everything that has to change with them, like the
+/// creatures of a removed type and the undo history.
+#[derive(SystemParam)]
+struct FactorEditing<'w, 's> {
+    commands: Commands<'w, 's>,
+    all_factors: ResMut<'w, FactorInfo>,
+    creature_query: Query<'w, 's, (Entity, &'static mut CreatureType)>,
+    frozen_types: ResMut<'w, FrozenTypes>,
+    history: ResMut<'w, FactorHistory>,
+}
+
+/// Where types can be sent, for the Goals and Patrol Paths sections
+#[derive(SystemParam)]
+struct Destinations<'w> {
+    goals: ResMut<'w, Goals>,
+    paths: ResMut<'w, PatrolPaths>,
+    path_editor: ResMut<'w, PathEditor>,
+}
+
+/// The types and everything the Edit Factors tab does with them.
+#[derive(SystemParam)]
+struct FactorsPanel<'w, 's> {
+    editing: FactorEditing<'w, 's>,
+    selected_creature_type: ResMut<'w, CreatureType>,
+    rng: ResMut<'w, SimRng>,
+    state: Local<'s, FactorsPanelState>,
+    destinations: Destinations<'w>,
+    palette: ResMut<'w, ColorPalette>,
+}
+
+fn factors_panel(ui: &mut egui::Ui, panel: FactorsPanel) {
+    let FactorsPanel {
+        editing,
+        mut selected_creature_type,
+        mut rng,
+        mut state,
+        destinations,
+        mut palette,
+    } = panel;
+    let FactorEditing {
+        mut commands,
+        mut all_factors,
+        mut creature_query,
+        mut frozen_types,
+        mut history,
+    } = editing;
+    let FactorsPanelState {
+        convert_percent,
+        randomize_prey_too,
+        clipboard,
+    } = &mut *state;
+    let Destinations {
+        mut goals,
+        mut paths,
+        mut path_editor,
+    } = destinations;
+    let before = all_factors.clone();
+    let mut recorded_edit = false;
+    let editing = ui.input(|input| input.pointer.any_down()) || ui.ctx().wants_keyboard_input();
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(history.can_undo(), egui::Button::new("Undo"))
+            .on_hover_text("Ctrl+Z")
+            .clicked()
+        {
+            history.request(HistoryAction::Undo);
+        }
+        if ui
+            .add_enabled(history.can_redo(), egui::Button::new("Redo"))
+            .on_hover_text("Ctrl+Y")
+            .clicked()
+        {
+            history.request(HistoryAction::Redo);
+        }
+    });
+
+    let mut selected_type_index = selected_creature_type.0;
+
+    egui::ComboBox::from_label("Select")
+        .selected_text(all_factors.name_of(CreatureType(selected_type_index)))
+        .show_ui(ui, |ui| {
+            (0..all_factors.factors.len()).for_each(|creature_index| {
+                ui.horizontal(|ui| {
+                    let creature_type = CreatureType(creature_index);
+                    let factors = all_factors.factors.get(&creature_type).unwrap();
+                    ui.selectable_value(
+                        &mut selected_type_index,
+                        creature_index,
+                        all_factors.name_of(creature_type),
+                    );
+                    color_swatch(ui, factors.color);
+                });
+            });
+        });
+
+    selected_creature_type.0 = selected_type_index;
+
+    ui.horizontal(|ui| {
+        if all_factors.factors.len() > 1 && ui.button("Remove Selected").clicked() {
+            let removed = *selected_creature_type;
+            history.record(FactorEdit::RemovedType {
+                creature_type: removed,
+                before: all_factors.clone(),
+                frozen_before: frozen_types.clone(),
+            });
+            recorded_edit = true;
+            remove_creature_type(
+                &mut commands,
+                &mut all_factors,
+                &mut frozen_types,
+                &mut creature_query,
+                removed,
+            );
+            selected_creature_type.0 = selected_creature_type.0.min(all_factors.factors.len() - 1);
+        }
+
+        if ui.button("Add New").clicked() {
+            let new_creature_type = CreatureType(all_factors.factors.len());
+            let factors = Factors {
+                color: palette.next_color(&all_factors),
+                ..Factors::default()
+            };
+            all_factors.factors.insert(new_creature_type, factors);
+            selected_creature_type.0 = new_creature_type.0;
+        }
+
+        if ui
+            .button("Duplicate Selected")
+            .on_hover_text("Copies the selected type's factors into a new type")
+            .clicked()
+        {
+            let original = *selected_creature_type;
+            let before = all_factors.clone();
+            let duplicate = duplicate_creature_type(&mut all_factors, original);
+            let mut originals = creature_query
+                .iter()
+                .filter(|(_, creature_type)| **creature_type == original)
+                .map(|(entity, _)| entity)
+                .collect::<Vec<_>>();
+            originals.shuffle(rng.as_mut());
+            let count = (originals.len() as f32 * *convert_percent / 100.0).round();
+            originals.truncate(count as usize);
+            for &entity in originals.iter() {
+                if let Ok((_, mut creature_type)) = creature_query.get_mut(entity) {
+                    *creature_type = duplicate;
+                }
+            }
+            history.record(FactorEdit::DuplicatedType {
+                original,
+                duplicate,
+                converted: originals,
+                before,
+                after: all_factors.clone(),
+            });
+            recorded_edit = true;
+            selected_creature_type.0 = duplicate.0;
+        }
+    });
+    ui.add(
+        egui::Slider::new(&mut *convert_percent, 0.0..=100.0)
+            .suffix("%")
+            .text("Converted When Duplicating"),
+    );
+
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_label("Palette")
+            .selected_text(palette.to_string())
+            .show_ui(ui, |ui| {
+                for option in ColorPalette::ALL {
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut *palette, option, option.to_string());
+                        for color in option.colors() {
+                            color_swatch(ui, color);
+                        }
+                    });
+                }
+            })
+            .response
+            .on_hover_text("New types get their color from this");
+        if ui
+            .button("Recolor All")
+            .on_hover_text("Gives every type a color from the palette")
+            .clicked()
+        {
+            palette.apply(&mut all_factors);
+        }
+    });
+
+    ui.horizontal(|ui| {
+        let randomize_selected = ui
+            .button("Randomize")
+            .on_hover_text("Rolls new factors for the selected type")
+            .clicked();
+        let randomize_all = ui.button("Randomize All").clicked();
+        ui.checkbox(&mut *randomize_prey_too, "Prey Too");
+        let creature_types = if randomize_all {
+            (0..all_factors.factors.len()).map(CreatureType).collect()
+        } else if randomize_selected {
+            vec![*selected_creature_type]
+        } else {
+            Vec::new()
+        };
+        for creature_type in creature_types {
+            let factors = all_factors.factors.get_mut(&creature_type).unwrap();
+            factors.randomize(rng.as_mut());
+            if *randomize_prey_too {
+                randomize_prey(&mut all_factors, creature_type, rng.as_mut());
+            }
+        }
+    });
+
+    egui::CollapsingHeader::new("Copy / Paste")
+        .id_source("factor_clipboard")
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let copy_selected = ui
+                    .button("Copy Factors")
+                    .on_hover_text("Copies the selected type as JSON")
+                    .clicked();
+                let copy_all = ui.button("Copy All").clicked();
+                let copied = if copy_selected {
+                    Some(factors_to_json(&all_factors, Some(*selected_creature_type)))
+                } else if copy_all {
+                    Some(factors_to_json(&all_factors, None))
+                } else {
+                    None
+                };
+                match copied {
+                    Some(Ok(json)) => {
+                        ui.output_mut(|output| output.copied_text = json);
+                        clipboard.status = Some("Copied to the clipboard".to_string());
+                    }
+                    Some(Err(error)) => {
+                        clipboard.status = Some(format!("Couldn't copy: {}", error));
+                    }
+                    None => {}
+                }
+            });
+            ui.add(
+                egui::TextEdit::multiline(&mut clipboard.text)
+                    .hint_text("Paste factors JSON here")
+                    .desired_rows(3),
+            );
+            let can_paste = !clipboard.text.trim().is_empty();
+            if ui
+                .add_enabled(can_paste, egui::Button::new("Paste Factors"))
+                .on_hover_text("One type replaces the selected type, a list replaces them all")
+                .clicked()
+            {
+                let before = all_factors.clone();
+                match factors_from_json(&clipboard.text) {
+                    Ok(PastedFactors::One(mut factors)) => {
+                        // Hunting types that aren't here would point at nothing
+                        let type_count = all_factors.factors.len();
+                        factors.predator_of.retain(|prey| prey.0 < type_count);
+                        all_factors.factors.insert(*selected_creature_type, factors);
+                        clipboard.status = Some("Pasted over the selected type".to_string());
+                    }
+                    Ok(PastedFactors::All(pasted)) => {
+                        clipboard.status = Some(format!("Pasted {} types", pasted.factors.len()));
+                        restore_factors(
+                            &mut commands,
+                            &mut all_factors,
+                            &mut frozen_types,
+                            &creature_query,
+                            pasted,
+                        );
+                        selected_creature_type.0 =
+                            selected_creature_type.0.min(all_factors.factors.len() - 1);
+                    }
+                    Err(error) => {
+                        clipboard.status = Some(format!("Couldn't paste: {}", error));
+                    }
+                }
+                if *all_factors != before {
+                    history.record(FactorEdit::Changed {
+                        before,
+                        after: all_factors.clone(),
+                    });
+                    recorded_edit = true;
+                    clipboard.text.clear();
+                }
+            }
+            if let Some(status) = &clipboard.status {
+                ui.label(status);
+            }
+        });
+
+    ui.separator();
+
+    let selected_creature_type = *selected_creature_type.as_ref();
+    let all_creature_types = (0..all_factors.factors.len())
+        .map(CreatureType)
+        .collect::<Vec<_>>();
+    let type_names = all_creature_types
+        .iter()
+        .map(|&creature_type| all_factors.name_of(creature_type))
+        .collect::<Vec<_>>();
+    let factors = all_factors
+        .factors
+        .get_mut(&selected_creature_type)
+        .unwrap();
+
+    let mut frozen = frozen_types.contains(&selected_creature_type);
+    if ui
+        .checkbox(&mut frozen, "Frozen")
+        .on_hover_text("Stops this type from moving and acting")
+        .changed()
+    {
+        if frozen {
+            frozen_types.0.insert(selected_creature_type);
+        } else {
+            frozen_types.0.remove(&selected_creature_type);
+        }
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Name");
+        ui.add(
+            egui::TextEdit::singleline(&mut factors.name)
+                .hint_text(selected_creature_type.to_string()),
+        );
+    });
+    ui.add(
+        egui::TextEdit::multiline(&mut factors.description)
+            .hint_text("Description")
+            .desired_rows(2),
+    );
+
+    ui.horizontal(|ui| {
+        let mut color = [factors.color.r(), factors.color.g(), factors.color.b()];
+        color_edit_button_rgb(ui, &mut color);
+        factors.color = color.into();
+        ui.label("Color");
+    });
+
+    ui.add(egui::Slider::new(&mut factors.speed, 5.0..=200.0).text("Speed"));
+    ui.add(egui::Slider::new(&mut factors.vision, 5.0..=100.0).text("Vision"))
+        .on_hover_text("How far away predators and prey can be seen");
+    ui.add(egui::Slider::new(&mut factors.size, 0.5..=10.0).text("Size"));
+    ui.add(egui::Slider::new(&mut factors.layer, -MAX_LAYER..=MAX_LAYER).text("Draw Layer"))
+        .on_hover_text("Higher layers are drawn on top, like predators over their prey");
+    ui.add(egui::Slider::new(&mut factors.max_energy, 20.0..=200.0).text("Max Energy"));
+    ui.add(egui::Slider::new(&mut factors.comfort_min, -20.0..=60.0).text("Comfort Min (°)"))
+        .on_hover_text("Colder than this drains energy when temperature is on");
+    ui.add(egui::Slider::new(&mut factors.comfort_max, -20.0..=60.0).text("Comfort Max (°)"))
+        .on_hover_text("Hotter than this drains energy when temperature is on");
+    factors.comfort_max = factors.comfort_max.max(factors.comfort_min);
+
+    egui::ComboBox::from_label("Death Animation")
+        .selected_text(factors.death_animation.to_string())
+        .show_ui(ui, |ui| {
+            for animation in DeathAnimation::ALL {
+                ui.selectable_value(
+                    &mut factors.death_animation,
+                    animation,
+                    animation.to_string(),
+                );
+            }
+        });
+
+    egui::ComboBox::from_label("Texture")
+        .selected_text(factors.texture.as_deref().unwrap_or("Plain Square"))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut factors.texture, None, "Plain Square");
+            for texture in CREATURE_TEXTURES {
+                ui.selectable_value(&mut factors.texture, Some(texture.to_string()), texture);
+            }
+        });
+
+    ui.collapsing("Boids System", |ui| {
+        ui.add(
+            egui::Slider::new(&mut factors.cohesion_radius, 1.0..=100.0).text("Cohesion Radius"),
+        );
+        ui.add(
+            egui::Slider::new(&mut factors.alignment_radius, 1.0..=100.0).text("Alignment Radius"),
+        );
+        ui.add(
+            egui::Slider::new(&mut factors.separation_radius, 1.0..=100.0)
+                .text("Separation Radius"),
+        );
+        ui.add(egui::Slider::new(&mut factors.alignment, 0.0..=50.0).text("Alignment"));
+        ui.add(egui::Slider::new(&mut factors.cohesion, 0.0..=50.0).text("Cohesion"));
+        ui.add(egui::Slider::new(&mut factors.separation, 0.0..=50.0).text("Separation"));
+        ui.add(
+            egui::Slider::new(&mut factors.collision_avoidance, 0.0..=50.0)
+                .text("Collision Avoidance"),
+        );
+        ui.add(egui::Slider::new(&mut factors.goal_seek, 0.0..=50.0).text("Goal Seek"))
+            .on_hover_text("How hard it heads for its goal (G puts one at the cursor)");
+        ui.horizontal(|ui| {
+            match goals.0.get(&selected_creature_type) {
+                Some(goal) => ui.label(format!("Goal: ({:.0}, {:.0})", goal.x, goal.y)),
+                None => ui.label("No Goal"),
+            };
+            if goals.0.contains_key(&selected_creature_type) && ui.button("Clear").clicked() {
+                goals.0.remove(&selected_creature_type);
+            }
+        });
+        ui.add(egui::Slider::new(&mut factors.path_follow, 0.0..=50.0).text("Path Follow"))
+            .on_hover_text("How hard it sticks to its patrol path");
+        ui.add(egui::Slider::new(&mut factors.path_lookahead, 0.0..=200.0).text("Path Lookahead"))
+            .on_hover_text("How far along the path it aims ahead of itself");
+        ui.horizontal(|ui| {
+            if path_editor.drawing {
+                ui.label(format!("{} points", path_editor.points.len()))
+                    .on_hover_text(
+                        "Left click to add points, then click the first one or press \
+                            Enter to close the loop",
+                    );
+                if ui.button("Cancel").clicked() {
+                    *path_editor = PathEditor::default();
+                }
+                return;
+            }
+            let has_path = paths.0.contains_key(&selected_creature_type);
+            if ui
+                .button(if has_path { "Redraw Path" } else { "Draw Path" })
+                .clicked()
+            {
+                path_editor.drawing = true;
+                path_editor.points.clear();
+            }
+            if has_path && ui.button("Clear").clicked() {
+                paths.0.remove(&selected_creature_type);
+            }
+        });
+        if let Some(path) = paths.0.get_mut(&selected_creature_type) {
+            ui.add(egui::Slider::new(&mut path.corridor, 5.0..=200.0).text("Corridor"))
+                .on_hover_text("How far off the path it can wander before heading back");
+            if ui
+                .checkbox(&mut path.smooth, "Smooth")
+                .on_hover_text("Curve through the points instead of going straight")
+                .changed()
+            {
+                path.resample();
+            }
+        }
+    });
+
+    ui.collapsing("Predator/Prey (Chase/Run) System", |ui| {
+        let factors = all_factors
+            .factors
+            .get_mut(&selected_creature_type)
+            .unwrap();
+
+        ui.label(concat!(
+            "You can be both predator and prey to another type. ",
+            "If both, running/chasing is determined by your run/chase factor. ",
+            "On contact and w/ killing, whomever has more energy shall prevail."
+        ));
+
+        ui.add(egui::Slider::new(&mut factors.chase, 0.0..=50.0).text("Chase"));
+        ui.checkbox(&mut factors.slowed_by_rain, "Slowed By Rain");
+        ui.checkbox(&mut factors.grazer, "Grazer")
+            .on_hover_text("Eats from food patches (see the Environment window)");
+        ui.checkbox(&mut factors.pack_hunter, "Pack Hunter")
+            .on_hover_text("Hunts with the rest of its type in sight, surrounding the same prey");
+        ui.add(egui::Slider::new(&mut factors.scare, 0.0..=50.0).text("Scare"));
+        ui.add(egui::Slider::new(&mut factors.fear_memory, 0.0..=10.0).text("Fear Memory (s)"))
+            .on_hover_text("How long it keeps running after losing sight of a predator");
+        ui.add(
+            egui::Slider::new(&mut factors.zigzag_amplitude, 0.0..=3.0).text("Zig-Zag Amplitude"),
+        )
+        .on_hover_text("How hard it swerves running from a close predator (0 runs straight)");
+        ui.add_enabled(
+            factors.zigzag_amplitude > 0.0,
+            egui::Slider::new(&mut factors.zigzag_frequency, 0.1..=10.0)
+                .text("Zig-Zags Per Second"),
+        );
+        ui.add_enabled(
+            factors.zigzag_amplitude > 0.0,
+            egui::Slider::new(&mut factors.zigzag_distance, 1.0..=100.0).text("Zig-Zag Distance"),
+        );
+        ui.add(egui::Slider::new(&mut factors.mob_threshold, 0..=50).text("Mob Threshold"))
+            .on_hover_text(
+                "This many together turn on a predator that's on its own (0 never does)",
+            );
+
+        ui.collapsing("Predator of", |ui| {
+            for &other_creature_type in all_creature_types.iter() {
+                if selected_creature_type == other_creature_type {
+                    continue;
+                }
+                let mut is_predator_of_other = factors.predator_of.contains(&other_creature_type);
+                ui.checkbox(
+                    &mut is_predator_of_other,
+                    type_names[other_creature_type.0].as_str(),
+                );
+                if is_predator_of_other {
+                    factors.predator_of.insert(other_creature_type);
+                } else {
+                    factors.predator_of.remove(&other_creature_type);
+                }
+            }
+        });
+
+        ui.collapsing("Prey of", |ui| {
+            for &other_creature_type in all_creature_types.iter() {
+                let other_factors = all_factors.factors.get_mut(&other_creature_type).unwrap();
+                if selected_creature_type == other_creature_type {
+                    continue;
+                }
+                let mut is_scared_of_other =
+                    other_factors.predator_of.contains(&selected_creature_type);
+                ui.checkbox(
+                    &mut is_scared_of_other,
+                    type_names[other_creature_type.0].as_str(),
+                );
+                if is_scared_of_other {
+                    other_factors.predator_of.insert(selected_creature_type);
+                } else {
+                    other_factors.predator_of.remove(&selected_creature_type);
+                }
+            }
+        });
+    });
+
+    // Removing and duplicating already went in the history as their own edits
+    if !recorded_edit {
+        history.track(&before, &all_factors, editing);
+    }
+}
+
+/// The state of each docked panel's parameters, kept between frames so their
+/// locals stick around and their events get tracked like a system's would.
+struct DockedPanels {
+    statistics: SystemState<StatisticsPanel<'static, 'static>>,
+    scenarios: SystemState<ScenariosPanel<'static>>,
+    settings: SystemState<SettingsPanel<'static, 'static>>,
+    factors: SystemState<FactorsPanel<'static, 'static>>,
+}
+
+impl FromWorld for DockedPanels {
+    fn from_world(world: &mut World) -> Self {
+        DockedPanels {
+            statistics: SystemState::new(world),
+            scenarios: SystemState::new(world),
+            settings: SystemState::new(world),
+            factors: SystemState::new(world),
+        }
+    }
+}
+
+/// Fills in whichever tabs of the dock are showing
+struct PanelViewer<'a> {
+    world: &'a mut World,
+    panels: &'a mut DockedPanels,
+}
+
+impl TabViewer for PanelViewer<'_> {
+    type Tab = Panel;
+
+    fn ui(&mut self, ui: &mut egui::Ui, panel: &mut Panel) {
+        let PanelViewer { world, panels } = self;
+        match panel {
+            Panel::Statistics => {
+                statistics_panel(ui, panels.statistics.get_mut(world));
+                panels.statistics.apply(world);
+            }
+            Panel::Scenarios => {
+                scenarios_panel(ui, panels.scenarios.get_mut(world));
+                panels.scenarios.apply(world);
+            }
+            Panel::Settings => {
+                settings_panel(ui, panels.settings.get_mut(world));
+                panels.settings.apply(world);
+            }
+            Panel::EditFactors => {
+                factors_panel(ui, panels.factors.get_mut(world));
+                panels.factors.apply(world);
+            }
+        }
+    }
+
+    fn title(&mut self, panel: &mut Panel) -> egui::WidgetText {
+        panel.to_string().into()
+    }
+}
+
+/// Exclusive since a tab can be any of the panels and there's no telling
+/// which until the dock is being drawn.
+fn dock_system(world: &mut World, mut panels: Local<DockedPanels>) {
+    let mut context_query = world.query_filtered::<&mut EguiContext, With<PrimaryWindow>>();
+    let Ok(mut egui_context) = context_query.get_single_mut(world) else {
+        return;
+    };
+    let ctx = egui_context.get_mut().clone();
+
+    let mut layout = world.resource::<DockLayout>().clone();
+    layout.show(
+        &ctx,
+        &mut PanelViewer {
+            world,
+            panels: &mut panels,
+        },
+    );
+    // Only when it's actually moved so it doesn't get saved every frame
+    if layout.differs_from(world.resource::<DockLayout>()) {
+        *world.resource_mut::<DockLayout>() = layout;
+    }
+}
+
+/// Settings for the right side of a split screen comparison.
+fn comparison_window_system(
+    mut egui_context: EguiContexts,
+    mut comparison: ResMut<Comparison>,
+    factor_info: Res<FactorInfo>,
+    features: Res<Features>,
+    selected_creature_type: Res<CreatureType>,
+    mut restart_events: EventWriter<RestartEvent>,
+) {
+    if !comparison.enabled {
+        return;
+    }
+
+    egui::Window::new("Right Side")
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Copy Left Settings").clicked() {
+                    comparison.copy_from(&factor_info, &features);
+                }
+                if ui
+                    .button("Restart Both")
+                    .on_hover_text("Same seed on both sides so only the settings differ")
+                    .clicked()
+                {
+                    restart_events.send(RestartEvent);
+                    comparison.restart = true;
+                }
+            });
+
+            let mut changed = false;
+            ui.collapsing("Features", |ui| {
+                let features = &mut comparison.features;
+                changed |= ui.checkbox(&mut features.flocking, "Flocking").changed();
+                changed |= ui.checkbox(&mut features.chasing, "Chasing").changed();
+                changed |= ui.checkbox(&mut features.running, "Running").changed();
+                changed |= ui.checkbox(&mut features.killing, "Killing").changed();
+                changed |= ui
+                    .checkbox(&mut features.energy_draining, "Energy Draining")
+                    .changed();
+            });
+
+            let Some(factors) = comparison
+                .factor_info
+                .factors
+                .get_mut(&selected_creature_type)
+            else {
+                return;
+            };
+            ui.label(format!(
+                "{} (pick another in Factors)",
+                factor_info.name_of(*selected_creature_type)
+            ));
+            let sliders = [
+                (&mut factors.speed, 5.0..=200.0, "Speed"),
+                (&mut factors.vision, 5.0..=100.0, "Vision"),
+                (&mut factors.cohesion_radius, 1.0..=100.0, "Cohesion Radius"),
+                (
+                    &mut factors.alignment_radius,
+                    1.0..=100.0,
+                    "Alignment Radius",
+                ),
+                (
+                    &mut factors.separation_radius,
+                    1.0..=100.0,
+                    "Separation Radius",
+                ),
+                (&mut factors.size, 0.5..=10.0, "Size"),
+                (&mut factors.alignment, 0.0..=50.0, "Alignment"),
+                (&mut factors.cohesion, 0.0..=50.0, "Cohesion"),
+                (&mut factors.separation, 0.0..=50.0, "Separation"),
+                (
+                    &mut factors.collision_avoidance,
+                    0.0..=50.0,
+                    "Collision Avoidance",
+                ),
+                (&mut factors.chase, 0.0..=50.0, "Chase"),
+                (&mut factors.scare, 0.0..=50.0, "Scare"),
+                (&mut factors.fear_memory, 0.0..=10.0, "Fear Memory"),
+            ];
+            for (value, range, name) in sliders {
+                changed |= ui.add(egui::Slider::new(value, range).text(name)).changed();
+            }
+
+            if changed {
+                comparison.changed = true;
+            }
+        });
+}
+
+#[derive(Default)]
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        // The app we're dropped into might already be using egui
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugin(EguiPlugin);
+        }
+        app.insert_resource(UiVisibility::default())
+            .init_resource::<ColorPalette>()
+            // Saved settings can have put a layout in already
+            .init_resource::<DockLayout>()
+            .add_plugin(OverlayPlugin)
+            .add_plugin(ThemePlugin)
+            .add_startup_system(fps_text_setup);
+
+        app.add_system(dock_system.run_if(windows_shown));
 
-        app.add_system(settings_system)
-            .add_system(statistics_system)
-            .add_system(fps_text_update_system);
+        app.add_systems(
+            (
+                timeline_system,
+                event_log_window_system,
+                environment_system,
+                evolution_system,
+                inspector_system,
+                comparison_window_system,
+                selected_type_indicator_system,
+                weather_indicator_system,
+            )
+                .distributive_run_if(windows_shown)
+                // So the windows anchored to the edges line up against the dock
+                .after(dock_system),
+        )
+        .add_system(pause_overlay_system.run_if(overlays_shown))
+        .add_system(fps_text_update_system)
+        .add_system(type_hotkeys_system)
+        .add_system(ui_visibility_system);
     }
 }
@@ -0,0 +1,95 @@
+// Creatures of a type are all a little different so flocks don't move in
+// perfect lockstep. Each one gets its own multipliers on its type's speed,
+// vision, and size when it shows up. They're multipliers instead of the actual
+// values so changing the type's factors still changes everyone.
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{CreatureType, FactorInfo, Factors, SimRng, SpawnProperties};
+
+#[derive(Debug, Clone, Copy, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Variation {
+    pub speed: f32,
+    /// Goes for the flocking radii too since they're how far it notices flockmates
+    pub vision: f32,
+    pub size: f32,
+}
+
+impl Default for Variation {
+    fn default() -> Self {
+        Variation {
+            speed: 1.0,
+            vision: 1.0,
+            size: 1.0,
+        }
+    }
+}
+
+impl Variation {
+    /// Every multiplier somewhere within `amount` of 1 (0.1 is ±10%)
+    pub fn random(rng: &mut impl Rng, amount: f32) -> Self {
+        let amount = amount.clamp(0.0, 0.9);
+        let mut roll = || 1.0 + rng.gen_range(-amount..=amount);
+        Variation {
+            speed: roll(),
+            vision: roll(),
+            size: roll(),
+        }
+    }
+
+    pub fn speed(&self, factors: &Factors) -> f32 {
+        factors.speed * self.speed
+    }
+
+    pub fn vision(&self, factors: &Factors) -> f32 {
+        factors.vision * self.vision
+    }
+
+    pub fn size(&self, factors: &Factors) -> f32 {
+        factors.size * self.size
+    }
+
+    /// One of the type's flocking radii
+    pub fn radius(&self, radius: f32) -> f32 {
+        radius * self.vision
+    }
+
+    /// At least as far as anything this creature reacts to
+    pub fn reach(&self, factors: &Factors) -> f32 {
+        factors.reach() * self.vision.max(self.size)
+    }
+}
+
+type NewCreature = (Added<CreatureType>, Without<Variation>);
+
+/// Creatures get spawned from all over so it's easier to catch new ones here
+/// than to roll a variation in every one of those places.
+fn vary_new_creatures_system(
+    mut commands: Commands,
+    mut rng: ResMut<SimRng>,
+    spawn_properties: Res<SpawnProperties>,
+    factor_info: Res<FactorInfo>,
+    mut creature_query: Query<(Entity, &CreatureType, &mut Sprite), NewCreature>,
+) {
+    for (entity, creature_type, mut sprite) in creature_query.iter_mut() {
+        let Some(factors) = factor_info.factors.get(creature_type) else {
+            continue;
+        };
+        let variation = Variation::random(rng.as_mut(), spawn_properties.variation);
+        sprite.custom_size = Some(Vec2::splat(variation.size(factors)));
+        commands.entity(entity).insert(variation);
+    }
+}
+
+#[derive(Default)]
+pub struct VariationPlugin;
+
+impl Plugin for VariationPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Variation>()
+            .add_system(vary_new_creatures_system.in_base_set(CoreSet::PostUpdate));
+    }
+}
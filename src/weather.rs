@@ -0,0 +1,211 @@
+// Every so often the weather turns. Storms blow everything one way, rain slows
+// down the types that don't like getting wet, and fog cuts down how far anyone
+// can see. Each one fades in and out so nothing snaps all at once.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ApplyForceEvent, CreatureType, Factors, ForceKind, FrozenTypes, RestartEvent, SimRng, SimState,
+    SimTime, SystemStages,
+};
+
+/// Seconds it takes weather to fully set in or clear up
+const FADE: f32 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WeatherKind {
+    Storm,
+    Rain,
+    Fog,
+}
+
+impl WeatherKind {
+    pub const ALL: [WeatherKind; 3] = [WeatherKind::Storm, WeatherKind::Rain, WeatherKind::Fog];
+}
+
+impl std::fmt::Display for WeatherKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WeatherSettings {
+    pub enabled: bool,
+    /// Average number of weather events a minute
+    pub frequency: f32,
+    /// Seconds each one lasts
+    pub duration: f32,
+    /// How hard storms push
+    pub storm_strength: f32,
+    /// Multiplies the speed of types slowed by rain
+    pub rain_speed_scale: f32,
+    /// Multiplies everyone's vision and flocking radii in fog
+    pub fog_vision_scale: f32,
+}
+
+impl Default for WeatherSettings {
+    fn default() -> Self {
+        WeatherSettings {
+            enabled: false,
+            frequency: 1.0,
+            duration: 10.0,
+            storm_strength: 2.0,
+            rain_speed_scale: 0.5,
+            fog_vision_scale: 0.4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActiveWeather {
+    pub kind: WeatherKind,
+    /// Which way storms blow
+    pub direction: Vec2,
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+impl ActiveWeather {
+    pub fn remaining(&self) -> f32 {
+        (self.duration - self.elapsed).max(0.0)
+    }
+
+    /// 0 to 1 fading in at the start and out at the end
+    pub fn intensity(&self) -> f32 {
+        (self.elapsed.min(self.remaining()) / FADE).clamp(0.0, 1.0)
+    }
+}
+
+/// Whatever the weather's doing right now.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct Weather {
+    pub current: Option<ActiveWeather>,
+}
+
+impl Weather {
+    fn intensity_of(&self, kind: WeatherKind) -> f32 {
+        self.current
+            .filter(|weather| weather.kind == kind)
+            .map_or(0.0, |weather| weather.intensity())
+    }
+
+    /// Multiplies a creature's speed
+    pub fn speed_scale(&self, settings: &WeatherSettings, factors: &Factors) -> f32 {
+        if !factors.slowed_by_rain {
+            return 1.0;
+        }
+        1.0 + (settings.rain_speed_scale - 1.0) * self.intensity_of(WeatherKind::Rain)
+    }
+
+    /// Multiplies everyone's vision
+    pub fn vision_scale(&self, settings: &WeatherSettings) -> f32 {
+        1.0 + (settings.fog_vision_scale - 1.0) * self.intensity_of(WeatherKind::Fog)
+    }
+}
+
+/// Starts some weather right away, or clears it up with `None`.
+pub struct ChangeWeatherEvent(pub Option<WeatherKind>);
+
+fn start_weather(rng: &mut impl Rng, kind: WeatherKind, duration: f32) -> ActiveWeather {
+    ActiveWeather {
+        kind,
+        direction: Vec2::from_angle(rng.gen_range(0.0..TAU)),
+        elapsed: 0.0,
+        duration,
+    }
+}
+
+/// Runs while paused too so the buttons in the Environment window always work.
+fn change_weather_system(
+    settings: Res<WeatherSettings>,
+    mut weather: ResMut<Weather>,
+    mut rng: ResMut<SimRng>,
+    mut change_events: EventReader<ChangeWeatherEvent>,
+    mut restart_events: EventReader<RestartEvent>,
+) {
+    if restart_events.iter().count() > 0 {
+        weather.current = None;
+    }
+    if let Some(ChangeWeatherEvent(kind)) = change_events.iter().last() {
+        weather.current = kind.map(|kind| start_weather(rng.as_mut(), kind, settings.duration));
+    }
+}
+
+fn weather_system(
+    sim_time: SimTime,
+    settings: Res<WeatherSettings>,
+    mut weather: ResMut<Weather>,
+    mut rng: ResMut<SimRng>,
+) {
+    let delta_seconds = sim_time.delta_seconds();
+    if let Some(current) = weather.current.as_mut() {
+        current.elapsed += delta_seconds;
+        if current.remaining() <= 0.0 {
+            weather.current = None;
+        }
+        return;
+    }
+    if !settings.enabled || settings.frequency <= 0.0 {
+        return;
+    }
+    // Same odds every frame no matter how long frames are
+    let chance = 1.0 - (-settings.frequency / 60.0 * delta_seconds).exp();
+    if rng.gen_bool(chance.clamp(0.0, 1.0) as f64) {
+        let kind = WeatherKind::ALL[rng.gen_range(0..WeatherKind::ALL.len())];
+        weather.current = Some(start_weather(rng.as_mut(), kind, settings.duration));
+    }
+}
+
+fn storm_system(
+    settings: Res<WeatherSettings>,
+    weather: Res<Weather>,
+    frozen_types: Res<FrozenTypes>,
+    creature_query: Query<(Entity, &CreatureType)>,
+    mut force_writer: EventWriter<ApplyForceEvent>,
+) {
+    let Some(storm) = weather
+        .current
+        .filter(|weather| weather.kind == WeatherKind::Storm)
+    else {
+        return;
+    };
+    let factor = settings.storm_strength * storm.intensity();
+    for (entity, creature_type) in creature_query.iter() {
+        if frozen_types.contains(creature_type) {
+            continue;
+        }
+        force_writer.send(ApplyForceEvent(
+            entity,
+            storm.direction,
+            factor,
+            ForceKind::Gust,
+        ));
+    }
+}
+
+#[derive(Default)]
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Weather>()
+            .add_event::<ChangeWeatherEvent>()
+            .add_system(change_weather_system.before(weather_system))
+            .add_system(
+                weather_system
+                    .before(SystemStages::Calculate)
+                    .in_set(OnUpdate(SimState::Running)),
+            )
+            .add_system(
+                storm_system
+                    .in_set(SystemStages::Calculate)
+                    .in_set(OnUpdate(SimState::Running)),
+            );
+    }
+}
@@ -0,0 +1,158 @@
+// F11 goes between a normal window, borderless fullscreen, and exclusive
+// fullscreen. The window can end up any size after that so the world and
+// camera follow along instead of assuming the size it started at.
+
+use bevy::{
+    prelude::*,
+    window::{PrimaryWindow, WindowMode, WindowResized},
+};
+
+use crate::{selection::CameraFollow, MainCamera, WorldBounds, IS_WASM};
+
+pub const WINDOW_MODE_KEY: KeyCode = KeyCode::F11;
+
+/// Browsers only do borderless so exclusive fullscreen gets skipped there
+pub fn window_modes() -> &'static [WindowMode] {
+    if IS_WASM {
+        &[WindowMode::Windowed, WindowMode::BorderlessFullscreen]
+    } else {
+        &[
+            WindowMode::Windowed,
+            WindowMode::BorderlessFullscreen,
+            WindowMode::Fullscreen,
+        ]
+    }
+}
+
+pub fn window_mode_name(mode: WindowMode) -> &'static str {
+    match mode {
+        WindowMode::Windowed => "Windowed",
+        WindowMode::BorderlessFullscreen => "Borderless",
+        WindowMode::Fullscreen => "Fullscreen",
+        WindowMode::SizedFullscreen => "Sized Fullscreen",
+    }
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct WindowModeSettings {
+    /// Grow or shrink the world with the window when it was the same size as
+    /// the window before. Worlds with their own size are left alone.
+    pub world_follows_window: bool,
+    /// What the window was before going fullscreen so it can go back to it
+    windowed_size: Option<Vec2>,
+}
+
+impl Default for WindowModeSettings {
+    fn default() -> Self {
+        WindowModeSettings {
+            world_follows_window: true,
+            windowed_size: None,
+        }
+    }
+}
+
+impl WindowModeSettings {
+    /// Switches the window over, remembering its size when leaving windowed
+    pub fn set_mode(&mut self, window: &mut Window, mode: WindowMode) {
+        if window.mode == mode {
+            return;
+        }
+        if window.mode == WindowMode::Windowed {
+            self.windowed_size = Some(Vec2::new(window.width(), window.height()));
+        }
+        window.mode = mode;
+        if mode == WindowMode::Windowed {
+            if let Some(size) = self.windowed_size.take() {
+                window.resolution.set(size.x, size.y);
+            }
+        }
+    }
+
+    /// Size the window would be if it wasn't fullscreen. Saved settings use
+    /// this so the fullscreen resolution doesn't stick around as the window size.
+    pub fn windowed_size(&self, window: &Window) -> Vec2 {
+        match (window.mode, self.windowed_size) {
+            (WindowMode::Windowed, _) | (_, None) => Vec2::new(window.width(), window.height()),
+            (_, Some(size)) => size,
+        }
+    }
+}
+
+fn window_mode_key_system(
+    keys: Res<Input<KeyCode>>,
+    mut settings: ResMut<WindowModeSettings>,
+    mut primary_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !keys.just_pressed(WINDOW_MODE_KEY) {
+        return;
+    }
+    let Ok(mut window) = primary_query.get_single_mut() else {
+        return;
+    };
+    let modes = window_modes();
+    let next = modes
+        .iter()
+        .position(|&mode| mode == window.mode)
+        .map_or(0, |index| (index + 1) % modes.len());
+    settings.set_mode(&mut window, modes[next]);
+}
+
+/// Keeps a world that matched the window matching it, and keeps the camera
+/// looking at the same part of the world when that happens.
+fn window_resized_system(
+    settings: Res<WindowModeSettings>,
+    mut resized_events: EventReader<WindowResized>,
+    mut world_bounds: ResMut<WorldBounds>,
+    camera_follow: Res<CameraFollow>,
+    primary_query: Query<(Entity, &Window), With<PrimaryWindow>>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+    mut last_size: Local<Option<Vec2>>,
+) {
+    let Ok((primary, window)) = primary_query.get_single() else {
+        return;
+    };
+    let old_size = *last_size.get_or_insert(Vec2::new(window.width(), window.height()));
+    let Some(resized) = resized_events
+        .iter()
+        .filter(|resized| resized.window == primary)
+        .last()
+    else {
+        return;
+    };
+    let new_size = Vec2::new(resized.width, resized.height);
+    // Minimizing on some platforms reports a zero sized window
+    if new_size.min_element() < 1.0 {
+        return;
+    }
+    *last_size = Some(new_size);
+    if new_size == old_size {
+        return;
+    }
+
+    let matched_window = world_bounds.size().abs_diff_eq(old_size, 1.0);
+    if !settings.world_follows_window || !matched_window {
+        return;
+    }
+    *world_bounds = WorldBounds::new(new_size.x, new_size.y);
+
+    // Following a creature already keeps it in view
+    if camera_follow.enabled {
+        return;
+    }
+    let ratio = new_size / old_size;
+    for mut transform in camera_query.iter_mut() {
+        let position = transform.translation.truncate() * ratio;
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
+
+#[derive(Default)]
+pub struct WindowModePlugin;
+
+impl Plugin for WindowModePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WindowModeSettings::default())
+            .add_system(window_mode_key_system)
+            .add_system(window_resized_system.in_base_set(CoreSet::PreUpdate));
+    }
+}